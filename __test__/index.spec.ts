@@ -1,6 +1,7 @@
 import test from 'ava'
 
 import {
+  clearDocumentCache,
   extractAnnotationsPerPage,
   extractAnnotationsPerPageAsync,
   extractImagesPerPage,
@@ -307,6 +308,52 @@ test('PdfDown.documentAsync (async method) — matches standalone pdfDocument',
   t.is(classResult.modificationDate ?? null, standalone.modificationDate ?? null, 'modificationDate should match')
 })
 
+test('PdfDown.streamPages — calls back once per page before resolving', async (t) => {
+  const standalone = pdfDocument(pdf3)
+  const seen: number[] = []
+  await pdfDown3.streamPages(
+    (chunk) => {
+      seen.push(chunk.page)
+      t.is(typeof chunk.text, 'string', 'text should be a string')
+      t.true(Array.isArray(chunk.images), 'images should be included when requested')
+      t.is(chunk.annotations, undefined, 'annotations should be omitted by default')
+    },
+    { includeImages: true },
+  )
+
+  t.deepEqual(
+    [...seen].sort((a, b) => a - b),
+    standalone.text.map((p) => p.page),
+    'every page should be streamed exactly once',
+  )
+})
+
+test('PdfDown.streamPages — rejects when the callback throws', async (t) => {
+  await t.throwsAsync(
+    pdfDown3.streamPages(() => {
+      throw new Error('client went away')
+    }),
+    { message: 'client went away' },
+  )
+})
+
+test('PdfDown.fromCached — reuses the parse for identical bytes', (t) => {
+  const first = PdfDown.fromCached(pdf3)
+  const again = PdfDown.fromCached(Buffer.from(pdf3))
+
+  t.true(again instanceof PdfDown, 'should return a PdfDown instance')
+  t.deepEqual(again.metadata(), first.metadata(), 'cached instance should describe the same document')
+  t.deepEqual(again.metadata(), pdfDown3.metadata(), 'should match a freshly constructed instance')
+})
+
+test('clearDocumentCache — cached instances stay usable and the next lookup parses again', (t) => {
+  const cached = PdfDown.fromCached(pdf3)
+  clearDocumentCache()
+
+  t.deepEqual(cached.metadata(), pdfDown3.metadata(), 'instances handed out before clearing should keep working')
+  t.deepEqual(PdfDown.fromCached(pdf3).metadata(), pdfDown3.metadata(), 'should parse again after clearing')
+})
+
 // ── Structured text (header/footer detection) tests ─────────────────────────
 
 test('extractStructuredTextPerPage (sync) — returns structured text with valid structure', (t) => {
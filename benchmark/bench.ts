@@ -3,6 +3,7 @@ import { Bench } from 'tinybench'
 import {
   extractImagesPerPage,
   extractImagesPerPageAsync,
+  extractRawTextPerPage,
   extractTextPerPage,
   extractTextPerPageAsync,
   pdfMetadata,
@@ -26,6 +27,10 @@ b.add('extractTextPerPageAsync', async () => {
   return await extractTextPerPageAsync(pdf)
 })
 
+b.add('extractRawTextPerPage (sync)', () => {
+  return extractRawTextPerPage(pdf)
+})
+
 b.add('extractImagesPerPage (sync)', () => {
   return extractImagesPerPage(pdf)
 })
@@ -0,0 +1,451 @@
+use crate::core::objects::deref_dict;
+use lopdf::{Dictionary, Document, Encoding, Object};
+use std::collections::HashMap;
+
+/// Cap on the number of codes a single `bfrange` may expand to, so a corrupt
+/// `<0000> <FFFFFFFF>` range can't allocate unbounded memory.
+const MAX_RANGE_SPAN: u32 = 0x1_0000;
+
+/// Source code → Unicode mapping parsed from a `/ToUnicode` CMap stream.
+#[derive(Default)]
+pub(crate) struct ToUnicodeMap {
+  /// Keyed by (code length in bytes, code value).
+  map: HashMap<(u8, u32), String>,
+  /// Distinct code lengths, ascending — from `codespacerange`, or inferred
+  /// from the mapped codes when the CMap omits it.
+  code_lengths: Vec<u8>,
+}
+
+impl ToUnicodeMap {
+  pub(crate) fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  /// Decode a string operand. Unmapped codes are dropped.
+  pub(crate) fn decode(&self, bytes: &[u8]) -> String {
+    let fallback_len = self.code_lengths.first().copied().unwrap_or(1) as usize;
+    let mut out = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+      let hit = self.code_lengths.iter().find_map(|&len| {
+        let end = i + len as usize;
+        let code = bytes
+          .get(i..end)?
+          .iter()
+          .fold(0u32, |acc, &b| (acc << 8) | b as u32);
+        self.map.get(&(len, code)).map(|s| (s, len as usize))
+      });
+      match hit {
+        Some((s, len)) => {
+          out.push_str(s);
+          i += len;
+        }
+        None => i += fallback_len,
+      }
+    }
+    out
+  }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+  Hex(Vec<u8>),
+  Word(Vec<u8>),
+  ArrayStart,
+  ArrayEnd,
+}
+
+/// Minimal PostScript-ish tokenizer — enough for CMap bodies. Names, dict
+/// delimiters and literal strings are skipped or reduced to words.
+fn tokenize(data: &[u8]) -> Vec<Token> {
+  let mut tokens = Vec::new();
+  let mut i = 0;
+  while i < data.len() {
+    let b = data[i];
+    match b {
+      b'%' => {
+        while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+          i += 1;
+        }
+      }
+      b'<' if data.get(i + 1) == Some(&b'<') => i += 2,
+      b'>' if data.get(i + 1) == Some(&b'>') => i += 2,
+      b'<' => {
+        let start = i + 1;
+        let end = data[start..]
+          .iter()
+          .position(|&c| c == b'>')
+          .map_or(data.len(), |p| start + p);
+        let digits: Vec<u8> = data[start..end]
+          .iter()
+          .copied()
+          .filter(|c| c.is_ascii_hexdigit())
+          .collect();
+        let bytes = digits
+          .chunks(2)
+          .map(|pair| {
+            let hi = hex_val(pair[0]);
+            let lo = pair.get(1).map_or(0, |&c| hex_val(c));
+            (hi << 4) | lo
+          })
+          .collect();
+        tokens.push(Token::Hex(bytes));
+        i = end + 1;
+      }
+      b'[' => {
+        tokens.push(Token::ArrayStart);
+        i += 1;
+      }
+      b']' => {
+        tokens.push(Token::ArrayEnd);
+        i += 1;
+      }
+      b'(' => {
+        let mut depth = 0usize;
+        while i < data.len() {
+          match data[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' => {
+              depth -= 1;
+              if depth == 0 {
+                break;
+              }
+            }
+            _ => {}
+          }
+          i += 1;
+        }
+        i += 1;
+      }
+      _ if b.is_ascii_whitespace() => i += 1,
+      _ => {
+        let start = i;
+        while i < data.len() && !data[i].is_ascii_whitespace() && !b"<>[]()%".contains(&data[i]) {
+          i += 1;
+        }
+        if i == start {
+          i += 1;
+        } else {
+          tokens.push(Token::Word(data[start..i].to_vec()));
+        }
+      }
+    }
+  }
+  tokens
+}
+
+fn hex_val(c: u8) -> u8 {
+  match c {
+    b'0'..=b'9' => c - b'0',
+    b'a'..=b'f' => c - b'a' + 10,
+    b'A'..=b'F' => c - b'A' + 10,
+    _ => 0,
+  }
+}
+
+fn code_of(bytes: &[u8]) -> Option<(u8, u32)> {
+  if bytes.is_empty() || bytes.len() > 4 {
+    return None;
+  }
+  let code = bytes.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+  Some((bytes.len() as u8, code))
+}
+
+/// Interpret destination bytes as UTF-16BE (the encoding the spec mandates for
+/// `bfchar`/`bfrange` targets).
+fn utf16be_units(bytes: &[u8]) -> Vec<u16> {
+  bytes
+    .chunks(2)
+    .map(|c| u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)]))
+    .collect()
+}
+
+/// Parse a decompressed `/ToUnicode` CMap (`bfchar` and `bfrange` sections).
+pub(crate) fn parse_to_unicode_cmap(data: &[u8]) -> ToUnicodeMap {
+  let tokens = tokenize(data);
+  let mut result = ToUnicodeMap::default();
+  let mut codespace_lengths: Vec<u8> = Vec::new();
+  let mut i = 0;
+
+  while i < tokens.len() {
+    let section = match &tokens[i] {
+      Token::Word(w) => w.as_slice(),
+      _ => {
+        i += 1;
+        continue;
+      }
+    };
+    i += 1;
+    match section {
+      b"begincodespacerange" => {
+        while let (Some(Token::Hex(lo)), Some(Token::Hex(_))) = (tokens.get(i), tokens.get(i + 1)) {
+          if (1..=4).contains(&lo.len()) {
+            codespace_lengths.push(lo.len() as u8);
+          }
+          i += 2;
+        }
+      }
+      b"beginbfchar" => {
+        while let (Some(Token::Hex(src)), Some(Token::Hex(dst))) =
+          (tokens.get(i), tokens.get(i + 1))
+        {
+          if let Some(key) = code_of(src) {
+            result
+              .map
+              .insert(key, String::from_utf16_lossy(&utf16be_units(dst)));
+          }
+          i += 2;
+        }
+      }
+      b"beginbfrange" => {
+        while let (Some(Token::Hex(lo)), Some(Token::Hex(hi))) = (tokens.get(i), tokens.get(i + 1))
+        {
+          let (Some((len, lo_code)), Some((_, hi_code))) = (code_of(lo), code_of(hi)) else {
+            i += 2;
+            continue;
+          };
+          let span = hi_code.saturating_sub(lo_code).min(MAX_RANGE_SPAN - 1);
+          match tokens.get(i + 2) {
+            Some(Token::Hex(dst)) => {
+              let mut units = utf16be_units(dst);
+              for offset in 0..=span {
+                result
+                  .map
+                  .insert((len, lo_code + offset), String::from_utf16_lossy(&units));
+                if let Some(last) = units.last_mut() {
+                  *last = last.wrapping_add(1);
+                }
+              }
+              i += 3;
+            }
+            Some(Token::ArrayStart) => {
+              i += 3;
+              let mut offset = 0u32;
+              while let Some(Token::Hex(dst)) = tokens.get(i) {
+                if offset <= span {
+                  result.map.insert(
+                    (len, lo_code + offset),
+                    String::from_utf16_lossy(&utf16be_units(dst)),
+                  );
+                }
+                offset += 1;
+                i += 1;
+              }
+              if tokens.get(i) == Some(&Token::ArrayEnd) {
+                i += 1;
+              }
+            }
+            _ => i += 2,
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  let mut lengths = if codespace_lengths.is_empty() {
+    result.map.keys().map(|&(len, _)| len).collect()
+  } else {
+    codespace_lengths
+  };
+  lengths.sort_unstable();
+  lengths.dedup();
+  result.code_lengths = lengths;
+  result
+}
+
+/// Decoder used when lopdf can't produce text for a composite (Type0) font.
+pub(crate) enum CidDecoder {
+  /// The font's own `/ToUnicode` CMap.
+  ToUnicode(ToUnicodeMap),
+  /// Predefined Unicode CMaps (`Uni*-UCS2-*`, `Uni*-UTF16-*`): codes are UTF-16BE.
+  Utf16,
+  /// `Identity-H`/`Identity-V` over a known Adobe CJK collection with no
+  /// ToUnicode. Only CIDs 1–95 — the proportional Latin block every Adobe
+  /// collection shares with ASCII — can be mapped without the full tables;
+  /// every other CID, including all kana, hangul and ideographs, decodes to
+  /// nothing.
+  AdobeCollection,
+}
+
+impl CidDecoder {
+  pub(crate) fn decode(&self, bytes: &[u8]) -> String {
+    match self {
+      CidDecoder::ToUnicode(map) => map.decode(bytes),
+      CidDecoder::Utf16 => String::from_utf16_lossy(&utf16be_units(bytes)),
+      CidDecoder::AdobeCollection => bytes
+        .chunks_exact(2)
+        .filter_map(|c| {
+          let cid = u16::from_be_bytes([c[0], c[1]]);
+          (1..=95)
+            .contains(&cid)
+            .then(|| char::from(0x1F + cid as u8))
+        })
+        .collect(),
+    }
+  }
+}
+
+/// Pick a fallback decoder for a font dictionary, preferring its ToUnicode CMap
+/// and falling back to the predefined CMap named by `/Encoding`.
+pub(crate) fn cid_decoder_for_font(doc: &Document, font: &Dictionary) -> Option<CidDecoder> {
+  if let Ok(obj) = font.get_deref(b"ToUnicode", doc)
+    && let Ok(stream) = obj.as_stream()
+  {
+    let data = stream
+      .decompressed_content()
+      .unwrap_or_else(|_| stream.content.clone());
+    let map = parse_to_unicode_cmap(&data);
+    if !map.is_empty() {
+      return Some(CidDecoder::ToUnicode(map));
+    }
+  }
+
+  if font.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Type0".as_slice()) {
+    return None;
+  }
+  let encoding = font.get(b"Encoding").and_then(Object::as_name).ok()?;
+  if encoding.starts_with(b"Uni")
+    && (encoding.windows(4).any(|w| w == b"UCS2") || encoding.windows(5).any(|w| w == b"UTF16"))
+  {
+    return Some(CidDecoder::Utf16);
+  }
+  if encoding != b"Identity-H" && encoding != b"Identity-V" {
+    return None;
+  }
+
+  let descendant = font
+    .get(b"DescendantFonts")
+    .ok()
+    .and_then(|o| match o {
+      Object::Array(arr) => arr.first(),
+      Object::Reference(id) => doc
+        .get_object(*id)
+        .ok()
+        .and_then(|o| o.as_array().ok())
+        .and_then(|arr| arr.first()),
+      _ => None,
+    })
+    .and_then(|o| deref_dict(doc, o))?;
+  let system_info = deref_dict(doc, descendant.get(b"CIDSystemInfo").ok()?)?;
+  let ordering = match system_info.get(b"Ordering").ok()? {
+    Object::String(bytes, _) => bytes.as_slice(),
+    Object::Name(n) => n.as_slice(),
+    _ => return None,
+  };
+  matches!(ordering, b"Japan1" | b"GB1" | b"CNS1" | b"Korea1")
+    .then_some(CidDecoder::AdobeCollection)
+}
+
+/// Text decoder for one font: the CMap fallback when one applies, otherwise
+/// lopdf's own encoding for the font.
+pub(crate) enum FontDecoder<'a> {
+  Cid(CidDecoder),
+  Native(Encoding<'a>),
+}
+
+impl<'a> FontDecoder<'a> {
+  pub(crate) fn for_font(doc: &'a Document, font: &'a Dictionary) -> Option<Self> {
+    match cid_decoder_for_font(doc, font) {
+      Some(cid) => Some(FontDecoder::Cid(cid)),
+      None => font.get_font_encoding(doc).ok().map(FontDecoder::Native),
+    }
+  }
+
+  pub(crate) fn decode(&self, bytes: &[u8]) -> String {
+    match self {
+      FontDecoder::Cid(cid) => cid.decode(bytes),
+      FontDecoder::Native(enc) => Document::decode_text(enc, bytes).unwrap_or_default(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_bfchar_entries() {
+    let cmap = b"1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+      2 beginbfchar <0003> <0020> <0024> <0041> endbfchar";
+    let map = parse_to_unicode_cmap(cmap);
+    assert_eq!(map.decode(&[0x00, 0x24, 0x00, 0x03, 0x00, 0x24]), "A A");
+  }
+
+  #[test]
+  fn parses_bfrange_with_incrementing_destination() {
+    let cmap = b"begincodespacerange <0000> <FFFF> endcodespacerange\n\
+      beginbfrange <4E00> <4E02> <4E00> endbfrange";
+    let map = parse_to_unicode_cmap(cmap);
+    assert_eq!(map.decode(&[0x4E, 0x00, 0x4E, 0x02]), "一丂");
+  }
+
+  #[test]
+  fn parses_bfrange_with_destination_array() {
+    let cmap = b"beginbfrange <01> <03> [<0066> <FB01> <0068>] endbfrange";
+    let map = parse_to_unicode_cmap(cmap);
+    assert_eq!(map.decode(&[0x01, 0x02, 0x03]), "f\u{FB01}h");
+  }
+
+  #[test]
+  fn unmapped_codes_are_dropped() {
+    let cmap = b"begincodespacerange <0000> <FFFF> endcodespacerange\n\
+      beginbfchar <0001> <0041> endbfchar";
+    let map = parse_to_unicode_cmap(cmap);
+    assert_eq!(map.decode(&[0x00, 0x01, 0x00, 0x09, 0x00, 0x01]), "AA");
+  }
+
+  #[test]
+  fn destination_surrogate_pairs_decode() {
+    let cmap = b"beginbfchar <0001> <D835DC00> endbfchar";
+    let map = parse_to_unicode_cmap(cmap);
+    assert_eq!(map.decode(&[0x00, 0x01]), "\u{1D400}");
+  }
+
+  #[test]
+  fn adobe_collection_maps_shared_latin_block() {
+    // CID 34 → 'A', CID 1 → space, CID 500 is outside the shared block
+    let text = CidDecoder::AdobeCollection.decode(&[0x00, 0x22, 0x00, 0x01, 0x01, 0xF4]);
+    assert_eq!(text, "A ");
+  }
+
+  #[test]
+  fn adobe_collections_drop_cids_past_the_latin_block() {
+    use lopdf::dictionary;
+
+    let mut doc = Document::with_version("1.7");
+    let mut font = |ordering: &str| {
+      let descendant = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "CIDFontType0",
+        "CIDSystemInfo" => dictionary! {
+          "Registry" => Object::string_literal("Adobe"),
+          "Ordering" => Object::string_literal(ordering),
+          "Supplement" => 0,
+        },
+      });
+      dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type0",
+        "Encoding" => "Identity-H",
+        "DescendantFonts" => vec![descendant.into()],
+      }
+    };
+    let fonts: Vec<_> = ["Japan1", "GB1", "CNS1", "Korea1", "Identity"]
+      .into_iter()
+      .map(&mut font)
+      .collect();
+
+    // CID 95 is '~', the end of the shared block; CID 96 and the collections'
+    // CJK CIDs (e.g. Japan1 1200, a kanji) have no mapping here
+    let codes = [0x00, 0x5F, 0x00, 0x60, 0x04, 0xB0];
+    for font in &fonts[..4] {
+      let decoder = cid_decoder_for_font(&doc, font).unwrap();
+      assert!(matches!(decoder, CidDecoder::AdobeCollection));
+      assert_eq!(decoder.decode(&codes), "~");
+    }
+    // Only the Adobe CJK collections get the fallback
+    assert!(cid_decoder_for_font(&doc, &fonts[4]).is_none());
+  }
+}
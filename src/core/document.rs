@@ -1,29 +1,71 @@
-use crate::core::images::extract_images_raw;
-use crate::core::meta::extract_metadata;
-use crate::core::text::{detect_headers_footers, extract_text};
-use crate::types::{PageAnnotation, RawPdfDocument};
+use crate::core::images::{extract_images_raw, extract_page_images_raw};
+use crate::core::layout::{extract_page_glyphs, text_within};
+use crate::core::meta::{decode_pdf_string, extract_metadata, page_tree_repairs};
+use crate::core::objects::{deref, deref_array, deref_dict};
+use crate::core::text::{
+  DEFAULT_PAGE_MARKER, attach_text_coverage, detect_headers_footers, extract_page_text_raw,
+  extract_text, form_xobject_text, join_pages_with_markers, strip_footer_artifacts,
+};
+use crate::types::{
+  AnnotationSummary, CleanReport, DocumentAction, HighlightedText, PageAnnotation, RawPageChunk,
+  RawPdfDocument, UriRef,
+};
 use lopdf::{Document, Object, ObjectId};
 use napi::Result;
 use rayon::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 #[cfg(feature = "ocr")]
-use crate::core::ocr::{detect_headers_footers_ocr, extract_text_with_ocr};
+use crate::core::ocr::{detect_headers_footers_ocr, extract_text_with_ocr, native_ocr_pages};
 #[cfg(feature = "ocr")]
 use crate::types::RawPdfDocumentOcr;
 
+/// The page's `/Annots` with their object ids, like
+/// `Document::get_page_annotations` (which drops the ids).
+fn page_annotations(doc: &Document, page_id: ObjectId) -> Vec<(ObjectId, &lopdf::Dictionary)> {
+  let annots = match doc.get_dictionary(page_id).and_then(|p| p.get(b"Annots")) {
+    Ok(Object::Reference(id)) => doc.get_object(*id).and_then(Object::as_array).ok(),
+    Ok(Object::Array(arr)) => Some(arr),
+    _ => None,
+  };
+  annots
+    .into_iter()
+    .flatten()
+    .filter_map(|a| {
+      let id = a.as_reference().ok()?;
+      Some((id, doc.get_dictionary(id).ok()?))
+    })
+    .collect()
+}
+
+pub(crate) fn format_object_id(id: ObjectId) -> String {
+  format!("{} {} obj", id.0, id.1)
+}
+
+/// Which optional `PageAnnotation` fields to fill, resolved from
+/// `AnnotationOptions`.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct AnnotationConfig {
+  pub rendered_text: bool,
+}
+
 fn collect_page_annotations(
   doc: &Document,
   page_id: ObjectId,
   page_num: u32,
 ) -> Vec<PageAnnotation> {
-  let annots = match doc.get_page_annotations(page_id) {
-    Ok(a) => a,
-    Err(_) => return Vec::new(),
-  };
+  collect_page_annotations_with_config(doc, page_id, page_num, AnnotationConfig::default())
+}
 
+fn collect_page_annotations_with_config(
+  doc: &Document,
+  page_id: ObjectId,
+  page_num: u32,
+  config: AnnotationConfig,
+) -> Vec<PageAnnotation> {
   let mut results = Vec::new();
-  for annot in annots {
+  for (annot_id, annot) in page_annotations(doc, page_id) {
     let subtype = annot
       .get(b"Subtype")
       .ok()
@@ -57,65 +99,934 @@ fn collect_page_annotations(
       })
       .unwrap_or_default();
 
-    // Extract URI from /A action dictionary
-    let uri = annot.get(b"A").ok().and_then(|action| {
-      let action_dict = match action {
-        Object::Dictionary(d) => Some(d),
-        Object::Reference(id) => doc.get_dictionary(*id).ok(),
+    // The /A action dictionary, if any
+    let action = annot.get(b"A").ok().and_then(|action| match action {
+      Object::Dictionary(d) => Some(d),
+      Object::Reference(id) => doc.get_dictionary(*id).ok(),
+      _ => None,
+    });
+    let action_name = action.and_then(|a| {
+      let name = a.get(b"S").ok()?.as_name().ok()?;
+      Some(String::from_utf8_lossy(name).to_string())
+    });
+
+    let uri = action.and_then(|a| match a.get(b"URI").ok()? {
+      Object::String(bytes, _) => Some(decode_pdf_string(bytes)),
+      _ => None,
+    });
+
+    // Extract /Dest (named or direct destination), else a named /D on a
+    // GoTo or GoToR action
+    let dest = annot
+      .get(b"Dest")
+      .ok()
+      .and_then(destination_name)
+      .or_else(|| match action_name.as_deref() {
+        Some("GoTo" | "GoToR") => action
+          .and_then(|a| a.get(b"D").ok())
+          .and_then(destination_name),
         _ => None,
-      }?;
-      let uri_obj = action_dict.get(b"URI").ok()?;
-      match uri_obj {
-        Object::String(bytes, _) => Some(String::from_utf8_lossy(bytes).to_string()),
+      });
+
+    // Explicit destination array: [page /XYZ left top zoom], [page /Fit], ...
+    let dest_array = annot
+      .get(b"Dest")
+      .ok()
+      .and_then(|d| destination_array(doc, d, true))
+      .or_else(|| match action_name.as_deref() {
+        Some(name @ ("GoTo" | "GoToR")) => action
+          .and_then(|a| a.get(b"D").ok())
+          .and_then(|d| destination_array(doc, d, name == "GoTo")),
         _ => None,
-      }
+      });
+    let dest_fit = dest_array
+      .and_then(|arr| arr.get(1)?.as_name().ok())
+      .map(|fit| String::from_utf8_lossy(fit).to_string());
+    let dest_params = dest_array.filter(|_| dest_fit.is_some()).map(|arr| {
+      arr[2..]
+        .iter()
+        .map(|o| match o {
+          Object::Integer(i) => Some(*i as f64),
+          Object::Real(f) => Some(*f as f64),
+          _ => None,
+        })
+        .collect()
     });
 
-    // Extract /Dest (named or direct destination)
-    let dest = annot.get(b"Dest").ok().and_then(|d| match d {
-      Object::String(bytes, _) => Some(String::from_utf8_lossy(bytes).to_string()),
-      Object::Name(n) => Some(String::from_utf8_lossy(n).to_string()),
+    let remote_file = match action_name.as_deref() {
+      Some("GoToR" | "Launch") => action
+        .and_then(|a| a.get(b"F").ok())
+        .and_then(|f| file_spec_name(doc, f)),
       _ => None,
-    });
+    };
 
-    // Extract /Contents (tooltip / alt text)
-    let content = annot.get(b"Contents").ok().and_then(|c| match c {
-      Object::String(bytes, _) => Some(String::from_utf8_lossy(bytes).to_string()),
+    // A bare /Dest is shorthand for a GoTo action
+    let action_type = match action_name {
+      Some(name) => name,
+      None if uri.is_some() => "URI".to_string(),
+      None if annot.has(b"Dest") => "GoTo".to_string(),
+      None => String::new(),
+    };
+
+    // Extract /Contents (tooltip / alt text) and /T (author of a markup
+    // annotation)
+    let text_entry = |key: &[u8]| match annot.get(key).ok()? {
+      Object::String(bytes, _) => Some(decode_pdf_string(bytes)),
       _ => None,
-    });
+    };
+    let content = text_entry(b"Contents");
+    let author = text_entry(b"T");
+    let overlay_text = text_entry(b"OverlayText");
+
+    // Comment threads: a Popup's /Parent is the markup annotation it belongs
+    // to, and /IRT points a reply at the annotation it answers
+    let reference = |key: &[u8]| annot.get(key).and_then(Object::as_reference).ok();
+    let popup_for = reference(b"Parent")
+      .filter(|_| subtype == "Popup")
+      .map(format_object_id);
+    let in_reply_to = reference(b"IRT").map(format_object_id);
+    let open = annot.get(b"Open").and_then(Object::as_bool).ok();
+    let media = annotation_media(doc, annot, &subtype);
+
+    // Regions of text markup and redaction annotations: 8 numbers per quad
+    let quad_points = annot
+      .get(b"QuadPoints")
+      .ok()
+      .and_then(|q| deref_array(doc, q))
+      .map(|arr| {
+        arr
+          .iter()
+          .filter_map(|o| match o {
+            Object::Real(f) => Some(*f as f64),
+            Object::Integer(i) => Some(*i as f64),
+            _ => None,
+          })
+          .collect::<Vec<f64>>()
+      })
+      .filter(|quads| !quads.is_empty() && quads.len() % 8 == 0);
 
     results.push(PageAnnotation {
       page: page_num,
+      object_id: format_object_id(annot_id),
       subtype,
       rect,
       uri,
       dest,
       content,
+      author,
+      action_type,
+      remote_file,
+      dest_fit,
+      dest_params,
+      quad_points,
+      overlay_text,
+      popup_for,
+      in_reply_to,
+      open,
+      media_type: media.as_ref().and_then(|m| m.media_type.clone()),
+      media_file: media.as_ref().and_then(|m| m.file_name.clone()),
+      media_object_id: media.and_then(|m| m.file_id).map(format_object_id),
+      rendered_text: config
+        .rendered_text
+        .then(|| appearance_stream(doc, annot).and_then(|ap| form_xobject_text(doc, ap)))
+        .flatten(),
     });
   }
 
   results
 }
 
+/// The normal (`/AP /N`) appearance an annotation is drawn with; for an
+/// appearance with several states, the one selected by `/AS`.
+fn appearance_stream<'a>(
+  doc: &'a Document,
+  annot: &'a lopdf::Dictionary,
+) -> Option<&'a lopdf::Stream> {
+  let normal = deref_dict(doc, annot.get(b"AP").ok()?)?.get(b"N").ok()?;
+  match deref(doc, normal)? {
+    Object::Stream(stream) => Some(stream),
+    Object::Dictionary(states) => {
+      let state = annot.get(b"AS").and_then(Object::as_name).ok()?;
+      doc
+        .get_object(states.get(state).ok()?.as_reference().ok()?)
+        .and_then(Object::as_stream)
+        .ok()
+    }
+    _ => None,
+  }
+}
+
+/// Media played by a `Screen` or `RichMedia` annotation.
+struct AnnotationMedia {
+  media_type: Option<String>,
+  file_name: Option<String>,
+  /// The embedded file stream (`/EF`) of the file specification.
+  file_id: Option<ObjectId>,
+}
+
+/// The media of a `Screen` annotation (through its Rendition action's media
+/// clip) or a `RichMedia` annotation (its first configuration's first
+/// instance, else its first asset).
+fn annotation_media(
+  doc: &Document,
+  annot: &lopdf::Dictionary,
+  subtype: &str,
+) -> Option<AnnotationMedia> {
+  let (spec, clip_type, config_type) = match subtype {
+    "Screen" => {
+      let action = deref_dict(doc, annot.get(b"A").ok()?)?;
+      if action.get(b"S").and_then(Object::as_name).ok()? != b"Rendition" {
+        return None;
+      }
+      let mut rendition = deref_dict(doc, action.get(b"R").ok()?)?;
+      // A selector rendition lists alternatives; take the first
+      if rendition.get(b"S").and_then(Object::as_name).ok() == Some(b"SR") {
+        let first = rendition
+          .get(b"R")
+          .and_then(Object::as_array)
+          .ok()?
+          .first()?;
+        rendition = deref_dict(doc, first)?;
+      }
+      let clip = deref_dict(doc, rendition.get(b"C").ok()?)?;
+      let clip_type = match clip.get(b"CT") {
+        Ok(Object::String(bytes, _)) => Some(decode_pdf_string(bytes)),
+        _ => None,
+      };
+      (clip.get(b"D").ok(), clip_type, None)
+    }
+    "RichMedia" => {
+      let content = deref_dict(doc, annot.get(b"RichMediaContent").ok()?)?;
+      let config = content
+        .get(b"Configurations")
+        .ok()
+        .and_then(|c| deref_array(doc, c))
+        .and_then(|configs| deref_dict(doc, configs.first()?));
+      let instance_asset = config
+        .and_then(|c| c.get(b"Instances").ok())
+        .and_then(|i| deref_array(doc, i))
+        .and_then(|instances| deref_dict(doc, instances.first()?))
+        .and_then(|instance| instance.get(b"Asset").ok());
+      let first_asset = || {
+        let assets = deref_dict(doc, content.get(b"Assets").ok()?)?;
+        assets.get(b"Names").and_then(Object::as_array).ok()?.get(1)
+      };
+      let config_type = config
+        .and_then(|c| c.get(b"Subtype").and_then(Object::as_name).ok())
+        .map(|t| String::from_utf8_lossy(t).to_string());
+      (instance_asset.or_else(first_asset), None, config_type)
+    }
+    _ => return None,
+  };
+
+  let file_name = spec.and_then(|s| file_spec_name(doc, s));
+  let file_id = spec
+    .and_then(|s| deref_dict(doc, s))
+    .and_then(|s| s.get(b"EF").ok())
+    .and_then(|ef| deref_dict(doc, ef))
+    .and_then(|ef| ef.get(b"UF").or_else(|_| ef.get(b"F")).ok())
+    .and_then(|f| f.as_reference().ok());
+  let file_type = file_id
+    .and_then(|id| doc.get_object(id).ok())
+    .and_then(|o| o.as_stream().ok())
+    .and_then(|s| s.dict.get(b"Subtype").and_then(Object::as_name).ok())
+    .map(|t| String::from_utf8_lossy(t).to_string());
+  Some(AnnotationMedia {
+    media_type: clip_type.or(file_type).or(config_type),
+    file_name,
+    file_id,
+  })
+}
+
+fn destination_name(dest: &Object) -> Option<String> {
+  match dest {
+    Object::String(bytes, _) => Some(String::from_utf8_lossy(bytes).to_string()),
+    Object::Name(n) => Some(String::from_utf8_lossy(n).to_string()),
+    _ => None,
+  }
+}
+
+/// The explicit destination array behind `dest`: direct, wrapped in a `/D`
+/// dictionary, or (when `resolve_names` is set) looked up by name in the
+/// catalog's `/Dests` dictionary or `/Names` → `/Dests` name tree.
+fn destination_array<'a>(
+  doc: &'a Document,
+  dest: &'a Object,
+  resolve_names: bool,
+) -> Option<&'a Vec<Object>> {
+  match deref(doc, dest)? {
+    Object::Array(arr) => Some(arr),
+    Object::Dictionary(d) => destination_array(doc, d.get(b"D").ok()?, false),
+    Object::Name(name) | Object::String(name, _) if resolve_names => {
+      destination_array(doc, named_destination(doc, name)?, false)
+    }
+    _ => None,
+  }
+}
+
+fn named_destination<'a>(doc: &'a Document, name: &[u8]) -> Option<&'a Object> {
+  let catalog = doc.catalog().ok()?;
+  let from_dests = catalog
+    .get(b"Dests")
+    .ok()
+    .and_then(|o| deref_dict(doc, o))
+    .and_then(|dests| dests.get(name).ok());
+  from_dests.or_else(|| {
+    let names = deref_dict(doc, catalog.get(b"Names").ok()?)?;
+    let tree = deref_dict(doc, names.get(b"Dests").ok()?)?;
+    name_tree_lookup(doc, tree, name, 0)
+  })
+}
+
+/// How deep name tree `/Kids` are followed before giving up.
+const MAX_NAME_TREE_DEPTH: u32 = 32;
+
+/// Find `key` in a name tree, descending only into kids whose `/Limits`
+/// cover it.
+fn name_tree_lookup<'a>(
+  doc: &'a Document,
+  node: &'a lopdf::Dictionary,
+  key: &[u8],
+  depth: u32,
+) -> Option<&'a Object> {
+  if depth > MAX_NAME_TREE_DEPTH {
+    return None;
+  }
+  if let Ok(names) = node.get(b"Names").and_then(Object::as_array) {
+    return names
+      .chunks_exact(2)
+      .find(|pair| pair[0].as_str().is_ok_and(|k| k == key))
+      .map(|pair| &pair[1]);
+  }
+  let kids = node.get(b"Kids").and_then(Object::as_array).ok()?;
+  kids.iter().find_map(|kid| {
+    let kid = deref_dict(doc, kid)?;
+    if let Ok(limits) = kid.get(b"Limits").and_then(Object::as_array)
+      && let (Some(Ok(lo)), Some(Ok(hi))) = (
+        limits.first().map(Object::as_str),
+        limits.get(1).map(Object::as_str),
+      )
+      && !(lo..=hi).contains(&key)
+    {
+      return None;
+    }
+    name_tree_lookup(doc, kid, key, depth + 1)
+  })
+}
+
+/// Append every key/value pair of a name tree to `out`, in key order.
+/// `seen` holds the kid nodes already visited so a cyclic tree is walked
+/// once.
+fn name_tree_entries<'a>(
+  doc: &'a Document,
+  node: &'a lopdf::Dictionary,
+  depth: u32,
+  seen: &mut HashSet<ObjectId>,
+  out: &mut Vec<(&'a [u8], &'a Object)>,
+) {
+  if depth > MAX_NAME_TREE_DEPTH {
+    return;
+  }
+  if let Ok(names) = node.get(b"Names").and_then(Object::as_array) {
+    out.extend(
+      names
+        .chunks_exact(2)
+        .filter_map(|pair| Some((pair[0].as_str().ok()?, &pair[1]))),
+    );
+  }
+  if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+    for kid in kids {
+      if let Ok(id) = kid.as_reference()
+        && !seen.insert(id)
+      {
+        continue;
+      }
+      if let Some(kid) = deref_dict(doc, kid) {
+        name_tree_entries(doc, kid, depth + 1, seen, out);
+      }
+    }
+  }
+}
+
+/// Every named destination with the page it targets, from the catalog's
+/// `/Dests` dictionary and the `/Names` → `/Dests` name tree. Destinations
+/// whose page can't be resolved are skipped.
+pub(crate) fn named_destinations(doc: &Document) -> Vec<(String, ObjectId)> {
+  let Ok(catalog) = doc.catalog() else {
+    return Vec::new();
+  };
+  let mut entries: Vec<(String, &Object)> = Vec::new();
+  if let Some(dests) = catalog.get(b"Dests").ok().and_then(|o| deref_dict(doc, o)) {
+    entries.extend(
+      dests
+        .iter()
+        .map(|(name, dest)| (String::from_utf8_lossy(name).to_string(), dest)),
+    );
+  }
+  if let Some(tree) = catalog
+    .get(b"Names")
+    .ok()
+    .and_then(|o| deref_dict(doc, o))
+    .and_then(|names| deref_dict(doc, names.get(b"Dests").ok()?))
+  {
+    let mut pairs = Vec::new();
+    name_tree_entries(doc, tree, 0, &mut HashSet::new(), &mut pairs);
+    entries.extend(
+      pairs
+        .into_iter()
+        .map(|(name, dest)| (decode_pdf_string(name), dest)),
+    );
+  }
+  entries
+    .into_iter()
+    .filter_map(|(name, dest)| Some((name, destination_page(doc, dest)?)))
+    .collect()
+}
+
+/// The page object an explicit or named destination points to.
+pub(crate) fn destination_page(doc: &Document, dest: &Object) -> Option<ObjectId> {
+  destination_array(doc, dest, true)?
+    .first()?
+    .as_reference()
+    .ok()
+}
+
+/// The file named by a file specification: either a plain string or a
+/// dictionary, preferring its Unicode `/UF` entry over `/F`.
+fn file_spec_name(doc: &Document, spec: &Object) -> Option<String> {
+  let bytes = match deref(doc, spec)? {
+    Object::String(bytes, _) => bytes,
+    Object::Dictionary(d) => [b"UF".as_slice(), b"F", b"Unix", b"DOS", b"Mac"]
+      .iter()
+      .find_map(|key| match d.get(key).ok()? {
+        Object::String(bytes, _) => Some(bytes),
+        _ => None,
+      })?,
+    _ => return None,
+  };
+  Some(decode_pdf_string(bytes))
+}
+
 pub(crate) fn extract_annotations(doc: &Document) -> Vec<PageAnnotation> {
+  extract_annotations_with_config(doc, AnnotationConfig::default())
+}
+
+pub(crate) fn extract_annotations_with_config(
+  doc: &Document,
+  config: AnnotationConfig,
+) -> Vec<PageAnnotation> {
   let pages = doc.get_pages();
   let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
   let mut results: Vec<PageAnnotation> = page_entries
     .par_iter()
-    .flat_map(|&(page_num, page_id)| collect_page_annotations(doc, page_id, page_num))
+    .flat_map(|&(page_num, page_id)| {
+      collect_page_annotations_with_config(doc, page_id, page_num, config)
+    })
     .collect();
   results.sort_unstable_by_key(|a| a.page);
   results
 }
 
-pub(crate) fn extract_all(doc: &Document) -> Result<RawPdfDocument> {
+/// Per page, the number of annotations and the union of their `/Rect`s.
+/// Every page is listed, including those without annotations.
+pub(crate) fn collect_annotation_summary(doc: &Document) -> Vec<AnnotationSummary> {
+  let pages: Vec<(u32, ObjectId)> = doc.get_pages().into_iter().collect();
+  pages
+    .par_iter()
+    .map(|&(page, page_id)| {
+      let annots = collect_page_annotations(doc, page_id, page);
+      let bounds = annots
+        .iter()
+        .filter_map(|annot| rect_bounds(&annot.rect))
+        .reduce(|a, b| {
+          [
+            a[0].min(b[0]),
+            a[1].min(b[1]),
+            a[2].max(b[2]),
+            a[3].max(b[3]),
+          ]
+        });
+      AnnotationSummary {
+        page,
+        annotation_count: annots.len() as u32,
+        annotation_bounds: bounds.map(Vec::from),
+      }
+    })
+    .collect()
+}
+
+/// A `/Rect` as read into `PageAnnotation::rect`, normalized to
+/// `[left, bottom, right, top]`.
+fn rect_bounds(rect: &[f64]) -> Option<[f64; 4]> {
+  let &[x1, y1, x2, y2] = rect else {
+    return None;
+  };
+  Some([x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)])
+}
+
+/// The regions a markup annotation covers, as `[left, bottom, right, top]`:
+/// the bounds of each `/QuadPoints` quadrilateral, else its `/Rect`.
+fn annotation_quads(annot: &PageAnnotation) -> Vec<[f64; 4]> {
+  let Some(points) = &annot.quad_points else {
+    return rect_bounds(&annot.rect).into_iter().collect();
+  };
+  points
+    .chunks_exact(8)
+    .map(|quad| {
+      let xs = quad.iter().step_by(2);
+      let ys = quad.iter().skip(1).step_by(2);
+      [
+        xs.clone().copied().fold(f64::MAX, f64::min),
+        ys.clone().copied().fold(f64::MAX, f64::min),
+        xs.copied().fold(f64::MIN, f64::max),
+        ys.copied().fold(f64::MIN, f64::max),
+      ]
+    })
+    .collect()
+}
+
+/// The page text under every `Highlight` annotation: the glyphs whose centre
+/// falls inside its `/QuadPoints` (or `/Rect`), read in line order.
+/// `annotation_index` is the highlight's position in the page's `/Annots`.
+/// Highlights over no text are listed with empty text.
+pub(crate) fn collect_highlighted_text(doc: &Document) -> Vec<HighlightedText> {
+  let pages: Vec<(u32, ObjectId)> = doc.get_pages().into_iter().collect();
+  pages
+    .par_iter()
+    .flat_map_iter(|&(page, page_id)| {
+      let highlights: Vec<(usize, Vec<[f64; 4]>)> = collect_page_annotations(doc, page_id, page)
+        .iter()
+        .enumerate()
+        .filter(|(_, annot)| annot.subtype == "Highlight")
+        .map(|(index, annot)| (index, annotation_quads(annot)))
+        .collect();
+      // Only interpret the page when something on it is highlighted
+      let glyphs = if highlights.is_empty() {
+        Vec::new()
+      } else {
+        extract_page_glyphs(doc, page_id)
+      };
+      highlights
+        .into_iter()
+        .map(move |(index, regions)| HighlightedText {
+          page,
+          annotation_index: index as u32,
+          text: text_within(&glyphs, &regions),
+        })
+    })
+    .collect()
+}
+
+/// `http(s)://` URLs in plain text. A URL runs to the next whitespace or
+/// bracket/quote delimiter; trailing sentence punctuation and an unbalanced
+/// closing parenthesis are dropped.
+pub(crate) fn find_urls(text: &str) -> Vec<&str> {
+  let mut urls = Vec::new();
+  let mut rest = text;
+  while let Some(start) = ["http://", "https://"]
+    .iter()
+    .filter_map(|scheme| rest.find(scheme))
+    .min()
+  {
+    let candidate = &rest[start..];
+    let end = candidate
+      .find(|c: char| c.is_whitespace() || "<>\"'{}|\\^`[]".contains(c))
+      .unwrap_or(candidate.len());
+    let mut url = &candidate[..end];
+    loop {
+      let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+      let trimmed = match trimmed.strip_suffix(')') {
+        Some(inner) if inner.matches('(').count() < inner.matches(')').count() + 1 => inner,
+        _ => trimmed,
+      };
+      if trimmed.len() == url.len() {
+        break;
+      }
+      url = trimmed;
+    }
+    if url.len() > url.find("://").unwrap_or(0) + 3 {
+      urls.push(url);
+    }
+    rest = &candidate[end.max(1)..];
+  }
+  urls
+}
+
+/// Push the `/URI` of every URI action dictionary inside `obj`, descending
+/// into nested dictionaries, arrays and stream dictionaries (not references).
+fn collect_uri_actions(obj: &Object, out: &mut Vec<String>) {
+  match obj {
+    Object::Dictionary(d) => {
+      if d
+        .get(b"S")
+        .and_then(Object::as_name)
+        .is_ok_and(|s| s == b"URI")
+        && let Ok(Object::String(uri, _)) = d.get(b"URI")
+      {
+        out.push(decode_pdf_string(uri));
+      }
+      for (_, value) in d.iter() {
+        collect_uri_actions(value, out);
+      }
+    }
+    Object::Array(items) => items.iter().for_each(|o| collect_uri_actions(o, out)),
+    Object::Stream(stream) => {
+      for (_, value) in stream.dict.iter() {
+        collect_uri_actions(value, out);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Every URI in the document: link annotations (`annotation`), URI actions
+/// anywhere else such as outlines, open actions, form fields or name trees
+/// (`action`, no page), and URLs in page text (`text`). A URI is listed once
+/// per page; an action URI already found on a link annotation isn't
+/// repeated. Ordered by page, with page-less action URIs last.
+pub(crate) fn collect_uris(doc: &Document) -> Result<Vec<UriRef>> {
+  let (annotations, text) = rayon::join(|| extract_annotations(doc), || extract_text(doc));
+  let text = text?;
+
+  let mut found: Vec<UriRef> = Vec::new();
+  let mut seen: HashSet<(String, Option<u32>)> = HashSet::new();
+  let mut push = |uri: String, page: Option<u32>, source: &str, found: &mut Vec<UriRef>| {
+    if seen.insert((uri.clone(), page)) {
+      found.push(UriRef {
+        uri,
+        page,
+        source: source.to_string(),
+      });
+    }
+  };
+  // Per page: annotation URIs first, then text URLs
+  let mut by_page: HashMap<u32, Vec<String>> = HashMap::new();
+  for annot in annotations {
+    if let Some(uri) = annot.uri {
+      by_page.entry(annot.page).or_default().push(uri);
+    }
+  }
+  for page in &text {
+    for uri in by_page.remove(&page.page).unwrap_or_default() {
+      push(uri, Some(page.page), "annotation", &mut found);
+    }
+    for url in find_urls(&page.text) {
+      push(url.to_string(), Some(page.page), "text", &mut found);
+    }
+  }
+
+  let listed: HashSet<String> = found
+    .iter()
+    .filter(|u| u.source == "annotation")
+    .map(|u| u.uri.clone())
+    .collect();
+  let mut actions = Vec::new();
+  for object in doc.objects.values() {
+    collect_uri_actions(object, &mut actions);
+  }
+  for uri in actions {
+    if !listed.contains(&uri) {
+      push(uri, None, "action", &mut found);
+    }
+  }
+  Ok(found)
+}
+
+/// Document-level additional-action triggers (`/AA` in the catalog, ISO
+/// 32000-1 table 197) and the names they are reported under.
+const DOCUMENT_TRIGGERS: &[(&[u8], &str)] = &[
+  (b"WC", "WillClose"),
+  (b"WS", "WillSave"),
+  (b"DS", "DidSave"),
+  (b"WP", "WillPrint"),
+  (b"DP", "DidPrint"),
+];
+
+/// Actions followed through `/Next` chains before giving up.
+const MAX_ACTION_CHAIN: usize = 64;
+
+/// The most telling parameter of an action: the script of `JavaScript`, the
+/// target of `URI`, `GoTo` (a destination name or `page N`), `GoToR`,
+/// `Launch` and `SubmitForm`, or the `Named` action to run.
+fn action_detail(
+  doc: &Document,
+  action: &lopdf::Dictionary,
+  action_type: &str,
+  page_nums: &HashMap<ObjectId, u32>,
+) -> Option<String> {
+  let text = |obj: &Object| match obj {
+    Object::Reference(id) => match doc.get_object(*id).ok()? {
+      Object::String(bytes, _) => Some(decode_pdf_string(bytes)),
+      Object::Stream(s) => Some(decode_pdf_string(
+        &s.decompressed_content()
+          .unwrap_or_else(|_| s.content.clone()),
+      )),
+      _ => None,
+    },
+    Object::String(bytes, _) => Some(decode_pdf_string(bytes)),
+    _ => None,
+  };
+  match action_type {
+    "JavaScript" => text(action.get(b"JS").ok()?),
+    "URI" => text(action.get(b"URI").ok()?),
+    "GoTo" => goto_detail(doc, action.get(b"D").ok()?, page_nums),
+    "GoToR" | "GoToE" | "Launch" | "SubmitForm" => file_spec_name(doc, action.get(b"F").ok()?),
+    "Named" => action
+      .get(b"N")
+      .and_then(Object::as_name)
+      .ok()
+      .map(|n| String::from_utf8_lossy(n).to_string()),
+    _ => None,
+  }
+}
+
+/// A `GoTo` target: the destination's name, or `page N` for an explicit one.
+fn goto_detail(
+  doc: &Document,
+  dest: &Object,
+  page_nums: &HashMap<ObjectId, u32>,
+) -> Option<String> {
+  destination_name(dest).or_else(|| {
+    let page = destination_array(doc, dest, false)?
+      .first()?
+      .as_reference()
+      .ok()?;
+    Some(format!("page {}", page_nums.get(&page)?))
+  })
+}
+
+/// Append `action` and everything reachable through its `/Next` entries.
+fn push_action_chain(
+  doc: &Document,
+  trigger: &str,
+  action: &Object,
+  page_nums: &HashMap<ObjectId, u32>,
+  out: &mut Vec<DocumentAction>,
+) {
+  let mut queue = vec![action];
+  let mut seen = HashSet::new();
+  while let Some(next) = queue.pop() {
+    if seen.len() >= MAX_ACTION_CHAIN {
+      break;
+    }
+    if let Object::Reference(id) = next
+      && !seen.insert(*id)
+    {
+      continue;
+    }
+    let Some(action) = deref_dict(doc, next) else {
+      continue;
+    };
+    let action_type = action
+      .get(b"S")
+      .and_then(Object::as_name)
+      .map(|s| String::from_utf8_lossy(s).to_string())
+      .unwrap_or_default();
+    out.push(DocumentAction {
+      trigger: trigger.to_string(),
+      detail: action_detail(doc, action, &action_type, page_nums),
+      action_type,
+    });
+    // Run in array order, so push the array reversed onto the stack
+    match action.get(b"Next") {
+      Ok(Object::Array(items)) => queue.extend(items.iter().rev()),
+      Ok(other) => queue.push(other),
+      Err(_) => {}
+    }
+  }
+}
+
+/// Everything the document does by itself: the `/OpenAction` (trigger
+/// `Open`) and the catalog's additional actions (`WillClose`, `WillSave`,
+/// `DidSave`, `WillPrint`, `DidPrint`), with `/Next` chains expanded in
+/// order. An `/OpenAction` that is a bare destination reports `GoTo`.
+pub(crate) fn collect_document_actions(doc: &Document) -> Vec<DocumentAction> {
+  let mut actions = Vec::new();
+  let Ok(catalog) = doc.catalog() else {
+    return actions;
+  };
+  let page_nums: HashMap<ObjectId, u32> = doc
+    .get_pages()
+    .into_iter()
+    .map(|(num, id)| (id, num))
+    .collect();
+
+  if let Ok(open) = catalog.get(b"OpenAction") {
+    if let Some(Object::Array(_)) = deref(doc, open) {
+      actions.push(DocumentAction {
+        trigger: "Open".to_string(),
+        action_type: "GoTo".to_string(),
+        detail: goto_detail(doc, open, &page_nums),
+      });
+    } else {
+      push_action_chain(doc, "Open", open, &page_nums, &mut actions);
+    }
+  }
+
+  if let Some(aa) = catalog.get(b"AA").ok().and_then(|o| deref_dict(doc, o)) {
+    for (key, trigger) in DOCUMENT_TRIGGERS {
+      if let Ok(action) = aa.get(key) {
+        push_action_chain(doc, trigger, action, &page_nums, &mut actions);
+      }
+    }
+  }
+  actions
+}
+
+/// Flag active content inside `obj`, descending into nested dictionaries,
+/// arrays and stream dictionaries (not references): actions with a script
+/// (`/S /JavaScript`, or `/JS` on e.g. a Rendition action), `Launch` actions,
+/// and embedded file streams or file specifications carrying one (`/EF`).
+fn scan_active_content(obj: &Object, report: &mut CleanReport) {
+  let dict = match obj {
+    Object::Dictionary(d) => d,
+    Object::Stream(stream) => &stream.dict,
+    Object::Array(items) => {
+      items.iter().for_each(|o| scan_active_content(o, report));
+      return;
+    }
+    _ => return,
+  };
+  let name = |key: &[u8]| dict.get(key).and_then(Object::as_name).ok();
+  match name(b"S") {
+    Some(b"JavaScript") => report.has_javascript = true,
+    Some(b"Launch") => report.has_launch_action = true,
+    _ => {}
+  }
+  if dict.has(b"JS") {
+    report.has_javascript = true;
+  }
+  if name(b"Type") == Some(b"EmbeddedFile") || dict.has(b"EF") {
+    report.has_embedded_files = true;
+  }
+  for (_, value) in dict.iter() {
+    scan_active_content(value, report);
+  }
+}
+
+/// Whether the document carries active content anywhere — JavaScript,
+/// `Launch` actions, embedded files — and whether it has an `/OpenAction`.
+/// Every object is scanned, so actions on annotations, form fields, outlines
+/// and name trees count as well as document-level ones.
+pub(crate) fn collect_clean_report(doc: &Document) -> CleanReport {
+  let mut report = CleanReport {
+    has_javascript: false,
+    has_launch_action: false,
+    has_embedded_files: false,
+    has_open_action: doc.catalog().is_ok_and(|c| c.has(b"OpenAction")),
+    is_clean: false,
+  };
+  for object in doc.objects.values() {
+    scan_active_content(object, &mut report);
+  }
+  report.is_clean =
+    !(report.has_javascript || report.has_launch_action || report.has_embedded_files);
+  report
+}
+
+/// Which optional stages `extract_all` runs. Text and metadata always run.
+#[derive(Clone, Copy)]
+pub(crate) struct DocumentConfig {
+  pub include_images: bool,
+  pub include_annotations: bool,
+  pub include_structured_text: bool,
+  pub reindex_pages: bool,
+  pub include_full_text: bool,
+}
+
+impl Default for DocumentConfig {
+  fn default() -> Self {
+    DocumentConfig {
+      include_images: true,
+      include_annotations: true,
+      include_structured_text: true,
+      reindex_pages: false,
+      include_full_text: false,
+    }
+  }
+}
+
+/// What `stream_pages` extracts besides text, resolved from
+/// `StreamPagesOptions`.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct StreamPagesConfig {
+  pub include_images: bool,
+  pub include_annotations: bool,
+}
+
+/// Extract every page on the rayon pool and hand each one to `emit` as soon
+/// as it is done. Pages arrive in completion order, not page order; text is
+/// cleaned up as in `extract_text`.
+pub(crate) fn stream_pages(
+  doc: &Document,
+  config: StreamPagesConfig,
+  emit: impl Fn(RawPageChunk) + Sync,
+) {
+  let pages = doc.get_pages();
+  let page_count_str = pages.len().to_string();
+  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
+  page_entries.par_iter().for_each(|&(page_num, page_id)| {
+    let raw = extract_page_text_raw(doc, page_num, page_id);
+    emit(RawPageChunk {
+      page: page_num,
+      text: strip_footer_artifacts(&raw, &page_count_str).into_owned(),
+      images: config
+        .include_images
+        .then(|| extract_page_images_raw(doc, page_id, page_num)),
+      annotations: config
+        .include_annotations
+        .then(|| collect_page_annotations(doc, page_id, page_num)),
+    });
+  });
+}
+
+/// Extract everything `config` asks for. With `reindex_pages`, a broken
+/// page tree is repaired first: in place when `doc` isn't shared, otherwise
+/// on a copy, so a `PdfDown` keeps its own numbering.
+pub(crate) fn extract_all(
+  mut doc: Arc<Document>,
+  config: DocumentConfig,
+) -> Result<RawPdfDocument> {
+  let mut reindexed_pages = None;
+  if config.reindex_pages
+    && let Some(repairs) = page_tree_repairs(&doc)
+  {
+    reindexed_pages = Some(repairs.apply(Arc::make_mut(&mut doc)));
+  }
+  let doc = doc.as_ref();
   let meta = extract_metadata(doc);
   let ((text, images), annotations) = rayon::join(
-    || rayon::join(|| extract_text(doc), || extract_images_raw(doc)),
-    || extract_annotations(doc),
+    || {
+      rayon::join(
+        || extract_text(doc),
+        || {
+          if config.include_images {
+            extract_images_raw(doc)
+          } else {
+            Vec::new()
+          }
+        },
+      )
+    },
+    || {
+      if config.include_annotations {
+        extract_annotations(doc)
+      } else {
+        Vec::new()
+      }
+    },
   );
   let text = text?;
-  let structured_text = detect_headers_footers(&text);
+  let structured_text = if config.include_structured_text {
+    let mut structured = detect_headers_footers(&text);
+    attach_text_coverage(doc, &mut structured);
+    structured
+  } else {
+    Vec::new()
+  };
+  let full_text = config
+    .include_full_text
+    .then(|| join_pages_with_markers(&text, DEFAULT_PAGE_MARKER));
 
   let mut image_pages: Vec<u32> = images
     .iter()
@@ -141,14 +1052,18 @@ pub(crate) fn extract_all(doc: &Document) -> Result<RawPdfDocument> {
     annotations,
     image_pages,
     annotation_pages,
+    reindexed_pages,
+    full_text,
   })
 }
 
 #[cfg(feature = "ocr")]
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn extract_all_with_ocr(
   doc: &Document,
   pdf_bytes: &[u8],
   lang: &str,
+  tessdata_path: Option<&str>,
   min_len: u32,
   max_threads: u32,
   render_dpi: u32,
@@ -161,6 +1076,7 @@ pub(crate) fn extract_all_with_ocr(
         doc,
         pdf_bytes,
         lang,
+        tessdata_path,
         min_len,
         max_threads,
         render_dpi,
@@ -169,7 +1085,11 @@ pub(crate) fn extract_all_with_ocr(
     },
     || rayon::join(|| extract_images_raw(doc), || extract_annotations(doc)),
   );
-  let text = text?;
+  // OCR problems never fail the whole document — fall back to native text
+  let (text, ocr_available) = match text {
+    Ok(ocr) => (ocr.pages, ocr.ocr_available),
+    Err(_) => (native_ocr_pages(doc), false),
+  };
   let structured_text = detect_headers_footers_ocr(&text);
 
   let mut image_pages: Vec<u32> = images
@@ -190,6 +1110,7 @@ pub(crate) fn extract_all_with_ocr(
 
   Ok(RawPdfDocumentOcr {
     meta,
+    ocr_available,
     text,
     structured_text,
     images,
@@ -198,3 +1119,693 @@ pub(crate) fn extract_all_with_ocr(
     annotation_pages,
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::test_docs::{doc_with_pages, helvetica};
+  use lopdf::{StringFormat, dictionary};
+
+  fn literal(s: &str) -> Object {
+    Object::String(s.as_bytes().to_vec(), StringFormat::Literal)
+  }
+
+  /// Add `annots` as objects and list them in the page's `/Annots`.
+  fn set_annots(
+    doc: &mut Document,
+    page_id: ObjectId,
+    annots: Vec<lopdf::Dictionary>,
+  ) -> Vec<ObjectId> {
+    let ids: Vec<ObjectId> = annots.into_iter().map(|a| doc.add_object(a)).collect();
+    let refs: Vec<Object> = ids.iter().map(|&id| id.into()).collect();
+    doc.get_dictionary_mut(page_id).unwrap().set("Annots", refs);
+    ids
+  }
+
+  #[test]
+  fn link_actions_report_type_and_remote_file() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    let link = |action: lopdf::Dictionary| {
+      dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Link",
+        "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+        "A" => action,
+      }
+    };
+    set_annots(
+      &mut doc,
+      pages[0],
+      vec![
+        link(dictionary! { "S" => "URI", "URI" => literal("https://example.com") }),
+        link(dictionary! {
+          "S" => "GoToR",
+          "F" => dictionary! { "Type" => "Filespec", "F" => literal("other.pdf") },
+          "D" => literal("chapter2"),
+        }),
+        link(dictionary! { "S" => "Launch", "F" => literal("run.sh") }),
+        link(dictionary! { "S" => "Named", "N" => "NextPage" }),
+        dictionary! {
+          "Type" => "Annot",
+          "Subtype" => "Link",
+          "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+          "Dest" => literal("intro"),
+        },
+      ],
+    );
+
+    let found = collect_page_annotations(&doc, pages[0], 1);
+    let types: Vec<&str> = found.iter().map(|a| a.action_type.as_str()).collect();
+    assert_eq!(types, ["URI", "GoToR", "Launch", "Named", "GoTo"]);
+    assert_eq!(found[0].uri.as_deref(), Some("https://example.com"));
+    assert_eq!(found[1].remote_file.as_deref(), Some("other.pdf"));
+    assert_eq!(found[1].dest.as_deref(), Some("chapter2"));
+    assert_eq!(found[2].remote_file.as_deref(), Some("run.sh"));
+    assert_eq!(found[3].remote_file, None);
+    assert_eq!(found[4].dest.as_deref(), Some("intro"));
+  }
+
+  #[test]
+  fn destinations_report_fit_type_and_parameters() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    let page_id = pages[0];
+    let link = |key: &str, target: Object| {
+      let mut annot = dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Link",
+        "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+      };
+      annot.set(key, target);
+      annot
+    };
+    set_annots(
+      &mut doc,
+      page_id,
+      vec![
+        link(
+          "Dest",
+          vec![
+            page_id.into(),
+            "XYZ".into(),
+            72.into(),
+            700.into(),
+            Object::Null,
+          ]
+          .into(),
+        ),
+        link(
+          "A",
+          dictionary! { "S" => "GoTo", "D" => vec![page_id.into(), "FitH".into(), 500.5.into()] }
+            .into(),
+        ),
+        // Named destinations: one in the name tree, one in the /Dests dictionary
+        link("Dest", literal("intro")),
+        link("Dest", "legacy".into()),
+        link("Dest", literal("missing")),
+      ],
+    );
+    let leaf = doc.add_object(dictionary! {
+      "Limits" => vec![literal("a"), literal("m")],
+      "Names" => vec![
+        literal("appendix"),
+        vec![page_id.into(), "Fit".into()].into(),
+        literal("intro"),
+        dictionary! { "D" => vec![page_id.into(), "FitR".into(), 0.into(), 0.into(), 100.into(), 50.into()] }.into(),
+      ],
+    });
+    let other_leaf = doc.add_object(dictionary! {
+      "Limits" => vec![literal("n"), literal("z")],
+      "Names" => vec![literal("outro"), vec![page_id.into(), "FitB".into()].into()],
+    });
+    let catalog = doc.catalog_mut().unwrap();
+    catalog.set(
+      "Dests",
+      dictionary! { "legacy" => vec![page_id.into(), "FitV".into(), 30.into()] },
+    );
+    catalog.set(
+      "Names",
+      dictionary! {
+        "Dests" => dictionary! { "Kids" => vec![leaf.into(), other_leaf.into()] },
+      },
+    );
+
+    let found = collect_page_annotations(&doc, page_id, 1);
+    let fits: Vec<_> = found
+      .iter()
+      .map(|a| (a.dest_fit.as_deref(), a.dest_params.as_deref()))
+      .collect();
+    assert_eq!(
+      fits,
+      [
+        (
+          Some("XYZ"),
+          Some([Some(72.0), Some(700.0), None].as_slice())
+        ),
+        (Some("FitH"), Some([Some(500.5)].as_slice())),
+        (
+          Some("FitR"),
+          Some([Some(0.0), Some(0.0), Some(100.0), Some(50.0)].as_slice())
+        ),
+        (Some("FitV"), Some([Some(30.0)].as_slice())),
+        (None, None),
+      ]
+    );
+  }
+
+  #[test]
+  fn urls_are_found_in_text_without_trailing_punctuation() {
+    let text = "See https://example.com/docs. Or (http://a.org/x_(y)) and\nhttps://b.io/?q=1, \"https://c.net\" http://";
+    assert_eq!(
+      find_urls(text),
+      [
+        "https://example.com/docs",
+        "http://a.org/x_(y)",
+        "https://b.io/?q=1",
+        "https://c.net",
+      ]
+    );
+  }
+
+  #[test]
+  fn uris_are_collected_from_annotations_actions_and_text() {
+    use lopdf::Stream;
+
+    let (mut doc, pages) = doc_with_pages(|doc| {
+      let font_id = helvetica(doc);
+      let content =
+        b"BT /F1 12 Tf 10 50 Td (Visit https://linked.example or https://typed.example.) Tj ET";
+      let content_id = doc.add_object(Stream::new(dictionary! {}, content.to_vec()));
+      vec![dictionary! {
+        "MediaBox" => vec![0.into(), 0.into(), 400.into(), 100.into()],
+        "Contents" => content_id,
+        "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+      }]
+    });
+    set_annots(
+      &mut doc,
+      pages[0],
+      vec![dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Link",
+        "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+        "A" => dictionary! { "S" => "URI", "URI" => literal("https://linked.example") },
+      }],
+    );
+    let outline = doc.add_object(dictionary! {
+      "Title" => literal("Home"),
+      "A" => dictionary! { "S" => "URI", "URI" => literal("https://outline.example") },
+    });
+    doc.catalog_mut().unwrap().set(
+      "Outlines",
+      dictionary! { "First" => outline, "Last" => outline },
+    );
+
+    let uris: Vec<(String, Option<u32>, String)> = collect_uris(&doc)
+      .unwrap()
+      .into_iter()
+      .map(|u| (u.uri, u.page, u.source))
+      .collect();
+    let entry = |uri: &str, page, source: &str| (uri.to_string(), page, source.to_string());
+    assert_eq!(
+      uris,
+      [
+        entry("https://linked.example", Some(1), "annotation"),
+        entry("https://typed.example", Some(1), "text"),
+        entry("https://outline.example", None, "action"),
+      ]
+    );
+  }
+
+  #[test]
+  fn annotation_text_fields_decode_utf16() {
+    let utf16 = |s: &str| {
+      let mut bytes = vec![0xFE, 0xFF];
+      bytes.extend(s.encode_utf16().flat_map(u16::to_be_bytes));
+      Object::String(bytes, lopdf::StringFormat::Hexadecimal)
+    };
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    set_annots(
+      &mut doc,
+      pages[0],
+      vec![dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Text",
+        "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+        "Contents" => utf16("Größe prüfen — 確認"),
+        "T" => utf16("Zoë"),
+      }],
+    );
+
+    let found = collect_page_annotations(&doc, pages[0], 1);
+    assert_eq!(found[0].content.as_deref(), Some("Größe prüfen — 確認"));
+    assert_eq!(found[0].author.as_deref(), Some("Zoë"));
+  }
+
+  #[test]
+  fn redactions_report_their_regions_and_overlay_text() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    let quads = doc.add_object(vec![
+      10.into(),
+      40.into(),
+      90.into(),
+      40.into(),
+      10.into(),
+      30.into(),
+      90.into(),
+      30.into(),
+    ]);
+    set_annots(
+      &mut doc,
+      pages[0],
+      vec![dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Redact",
+        "Rect" => vec![10.into(), 30.into(), 90.into(), 40.into()],
+        "QuadPoints" => quads,
+        "OverlayText" => Object::string_literal("WITHHELD"),
+      }],
+    );
+
+    let found = collect_page_annotations(&doc, pages[0], 1);
+    assert_eq!(found[0].subtype, "Redact");
+    assert_eq!(
+      found[0].quad_points.as_deref(),
+      Some([10.0, 40.0, 90.0, 40.0, 10.0, 30.0, 90.0, 30.0].as_slice())
+    );
+    assert_eq!(found[0].overlay_text.as_deref(), Some("WITHHELD"));
+  }
+
+  #[test]
+  fn rendered_text_reads_the_selected_appearance_stream() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    let font = helvetica(&mut doc);
+    let mut form = |text: &str| {
+      doc.add_object(lopdf::Stream::new(
+        dictionary! {
+          "Type" => "XObject",
+          "Subtype" => "Form",
+          "BBox" => vec![0.into(), 0.into(), 100.into(), 20.into()],
+          "Resources" => dictionary! { "Font" => dictionary! { "Helv" => font } },
+        },
+        format!("BT /Helv 10 Tf 2 5 Td ({text}) Tj ET").into_bytes(),
+      ))
+    };
+    let free_text_ap = form("Approved by QA");
+    let on = form("Selected");
+    let off = form("Unselected");
+    set_annots(
+      &mut doc,
+      pages[0],
+      vec![
+        dictionary! {
+          "Type" => "Annot",
+          "Subtype" => "FreeText",
+          "AP" => dictionary! { "N" => free_text_ap },
+        },
+        dictionary! {
+          "Type" => "Annot",
+          "Subtype" => "Stamp",
+          "AS" => "On",
+          "AP" => dictionary! { "N" => dictionary! { "On" => on, "Off" => off } },
+        },
+        dictionary! { "Type" => "Annot", "Subtype" => "Text" },
+      ],
+    );
+
+    let config = AnnotationConfig {
+      rendered_text: true,
+    };
+    let rendered: Vec<Option<String>> =
+      collect_page_annotations_with_config(&doc, pages[0], 1, config)
+        .into_iter()
+        .map(|a| a.rendered_text)
+        .collect();
+    assert_eq!(
+      rendered,
+      [
+        Some("Approved by QA".to_string()),
+        Some("Selected".to_string()),
+        None
+      ]
+    );
+    assert!(
+      collect_page_annotations(&doc, pages[0], 1)
+        .iter()
+        .all(|a| a.rendered_text.is_none())
+    );
+  }
+
+  #[test]
+  fn popups_and_replies_link_to_their_annotations() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    let comment_id = doc.new_object_id();
+    let popup = doc.add_object(dictionary! {
+      "Type" => "Annot",
+      "Subtype" => "Popup",
+      "Parent" => comment_id,
+      "Open" => true,
+    });
+    doc.objects.insert(
+      comment_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Text",
+        "Contents" => Object::string_literal("Check this figure"),
+        "Popup" => popup,
+      }),
+    );
+    let reply = doc.add_object(dictionary! {
+      "Type" => "Annot",
+      "Subtype" => "Text",
+      "Contents" => Object::string_literal("Fixed"),
+      "IRT" => comment_id,
+    });
+    doc.get_dictionary_mut(pages[0]).unwrap().set(
+      "Annots",
+      vec![comment_id.into(), popup.into(), reply.into()],
+    );
+
+    let found = collect_page_annotations(&doc, pages[0], 1);
+    let comment = format!("{} 0 obj", comment_id.0);
+    assert_eq!(found[0].object_id, comment);
+    assert_eq!(
+      (
+        found[0].popup_for.as_deref(),
+        found[0].in_reply_to.as_deref()
+      ),
+      (None, None)
+    );
+    assert_eq!(found[1].popup_for.as_deref(), Some(comment.as_str()));
+    assert_eq!(found[1].open, Some(true));
+    assert_eq!(found[2].in_reply_to.as_deref(), Some(comment.as_str()));
+  }
+
+  #[test]
+  fn screen_and_rich_media_annotations_report_their_media() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    let video = doc.add_object(lopdf::Stream::new(
+      dictionary! { "Type" => "EmbeddedFile", "Subtype" => "video/mp4" },
+      b"....ftypmp42".to_vec(),
+    ));
+    let sound = doc.add_object(lopdf::Stream::new(
+      dictionary! { "Type" => "EmbeddedFile" },
+      b"ID3".to_vec(),
+    ));
+    let asset = doc.add_object(dictionary! {
+      "Type" => "Filespec",
+      "UF" => literal("theme.mp3"),
+      "EF" => dictionary! { "F" => sound },
+    });
+    set_annots(
+      &mut doc,
+      pages[0],
+      vec![
+        dictionary! {
+          "Type" => "Annot",
+          "Subtype" => "Screen",
+          "A" => dictionary! {
+            "S" => "Rendition",
+            "R" => dictionary! {
+              "S" => "MR",
+              "C" => dictionary! {
+                "S" => "MCD",
+                "CT" => literal("video/mp4"),
+                "D" => dictionary! { "Type" => "Filespec", "F" => literal("intro.mp4"), "EF" => dictionary! { "F" => video } },
+              },
+            },
+          },
+        },
+        dictionary! {
+          "Type" => "Annot",
+          "Subtype" => "RichMedia",
+          "RichMediaContent" => dictionary! {
+            "Assets" => dictionary! { "Names" => vec![literal("theme.mp3"), asset.into()] },
+            "Configurations" => vec![Object::Dictionary(dictionary! {
+              "Subtype" => "Sound",
+              "Instances" => vec![Object::Dictionary(dictionary! { "Asset" => asset })],
+            })],
+          },
+        },
+      ],
+    );
+
+    let found = collect_page_annotations(&doc, pages[0], 1);
+    let media: Vec<_> = found
+      .iter()
+      .map(|a| {
+        (
+          a.media_type.as_deref(),
+          a.media_file.as_deref(),
+          a.media_object_id.clone(),
+        )
+      })
+      .collect();
+    assert_eq!(
+      media,
+      [
+        (
+          Some("video/mp4"),
+          Some("intro.mp4"),
+          Some(format!("{} 0 obj", video.0))
+        ),
+        (
+          Some("Sound"),
+          Some("theme.mp3"),
+          Some(format!("{} 0 obj", sound.0))
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn document_actions_cover_open_action_and_catalog_triggers() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    let page_id = pages[0];
+    let script = doc.add_object(lopdf::Stream::new(
+      dictionary! {},
+      b"app.alert('bye')".to_vec(),
+    ));
+    let print = doc.add_object(dictionary! { "S" => "Named", "N" => "Print" });
+    let catalog = doc.catalog_mut().unwrap();
+    catalog.set(
+      "OpenAction",
+      dictionary! {
+        "S" => "JavaScript",
+        "JS" => literal("this.print()"),
+        "Next" => vec![
+          print.into(),
+          Object::Dictionary(dictionary! {
+            "S" => "GoTo",
+            "D" => vec![page_id.into(), "Fit".into()],
+          }),
+        ],
+      },
+    );
+    catalog.set(
+      "AA",
+      dictionary! {
+        "WC" => dictionary! { "S" => "JavaScript", "JS" => script },
+        "WP" => dictionary! { "S" => "URI", "URI" => literal("https://example.com/log") },
+      },
+    );
+
+    let summary = |doc: &Document| -> Vec<_> {
+      collect_document_actions(doc)
+        .into_iter()
+        .map(|a| (a.trigger, a.action_type, a.detail))
+        .collect()
+    };
+    let entry = |trigger: &str, action_type: &str, detail: &str| {
+      (
+        trigger.to_string(),
+        action_type.to_string(),
+        Some(detail.to_string()),
+      )
+    };
+    assert_eq!(
+      summary(&doc),
+      [
+        entry("Open", "JavaScript", "this.print()"),
+        entry("Open", "Named", "Print"),
+        entry("Open", "GoTo", "page 1"),
+        entry("WillClose", "JavaScript", "app.alert('bye')"),
+        entry("WillPrint", "URI", "https://example.com/log"),
+      ]
+    );
+
+    // A bare destination as /OpenAction, and no additional actions
+    let catalog = doc.catalog_mut().unwrap();
+    catalog.set("OpenAction", vec![page_id.into(), "Fit".into()]);
+    catalog.remove(b"AA");
+    assert_eq!(summary(&doc), [entry("Open", "GoTo", "page 1")]);
+  }
+
+  #[test]
+  fn clean_report_finds_active_content_anywhere() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    doc
+      .catalog_mut()
+      .unwrap()
+      .set("OpenAction", vec![pages[0].into(), "Fit".into()]);
+    let flags = |doc: &Document| {
+      let r = collect_clean_report(doc);
+      (
+        r.has_javascript,
+        r.has_launch_action,
+        r.has_embedded_files,
+        r.has_open_action,
+        r.is_clean,
+      )
+    };
+    // A bare destination to open on is not active content
+    assert_eq!(flags(&doc), (false, false, false, true, true));
+
+    // A link annotation launching a program, and a widget running a script
+    // on mouse-up, nested inside /AA
+    doc.add_object(dictionary! {
+      "Type" => "Annot",
+      "Subtype" => "Link",
+      "A" => dictionary! { "S" => "Launch", "F" => literal("run.sh") },
+    });
+    doc.add_object(dictionary! {
+      "Type" => "Annot",
+      "Subtype" => "Widget",
+      "AA" => dictionary! { "U" => dictionary! { "S" => "JavaScript", "JS" => literal("x()") } },
+    });
+    assert_eq!(flags(&doc), (true, true, false, true, false));
+
+    let file = doc.add_object(lopdf::Stream::new(
+      dictionary! { "Type" => "EmbeddedFile" },
+      b"payload".to_vec(),
+    ));
+    doc.add_object(dictionary! { "Type" => "Filespec", "EF" => dictionary! { "F" => file } });
+    doc.catalog_mut().unwrap().remove(b"OpenAction");
+    assert_eq!(flags(&doc), (true, true, true, false, false));
+  }
+
+  #[test]
+  fn highlighted_text_selects_glyphs_under_quad_points() {
+    let nums = |n: &[f64]| -> Vec<Object> { n.iter().map(|&v| Object::Real(v as f32)).collect() };
+    let (mut doc, pages) = doc_with_pages(|doc| {
+      let font = helvetica(doc);
+      // Helvetica without /Widths: 5pt per glyph at 10pt, starting at x = 50
+      let content = doc.add_object(lopdf::Stream::new(
+        dictionary! {},
+        b"BT /F1 10 Tf 50 700 Td (Hello world again) Tj ET".to_vec(),
+      ));
+      vec![dictionary! {
+        "MediaBox" => nums(&[0.0, 0.0, 600.0, 800.0]),
+        "Contents" => content,
+        "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+      }]
+    });
+    set_annots(
+      &mut doc,
+      pages[0],
+      vec![
+        dictionary! { "Subtype" => "Text", "Rect" => nums(&[0.0, 0.0, 10.0, 10.0]) },
+        // Ends mid-word: "wor" of "world"
+        dictionary! {
+          "Subtype" => "Highlight",
+          "Rect" => nums(&[80.0, 695.0, 95.0, 712.0]),
+          "QuadPoints" => nums(&[80.0, 712.0, 95.0, 712.0, 80.0, 695.0, 95.0, 695.0]),
+        },
+        // No /QuadPoints: the /Rect is used
+        dictionary! {
+          "Subtype" => "Highlight",
+          "Rect" => nums(&[40.0, 695.0, 106.0, 712.0]),
+        },
+        dictionary! {
+          "Subtype" => "Highlight",
+          "Rect" => nums(&[300.0, 300.0, 400.0, 320.0]),
+        },
+      ],
+    );
+
+    let found: Vec<(u32, u32, String)> = collect_highlighted_text(&doc)
+      .into_iter()
+      .map(|h| (h.page, h.annotation_index, h.text))
+      .collect();
+    assert_eq!(
+      found,
+      [
+        (1, 1, "wor".to_string()),
+        (1, 2, "Hello world".to_string()),
+        (1, 3, String::new()),
+      ]
+    );
+  }
+
+  #[test]
+  fn full_text_joins_the_extracted_pages_when_requested() {
+    let (doc, _) = doc_with_pages(|doc| {
+      let font = helvetica(doc);
+      ["First", "Second"]
+        .iter()
+        .map(|text| {
+          let content = format!("BT /F1 10 Tf 50 700 Td ({text}) Tj ET");
+          let content = doc.add_object(lopdf::Stream::new(dictionary! {}, content.into_bytes()));
+          dictionary! {
+            "MediaBox" => vec![0.into(), 0.into(), 600.into(), 800.into()],
+            "Contents" => content,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+          }
+        })
+        .collect()
+    });
+
+    assert!(
+      extract_all(Arc::new(doc.clone()), DocumentConfig::default())
+        .unwrap()
+        .full_text
+        .is_none()
+    );
+    let config = DocumentConfig {
+      include_full_text: true,
+      ..DocumentConfig::default()
+    };
+    let raw = extract_all(Arc::new(doc), config).unwrap();
+    assert_eq!(
+      raw.full_text.unwrap(),
+      format!(
+        "{}\n\n--- Page 2 ---\n\n{}",
+        raw.text[0].text, raw.text[1].text
+      )
+    );
+    assert!(raw.text[1].text.contains("Second"));
+  }
+
+  #[test]
+  fn annotation_summary_unions_rects_per_page() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}, dictionary! {}]);
+    let square = |rect: Vec<Object>| {
+      dictionary! { "Type" => "Annot", "Subtype" => "Square", "Rect" => rect }
+    };
+    set_annots(
+      &mut doc,
+      pages[0],
+      vec![
+        square(vec![10.into(), 20.into(), 30.into(), 40.into()]),
+        // Corners in the "wrong" order are normalized
+        square(vec![50.into(), 15.5.into(), 25.into(), 5.into()]),
+        square(vec![1.into(), 2.into()]),
+      ],
+    );
+
+    let summary = collect_annotation_summary(&doc);
+    assert_eq!(summary.len(), 2);
+    assert_eq!(summary[0].annotation_count, 3);
+    assert_eq!(
+      summary[0].annotation_bounds,
+      Some(vec![10.0, 5.0, 50.0, 40.0])
+    );
+    assert_eq!(
+      (
+        summary[1].page,
+        summary[1].annotation_count,
+        &summary[1].annotation_bounds
+      ),
+      (2, 0, &None)
+    );
+  }
+}
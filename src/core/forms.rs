@@ -0,0 +1,621 @@
+use crate::core::document::format_object_id;
+use crate::core::meta::decode_pdf_string;
+use crate::core::objects::deref;
+use crate::core::tagged::structure_object_order;
+use crate::types::{FormField, FormFieldsResult};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{HashMap, HashSet};
+
+/// Button field flags (`/Ff`, ISO 32000-1 table 226).
+const FF_RADIO: i64 = 1 << 15;
+const FF_PUSHBUTTON: i64 = 1 << 16;
+
+/// Field hierarchies nested deeper than this are not followed.
+const MAX_FIELD_DEPTH: u32 = 64;
+
+/// Attributes a field inherits from its ancestors in the field tree.
+#[derive(Clone, Default)]
+struct Inherited<'a> {
+  name: String,
+  field_type: Option<&'a [u8]>,
+  flags: i64,
+  value: Option<&'a Object>,
+}
+
+struct FieldWalker<'a> {
+  doc: &'a Document,
+  /// Widget annotation → page number, from each page's `/Annots`.
+  annot_pages: HashMap<ObjectId, u32>,
+  page_nums: HashMap<ObjectId, u32>,
+  visited: HashSet<ObjectId>,
+  fields: Vec<FormField>,
+  /// Widget annotation → index of its field in `fields`.
+  widget_fields: HashMap<ObjectId, usize>,
+}
+
+/// A field value as text: strings are decoded, names lose their slash and
+/// arrays (multi-select choices) are joined with `, `.
+fn value_text(doc: &Document, value: &Object) -> Option<String> {
+  match deref(doc, value)? {
+    Object::String(bytes, _) => Some(decode_pdf_string(bytes)),
+    Object::Name(name) => Some(String::from_utf8_lossy(name).to_string()),
+    Object::Array(items) => {
+      let parts: Vec<String> = items.iter().filter_map(|v| value_text(doc, v)).collect();
+      Some(parts.join(", "))
+    }
+    _ => None,
+  }
+}
+
+/// Whether a checkbox or radio field is on. `/V` names the selected state and
+/// wins when present; otherwise the widgets' appearance states (`/AS`) are
+/// used. Any state other than `/Off` counts as on.
+fn button_checked(doc: &Document, value: Option<&Object>, widgets: &[&Dictionary]) -> bool {
+  if let Some(Object::Name(state)) = value.and_then(|v| deref(doc, v)) {
+    return state.as_slice() != b"Off";
+  }
+  widgets.iter().any(|w| {
+    w.get(b"AS")
+      .and_then(Object::as_name)
+      .is_ok_and(|state| state != b"Off")
+  })
+}
+
+impl<'a> FieldWalker<'a> {
+  fn walk(&mut self, obj: &'a Object, parent: &Inherited<'a>, depth: u32) {
+    if depth > MAX_FIELD_DEPTH {
+      return;
+    }
+    let id = match obj {
+      Object::Reference(id) => {
+        if !self.visited.insert(*id) {
+          return;
+        }
+        Some(*id)
+      }
+      _ => None,
+    };
+    let Some(Object::Dictionary(field)) = deref(self.doc, obj) else {
+      return;
+    };
+
+    let mut inherited = parent.clone();
+    if let Ok(Object::String(partial, _)) = field.get(b"T") {
+      let partial = decode_pdf_string(partial);
+      inherited.name = if parent.name.is_empty() {
+        partial
+      } else {
+        format!("{}.{partial}", parent.name)
+      };
+    }
+    if let Ok(ft) = field.get(b"FT").and_then(Object::as_name) {
+      inherited.field_type = Some(ft);
+    }
+    if let Ok(ff) = field.get(b"Ff").and_then(Object::as_i64) {
+      inherited.flags = ff;
+    }
+    if let Ok(v) = field.get(b"V") {
+      inherited.value = Some(v);
+    }
+
+    // Kids with a partial name are child fields; the rest are its widgets
+    let kids: Vec<(&'a Object, &'a Dictionary)> = field
+      .get(b"Kids")
+      .ok()
+      .and_then(|k| deref(self.doc, k))
+      .and_then(|k| k.as_array().ok())
+      .map(|kids| {
+        kids
+          .iter()
+          .filter_map(|kid| Some((kid, deref(self.doc, kid)?.as_dict().ok()?)))
+          .collect()
+      })
+      .unwrap_or_default();
+    if kids.iter().any(|(_, kid)| kid.has(b"T")) {
+      for (kid, _) in kids {
+        self.walk(kid, &inherited, depth + 1);
+      }
+      return;
+    }
+
+    let widgets: Vec<(Option<ObjectId>, &Dictionary)> = if kids.is_empty() {
+      vec![(id, field)]
+    } else {
+      kids
+        .iter()
+        .map(|(kid, dict)| (kid.as_reference().ok(), *dict))
+        .collect()
+    };
+    let page = widgets.iter().find_map(|(widget_id, widget)| {
+      widget
+        .get(b"P")
+        .and_then(Object::as_reference)
+        .ok()
+        .and_then(|p| self.page_nums.get(&p))
+        .or_else(|| self.annot_pages.get(&(*widget_id)?))
+        .copied()
+    });
+
+    let (field_type, checked) = match inherited.field_type {
+      Some(b"Btn") if inherited.flags & FF_PUSHBUTTON != 0 => ("button", None),
+      Some(b"Btn") => {
+        let widget_dicts: Vec<&Dictionary> = widgets.iter().map(|(_, w)| *w).collect();
+        let checked = button_checked(self.doc, inherited.value, &widget_dicts);
+        if inherited.flags & FF_RADIO != 0 {
+          ("radio", Some(checked))
+        } else {
+          ("checkbox", Some(checked))
+        }
+      }
+      Some(b"Tx") => ("text", None),
+      Some(b"Ch") => ("choice", None),
+      Some(b"Sig") => ("signature", None),
+      _ => ("unknown", None),
+    };
+
+    for widget_id in widgets.iter().filter_map(|(widget_id, _)| *widget_id) {
+      self.widget_fields.insert(widget_id, self.fields.len());
+    }
+    self.fields.push(FormField {
+      object_id: id.map(format_object_id),
+      name: inherited.name,
+      field_type: field_type.to_string(),
+      value: inherited.value.and_then(|v| value_text(self.doc, v)),
+      checked,
+      page,
+    });
+  }
+}
+
+/// Top-left corner of an annotation's `/Rect` as `(left, top)`.
+fn annot_corner(doc: &Document, annot_id: ObjectId) -> Option<(f64, f64)> {
+  let [x1, y1, x2, y2] = annot_rect(doc, doc.get_dictionary(annot_id).ok()?)?;
+  Some((x1.min(x2), y1.max(y2)))
+}
+
+/// Sort one page's annotations into the order its `/Tabs` entry asks for:
+/// `S` follows the structure tree (annotations it doesn't reference come
+/// last), `R` reads rows top to bottom and `C` columns left to right. Without
+/// `/Tabs` the `/Annots` array order is kept.
+fn sort_for_tabs(
+  doc: &Document,
+  tabs: Option<&[u8]>,
+  annots: &mut [ObjectId],
+  structure_rank: &mut Option<HashMap<ObjectId, usize>>,
+) {
+  let corner = |id: &ObjectId| annot_corner(doc, *id).unwrap_or((f64::MAX, f64::MIN));
+  match tabs {
+    Some(b"S") => {
+      let rank = structure_rank.get_or_insert_with(|| {
+        let order = structure_object_order(doc);
+        order
+          .into_iter()
+          .enumerate()
+          .map(|(i, id)| (id, i))
+          .collect()
+      });
+      annots.sort_by_key(|id| rank.get(id).copied().unwrap_or(usize::MAX));
+    }
+    Some(b"R") => annots.sort_by(|a, b| {
+      let ((ax, ay), (bx, by)) = (corner(a), corner(b));
+      by.total_cmp(&ay).then(ax.total_cmp(&bx))
+    }),
+    Some(b"C") => annots.sort_by(|a, b| {
+      let ((ax, ay), (bx, by)) = (corner(a), corner(b));
+      ax.total_cmp(&bx).then(by.total_cmp(&ay))
+    }),
+    _ => {}
+  }
+}
+
+/// A page's widget annotations, as listed in its `/Annots`.
+struct PageWidgets<'a> {
+  page: u32,
+  tabs: Option<&'a [u8]>,
+  annots: Vec<ObjectId>,
+}
+
+/// The catalog's `/AcroForm` dictionary.
+fn acro_form(doc: &Document) -> Option<&Dictionary> {
+  doc
+    .catalog()
+    .ok()
+    .and_then(|c| c.get(b"AcroForm").ok())
+    .and_then(|o| deref(doc, o))
+    .and_then(|o| o.as_dict().ok())
+}
+
+/// The form-wide default appearance string (`/DA`) and the names of the
+/// fonts in the default resources (`/DR /Font`), as referenced by `Tf` in
+/// appearance strings.
+fn form_defaults(doc: &Document) -> (Option<String>, Vec<String>) {
+  let Some(form) = acro_form(doc) else {
+    return (None, Vec::new());
+  };
+  let default_appearance = match form.get(b"DA").ok().and_then(|o| deref(doc, o)) {
+    Some(Object::String(bytes, _)) => Some(decode_pdf_string(bytes)),
+    _ => None,
+  };
+  let fonts = form
+    .get(b"DR")
+    .ok()
+    .and_then(|o| deref(doc, o))
+    .and_then(|o| o.as_dict().ok())
+    .and_then(|dr| dr.get(b"Font").ok())
+    .and_then(|o| deref(doc, o))
+    .and_then(|o| o.as_dict().ok())
+    .map(|fonts| {
+      fonts
+        .iter()
+        .map(|(name, _)| String::from_utf8_lossy(name).to_string())
+        .collect()
+    })
+    .unwrap_or_default();
+  (default_appearance, fonts)
+}
+
+/// Walk the `/AcroForm` field tree. `None` for documents without a form.
+fn walk_form(doc: &Document) -> Option<(FieldWalker<'_>, Vec<PageWidgets<'_>>)> {
+  let fields = acro_form(doc)
+    .and_then(|form| form.get(b"Fields").ok())
+    .and_then(|o| deref(doc, o))
+    .and_then(|o| o.as_array().ok())?;
+
+  let pages = doc.get_pages();
+  let mut annot_pages = HashMap::new();
+  let mut page_widgets = Vec::new();
+  for (&page_num, &page_id) in &pages {
+    let page = doc.get_dictionary(page_id).ok();
+    let annots: Vec<ObjectId> = page
+      .and_then(|p| p.get(b"Annots").ok())
+      .and_then(|o| deref(doc, o))
+      .and_then(|o| o.as_array().ok())
+      .into_iter()
+      .flatten()
+      .filter_map(|annot| annot.as_reference().ok())
+      .collect();
+    for &id in &annots {
+      annot_pages.entry(id).or_insert(page_num);
+    }
+    let tabs = page.and_then(|p| p.get(b"Tabs").and_then(Object::as_name).ok());
+    page_widgets.push(PageWidgets {
+      page: page_num,
+      tabs,
+      annots,
+    });
+  }
+
+  let mut walker = FieldWalker {
+    doc,
+    annot_pages,
+    page_nums: pages.iter().map(|(&num, &id)| (id, num)).collect(),
+    visited: HashSet::new(),
+    fields: Vec::new(),
+    widget_fields: HashMap::new(),
+  };
+  for field in fields {
+    walker.walk(field, &Inherited::default(), 0);
+  }
+  Some((walker, page_widgets))
+}
+
+/// Terminal fields of the document's interactive form (`/AcroForm`), in
+/// field-tree order, with the order a viewer tabs through them: page by
+/// page, each page's widgets in its `/Tabs` order. Fields without a widget
+/// in any page's `/Annots` are left out of the tab order. Documents without
+/// a form return empty lists.
+pub(crate) fn collect_form_fields(doc: &Document) -> FormFieldsResult {
+  let (default_appearance, fonts) = form_defaults(doc);
+  let Some((walker, page_widgets)) = walk_form(doc) else {
+    return FormFieldsResult {
+      fields: Vec::new(),
+      tab_order: Vec::new(),
+      default_appearance,
+      fonts,
+    };
+  };
+
+  let mut structure_rank = None;
+  let mut tabbed = HashSet::new();
+  let mut tab_order = Vec::new();
+  for PageWidgets {
+    tabs, mut annots, ..
+  } in page_widgets
+  {
+    sort_for_tabs(doc, tabs, &mut annots, &mut structure_rank);
+    for annot in annots {
+      let Some(&index) = walker.widget_fields.get(&annot) else {
+        continue;
+      };
+      // A radio group or a field with several widgets is tabbed to once
+      if tabbed.insert(index)
+        && let Some(id) = &walker.fields[index].object_id
+      {
+        tab_order.push(id.clone());
+      }
+    }
+  }
+  FormFieldsResult {
+    fields: walker.fields,
+    tab_order,
+    default_appearance,
+    fonts,
+  }
+}
+
+/// Annotation flag (`/F`) of widgets that are not displayed.
+const ANNOT_HIDDEN: i64 = 1 << 1;
+
+/// What each visible widget shows, keyed by page number: the widget's
+/// `/Rect` (default user space) with its field's value. Text and choice
+/// fields show their value; checkboxes and radio buttons show `[x]` when the
+/// widget's appearance state is on and `[ ]` otherwise. Empty fields, push
+/// buttons and signatures are left out.
+pub(crate) fn collect_widget_values(doc: &Document) -> HashMap<u32, Vec<([f64; 4], String)>> {
+  let Some((walker, page_widgets)) = walk_form(doc) else {
+    return HashMap::new();
+  };
+  let mut values: HashMap<u32, Vec<([f64; 4], String)>> = HashMap::new();
+  for PageWidgets { page, annots, .. } in page_widgets {
+    for annot_id in annots {
+      let Some(&index) = walker.widget_fields.get(&annot_id) else {
+        continue;
+      };
+      let Ok(widget) = doc.get_dictionary(annot_id) else {
+        continue;
+      };
+      if widget
+        .get(b"F")
+        .and_then(Object::as_i64)
+        .is_ok_and(|flags| flags & ANNOT_HIDDEN != 0)
+      {
+        continue;
+      }
+      let field = &walker.fields[index];
+      let shown = match field.field_type.as_str() {
+        "text" | "choice" => field.value.clone().filter(|v| !v.trim().is_empty()),
+        "checkbox" | "radio" => {
+          let on = match widget.get(b"AS").and_then(Object::as_name) {
+            Ok(state) => state != b"Off",
+            Err(_) => field.checked == Some(true),
+          };
+          Some(if on { "[x]" } else { "[ ]" }.to_string())
+        }
+        _ => None,
+      };
+      let rect = annot_rect(doc, widget);
+      if let (Some(shown), Some(rect)) = (shown, rect) {
+        values.entry(page).or_default().push((rect, shown));
+      }
+    }
+  }
+  values
+}
+
+/// An annotation's `/Rect` as `[x1, y1, x2, y2]`.
+fn annot_rect(doc: &Document, annot: &Dictionary) -> Option<[f64; 4]> {
+  let rect: Vec<f64> = deref(doc, annot.get(b"Rect").ok()?)?
+    .as_array()
+    .ok()?
+    .iter()
+    .map(|v| v.as_float().ok().map(f64::from))
+    .collect::<Option<_>>()?;
+  rect.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lopdf::{StringFormat, dictionary};
+
+  fn literal(s: &str) -> Object {
+    Object::String(s.as_bytes().to_vec(), StringFormat::Literal)
+  }
+
+  fn form_doc(build: impl FnOnce(&mut Document, ObjectId) -> Vec<Object>) -> Document {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let page_id = doc.add_object(dictionary! {
+      "Type" => "Page",
+      "Parent" => pages_id,
+      "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+    });
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => vec![page_id.into()],
+        "Count" => 1,
+      }),
+    );
+    let fields = build(&mut doc, page_id);
+    let catalog_id = doc.add_object(dictionary! {
+      "Type" => "Catalog",
+      "Pages" => pages_id,
+      "AcroForm" => dictionary! {
+        "Fields" => fields,
+        "DA" => literal("/Helv 0 Tf 0 g"),
+        "DR" => dictionary! {
+          "Font" => dictionary! {
+            "Helv" => dictionary! { "Type" => "Font", "BaseFont" => "Helvetica" },
+            "ZaDb" => dictionary! { "Type" => "Font", "BaseFont" => "ZapfDingbats" },
+          },
+        },
+      },
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc
+  }
+
+  fn checked_states(doc: &Document) -> Vec<(String, String, Option<bool>)> {
+    collect_form_fields(doc)
+      .fields
+      .into_iter()
+      .map(|f| (f.name, f.field_type, f.checked))
+      .collect()
+  }
+
+  #[test]
+  fn checkbox_state_comes_from_value_then_appearance() {
+    let doc = form_doc(|doc, page_id| {
+      let checkbox = |doc: &mut Document, name: &str, extra: Dictionary| {
+        let mut field = dictionary! {
+          "FT" => "Btn",
+          "T" => literal(name),
+          "Subtype" => "Widget",
+          "P" => page_id,
+        };
+        field.extend(&extra);
+        Object::Reference(doc.add_object(field))
+      };
+      vec![
+        checkbox(doc, "on", dictionary! { "V" => "Yes", "AS" => "Yes" }),
+        checkbox(doc, "off", dictionary! { "V" => "Off", "AS" => "Off" }),
+        // Custom on-state name, no /V: the appearance state decides
+        checkbox(doc, "as_only", dictionary! { "AS" => "Checked" }),
+        checkbox(doc, "unset", dictionary! {}),
+        // /V wins over a stale appearance state
+        checkbox(doc, "stale", dictionary! { "V" => "Off", "AS" => "Yes" }),
+        checkbox(doc, "push", dictionary! { "Ff" => FF_PUSHBUTTON }),
+      ]
+    });
+    let checkbox = |name: &str, checked| (name.to_string(), "checkbox".to_string(), Some(checked));
+    assert_eq!(
+      checked_states(&doc),
+      [
+        checkbox("on", true),
+        checkbox("off", false),
+        checkbox("as_only", true),
+        checkbox("unset", false),
+        checkbox("stale", false),
+        ("push".to_string(), "button".to_string(), None),
+      ]
+    );
+  }
+
+  #[test]
+  fn radio_groups_and_hierarchical_names() {
+    let doc = form_doc(|doc, page_id| {
+      let option_a =
+        doc.add_object(dictionary! { "Subtype" => "Widget", "AS" => "Off", "P" => page_id });
+      let option_b = doc.add_object(dictionary! { "Subtype" => "Widget", "AS" => "B" });
+      doc
+        .get_dictionary_mut(page_id)
+        .unwrap()
+        .set("Annots", vec![option_b.into()]);
+      let radio = doc.add_object(dictionary! {
+        "FT" => "Btn",
+        "Ff" => FF_RADIO,
+        "T" => literal("size"),
+        "V" => "B",
+        "Kids" => vec![option_a.into(), option_b.into()],
+      });
+      let unselected = doc.add_object(dictionary! {
+        "FT" => "Btn",
+        "Ff" => FF_RADIO,
+        "T" => literal("color"),
+        "Kids" => vec![
+          Object::Dictionary(dictionary! { "Subtype" => "Widget", "AS" => "Off" }),
+        ],
+      });
+      let name = doc.add_object(dictionary! { "T" => literal("name"), "V" => literal("Ada") });
+      let person = doc.add_object(dictionary! {
+        "FT" => "Tx",
+        "T" => literal("person"),
+        "Kids" => vec![name.into()],
+      });
+      vec![radio.into(), unselected.into(), person.into()]
+    });
+
+    let fields = collect_form_fields(&doc).fields;
+    let summary: Vec<_> = fields
+      .iter()
+      .map(|f| {
+        (
+          f.name.as_str(),
+          f.field_type.as_str(),
+          f.value.as_deref(),
+          f.checked,
+          f.page,
+        )
+      })
+      .collect();
+    assert_eq!(
+      summary,
+      [
+        ("size", "radio", Some("B"), Some(true), Some(1)),
+        ("color", "radio", None, Some(false), None),
+        ("person.name", "text", Some("Ada"), None, None),
+      ]
+    );
+  }
+
+  #[test]
+  fn tab_order_follows_the_page_tabs_entry() {
+    let mut ids = Vec::new();
+    let mut doc = form_doc(|doc, page_id| {
+      let mut text = |doc: &mut Document, name: &str, rect: [i64; 4]| {
+        let id = doc.add_object(dictionary! {
+          "FT" => "Tx",
+          "T" => literal(name),
+          "Subtype" => "Widget",
+          "Rect" => rect.iter().map(|&v| v.into()).collect::<Vec<Object>>(),
+        });
+        ids.push(id);
+        id
+      };
+      // Laid out as "top" above a row of "left" and "right"
+      let right = text(doc, "right", [60, 40, 90, 50]);
+      let top = text(doc, "top", [10, 80, 90, 90]);
+      let left = text(doc, "left", [10, 40, 40, 50]);
+      doc
+        .get_dictionary_mut(page_id)
+        .unwrap()
+        .set("Annots", vec![right.into(), top.into(), left.into()]);
+      vec![top.into(), left.into(), right.into()]
+    });
+    let page_id = doc.page_iter().next().unwrap();
+    let names = |doc: &Document| {
+      let result = collect_form_fields(doc);
+      result
+        .tab_order
+        .iter()
+        .map(|id| {
+          let field = result
+            .fields
+            .iter()
+            .find(|f| f.object_id.as_ref() == Some(id));
+          field.unwrap().name.clone()
+        })
+        .collect::<Vec<_>>()
+    };
+
+    // No /Tabs: the /Annots order
+    assert_eq!(names(&doc), ["right", "top", "left"]);
+
+    let set_tabs = |doc: &mut Document, tabs: &str| {
+      doc.get_dictionary_mut(page_id).unwrap().set("Tabs", tabs);
+    };
+    set_tabs(&mut doc, "R");
+    assert_eq!(names(&doc), ["top", "left", "right"]);
+    set_tabs(&mut doc, "C");
+    assert_eq!(names(&doc), ["top", "left", "right"]);
+
+    // Structure order lists "left" before "right"; "top" isn't tagged
+    let (right, left) = (ids[0], ids[2]);
+    let objr = |id: ObjectId| Object::Dictionary(dictionary! { "Type" => "OBJR", "Obj" => id });
+    let form = doc.add_object(dictionary! { "S" => "Form", "K" => vec![objr(left), objr(right)] });
+    let root = doc.add_object(dictionary! { "Type" => "StructTreeRoot", "K" => form });
+    doc.catalog_mut().unwrap().set("StructTreeRoot", root);
+    set_tabs(&mut doc, "S");
+    assert_eq!(names(&doc), ["left", "right", "top"]);
+  }
+
+  #[test]
+  fn form_defaults_report_appearance_and_fonts() {
+    let doc = form_doc(|_, _| Vec::new());
+    let result = collect_form_fields(&doc);
+    assert_eq!(result.default_appearance.as_deref(), Some("/Helv 0 Tf 0 g"));
+    assert_eq!(result.fonts, ["Helv", "ZaDb"]);
+  }
+}
@@ -1,60 +1,195 @@
-use crate::types::RawPageImage;
-use image::{DynamicImage, ImageBuffer, ImageFormat};
+use crate::core::layout::MAX_FORM_DEPTH;
+use crate::core::objects::{deref, deref_dict};
+use crate::core::tagged::page_image_alt_texts;
+use crate::types::{ImageDecodeFailure, PageImageBytes, RawImageExtraction, RawPageImage};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::imageops::FilterType;
+use image::{
+  DynamicImage, ExtendedColorType, ImageBuffer, ImageDecoder, ImageEncoder, ImageFormat,
+};
 use lopdf::{Document, Object, ObjectId};
+use napi::Error;
 use rayon::prelude::*;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// JPEG quality used when `ImageOptions.jpegQuality` is not set.
+pub(crate) const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// How extracted images are re-encoded.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum ImageEncoding {
+  Png,
+  /// Lossy, without an alpha channel; `quality` is 1–100.
+  Jpeg {
+    quality: u8,
+  },
+}
+
+impl ImageEncoding {
+  /// Extension of spilled files in this encoding.
+  fn extension(self) -> &'static str {
+    match self {
+      ImageEncoding::Png => "png",
+      ImageEncoding::Jpeg { .. } => "jpg",
+    }
+  }
+}
+
+/// Per-call image extraction settings, resolved from `ImageOptions`.
+#[derive(Clone)]
+pub(crate) struct ImageConfig {
+  /// Collect an `ImageDecodeFailure` for every image XObject that couldn't be
+  /// decoded instead of silently skipping it.
+  pub report_failures: bool,
+  /// Downscale images whose longest side exceeds this many pixels.
+  pub max_dimension: Option<u32>,
+  /// Write PNGs to this directory instead of keeping them in memory once
+  /// `spill_threshold_bytes` of image data is held.
+  pub spill_dir: Option<PathBuf>,
+  /// Bytes of PNG data kept in memory before images spill to `spill_dir`.
+  pub spill_threshold_bytes: u64,
+  /// Convert images to grayscale before encoding.
+  pub grayscale: bool,
+  /// Convert images to black and white: grayscale values at or above this
+  /// threshold become white, the rest black.
+  pub binarize: Option<u8>,
+  /// Compute the mean color and a grayscale check of each decoded image.
+  pub compute_stats: bool,
+  /// Rotate/flip DCTDecode images upright according to their EXIF
+  /// orientation tag.
+  pub apply_exif_orientation: bool,
+  /// Output format of the re-encoded images.
+  pub encoding: ImageEncoding,
+}
+
+impl Default for ImageConfig {
+  fn default() -> Self {
+    Self {
+      report_failures: false,
+      max_dimension: None,
+      spill_dir: None,
+      spill_threshold_bytes: 0,
+      grayscale: false,
+      binarize: None,
+      compute_stats: false,
+      apply_exif_orientation: true,
+      encoding: ImageEncoding::Png,
+    }
+  }
+}
 
 pub(crate) fn extract_images_raw(doc: &Document) -> Vec<RawPageImage> {
+  // Without a spill directory nothing is written, so this can't fail
+  extract_images_with_config(doc, &ImageConfig::default())
+    .map(|extraction| extraction.images)
+    .unwrap_or_default()
+}
+
+pub(crate) fn extract_images_with_config(
+  doc: &Document,
+  config: &ImageConfig,
+) -> napi::Result<RawImageExtraction> {
+  let held = AtomicU64::new(0);
+  let spill = config.spill_dir.as_deref().map(|dir| Spill {
+    dir,
+    call_dir: OnceLock::new(),
+    extension: config.encoding.extension(),
+    threshold: config.spill_threshold_bytes,
+    held: &held,
+  });
   let pages = doc.get_pages();
   let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
-  let mut results: Vec<RawPageImage> = page_entries
+  let per_page: Vec<(Vec<RawPageImage>, Vec<ImageDecodeFailure>)> = page_entries
     .par_iter()
-    .flat_map(|&(page_num, page_id)| collect_page_images_raw(doc, page_id, page_num))
-    .collect();
-  results.sort_unstable_by_key(|r| (r.page, r.image_index));
-  results
+    .map(|&(page_num, page_id)| {
+      collect_page_images_raw(doc, page_id, page_num, config, spill.as_ref())
+    })
+    .collect::<napi::Result<_>>()?;
+  let mut images = Vec::new();
+  let mut failures = Vec::new();
+  for (page_images, page_failures) in per_page {
+    images.extend(page_images);
+    failures.extend(page_failures);
+  }
+  images.sort_unstable_by_key(|r| (r.page, r.image_index));
+  failures.sort_by_key(|f| f.page);
+  Ok(RawImageExtraction { images, failures })
 }
 
-/// Decode all image XObjects on a page to DynamicImages (no PNG encoding).
-/// Used by OCR to avoid the PNG encode→decode roundtrip and by render for empty page detection.
-#[cfg(any(feature = "ocr", feature = "render"))]
-pub(crate) fn collect_page_decoded_images(doc: &Document, page_id: ObjectId) -> Vec<DynamicImage> {
-  let mut decoded = Vec::new();
-
-  let xobjects = match get_page_xobjects(doc, page_id) {
-    Some(x) => x,
-    None => return decoded,
-  };
-
-  let referenced_names = get_referenced_xobject_names(doc, page_id);
+/// Extractions that have spilled so far, numbering their subdirectories.
+static SPILL_CALLS: AtomicU64 = AtomicU64::new(0);
+
+/// Where images go once the PNG data held across an extraction (`held`)
+/// would exceed `threshold` bytes: a subdirectory of `dir` of this call's
+/// own, created by the first image that spills.
+struct Spill<'a> {
+  dir: &'a Path,
+  call_dir: OnceLock<Result<PathBuf, String>>,
+  extension: &'static str,
+  threshold: u64,
+  held: &'a AtomicU64,
+}
 
-  for (name, obj_ref) in xobjects.iter() {
-    if !referenced_names.is_empty() && !referenced_names.contains(name) {
-      continue;
+impl Spill<'_> {
+  /// Keep `image` in memory while the budget allows; otherwise write it to
+  /// `page-<page>-image-<index>.<extension>`, clearing `data` and setting
+  /// `file_path`. Called as each image is encoded, so a page with many large
+  /// images never holds more than one of them past the budget.
+  fn place(&self, image: &mut RawPageImage) -> napi::Result<()> {
+    let len = image.data.len() as u64;
+    if self.held.fetch_add(len, Ordering::Relaxed) + len <= self.threshold {
+      return Ok(());
     }
+    self.held.fetch_sub(len, Ordering::Relaxed);
+    let call_dir = self
+      .call_dir
+      .get_or_init(|| create_spill_dir(self.dir))
+      .as_ref()
+      .map_err(|reason| Error::from_reason(reason.clone()))?;
+    let path = call_dir.join(format!(
+      "page-{}-image-{}.{}",
+      image.page, image.image_index, self.extension
+    ));
+    std::fs::write(&path, &image.data)
+      .map_err(|e| Error::from_reason(format!("Failed to write {}: {e}", path.display())))?;
+    image.data = Vec::new();
+    image.file_path = Some(path.to_string_lossy().to_string());
+    Ok(())
+  }
+}
 
-    let obj_id = match obj_ref {
-      Object::Reference(id) => *id,
-      _ => continue,
-    };
-
-    let stream = match doc.get_object(obj_id) {
-      Ok(Object::Stream(s)) => s,
-      _ => continue,
-    };
-
-    let subtype = stream.dict.get(b"Subtype").ok().and_then(|v| {
-      if let Object::Name(n) = v {
-        Some(n.as_slice())
-      } else {
-        None
-      }
-    });
-    if subtype != Some(b"Image") {
-      continue;
+/// Create a new `<pid>-<n>` directory under `dir`, so files spilled by one
+/// extraction never overwrite those another call's results still point to.
+fn create_spill_dir(dir: &Path) -> Result<PathBuf, String> {
+  let failed = |path: &Path, e: std::io::Error| {
+    format!("Failed to create spill directory {}: {e}", path.display())
+  };
+  std::fs::create_dir_all(dir).map_err(|e| failed(dir, e))?;
+  loop {
+    let call = SPILL_CALLS.fetch_add(1, Ordering::Relaxed);
+    let path = dir.join(format!("{}-{call}", std::process::id()));
+    match std::fs::create_dir(&path) {
+      Ok(()) => return Ok(path),
+      // Left behind by an earlier process with the same id
+      Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+      Err(e) => return Err(failed(&path, e)),
     }
+  }
+}
+
+/// Decode all image XObjects on a page to DynamicImages (no PNG encoding),
+/// including those inside the Form XObjects it paints (see
+/// `page_image_xobjects`). Used by OCR to avoid the PNG encode→decode
+/// roundtrip, by render for empty page detection and to tile contact sheets.
+pub(crate) fn collect_page_decoded_images(doc: &Document, page_id: ObjectId) -> Vec<DynamicImage> {
+  let mut decoded = Vec::new();
 
+  for (_, _, stream) in page_image_xobjects(doc, page_id) {
     let width = get_dict_int(&stream.dict, b"Width").unwrap_or(0) as u32;
     let height = get_dict_int(&stream.dict, b"Height").unwrap_or(0) as u32;
     let bpc = get_dict_int(&stream.dict, b"BitsPerComponent").unwrap_or(8) as u32;
@@ -65,20 +200,20 @@ pub(crate) fn collect_page_decoded_images(doc: &Document, page_id: ObjectId) ->
     let color_space = resolve_color_space(&stream.dict, doc);
     let filter = resolve_filter(&stream.dict);
 
-    let content = match filter.as_str() {
-      "DCTDecode" | "JPXDecode" => stream.content.clone(),
+    let pixels = match filter.as_str() {
+      "DCTDecode" | "JPXDecode" => Ok((decode_filter_chain(stream), color_space, bpc)),
       _ => {
-        let mut s = stream.clone();
-        if s.decompress().is_ok() {
-          s.content
-        } else {
-          stream.content.clone()
-        }
+        let channels = color_space_channels(&color_space);
+        let content = decompress_stream_content(doc, stream, width, height, channels, bpc);
+        apply_tint_transform(doc, &stream.dict, content, width, height, bpc, &color_space)
       }
     };
+    let Ok((content, color_space, bpc)) = pixels else {
+      continue;
+    };
 
     // Skip SMask for OCR — to_rgb8() drops alpha anyway
-    if let Some(img) =
+    if let Ok(img) =
       decode_xobject_to_dynamic_image(&content, width, height, bpc, &color_space, &filter, None)
     {
       decoded.push(img);
@@ -88,23 +223,98 @@ pub(crate) fn collect_page_decoded_images(doc: &Document, page_id: ObjectId) ->
   decoded
 }
 
-fn collect_page_images_raw(doc: &Document, page_id: ObjectId, page_num: u32) -> Vec<RawPageImage> {
-  let mut images = Vec::new();
+/// Distinct filter names of the image XObjects painted on any page, directly
+/// or inside a Form XObject, without decoding them. For filter chains this is
+/// the innermost (codec) filter, as in `PageImage.filter`; unfiltered images
+/// report `"None"`.
+pub(crate) fn collect_image_filters(doc: &Document) -> Vec<String> {
+  let pages = doc.get_pages();
+  let page_ids: Vec<ObjectId> = pages.values().copied().collect();
+  let per_page: Vec<Vec<String>> = page_ids
+    .par_iter()
+    .map(|&page_id| {
+      page_image_xobjects(doc, page_id)
+        .into_iter()
+        .map(|(_, _, s)| resolve_filter(&s.dict))
+        .collect()
+    })
+    .collect();
+  let distinct: BTreeSet<String> = per_page.into_iter().flatten().collect();
+  distinct.into_iter().collect()
+}
+
+/// Per page, the number of distinct image XObjects it paints and the sum of
+/// their stored stream lengths, without decoding anything. Every page is
+/// listed, including those without images.
+pub(crate) fn collect_image_bytes_per_page(doc: &Document) -> Vec<PageImageBytes> {
+  let pages: Vec<(u32, ObjectId)> = doc.get_pages().into_iter().collect();
+  pages
+    .par_iter()
+    .map(|&(page, page_id)| {
+      let mut seen = HashSet::new();
+      let (mut total, mut count) = (0u64, 0u32);
+      for (_, obj_id, stream) in page_image_xobjects(doc, page_id) {
+        if seen.insert(obj_id) {
+          total += stream.content.len() as u64;
+          count += 1;
+        }
+      }
+      PageImageBytes {
+        page,
+        total_stream_bytes: total.min(u32::MAX as u64) as u32,
+        image_count: count,
+      }
+    })
+    .collect()
+}
 
+/// Image XObjects painted on a page via `Do`, in resource-dictionary order,
+/// with their resource names. Images inside Form XObjects the page paints
+/// follow, found through each form's own `/Resources` and named by their
+/// path, e.g. `Fm1/Im3`.
+fn page_image_xobjects(
+  doc: &Document,
+  page_id: ObjectId,
+) -> Vec<(Vec<u8>, ObjectId, &lopdf::Stream)> {
   // Get XObjects from page resources (with parent inheritance)
   let xobjects = match get_page_xobjects(doc, page_id) {
     Some(x) => x,
-    None => return images,
+    None => return Vec::new(),
   };
 
   // Get the set of XObject names actually referenced by Do operators in the content stream
   let referenced_names = get_referenced_xobject_names(doc, page_id);
 
-  let mut img_index = 0u32;
+  let mut found = Vec::new();
+  let mut visited_forms = HashSet::new();
+  collect_image_xobjects(
+    doc,
+    xobjects,
+    &referenced_names,
+    &[],
+    0,
+    &mut visited_forms,
+    &mut found,
+  );
+  found
+}
 
+/// Append the images of one XObject resource dictionary to `found`, then
+/// descend into its Form XObjects. Only names in `painted` count, unless it
+/// is empty. `prefix` is the path of the `depth` forms leading here.
+fn collect_image_xobjects<'a>(
+  doc: &'a Document,
+  xobjects: &lopdf::Dictionary,
+  painted: &HashSet<Vec<u8>>,
+  prefix: &[u8],
+  depth: u32,
+  visited_forms: &mut HashSet<ObjectId>,
+  found: &mut Vec<(Vec<u8>, ObjectId, &'a lopdf::Stream)>,
+) {
+  let mut forms = Vec::new();
   for (name, obj_ref) in xobjects.iter() {
-    // Only process XObjects actually painted on the page via Do operators
-    if !referenced_names.is_empty() && !referenced_names.contains(name) {
+    // Only process XObjects actually painted via Do operators
+    if !painted.is_empty() && !painted.contains(name) {
       continue;
     }
 
@@ -118,7 +328,6 @@ fn collect_page_images_raw(doc: &Document, page_id: ObjectId, page_num: u32) ->
       _ => continue,
     };
 
-    // Only process Image XObjects
     let subtype = stream.dict.get(b"Subtype").ok().and_then(|v| {
       if let Object::Name(n) = v {
         Some(n.as_slice())
@@ -127,86 +336,489 @@ fn collect_page_images_raw(doc: &Document, page_id: ObjectId, page_num: u32) ->
       }
     });
 
-    if subtype != Some(b"Image") {
-      continue;
+    match subtype {
+      // A nested image the page also paints directly is listed once
+      Some(b"Image") if prefix.is_empty() || !found.iter().any(|(_, id, _)| *id == obj_id) => {
+        found.push(([prefix, name.as_slice()].concat(), obj_id, stream));
+      }
+      Some(b"Form") => forms.push((name, obj_id, stream)),
+      _ => {}
     }
+  }
 
-    let width = get_dict_int(&stream.dict, b"Width").unwrap_or(0) as u32;
-    let height = get_dict_int(&stream.dict, b"Height").unwrap_or(0) as u32;
-    let bpc = get_dict_int(&stream.dict, b"BitsPerComponent").unwrap_or(8) as u32;
-
-    if width == 0 || height == 0 {
+  if depth >= MAX_FORM_DEPTH {
+    return;
+  }
+  for (name, obj_id, form) in forms {
+    if !visited_forms.insert(obj_id) {
+      continue;
+    }
+    // A form without /Resources uses the page's, already searched above
+    let Some(form_xobjects) = form
+      .dict
+      .get(b"Resources")
+      .ok()
+      .and_then(|r| deref_dict(doc, r))
+      .and_then(|r| deref_dict(doc, r.get(b"XObject").ok()?))
+    else {
+      continue;
+    };
+    let mut form_painted = HashSet::new();
+    let content = form
+      .decompressed_content()
+      .unwrap_or_else(|_| form.content.clone());
+    collect_do_names(&content, &mut form_painted);
+    if form_painted.is_empty() {
       continue;
     }
+    let form_prefix = [prefix, name.as_slice(), b"/"].concat();
+    collect_image_xobjects(
+      doc,
+      form_xobjects,
+      &form_painted,
+      &form_prefix,
+      depth + 1,
+      visited_forms,
+      found,
+    );
+  }
+}
 
-    let color_space = resolve_color_space(&stream.dict, doc);
-    let filter = resolve_filter(&stream.dict);
+/// Decoded images of one page, skipping any that can't be decoded.
+pub(crate) fn extract_page_images_raw(
+  doc: &Document,
+  page_id: ObjectId,
+  page_num: u32,
+) -> Vec<RawPageImage> {
+  // Without a spill nothing is written, so this can't fail
+  collect_page_images_raw(doc, page_id, page_num, &ImageConfig::default(), None)
+    .map(|(images, _)| images)
+    .unwrap_or_default()
+}
+
+fn collect_page_images_raw(
+  doc: &Document,
+  page_id: ObjectId,
+  page_num: u32,
+  config: &ImageConfig,
+  spill: Option<&Spill>,
+) -> napi::Result<(Vec<RawPageImage>, Vec<ImageDecodeFailure>)> {
+  let mut images = Vec::new();
+  let mut failures = Vec::new();
+  let mut img_index = 0u32;
 
-    let channels: u32 = match color_space.as_str() {
-      "DeviceRGB" | "ICCBased3" | "CalRGB" => 3,
-      "DeviceGray" | "ICCBased1" | "CalGray" => 1,
-      "DeviceCMYK" | "ICCBased4" => 4,
-      _ => 3,
+  let xobjects = page_image_xobjects(doc, page_id);
+  let mut alt_texts = page_image_alt_texts(doc, page_id, &xobjects);
+  for (name, obj_id, stream) in xobjects {
+    let image = match encode_image_stream(doc, stream, config) {
+      Ok(image) => image,
+      Err(reason) => {
+        if config.report_failures {
+          failures.push(ImageDecodeFailure {
+            page: page_num,
+            xobject_name: String::from_utf8_lossy(&name).to_string(),
+            filter: resolve_filter(&stream.dict),
+            reason,
+            recovered: false,
+          });
+        }
+        continue;
+      }
     };
+    if config.report_failures
+      && let Some(reason) = &image.repair
+    {
+      failures.push(ImageDecodeFailure {
+        page: page_num,
+        xobject_name: String::from_utf8_lossy(&name).to_string(),
+        filter: image.filter.clone(),
+        reason: reason.clone(),
+        recovered: true,
+      });
+    }
 
-    // Step 4: Skip the full stream clone for DCT/JPX — they're already in their
-    // target encoded format and don't need lopdf decompression.
-    let content = match filter.as_str() {
-      "DCTDecode" | "JPXDecode" => stream.content.clone(),
-      _ => decompress_stream_content(doc, stream, width, height, channels, bpc),
+    let (mean_color, is_grayscale) = match image.stats {
+      Some(stats) => (Some(stats.mean_color), Some(stats.is_grayscale)),
+      None => (None, None),
+    };
+    let mut raw = RawPageImage {
+      page: page_num,
+      image_index: img_index,
+      width: image.width,
+      height: image.height,
+      data: image.data,
+      color_space: image.color_space,
+      bits_per_component: image.bits_per_component,
+      filter: image.filter,
+      xobject_name: String::from_utf8_lossy(&name).to_string(),
+      object_id: format!("{} {} obj", obj_id.0, obj_id.1),
+      alt_text: alt_texts.remove(&name),
+      file_path: None,
+      mean_color,
+      is_grayscale,
+      compressed_bytes: stream.content.len() as u32,
+      compression_ratio: compression_ratio(image.decoded_bytes, stream.content.len()),
     };
+    if let Some(spill) = spill {
+      spill.place(&mut raw)?;
+    }
+    images.push(raw);
+
+    img_index += 1;
+  }
+
+  Ok((images, failures))
+}
+
+/// Decoded size over stored size; `0` for an empty stream.
+fn compression_ratio(decoded_bytes: u64, compressed_bytes: usize) -> f64 {
+  if compressed_bytes == 0 {
+    0.0
+  } else {
+    decoded_bytes as f64 / compressed_bytes as f64
+  }
+}
+
+/// One small preview per page: the page's largest image (by declared pixel
+/// count) is decoded, downscaled so its longest side is at most `max_edge`
+/// and encoded as PNG; the other images on the page are never decoded. When
+/// the largest image can't be decoded the next largest is tried. Pages
+/// without a decodable image are omitted. `image_index` is the image's
+/// position among the page's image XObjects.
+pub(crate) fn extract_sized_thumbnails_raw(doc: &Document, max_edge: u32) -> Vec<RawPageImage> {
+  let config = ImageConfig {
+    max_dimension: Some(max_edge),
+    ..ImageConfig::default()
+  };
+  let pages = doc.get_pages();
+  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
+  let mut thumbnails: Vec<RawPageImage> = page_entries
+    .par_iter()
+    .filter_map(|&(page_num, page_id)| {
+      let xobjects = page_image_xobjects(doc, page_id);
+      let alt_texts = page_image_alt_texts(doc, page_id, &xobjects);
+      let mut candidates: Vec<_> = xobjects
+        .into_iter()
+        .enumerate()
+        .map(|(i, xobject)| (i as u32, xobject))
+        .collect();
+      // Stable sort: among equally large images the first painted wins
+      candidates.sort_by_key(|(_, (_, _, stream))| {
+        let width = get_dict_int(&stream.dict, b"Width").unwrap_or(0).max(0) as u64;
+        let height = get_dict_int(&stream.dict, b"Height").unwrap_or(0).max(0) as u64;
+        std::cmp::Reverse(width * height)
+      });
+      candidates
+        .into_iter()
+        .find_map(|(index, (name, obj_id, stream))| {
+          let image = encode_image_stream(doc, stream, &config).ok()?;
+          Some(RawPageImage {
+            page: page_num,
+            image_index: index,
+            width: image.width,
+            height: image.height,
+            data: image.data,
+            color_space: image.color_space,
+            bits_per_component: image.bits_per_component,
+            filter: image.filter,
+            xobject_name: String::from_utf8_lossy(&name).to_string(),
+            object_id: format!("{} {} obj", obj_id.0, obj_id.1),
+            alt_text: alt_texts.get(&name).cloned(),
+            file_path: None,
+            mean_color: None,
+            is_grayscale: None,
+            compressed_bytes: stream.content.len() as u32,
+            compression_ratio: compression_ratio(image.decoded_bytes, stream.content.len()),
+          })
+        })
+    })
+    .collect();
+  thumbnails.sort_unstable_by_key(|t| t.page);
+  thumbnails
+}
 
-    // Check for SMask (alpha channel)
-    let smask_data = get_smask_data(doc, &stream.dict);
+/// Longest side of a contact sheet cell, in pixels.
+const CONTACT_SHEET_CELL: u32 = 256;
 
-    let png_data = match encode_to_png(
+/// Space between contact sheet cells and around the edge, in pixels.
+const CONTACT_SHEET_GAP: u32 = 8;
+
+/// All decodable images of a page tiled into one PNG, `columns` per row on a
+/// white background. Each image is shrunk to fit a 256px cell and centred in
+/// it; smaller images keep their size. `None` when the page has no decodable
+/// images.
+pub(crate) fn page_contact_sheet(
+  doc: &Document,
+  page_id: ObjectId,
+  columns: u32,
+) -> Option<Vec<u8>> {
+  let images = collect_page_decoded_images(doc, page_id);
+  if images.is_empty() {
+    return None;
+  }
+  let count = images.len() as u32;
+  let columns = columns.clamp(1, count);
+  let rows = count.div_ceil(columns);
+  let pitch = CONTACT_SHEET_CELL + CONTACT_SHEET_GAP;
+  let mut sheet = image::RgbaImage::from_pixel(
+    CONTACT_SHEET_GAP + columns * pitch,
+    CONTACT_SHEET_GAP + rows * pitch,
+    image::Rgba([255, 255, 255, 255]),
+  );
+  for (i, img) in images.into_iter().enumerate() {
+    let tile = downscale(img, CONTACT_SHEET_CELL).to_rgba8();
+    let (column, row) = (i as u32 % columns, i as u32 / columns);
+    let x = CONTACT_SHEET_GAP + column * pitch + (CONTACT_SHEET_CELL - tile.width()) / 2;
+    let y = CONTACT_SHEET_GAP + row * pitch + (CONTACT_SHEET_CELL - tile.height()) / 2;
+    image::imageops::overlay(&mut sheet, &tile, x as i64, y as i64);
+  }
+  let mut png = Cursor::new(Vec::new());
+  DynamicImage::ImageRgba8(sheet)
+    .write_to(&mut png, ImageFormat::Png)
+    .ok()?;
+  Some(png.into_inner())
+}
+
+/// An image stream decoded and re-encoded as PNG, or as JPEG with
+/// `ImageEncoding::Jpeg`.
+struct EncodedImage {
+  data: Vec<u8>,
+  /// Output size, after any downscaling.
+  width: u32,
+  height: u32,
+  color_space: String,
+  bits_per_component: u32,
+  filter: String,
+  /// How a malformed image was repaired to decode it, if it had to be.
+  repair: Option<String>,
+  /// Set when `compute_stats` is on.
+  stats: Option<ColorStats>,
+  /// Size of the pixel data once its filters are undone.
+  decoded_bytes: u64,
+}
+
+/// Color statistics of an image's decoded pixels.
+struct ColorStats {
+  /// Mean `[r, g, b]`; gray images repeat their mean luma.
+  mean_color: Vec<u8>,
+  /// Every pixel's channels are within `GRAY_TOLERANCE` of each other.
+  is_grayscale: bool,
+}
+
+/// Largest difference between a pixel's channels for it to count as gray.
+const GRAY_TOLERANCE: u8 = 8;
+
+/// Statistics over interleaved 8-bit samples with `channels` per pixel:
+/// gray (1), gray + alpha (2), RGB (3) or RGBA (4). Alpha is ignored.
+fn pixel_stats(samples: &[u8], channels: usize) -> ColorStats {
+  let color_channels = if channels <= 2 { 1 } else { 3 };
+  let mut sums = [0u64; 3];
+  let mut is_grayscale = true;
+  let mut count = 0u64;
+  for pixel in samples.chunks_exact(channels) {
+    let color = &pixel[..color_channels];
+    for (sum, &v) in sums.iter_mut().zip(color) {
+      *sum += v as u64;
+    }
+    if is_grayscale && color_channels == 3 {
+      let (min, max) = (color.iter().min(), color.iter().max());
+      is_grayscale = max
+        .zip(min)
+        .is_some_and(|(max, min)| max - min <= GRAY_TOLERANCE);
+    }
+    count += 1;
+  }
+  let mean = |sum: u64| sum.checked_div(count).unwrap_or(0) as u8;
+  let mean_color = if color_channels == 1 {
+    vec![mean(sums[0]); 3]
+  } else {
+    sums.iter().map(|&s| mean(s)).collect()
+  };
+  ColorStats {
+    mean_color,
+    is_grayscale,
+  }
+}
+
+/// `pixel_stats` of a decoded image, converting it to RGBA only when it isn't
+/// already 8-bit.
+fn image_stats(img: &DynamicImage) -> ColorStats {
+  match img {
+    DynamicImage::ImageLuma8(gray) => pixel_stats(gray.as_raw(), 1),
+    DynamicImage::ImageLumaA8(gray) => pixel_stats(gray.as_raw(), 2),
+    DynamicImage::ImageRgb8(rgb) => pixel_stats(rgb.as_raw(), 3),
+    DynamicImage::ImageRgba8(rgba) => pixel_stats(rgba.as_raw(), 4),
+    other => pixel_stats(other.to_rgba8().as_raw(), 4),
+  }
+}
+
+/// Decode an image stream (an Image XObject or a page `/Thumb`) and encode it
+/// as `config.encoding` says. The error says why the image couldn't be
+/// decoded.
+fn encode_image_stream(
+  doc: &Document,
+  stream: &lopdf::Stream,
+  config: &ImageConfig,
+) -> Result<EncodedImage, String> {
+  let width = get_dict_int(&stream.dict, b"Width").unwrap_or(0) as u32;
+  let height = get_dict_int(&stream.dict, b"Height").unwrap_or(0) as u32;
+  let bpc = get_dict_int(&stream.dict, b"BitsPerComponent").unwrap_or(8) as u32;
+  let filter = resolve_filter(&stream.dict);
+
+  if width == 0 || height == 0 {
+    return Err("missing or zero /Width or /Height".to_string());
+  }
+
+  let color_space = resolve_color_space(&stream.dict, doc);
+  let channels = color_space_channels(&color_space);
+
+  // DCT/JPX are already in their target encoded format — only strip any
+  // ASCII/Flate wrappers in front of them. Spot-color images are decoded in
+  // their alternate space, but still report their own color space and depth.
+  let samples = (!matches!(filter.as_str(), "DCTDecode" | "JPXDecode"))
+    .then(|| decompress_stream_content(doc, stream, width, height, channels, bpc));
+
+  // Alpha channel: an /SMask takes precedence over a /Mask
+  let alpha = get_smask_data(doc, &stream.dict).or_else(|| {
+    let samples = samples.as_deref();
+    get_mask_data(doc, &stream.dict, samples, width, height, channels, bpc)
+  });
+
+  // Filtered streams are sized as the samples they decompress to; DCT and
+  // JPX images as their decoded pixels at 8 bits per component
+  let sample_bytes = samples.as_ref().map(|s| s.len() as u64);
+  let (content, pixel_color_space, pixel_bpc) = match samples {
+    None => (decode_filter_chain(stream), color_space.clone(), bpc),
+    Some(content) => {
+      apply_tint_transform(doc, &stream.dict, content, width, height, bpc, &color_space)?
+    }
+  };
+
+  if alpha.is_none()
+    && config.encoding == ImageEncoding::Png
+    && !matches!(filter.as_str(), "DCTDecode" | "JPXDecode")
+    && let Some(png) = encode_samples_as_png(
       &content,
       width,
       height,
-      bpc,
-      &color_space,
-      &filter,
-      smask_data.as_deref(),
-    ) {
-      Some(data) => data,
-      None => continue,
-    };
-
-    let xobject_name = String::from_utf8_lossy(name).to_string();
-    let object_id_str = format!("{} {} obj", obj_id.0, obj_id.1);
-
-    images.push(RawPageImage {
-      page: page_num,
-      image_index: img_index,
+      pixel_bpc,
+      &pixel_color_space,
+      config,
+    )
+  {
+    let stats = config.compute_stats.then(|| {
+      let channels = png_sample_layout(&pixel_color_space).map_or(1, |(_, c)| c);
+      let len = width as usize * height as usize * channels;
+      pixel_stats(&content[..len.min(content.len())], channels)
+    });
+    return Ok(EncodedImage {
+      data: png?,
       width,
       height,
-      data: png_data,
       color_space,
       bits_per_component: bpc,
       filter,
-      xobject_name,
-      object_id: object_id_str,
+      repair: None,
+      stats,
+      decoded_bytes: sample_bytes.unwrap_or_default(),
     });
-
-    img_index += 1;
   }
 
-  images
+  let decoded = decode_xobject_to_dynamic_image(
+    &content,
+    width,
+    height,
+    pixel_bpc,
+    &pixel_color_space,
+    &filter,
+    alpha.as_deref(),
+  )?;
+  let decoded = if filter == "DCTDecode" && config.apply_exif_orientation {
+    apply_jpeg_orientation(decoded, &content)
+  } else {
+    decoded
+  };
+  let repair = (!matches!(filter.as_str(), "DCTDecode" | "JPXDecode")
+    && decoded.height() != height)
+    .then(|| {
+      format!(
+        "declared /Height {height} does not match the pixel data; decoded {} rows",
+        decoded.height()
+      )
+    });
+  let stats = config.compute_stats.then(|| image_stats(&decoded));
+  let decoded_bytes = sample_bytes.unwrap_or_else(|| {
+    u64::from(decoded.width()) * u64::from(decoded.height()) * u64::from(channels)
+  });
+  let (data, out_width, out_height) = encode_decoded(decoded, config)?;
+
+  Ok(EncodedImage {
+    data,
+    width: out_width,
+    height: out_height,
+    color_space,
+    bits_per_component: bpc,
+    filter,
+    repair,
+    stats,
+    decoded_bytes,
+  })
+}
+
+/// Decode each page's `/Thumb` thumbnail image, when present. Pages without a
+/// thumbnail, or whose thumbnail can't be decoded, are skipped.
+pub(crate) fn extract_thumbnails_raw(doc: &Document) -> Vec<RawPageImage> {
+  let pages = doc.get_pages();
+  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
+  let mut thumbnails: Vec<RawPageImage> = page_entries
+    .par_iter()
+    .filter_map(|&(page_num, page_id)| {
+      let thumb_id = doc
+        .get_dictionary(page_id)
+        .ok()?
+        .get(b"Thumb")
+        .ok()?
+        .as_reference()
+        .ok()?;
+      let stream = doc.get_object(thumb_id).ok()?.as_stream().ok()?;
+      let image = encode_image_stream(doc, stream, &ImageConfig::default()).ok()?;
+      Some(RawPageImage {
+        page: page_num,
+        image_index: 0,
+        width: image.width,
+        height: image.height,
+        data: image.data,
+        color_space: image.color_space,
+        bits_per_component: image.bits_per_component,
+        filter: image.filter,
+        xobject_name: "Thumb".to_string(),
+        object_id: format!("{} {} obj", thumb_id.0, thumb_id.1),
+        alt_text: None,
+        file_path: None,
+        mean_color: None,
+        is_grayscale: None,
+        compressed_bytes: stream.content.len() as u32,
+        compression_ratio: compression_ratio(image.decoded_bytes, stream.content.len()),
+      })
+    })
+    .collect();
+  thumbnails.sort_unstable_by_key(|t| t.page);
+  thumbnails
 }
 
 /// Walk the page tree to find /Resources (handles inheritance from /Parent)
-fn get_page_xobjects(doc: &Document, page_id: ObjectId) -> Option<lopdf::Dictionary> {
+fn get_page_xobjects(doc: &Document, page_id: ObjectId) -> Option<&lopdf::Dictionary> {
   let resources = get_inherited_resources(doc, page_id)?;
   let xobject_obj = resources.get(b"XObject").ok()?;
-  resolve_to_dict(doc, xobject_obj)
+  deref_dict(doc, xobject_obj)
 }
 
-fn get_inherited_resources(doc: &Document, page_id: ObjectId) -> Option<lopdf::Dictionary> {
+fn get_inherited_resources(doc: &Document, page_id: ObjectId) -> Option<&lopdf::Dictionary> {
   let mut current_id = Some(page_id);
   while let Some(id) = current_id {
     let dict = doc.get_dictionary(id).ok()?;
     if let Ok(resources_obj) = dict.get(b"Resources") {
-      return resolve_to_dict(doc, resources_obj);
+      return deref_dict(doc, resources_obj);
     }
     // Walk up to /Parent
     current_id = dict.get(b"Parent").ok().and_then(|p| match p {
@@ -510,41 +1122,26 @@ fn decompress_stream_content(
   channels: u32,
   bpc: u32,
 ) -> Vec<u8> {
-  let bytes_per_sample = if bpc > 8 { 2u32 } else { 1u32 };
-  let row_bytes = (width * channels * bpc / 8) as usize;
-  let expected = (width * height * channels * bytes_per_sample) as usize;
+  // Rows of 1/2/4-bit samples are padded to a whole byte
+  let row_bytes = (width * channels * bpc).div_ceil(8) as usize;
+  let expected = height as usize * row_bytes;
   let predicted_len = height as usize * (row_bytes + 1);
 
-  // Check if the stream uses FlateDecode
-  let uses_flate = match stream.dict.get(b"Filter") {
-    Ok(Object::Name(n)) => n == b"FlateDecode",
-    Ok(Object::Array(arr)) => arr
-      .iter()
-      .any(|o| matches!(o, Object::Name(n) if n == b"FlateDecode")),
-    _ => false,
-  };
-
-  // Step 1: Raw inflate — bypass lopdf's decompress to avoid its buggy predictor handling
-  let content = if uses_flate {
-    raw_inflate(&stream.content).unwrap_or_else(|| {
-      // Fallback: let lopdf try (handles edge cases like chained filters)
-      let mut s = stream.clone();
-      if s.decompress().is_ok() {
-        s.content
-      } else {
-        stream.content.clone()
-      }
-    })
-  } else {
-    stream.content.clone()
-  };
+  // Step 1: Undo the filter chain — raw inflate bypasses lopdf's decompress to
+  // avoid its buggy predictor handling
+  let content = decode_filter_chain(stream);
 
   // Step 2: Apply predictor reversal if DecodeParms specifies one
   if let Some(dp) = resolve_decode_parms(doc, &stream.dict) {
     let predictor = get_dict_int(&dp, b"Predictor").unwrap_or(1);
 
-    // TIFF Predictor 2: horizontal differencing (same size as raw pixels)
-    if predictor == 2 && content.len() == expected {
+    // TIFF Predictor 2: horizontal differencing (same size as raw pixels).
+    // Sub-byte samples would need bit-level differencing, which we don't do.
+    // A row short of the declared height is repaired when decoding pixels
+    if predictor == 2
+      && bpc >= 8
+      && (content.len() == expected || content.len() + row_bytes == expected)
+    {
       let bpp = (channels * bpc / 8).max(1) as usize;
       let mut data = content;
       apply_tiff_predictor2(&mut data, bpp, row_bytes);
@@ -552,7 +1149,9 @@ fn decompress_stream_content(
     }
 
     // PNG Predictors 10-15: each row has a leading filter type byte
-    if (10..=15).contains(&predictor) && content.len() == predicted_len {
+    if (10..=15).contains(&predictor)
+      && (content.len() == predicted_len || content.len() + row_bytes + 1 == predicted_len)
+    {
       let bpp = (channels * bpc / 8).max(1) as usize;
       if let Some(unfiltered) = apply_png_predictor(&content, bpp, row_bytes) {
         return unfiltered;
@@ -563,6 +1162,109 @@ fn decompress_stream_content(
   content
 }
 
+/// Names in a stream's /Filter entry, in decoding order.
+fn stream_filters(dict: &lopdf::Dictionary) -> Vec<Vec<u8>> {
+  match dict.get(b"Filter") {
+    Ok(Object::Name(n)) => vec![n.clone()],
+    Ok(Object::Array(arr)) => arr
+      .iter()
+      .filter_map(|o| o.as_name().ok().map(<[u8]>::to_vec))
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+/// Apply a stream's generic filters in chain order (ASCII wrappers, Flate,
+/// RunLength). Image codecs such as DCTDecode and JPXDecode are left for the
+/// image decoder, so `[/ASCII85Decode /DCTDecode]` yields the raw JPEG bytes.
+/// Predictors are not applied here.
+fn decode_filter_chain(stream: &lopdf::Stream) -> Vec<u8> {
+  let mut content = stream.content.clone();
+  for filter in stream_filters(&stream.dict) {
+    content = match filter.as_slice() {
+      b"ASCIIHexDecode" => ascii_hex_decode(&content),
+      b"ASCII85Decode" => ascii85_decode(&content),
+      b"RunLengthDecode" => run_length_decode(&content),
+      b"FlateDecode" => match raw_inflate(&content) {
+        Some(inflated) => inflated,
+        None => {
+          // Fallback: let lopdf try the whole chain
+          let mut s = stream.clone();
+          return if s.decompress().is_ok() {
+            s.content
+          } else {
+            content
+          };
+        }
+      },
+      _ => content,
+    };
+  }
+  content
+}
+
+/// Decode ASCIIHexDecode data: hex digit pairs, whitespace ignored, `>` ends
+/// the data. A trailing odd digit is treated as if followed by `0`.
+fn ascii_hex_decode(data: &[u8]) -> Vec<u8> {
+  let mut output = Vec::with_capacity(data.len() / 2);
+  let mut high: Option<u8> = None;
+  for &b in data {
+    if b == b'>' {
+      break;
+    }
+    let Some(nibble) = (b as char).to_digit(16) else {
+      continue;
+    };
+    match high.take() {
+      Some(h) => output.push((h << 4) | nibble as u8),
+      None => high = Some(nibble as u8),
+    }
+  }
+  if let Some(h) = high {
+    output.push(h << 4);
+  }
+  output
+}
+
+/// Decode ASCII85Decode data: groups of five `!`–`u` characters encode four
+/// bytes, `z` stands for four zero bytes, whitespace is ignored and `~>` ends
+/// the data. A final partial group of n characters yields n − 1 bytes.
+fn ascii85_decode(data: &[u8]) -> Vec<u8> {
+  let data = data.strip_prefix(b"<~").unwrap_or(data);
+  let mut output = Vec::with_capacity(data.len() * 4 / 5);
+  let mut group = [0u8; 5];
+  let mut len = 0;
+  for &b in data {
+    match b {
+      b'~' => break,
+      b'z' if len == 0 => output.extend_from_slice(&[0; 4]),
+      b'!'..=b'u' => {
+        group[len] = b - b'!';
+        len += 1;
+        if len == 5 {
+          let value = group
+            .iter()
+            .fold(0u32, |acc, &d| acc.wrapping_mul(85).wrapping_add(d as u32));
+          output.extend_from_slice(&value.to_be_bytes());
+          len = 0;
+        }
+      }
+      _ => {}
+    }
+  }
+  if len > 1 {
+    // Pad with the highest digit so the kept bytes round correctly
+    for slot in group.iter_mut().skip(len) {
+      *slot = 84;
+    }
+    let value = group
+      .iter()
+      .fold(0u32, |acc, &d| acc.wrapping_mul(85).wrapping_add(d as u32));
+    output.extend_from_slice(&value.to_be_bytes()[..len - 1]);
+  }
+  output
+}
+
 /// Raw zlib inflate without any predictor handling.
 fn raw_inflate(data: &[u8]) -> Option<Vec<u8>> {
   use std::io::Read;
@@ -585,6 +1287,35 @@ fn raw_inflate(data: &[u8]) -> Option<Vec<u8>> {
   None
 }
 
+/// Decode RunLengthDecode (PackBits-style) data. A length byte `n` of 0–127
+/// copies the next `n + 1` bytes literally, 129–255 repeats the next byte
+/// `257 - n` times, and 128 marks end of data. Truncated input yields whatever
+/// was decoded so far.
+fn run_length_decode(data: &[u8]) -> Vec<u8> {
+  let mut output = Vec::with_capacity(data.len() * 2);
+  let mut i = 0;
+  while i < data.len() {
+    let n = data[i] as usize;
+    i += 1;
+    match n {
+      0..=127 => {
+        let end = (i + n + 1).min(data.len());
+        output.extend_from_slice(&data[i..end]);
+        i = end;
+      }
+      128 => break,
+      _ => {
+        let Some(&byte) = data.get(i) else {
+          break;
+        };
+        output.resize(output.len() + 257 - n, byte);
+        i += 1;
+      }
+    }
+  }
+  output
+}
+
 /// Reverse TIFF Predictor 2 (horizontal differencing) in-place.
 /// Each byte after the first `bpp` bytes in each row is a delta from the previous byte.
 fn apply_tiff_predictor2(data: &mut [u8], bpp: usize, row_bytes: usize) {
@@ -629,51 +1360,125 @@ fn get_smask_data(doc: &Document, dict: &lopdf::Dictionary) -> Option<Vec<u8>> {
   let smask_width = get_dict_int(&smask_stream.dict, b"Width").unwrap_or(0) as u32;
   let smask_height = get_dict_int(&smask_stream.dict, b"Height").unwrap_or(0) as u32;
   let smask_bpc = get_dict_int(&smask_stream.dict, b"BitsPerComponent").unwrap_or(8) as u32;
-  Some(decompress_stream_content(
-    doc,
-    smask_stream,
-    smask_width,
-    smask_height,
-    1,
-    smask_bpc,
-  ))
-}
-
-fn resolve_to_dict(doc: &Document, obj: &Object) -> Option<lopdf::Dictionary> {
-  match obj {
-    Object::Dictionary(d) => Some(d.clone()),
-    Object::Reference(id) => match doc.get_object(*id).ok()? {
-      Object::Dictionary(d) => Some(d.clone()),
-      _ => None,
-    },
-    _ => None,
+  let data = decompress_stream_content(doc, smask_stream, smask_width, smask_height, 1, smask_bpc);
+  if smask_bpc < 8 {
+    Some(unpack_samples(
+      &data,
+      smask_width,
+      smask_height,
+      1,
+      smask_bpc,
+    ))
+  } else {
+    Some(data)
   }
 }
 
-fn get_dict_int(dict: &lopdf::Dictionary, key: &[u8]) -> Option<i64> {
-  match dict.get(key).ok()? {
-    Object::Integer(i) => Some(*i),
+/// Alpha channel from an image's `/Mask`: either a stencil mask image, whose
+/// 1 samples (0 with `/Decode [1 0]`) are masked out, or a color-key array
+/// `[min1 max1 … minN maxN]` whose matching pixels are made transparent.
+/// Color keys compare raw samples, so they need `samples` — the image data at
+/// its own depth and color space, before any tint transform.
+fn get_mask_data(
+  doc: &Document,
+  dict: &lopdf::Dictionary,
+  samples: Option<&[u8]>,
+  width: u32,
+  height: u32,
+  channels: u32,
+  bpc: u32,
+) -> Option<Vec<u8>> {
+  match deref(doc, dict.get(b"Mask").ok()?)? {
+    Object::Stream(mask) => stencil_mask_alpha(doc, mask, width, height),
+    Object::Array(ranges) => {
+      let ranges: Vec<u32> = ranges
+        .iter()
+        .map(|o| deref(doc, o).and_then(number).map(|n| n as u32))
+        .collect::<Option<_>>()?;
+      color_key_alpha(samples?, &ranges, width, height, channels, bpc)
+    }
     _ => None,
   }
 }
 
-fn resolve_color_space(dict: &lopdf::Dictionary, doc: &Document) -> String {
+/// Alpha of a stencil `/Mask`, scaled nearest-neighbour to the image size
+/// since the mask may have a different resolution than the image it masks.
+fn stencil_mask_alpha(
+  doc: &Document,
+  mask: &lopdf::Stream,
+  width: u32,
+  height: u32,
+) -> Option<Vec<u8>> {
+  let mask_width = get_dict_int(&mask.dict, b"Width").unwrap_or(0) as u32;
+  let mask_height = get_dict_int(&mask.dict, b"Height").unwrap_or(0) as u32;
+  if mask_width == 0 || mask_height == 0 {
+    return None;
+  }
+  let data = decompress_stream_content(doc, mask, mask_width, mask_height, 1, 1);
+  let samples = unpack_samples(&data, mask_width, mask_height, 1, 1);
+  if samples.len() < (mask_width * mask_height) as usize {
+    return None;
+  }
+  let inverted = number_array(doc, &mask.dict, b"Decode").is_some_and(|d| d.first() == Some(&1.0));
+  let (width, height) = (width as u64, height as u64);
+  let (mask_width, mask_height) = (mask_width as u64, mask_height as u64);
+  Some(
+    (0..height)
+      .flat_map(|y| {
+        let row = (y * mask_height / height) * mask_width;
+        (0..width).map(move |x| row + x * mask_width / width)
+      })
+      .map(|i| {
+        let painted = samples[i as usize] == 0;
+        if painted != inverted { 255 } else { 0 }
+      })
+      .collect(),
+  )
+}
+
+/// Alpha of a color-key `/Mask`: 0 where every component of a pixel lies in
+/// its `[min, max]` range, 255 elsewhere.
+fn color_key_alpha(
+  samples: &[u8],
+  ranges: &[u32],
+  width: u32,
+  height: u32,
+  channels: u32,
+  bpc: u32,
+) -> Option<Vec<u8>> {
+  let channels = channels as usize;
+  if ranges.len() < channels * 2 {
+    return None;
+  }
+  let row_bits = (width as usize * channels * bpc as usize).div_ceil(8) * 8;
+  let alpha = (0..height as usize)
+    .flat_map(|y| (0..width as usize).map(move |x| (y, x)))
+    .map(|(y, x)| {
+      let pixel = y * row_bits + x * channels * bpc as usize;
+      let keyed = (0..channels).all(|c| {
+        read_bits(samples, pixel + c * bpc as usize, bpc)
+          .is_some_and(|v| (ranges[c * 2]..=ranges[c * 2 + 1]).contains(&v))
+      });
+      if keyed { 0 } else { 255 }
+    })
+    .collect();
+  Some(alpha)
+}
+
+fn get_dict_int(dict: &lopdf::Dictionary, key: &[u8]) -> Option<i64> {
+  match dict.get(key).ok()? {
+    Object::Integer(i) => Some(*i),
+    _ => None,
+  }
+}
+
+fn resolve_color_space(dict: &lopdf::Dictionary, doc: &Document) -> String {
   let cs = match dict.get(b"ColorSpace") {
     Ok(obj) => obj,
     Err(_) => return "DeviceRGB".to_string(),
   };
 
-  match cs {
-    Object::Name(name) => String::from_utf8_lossy(name).to_string(),
-    Object::Reference(id) => match doc.get_object(*id) {
-      Ok(Object::Name(name)) => String::from_utf8_lossy(name).to_string(),
-      // ICCBased is typically [/ICCBased <stream ref>]
-      Ok(Object::Array(arr)) => parse_color_space_array(arr, doc),
-      _ => "DeviceRGB".to_string(),
-    },
-    Object::Array(arr) => parse_color_space_array(arr, doc),
-    _ => "DeviceRGB".to_string(),
-  }
+  color_space_name(cs, doc)
 }
 
 fn parse_color_space_array(arr: &[Object], doc: &Document) -> String {
@@ -699,9 +1504,408 @@ fn parse_color_space_array(arr: &[Object], doc: &Document) -> String {
     }
   }
 
+  // DeviceN carries its colorant count like ICCBased: [/DeviceN [names] alt fn]
+  if cs_name == "DeviceN"
+    && let Some(Object::Array(names)) = arr.get(1).and_then(|o| deref(doc, o))
+  {
+    return format!("DeviceN{}", names.len());
+  }
+
   cs_name
 }
 
+/// Components per pixel for a resolved color space name (RGB when unknown).
+fn color_space_channels(color_space: &str) -> u32 {
+  match color_space {
+    "DeviceRGB" | "ICCBased3" | "CalRGB" => 3,
+    "DeviceGray" | "ICCBased1" | "CalGray" => 1,
+    "DeviceCMYK" | "ICCBased4" => 4,
+    "Separation" => 1,
+    cs if cs.starts_with("DeviceN") => cs[7..].parse().unwrap_or(1),
+    _ => 3,
+  }
+}
+
+/// Resolve a color space object (a name, an array, or a reference to either)
+/// to the names used by `color_space_channels`.
+pub(crate) fn color_space_name(cs: &Object, doc: &Document) -> String {
+  match cs {
+    Object::Name(name) => String::from_utf8_lossy(name).to_string(),
+    Object::Reference(id) => match doc.get_object(*id) {
+      Ok(Object::Name(name)) => String::from_utf8_lossy(name).to_string(),
+      Ok(Object::Array(arr)) => parse_color_space_array(arr, doc),
+      _ => "DeviceRGB".to_string(),
+    },
+    Object::Array(arr) => parse_color_space_array(arr, doc),
+    _ => "DeviceRGB".to_string(),
+  }
+}
+
+fn number(obj: &Object) -> Option<f64> {
+  match obj {
+    Object::Integer(i) => Some(*i as f64),
+    Object::Real(r) => Some(*r as f64),
+    _ => None,
+  }
+}
+
+fn number_array(doc: &Document, dict: &lopdf::Dictionary, key: &[u8]) -> Option<Vec<f64>> {
+  match deref(doc, dict.get(key).ok()?)? {
+    Object::Array(arr) => arr.iter().map(|o| deref(doc, o).and_then(number)).collect(),
+    _ => None,
+  }
+}
+
+/// Read `bits` bits starting at bit `offset`, most significant bit first.
+fn read_bits(data: &[u8], offset: usize, bits: u32) -> Option<u32> {
+  match bits {
+    8 => data.get(offset / 8).map(|&b| b as u32),
+    16 => Some(u16::from_be_bytes([*data.get(offset / 8)?, *data.get(offset / 8 + 1)?]) as u32),
+    _ => (0..bits as usize).try_fold(0u32, |value, i| {
+      let bit = offset + i;
+      let byte = *data.get(bit / 8)?;
+      Some((value << 1) | ((byte >> (7 - bit % 8)) & 1) as u32)
+    }),
+  }
+}
+
+/// A PDF function (ISO 32000-1 §7.10) used as the tint transform of a
+/// Separation or DeviceN color space.
+enum PdfFunction {
+  /// Type 0: a table of `size[0] × size[1] × …` samples, each holding
+  /// `outputs` values normalized to 0–1, interpolated multilinearly.
+  Sampled {
+    domain: Vec<f64>,
+    range: Vec<f64>,
+    size: Vec<usize>,
+    encode: Vec<f64>,
+    decode: Vec<f64>,
+    samples: Vec<f64>,
+  },
+  /// Type 2: `c0 + x^n × (c1 − c0)` for a single input.
+  Exponential {
+    domain: [f64; 2],
+    c0: Vec<f64>,
+    c1: Vec<f64>,
+    n: f64,
+  },
+}
+
+/// Most inputs a sampled function may take: evaluating one interpolates
+/// between the 2^m corners of its grid cell, per pixel.
+const MAX_SAMPLED_INPUTS: usize = 8;
+
+/// Linear map of `x` from `[x0, x1]` to `[y0, y1]`.
+fn interpolate(x: f64, x0: f64, x1: f64, y0: f64, y1: f64) -> f64 {
+  if x1 == x0 {
+    y0
+  } else {
+    y0 + (x - x0) * (y1 - y0) / (x1 - x0)
+  }
+}
+
+impl PdfFunction {
+  fn parse(doc: &Document, obj: &Object) -> Result<PdfFunction, String> {
+    let (dict, stream) = match deref(doc, obj) {
+      Some(Object::Stream(s)) => (&s.dict, Some(s)),
+      Some(Object::Dictionary(d)) => (d, None),
+      _ => return Err("tint transform is not a function".to_string()),
+    };
+    let function_type = get_dict_int(dict, b"FunctionType").unwrap_or(-1);
+    let domain = number_array(doc, dict, b"Domain").unwrap_or_default();
+    if domain.is_empty() || !domain.len().is_multiple_of(2) {
+      return Err("tint transform has no valid /Domain".to_string());
+    }
+    match function_type {
+      0 => {
+        let stream = stream.ok_or("sampled tint transform is not a stream")?;
+        let range = number_array(doc, dict, b"Range").unwrap_or_default();
+        let size: Vec<usize> = number_array(doc, dict, b"Size")
+          .unwrap_or_default()
+          .into_iter()
+          .map(|s| s as usize)
+          .collect();
+        let bits = get_dict_int(dict, b"BitsPerSample").unwrap_or(0) as u32;
+        let inputs = domain.len() / 2;
+        let outputs = range.len() / 2;
+        if outputs == 0
+          || !range.len().is_multiple_of(2)
+          || size.len() != inputs
+          || size.contains(&0)
+        {
+          return Err("sampled tint transform has an invalid /Range or /Size".to_string());
+        }
+        if inputs > MAX_SAMPLED_INPUTS {
+          return Err(format!(
+            "sampled tint transform has {inputs} inputs; at most {MAX_SAMPLED_INPUTS} are supported"
+          ));
+        }
+        if ![1, 2, 4, 8, 12, 16, 24, 32].contains(&bits) {
+          return Err(format!(
+            "unsupported BitsPerSample {bits} in tint transform"
+          ));
+        }
+        let encode = number_array(doc, dict, b"Encode")
+          .filter(|e| e.len() == inputs * 2)
+          .unwrap_or_else(|| size.iter().flat_map(|&s| [0.0, (s - 1) as f64]).collect());
+        let decode = number_array(doc, dict, b"Decode")
+          .filter(|d| d.len() == range.len())
+          .unwrap_or_else(|| range.clone());
+
+        let data = stream
+          .decompressed_content()
+          .unwrap_or_else(|_| stream.content.clone());
+        // /Size comes from the file, so the table may not fit in memory;
+        // reject it before allocating when the stream can't hold it anyway
+        let count = size
+          .iter()
+          .try_fold(outputs, |n, &s| n.checked_mul(s))
+          .filter(|&n| {
+            n.checked_mul(bits as usize)
+              .is_some_and(|b| b <= data.len() * 8)
+          })
+          .ok_or("sampled tint transform has too few samples")?;
+        let max = ((1u64 << bits) - 1) as f64;
+        let samples = (0..count)
+          .map(|i| read_bits(&data, i * bits as usize, bits).map(|s| s as f64 / max))
+          .collect::<Option<Vec<f64>>>()
+          .ok_or("sampled tint transform has too few samples")?;
+        Ok(PdfFunction::Sampled {
+          domain,
+          range,
+          size,
+          encode,
+          decode,
+          samples,
+        })
+      }
+      2 => {
+        let c0 = number_array(doc, dict, b"C0").unwrap_or_else(|| vec![0.0]);
+        let c1 = number_array(doc, dict, b"C1").unwrap_or_else(|| vec![1.0]);
+        let n = dict.get(b"N").ok().and_then(number).unwrap_or(1.0);
+        if c0.len() != c1.len() {
+          return Err("exponential tint transform has mismatched /C0 and /C1".to_string());
+        }
+        Ok(PdfFunction::Exponential {
+          domain: [domain[0], domain[1]],
+          c0,
+          c1,
+          n,
+        })
+      }
+      other => Err(format!("unsupported tint transform FunctionType {other}")),
+    }
+  }
+
+  fn eval(&self, input: &[f64]) -> Vec<f64> {
+    match self {
+      PdfFunction::Sampled {
+        domain,
+        range,
+        size,
+        encode,
+        decode,
+        samples,
+      } => {
+        let outputs = range.len() / 2;
+        // Position of each input in the sample table, split into the lower
+        // grid index and the fraction towards the next one
+        let cells: Vec<(usize, f64)> = size
+          .iter()
+          .enumerate()
+          .map(|(i, &s)| {
+            let x = input.get(i).copied().unwrap_or(0.0);
+            let x = x.clamp(
+              domain[2 * i].min(domain[2 * i + 1]),
+              domain[2 * i].max(domain[2 * i + 1]),
+            );
+            let e = interpolate(
+              x,
+              domain[2 * i],
+              domain[2 * i + 1],
+              encode[2 * i],
+              encode[2 * i + 1],
+            );
+            let e = e.clamp(0.0, (s - 1) as f64);
+            let lower = (e.floor() as usize).min(s.saturating_sub(2));
+            (lower, e - lower as f64)
+          })
+          .collect();
+
+        let mut out = vec![0.0; outputs];
+        // Weighted sum over the 2^m corners of the enclosing cell
+        for corner in 0..1usize << size.len() {
+          let mut weight = 1.0;
+          let mut index = 0;
+          let mut stride = 1;
+          for (i, &(lower, frac)) in cells.iter().enumerate() {
+            let upper = corner >> i & 1 == 1;
+            weight *= if upper { frac } else { 1.0 - frac };
+            let at = if upper && size[i] > 1 {
+              lower + 1
+            } else {
+              lower
+            };
+            index += at * stride;
+            stride *= size[i];
+          }
+          if weight == 0.0 {
+            continue;
+          }
+          for (j, value) in out.iter_mut().enumerate() {
+            *value += weight * samples[index * outputs + j];
+          }
+        }
+        out
+          .iter()
+          .enumerate()
+          .map(|(j, &v)| {
+            let v = interpolate(v, 0.0, 1.0, decode[2 * j], decode[2 * j + 1]);
+            v.clamp(
+              range[2 * j].min(range[2 * j + 1]),
+              range[2 * j].max(range[2 * j + 1]),
+            )
+          })
+          .collect()
+      }
+      PdfFunction::Exponential { domain, c0, c1, n } => {
+        let x = input
+          .first()
+          .copied()
+          .unwrap_or(0.0)
+          .clamp(domain[0].min(domain[1]), domain[0].max(domain[1]));
+        let t = x.powf(*n);
+        c0.iter().zip(c1).map(|(a, b)| a + t * (b - a)).collect()
+      }
+    }
+  }
+}
+
+/// A Separation or DeviceN color space: `inputs` tint components per pixel
+/// mapped by `function` into the `alternate` color space.
+struct TintTransform {
+  inputs: u32,
+  alternate: String,
+  function: PdfFunction,
+}
+
+impl TintTransform {
+  /// Parse the image's `/ColorSpace` when it is `[/Separation name alt fn]`
+  /// or `[/DeviceN names alt fn …]`; `None` for any other color space.
+  fn from_image(doc: &Document, dict: &lopdf::Dictionary) -> Option<Result<TintTransform, String>> {
+    let arr = match deref(doc, dict.get(b"ColorSpace").ok()?)? {
+      Object::Array(arr) => arr,
+      _ => return None,
+    };
+    let inputs = match arr.first() {
+      Some(Object::Name(n)) if n == b"Separation" => 1,
+      Some(Object::Name(n)) if n == b"DeviceN" => match arr.get(1).and_then(|o| deref(doc, o)) {
+        Some(Object::Array(names)) => names.len() as u32,
+        _ => return Some(Err("DeviceN color space has no colorant names".to_string())),
+      },
+      _ => return None,
+    };
+    let (Some(alternate), Some(function)) = (arr.get(2), arr.get(3)) else {
+      return Some(Err(
+        "tint color space has no alternate space or tint transform".to_string(),
+      ));
+    };
+    let alternate = color_space_name(alternate, doc);
+    if !matches!(
+      alternate.as_str(),
+      "DeviceRGB"
+        | "ICCBased3"
+        | "CalRGB"
+        | "DeviceGray"
+        | "ICCBased1"
+        | "CalGray"
+        | "DeviceCMYK"
+        | "ICCBased4"
+    ) {
+      return Some(Err(format!(
+        "unsupported alternate color space {alternate}"
+      )));
+    }
+    Some(
+      PdfFunction::parse(doc, function).map(|function| TintTransform {
+        inputs,
+        alternate,
+        function,
+      }),
+    )
+  }
+
+  /// Map `width × height` tint samples at `bpc` bits to 8-bit samples in the
+  /// alternate space. Rows missing from truncated data are left out, so the
+  /// result can still be repaired or rejected like any other raw image.
+  fn apply(&self, content: &[u8], width: u32, height: u32, bpc: u32) -> Result<Vec<u8>, String> {
+    if ![1, 2, 4, 8, 16].contains(&bpc) {
+      return Err(format!("unsupported BitsPerComponent {bpc}"));
+    }
+    let inputs = self.inputs as usize;
+    let outputs = color_space_channels(&self.alternate) as usize;
+    let row_bytes = (width as usize * inputs * bpc as usize).div_ceil(8);
+    let rows = (content.len() / row_bytes.max(1)).min(height as usize);
+    let max = ((1u64 << bpc) - 1) as f64;
+    // Images use few distinct tints, so each is evaluated once. The key packs
+    // a pixel's raw samples; pixels too wide to pack are evaluated directly.
+    let packable = inputs * bpc as usize <= 64;
+    let mut memo: HashMap<u64, Vec<u8>> = HashMap::new();
+    let mut tints = vec![0.0; inputs];
+    let mut out = Vec::with_capacity(rows * width as usize * outputs);
+    for row in content.chunks(row_bytes).take(rows) {
+      for px in 0..width as usize {
+        let mut key = 0u64;
+        for (i, tint) in tints.iter_mut().enumerate() {
+          let sample = read_bits(row, (px * inputs + i) * bpc as usize, bpc).unwrap_or(0);
+          if packable {
+            key = key << bpc | sample as u64;
+          }
+          *tint = sample as f64 / max;
+        }
+        let eval = |tints: &[f64]| {
+          let mut color: Vec<u8> = self
+            .function
+            .eval(tints)
+            .iter()
+            .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+          color.resize(outputs, 0);
+          color
+        };
+        if packable {
+          out.extend_from_slice(memo.entry(key).or_insert_with(|| eval(&tints)));
+        } else {
+          out.extend(eval(&tints));
+        }
+      }
+    }
+    Ok(out)
+  }
+}
+
+/// Pixel data, color space and bit depth to decode a raw image with: for
+/// Separation and DeviceN images, the tints mapped into the alternate space
+/// at 8 bits per component; otherwise the stream's own.
+fn apply_tint_transform(
+  doc: &Document,
+  dict: &lopdf::Dictionary,
+  content: Vec<u8>,
+  width: u32,
+  height: u32,
+  bpc: u32,
+  color_space: &str,
+) -> Result<(Vec<u8>, String, u32), String> {
+  match TintTransform::from_image(doc, dict) {
+    Some(tint) => {
+      let tint = tint?;
+      let pixels = tint.apply(&content, width, height, bpc)?;
+      Ok((pixels, tint.alternate, 8))
+    }
+    None => Ok((content, color_space.to_string(), bpc)),
+  }
+}
+
 fn resolve_filter(dict: &lopdf::Dictionary) -> String {
   match dict.get(b"Filter") {
     Ok(Object::Name(name)) => String::from_utf8_lossy(name).to_string(),
@@ -718,6 +1922,7 @@ fn resolve_filter(dict: &lopdf::Dictionary) -> String {
 }
 
 /// Decode an XObject stream into a DynamicImage (shared by PNG export and OCR).
+/// The error string says why the image couldn't be decoded.
 fn decode_xobject_to_dynamic_image(
   content: &[u8],
   width: u32,
@@ -726,43 +1931,160 @@ fn decode_xobject_to_dynamic_image(
   color_space: &str,
   filter: &str,
   smask: Option<&[u8]>,
-) -> Option<DynamicImage> {
+) -> Result<DynamicImage, String> {
   let dynamic_img = if filter == "DCTDecode" {
-    image::load_from_memory_with_format(content, ImageFormat::Jpeg).ok()?
+    image::load_from_memory_with_format(content, ImageFormat::Jpeg)
+      .map_err(|e| format!("JPEG decode failed: {e}"))?
   } else if filter == "JPXDecode" {
     decode_jpx(content)?
   } else {
     decode_raw_pixels(content, width, height, bpc, color_space)?
   };
 
-  Some(if let Some(mask_data) = smask {
+  // Raw pixel data may have decoded one row short of the declared height
+  let height = if matches!(filter, "DCTDecode" | "JPXDecode") {
+    height
+  } else {
+    dynamic_img.height()
+  };
+  Ok(if let Some(mask_data) = smask {
     apply_smask(dynamic_img, mask_data, width, height)
   } else {
     dynamic_img
   })
 }
 
-fn encode_to_png(
+/// Rotate/flip a decoded JPEG so it displays upright, following the EXIF
+/// orientation tag in its APP1 segment. The PNG output carries no EXIF, so the
+/// tag doesn't apply a second time downstream.
+fn apply_jpeg_orientation(mut img: DynamicImage, jpeg: &[u8]) -> DynamicImage {
+  let orientation = image::codecs::jpeg::JpegDecoder::new(Cursor::new(jpeg))
+    .and_then(|mut decoder| decoder.orientation());
+  if let Ok(orientation) = orientation {
+    img.apply_orientation(orientation);
+  }
+  img
+}
+
+/// Apply the per-call options to a decoded image and encode it in
+/// `config.encoding`. Returns the encoded bytes with the output width and
+/// height.
+fn encode_decoded(
+  decoded: DynamicImage,
+  config: &ImageConfig,
+) -> Result<(Vec<u8>, u32, u32), String> {
+  let resized = match config.max_dimension {
+    Some(max) => downscale(decoded, max),
+    None => decoded,
+  };
+  let final_img = match config.binarize {
+    Some(threshold) => binarize(resized, threshold),
+    None if config.grayscale => to_grayscale(resized),
+    None => resized,
+  };
+  let (width, height) = (final_img.width(), final_img.height());
+  let mut buf = Cursor::new(Vec::new());
+  match config.encoding {
+    ImageEncoding::Png => final_img
+      .write_to(&mut buf, ImageFormat::Png)
+      .map_err(|e| format!("PNG encode failed: {e}"))?,
+    ImageEncoding::Jpeg { quality } => {
+      // JPEG has no alpha channel: keep the color and drop transparency
+      let opaque = if final_img.color().has_color() {
+        DynamicImage::ImageRgb8(final_img.to_rgb8())
+      } else {
+        DynamicImage::ImageLuma8(final_img.to_luma8())
+      };
+      opaque
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut buf, quality))
+        .map_err(|e| format!("JPEG encode failed: {e}"))?
+    }
+  }
+  Ok((buf.into_inner(), width, height))
+}
+
+/// PNG color type and channel count of 8-bit samples in `color_space`, for
+/// the color spaces whose samples PNG can store as they are.
+fn png_sample_layout(color_space: &str) -> Option<(ExtendedColorType, usize)> {
+  match color_space {
+    "DeviceRGB" | "ICCBased3" | "CalRGB" => Some((ExtendedColorType::Rgb8, 3)),
+    "DeviceGray" | "ICCBased1" | "CalGray" => Some((ExtendedColorType::L8, 1)),
+    _ => None,
+  }
+}
+
+/// Fast path for 8-bit gray and RGB samples, which already have the layout
+/// PNG stores: encode them straight from the decoded stream, without copying
+/// them into an intermediate `DynamicImage`. `None` when the image needs the
+/// general path (other depths or color spaces, downscaling, grayscale or
+/// black-and-white conversion, short data).
+fn encode_samples_as_png(
   content: &[u8],
   width: u32,
   height: u32,
   bpc: u32,
   color_space: &str,
-  filter: &str,
-  smask: Option<&[u8]>,
-) -> Option<Vec<u8>> {
-  let final_img =
-    decode_xobject_to_dynamic_image(content, width, height, bpc, color_space, filter, smask)?;
-  let mut png_buf = Cursor::new(Vec::new());
-  final_img.write_to(&mut png_buf, ImageFormat::Png).ok()?;
-  Some(png_buf.into_inner())
+  config: &ImageConfig,
+) -> Option<Result<Vec<u8>, String>> {
+  let (color_type, channels) = png_sample_layout(color_space)?;
+  let downscaled = config
+    .max_dimension
+    .is_some_and(|max| max > 0 && width.max(height) > max);
+  let converted = config.binarize.is_some() || (config.grayscale && channels != 1);
+  if bpc != 8 || downscaled || converted {
+    return None;
+  }
+  let pixels = content.get(..width as usize * height as usize * channels)?;
+  let mut png = Vec::new();
+  Some(
+    PngEncoder::new(&mut png)
+      .write_image(pixels, width, height, color_type)
+      .map(|()| png)
+      .map_err(|e| format!("PNG encode failed: {e}")),
+  )
+}
+
+/// Luma version of `img`, keeping an alpha channel if it has one.
+fn to_grayscale(img: DynamicImage) -> DynamicImage {
+  if img.color().has_alpha() {
+    DynamicImage::ImageLumaA8(img.to_luma_alpha8())
+  } else {
+    DynamicImage::ImageLuma8(img.to_luma8())
+  }
+}
+
+/// Black-and-white version of `img`: luma at or above `threshold` becomes
+/// 255, the rest 0. Stored as 8-bit gray (PNG compresses the two values
+/// well); an alpha channel is kept.
+fn binarize(img: DynamicImage, threshold: u8) -> DynamicImage {
+  let threshold_pixel = |luma: u8| if luma >= threshold { 255 } else { 0 };
+  match to_grayscale(img) {
+    DynamicImage::ImageLumaA8(mut gray) => {
+      gray.pixels_mut().for_each(|p| p[0] = threshold_pixel(p[0]));
+      DynamicImage::ImageLumaA8(gray)
+    }
+    DynamicImage::ImageLuma8(mut gray) => {
+      gray.pixels_mut().for_each(|p| p[0] = threshold_pixel(p[0]));
+      DynamicImage::ImageLuma8(gray)
+    }
+    other => other,
+  }
+}
+
+/// Shrink `img` so its longest side is at most `max_dimension`, keeping the
+/// aspect ratio. Smaller images (and a limit of 0) are returned unchanged.
+fn downscale(img: DynamicImage, max_dimension: u32) -> DynamicImage {
+  if max_dimension == 0 || img.width().max(img.height()) <= max_dimension {
+    return img;
+  }
+  img.resize(max_dimension, max_dimension, FilterType::Triangle)
 }
 
 /// Decode a JPEG 2000 (JPXDecode) stream using hayro-jpeg2000 (pure Rust)
-fn decode_jpx(content: &[u8]) -> Option<DynamicImage> {
-  let jp2_img =
-    hayro_jpeg2000::Image::new(content, &hayro_jpeg2000::DecodeSettings::default()).ok()?;
-  DynamicImage::from_decoder(jp2_img).ok()
+fn decode_jpx(content: &[u8]) -> Result<DynamicImage, String> {
+  let jp2_img = hayro_jpeg2000::Image::new(content, &hayro_jpeg2000::DecodeSettings::default())
+    .map_err(|e| format!("JPEG 2000 decode failed: {e}"))?;
+  DynamicImage::from_decoder(jp2_img).map_err(|e| format!("JPEG 2000 decode failed: {e}"))
 }
 
 /// Decode raw pixel data (FlateDecode / uncompressed) into a DynamicImage
@@ -772,25 +2094,43 @@ fn decode_raw_pixels(
   height: u32,
   bpc: u32,
   color_space: &str,
-) -> Option<DynamicImage> {
-  let channels: u32 = match color_space {
-    "DeviceRGB" | "ICCBased3" | "CalRGB" => 3,
-    "DeviceGray" | "ICCBased1" | "CalGray" => 1,
-    "DeviceCMYK" | "ICCBased4" => 4,
-    _ => 3,
-  };
+) -> Result<DynamicImage, String> {
+  let channels = color_space_channels(color_space);
   let bytes_per_sample = if bpc > 8 { 2u32 } else { 1u32 };
-  let expected = (width * height * channels * bytes_per_sample) as usize;
+  if bpc < 8 && ![1, 2, 4].contains(&bpc) {
+    return Err(format!("unsupported BitsPerComponent {bpc}"));
+  }
+  let row_bytes = if bpc < 8 {
+    (width * channels * bpc).div_ceil(8) as usize
+  } else {
+    (width * channels * bytes_per_sample) as usize
+  };
+  // Off-by-one /Height: data that holds every row but the last decodes as an
+  // image one row shorter instead of failing
+  let height = if height > 1
+    && content.len() < row_bytes * height as usize
+    && content.len() >= row_bytes * (height - 1) as usize
+  {
+    height - 1
+  } else {
+    height
+  };
+  let expected = row_bytes * height as usize;
 
   // Validate buffer size before constructing image
   if content.len() < expected {
-    return None;
+    return Err(format!(
+      "pixel data truncated: expected {expected} bytes for {width}x{height} {color_space} at {bpc} bpc, got {}",
+      content.len()
+    ));
   }
   // Use exactly the expected number of bytes
   let pixel_data = &content[..expected];
 
-  // Downscale 16-bit to 8-bit if needed
-  let pixel_data_8bit: Vec<u8> = if bytes_per_sample == 2 {
+  // Expand packed 1/2/4-bit samples, or downscale 16-bit to 8-bit
+  let pixel_data_8bit: Vec<u8> = if bpc < 8 {
+    unpack_samples(pixel_data, width, height, channels, bpc)
+  } else if bytes_per_sample == 2 {
     pixel_data
       .chunks_exact(2)
       .map(|pair| (u16::from_be_bytes([pair[0], pair[1]]) >> 8) as u8)
@@ -799,37 +2139,62 @@ fn decode_raw_pixels(
     pixel_data.to_vec()
   };
 
+  let mismatch = || format!("pixel buffer does not match {width}x{height}");
   match color_space {
     "DeviceRGB" | "ICCBased3" | "CalRGB" => {
       let img: ImageBuffer<image::Rgb<u8>, Vec<u8>> =
-        ImageBuffer::from_raw(width, height, pixel_data_8bit)?;
-      Some(DynamicImage::ImageRgb8(img))
+        ImageBuffer::from_raw(width, height, pixel_data_8bit).ok_or_else(mismatch)?;
+      Ok(DynamicImage::ImageRgb8(img))
     }
     "DeviceGray" | "ICCBased1" | "CalGray" => {
       let img: ImageBuffer<image::Luma<u8>, Vec<u8>> =
-        ImageBuffer::from_raw(width, height, pixel_data_8bit)?;
-      Some(DynamicImage::ImageLuma8(img))
+        ImageBuffer::from_raw(width, height, pixel_data_8bit).ok_or_else(mismatch)?;
+      Ok(DynamicImage::ImageLuma8(img))
     }
     "DeviceCMYK" | "ICCBased4" => {
       let rgb_bytes = cmyk_to_rgb(&pixel_data_8bit);
       let img: ImageBuffer<image::Rgb<u8>, Vec<u8>> =
-        ImageBuffer::from_raw(width, height, rgb_bytes)?;
-      Some(DynamicImage::ImageRgb8(img))
+        ImageBuffer::from_raw(width, height, rgb_bytes).ok_or_else(mismatch)?;
+      Ok(DynamicImage::ImageRgb8(img))
     }
     _ => {
       let expected_rgb = (width * height * 3) as usize;
       if pixel_data_8bit.len() == expected_rgb {
         let img: ImageBuffer<image::Rgb<u8>, Vec<u8>> =
-          ImageBuffer::from_raw(width, height, pixel_data_8bit)?;
-        Some(DynamicImage::ImageRgb8(img))
+          ImageBuffer::from_raw(width, height, pixel_data_8bit).ok_or_else(mismatch)?;
+        Ok(DynamicImage::ImageRgb8(img))
       } else {
-        None
+        Err(format!("unsupported color space {color_space}"))
       }
     }
   }
 }
 
-/// Combine a base RGB image with a grayscale SMask to produce an RGBA PNG
+/// Expand packed 1, 2 or 4-bit samples to one byte each, scaled to 0–255.
+/// Each row starts on a byte boundary, so trailing bits of a row are skipped.
+fn unpack_samples(data: &[u8], width: u32, height: u32, channels: u32, bpc: u32) -> Vec<u8> {
+  let samples_per_row = (width * channels) as usize;
+  let row_bytes = (width * channels * bpc).div_ceil(8) as usize;
+  let bpc = bpc as usize;
+  let max = (1u16 << bpc) - 1;
+  let mask = max as u8;
+  let mut out = Vec::with_capacity(samples_per_row * height as usize);
+  for row in data.chunks(row_bytes).take(height as usize) {
+    for i in 0..samples_per_row {
+      let bit = i * bpc;
+      let Some(&byte) = row.get(bit / 8) else {
+        out.push(0);
+        continue;
+      };
+      let sample = (byte >> (8 - bpc - bit % 8)) & mask;
+      out.push((sample as u16 * 255 / max) as u8);
+    }
+  }
+  out
+}
+
+/// Combine a base RGB image with a grayscale alpha mask (an SMask or a
+/// `/Mask`) to produce an RGBA PNG
 fn apply_smask(base: DynamicImage, mask_data: &[u8], width: u32, height: u32) -> DynamicImage {
   let rgb = base.to_rgb8();
   let expected_mask_len = (width * height) as usize;
@@ -873,3 +2238,857 @@ fn cmyk_to_rgb(cmyk: &[u8]) -> Vec<u8> {
 
   rgb
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn run_length_decodes_literals_and_runs() {
+    // 2 literal bytes, then 'z' repeated 3 times, then EOD
+    let data = [1, b'a', b'b', 254, b'z', 128, b'x'];
+    assert_eq!(run_length_decode(&data), b"abzzz");
+  }
+
+  #[test]
+  fn ascii_hex_ignores_whitespace_and_pads_odd_digit() {
+    assert_eq!(ascii_hex_decode(b"48 65\n6c6C 6f>ff"), b"Hello");
+    assert_eq!(ascii_hex_decode(b"7>"), [0x70]);
+  }
+
+  #[test]
+  fn ascii85_decodes_groups_zero_shorthand_and_partial_tail() {
+    assert_eq!(ascii85_decode(b"<~87cURD]i,\"Ebo80~>"), b"Hello World!");
+    assert_eq!(ascii85_decode(b"z87cU\nRDZ~>"), b"\0\0\0\0Hello");
+  }
+
+  #[test]
+  fn filter_chain_unwraps_ascii_before_flate() {
+    use lopdf::{Stream, dictionary};
+    use std::io::Write;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(b"pixels").unwrap();
+    let hex: String = encoder
+      .finish()
+      .unwrap()
+      .iter()
+      .map(|b| format!("{b:02x}"))
+      .collect();
+    let stream = Stream::new(
+      dictionary! { "Filter" => vec!["ASCIIHexDecode".into(), "FlateDecode".into()] },
+      format!("{hex}>").into_bytes(),
+    );
+    assert_eq!(decode_filter_chain(&stream), b"pixels");
+  }
+
+  #[test]
+  fn downscale_caps_longest_side_and_keeps_aspect_ratio() {
+    let wide = DynamicImage::new_rgb8(400, 100);
+    let scaled = downscale(wide, 200);
+    assert_eq!((scaled.width(), scaled.height()), (200, 50));
+
+    let small = DynamicImage::new_rgb8(40, 10);
+    let unchanged = downscale(small, 200);
+    assert_eq!((unchanged.width(), unchanged.height()), (40, 10));
+  }
+
+  /// One page painting a 10x2 1-bit DeviceGray image: a black-to-white
+  /// alternating first row and an all-white second row.
+  fn bilevel_image_doc() -> (Document, ObjectId) {
+    use lopdf::{Stream, dictionary};
+
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let image_id = doc.add_object(Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 10,
+        "Height" => 2,
+        "ColorSpace" => "DeviceGray",
+        "BitsPerComponent" => 1,
+      },
+      // Each 10-pixel row is padded to 2 bytes
+      vec![0b0101_0101, 0b0100_0000, 0xff, 0xc0],
+    ));
+    let content_id = doc.add_object(Stream::new(
+      dictionary! {},
+      b"q 10 0 0 2 0 0 cm /Im1 Do Q".to_vec(),
+    ));
+    let page_id = doc.add_object(dictionary! {
+      "Type" => "Page",
+      "Parent" => pages_id,
+      "MediaBox" => vec![0.into(), 0.into(), 10.into(), 2.into()],
+      "Contents" => content_id,
+      "Resources" => dictionary! { "XObject" => dictionary! { "Im1" => image_id } },
+    });
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => vec![page_id.into()],
+        "Count" => 1,
+      }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+    (doc, page_id)
+  }
+
+  fn assert_bilevel_pixels(img: &DynamicImage) {
+    let luma = img.to_luma8();
+    assert_eq!(luma.dimensions(), (10, 2));
+    let first_row: Vec<u8> = (0..10).map(|x| luma.get_pixel(x, 0)[0]).collect();
+    assert_eq!(first_row, [0, 255, 0, 255, 0, 255, 0, 255, 0, 255]);
+    assert!((0..10).all(|x| luma.get_pixel(x, 1)[0] == 255));
+  }
+
+  #[test]
+  fn unpack_samples_scales_sub_byte_depths_and_skips_row_padding() {
+    assert_eq!(unpack_samples(&[0b1010_0000], 3, 1, 1, 1), [255, 0, 255]);
+    assert_eq!(
+      unpack_samples(&[0b0001_1011], 4, 1, 1, 2),
+      [0, 85, 170, 255]
+    );
+    assert_eq!(unpack_samples(&[0x0f, 0xf0], 1, 2, 1, 4), [0, 255]);
+  }
+
+  #[test]
+  fn one_bit_gray_images_are_extracted() {
+    let (doc, _) = bilevel_image_doc();
+    let extraction = extract_images_with_config(
+      &doc,
+      &ImageConfig {
+        report_failures: true,
+        ..Default::default()
+      },
+    )
+    .unwrap();
+    assert!(extraction.failures.is_empty());
+    assert_eq!(extraction.images.len(), 1);
+    let png = image::load_from_memory(&extraction.images[0].data).unwrap();
+    assert_bilevel_pixels(&png);
+  }
+
+  #[test]
+  fn images_report_stored_size_and_compression_ratio() {
+    use lopdf::{Stream, dictionary};
+
+    let (mut doc, page_id) = bilevel_image_doc();
+    let images = extract_page_images_raw(&doc, page_id, 1);
+    // Stored uncompressed: 4 bytes in, 4 bytes of samples out
+    assert_eq!(images[0].compressed_bytes, 4);
+    assert_eq!(images[0].compression_ratio, 1.0);
+
+    let mut flat = Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 64,
+        "Height" => 64,
+        "ColorSpace" => "DeviceGray",
+        "BitsPerComponent" => 8,
+      },
+      vec![128; 64 * 64],
+    );
+    flat.compress().unwrap();
+    let stored = flat.content.len();
+    let flat_id = doc.add_object(flat);
+    doc.get_dictionary_mut(page_id).unwrap().set(
+      "Resources",
+      dictionary! { "XObject" => dictionary! { "Im1" => flat_id } },
+    );
+    let images = extract_page_images_raw(&doc, page_id, 1);
+    assert_eq!(images[0].compressed_bytes as usize, stored);
+    assert_eq!(images[0].compression_ratio, 4096.0 / stored as f64);
+    assert!(images[0].compression_ratio > 10.0);
+  }
+
+  /// `bilevel_image_doc` with its image painted only through nested Form
+  /// XObjects: Page -> Fm1 -> Fm2 -> Photo, with Fm2 also painting Fm1 again.
+  fn form_wrapped_image_doc() -> (Document, ObjectId) {
+    use lopdf::{Stream, dictionary};
+
+    let (mut doc, page_id) = bilevel_image_doc();
+    let image_id = doc
+      .get_dictionary(page_id)
+      .unwrap()
+      .get_deref(b"Resources", &doc)
+      .and_then(|r| r.as_dict())
+      .and_then(|r| r.get(b"XObject"))
+      .and_then(|x| x.as_dict())
+      .and_then(|x| x.get(b"Im1"))
+      .and_then(|im| im.as_reference())
+      .unwrap();
+    let inner_id = doc.new_object_id();
+    let outer_id = doc.add_object(Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Form",
+        "BBox" => vec![0.into(), 0.into(), 10.into(), 2.into()],
+        "Resources" => dictionary! { "XObject" => dictionary! { "Fm2" => inner_id } },
+      },
+      b"/Fm2 Do".to_vec(),
+    ));
+    doc.objects.insert(
+      inner_id,
+      Object::Stream(Stream::new(
+        dictionary! {
+          "Type" => "XObject",
+          "Subtype" => "Form",
+          "BBox" => vec![0.into(), 0.into(), 10.into(), 2.into()],
+          "Resources" => dictionary! {
+            "XObject" => dictionary! { "Photo" => image_id, "Fm1" => outer_id },
+          },
+        },
+        b"/Photo Do /Fm1 Do".to_vec(),
+      )),
+    );
+    let content_id = doc.get_page_contents(page_id)[0];
+    if let Ok(Object::Stream(content)) = doc.get_object_mut(content_id) {
+      content.set_content(b"/Fm1 Do".to_vec());
+    }
+    doc.get_dictionary_mut(page_id).unwrap().set(
+      "Resources",
+      dictionary! { "XObject" => dictionary! { "Fm1" => outer_id } },
+    );
+    (doc, page_id)
+  }
+
+  #[test]
+  fn images_inside_form_xobjects_are_extracted() {
+    let (doc, page_id) = form_wrapped_image_doc();
+    let images = extract_page_images_raw(&doc, page_id, 1);
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].xobject_name, "Fm1/Fm2/Photo");
+    assert_bilevel_pixels(&image::load_from_memory(&images[0].data).unwrap());
+    assert_eq!(collect_image_bytes_per_page(&doc)[0].image_count, 1);
+    assert_eq!(collect_image_filters(&doc), ["None"]);
+
+    // The decode path behind OCR, render and contact sheets finds it too
+    let decoded = collect_page_decoded_images(&doc, page_id);
+    assert_eq!(decoded.len(), 1);
+    assert_bilevel_pixels(&decoded[0]);
+  }
+
+  #[test]
+  fn images_one_row_short_of_their_height_are_recovered() {
+    let (mut doc, _) = bilevel_image_doc();
+    let image_id = doc
+      .objects
+      .iter()
+      .find_map(|(id, obj)| {
+        obj
+          .as_stream()
+          .ok()
+          .filter(|s| s.dict.has(b"Width"))
+          .map(|_| *id)
+      })
+      .unwrap();
+    doc
+      .get_object_mut(image_id)
+      .and_then(Object::as_stream_mut)
+      .unwrap()
+      .dict
+      .set("Height", 3);
+
+    let extraction = extract_images_with_config(
+      &doc,
+      &ImageConfig {
+        report_failures: true,
+        ..Default::default()
+      },
+    )
+    .unwrap();
+    assert_eq!(extraction.images.len(), 1);
+    assert_eq!(extraction.images[0].height, 2);
+    let png = image::load_from_memory(&extraction.images[0].data).unwrap();
+    assert_bilevel_pixels(&png);
+    assert_eq!(extraction.failures.len(), 1);
+    let failure = &extraction.failures[0];
+    assert!(failure.recovered);
+    assert_eq!(failure.xobject_name, "Im1");
+    assert!(failure.reason.contains("/Height 3"));
+  }
+
+  #[test]
+  fn separation_images_decode_through_sampled_tint_transforms() {
+    use lopdf::{Stream, dictionary};
+
+    let mut doc = Document::with_version("1.7");
+    // Spot red: tint 0 is no ink, tint 1 is full magenta and yellow
+    let tint_fn = doc.add_object(Stream::new(
+      dictionary! {
+        "FunctionType" => 0,
+        "Domain" => vec![0.into(), 1.into()],
+        "Range" => vec![0.into(), 1.into(), 0.into(), 1.into(), 0.into(), 1.into(), 0.into(), 1.into()],
+        "Size" => vec![2.into()],
+        "BitsPerSample" => 8,
+      },
+      vec![0, 0, 0, 0, 0, 255, 255, 0],
+    ));
+    let image = Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 3,
+        "Height" => 1,
+        "ColorSpace" => vec!["Separation".into(), "SpotRed".into(), "DeviceCMYK".into(), tint_fn.into()],
+        "BitsPerComponent" => 8,
+      },
+      vec![0, 128, 255],
+    );
+    let encoded = encode_image_stream(&doc, &image, &ImageConfig::default()).unwrap();
+    assert_eq!(encoded.color_space, "Separation");
+    let rgb = image::load_from_memory(&encoded.data).unwrap().to_rgb8();
+    let pixels: Vec<[u8; 3]> = (0..3).map(|x| rgb.get_pixel(x, 0).0).collect();
+    assert_eq!(pixels, [[255, 255, 255], [255, 126, 126], [255, 0, 0]]);
+  }
+
+  #[test]
+  fn oversized_sampled_functions_are_rejected() {
+    use lopdf::{Stream, dictionary};
+
+    let doc = Document::with_version("1.7");
+    let sampled = |inputs: usize, size: i64| {
+      let function = Object::Stream(Stream::new(
+        dictionary! {
+          "FunctionType" => 0,
+          "Domain" => (0..inputs).flat_map(|_| [0.into(), 1.into()]).collect::<Vec<Object>>(),
+          "Range" => vec![0.into(), 1.into()],
+          "Size" => vec![size.into(); inputs],
+          "BitsPerSample" => 8,
+        },
+        vec![0; 16],
+      ));
+      PdfFunction::parse(&doc, &function).err()
+    };
+    assert_eq!(sampled(4, 2), None);
+    // 2^4 samples would need more than the 16 bytes the stream holds
+    assert!(sampled(4, 3).unwrap().contains("too few samples"));
+    // A table whose sample count overflows is refused without allocating
+    assert!(
+      sampled(4, i64::from(u32::MAX))
+        .unwrap()
+        .contains("too few samples")
+    );
+    assert!(
+      sampled(MAX_SAMPLED_INPUTS + 1, 1)
+        .unwrap()
+        .contains("at most 8")
+    );
+  }
+
+  #[test]
+  fn image_bytes_count_each_painted_image_once() {
+    let (mut doc, page_id) = bilevel_image_doc();
+    // Paint the same image under a second name
+    let content_id = doc.get_page_contents(page_id)[0];
+    if let Ok(Object::Stream(content)) = doc.get_object_mut(content_id) {
+      content.set_content(b"/Im1 Do /Im2 Do".to_vec());
+    }
+    let resources = doc
+      .get_dictionary_mut(page_id)
+      .unwrap()
+      .get_mut(b"Resources")
+      .unwrap();
+    let xobjects = resources
+      .as_dict_mut()
+      .unwrap()
+      .get_mut(b"XObject")
+      .unwrap();
+    let image = xobjects.as_dict().unwrap().get(b"Im1").unwrap().clone();
+    xobjects.as_dict_mut().unwrap().set("Im2", image);
+
+    let bytes = collect_image_bytes_per_page(&doc);
+    assert_eq!(bytes.len(), 1);
+    assert_eq!((bytes[0].page, bytes[0].image_count), (1, 1));
+    assert_eq!(bytes[0].total_stream_bytes, 4);
+  }
+
+  #[test]
+  fn plain_rgb_samples_encode_like_the_general_path() {
+    use lopdf::{Stream, dictionary};
+
+    let doc = Document::with_version("1.7");
+    let samples: Vec<u8> = (0..2 * 3 * 3).map(|i| i as u8 * 14).collect();
+    let image = Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 3,
+        "Height" => 2,
+        "ColorSpace" => "DeviceRGB",
+        "BitsPerComponent" => 8,
+      },
+      samples.clone(),
+    );
+    let encoded = encode_image_stream(&doc, &image, &ImageConfig::default()).unwrap();
+    let general = decode_raw_pixels(&samples, 3, 2, 8, "DeviceRGB").unwrap();
+    let (png, _, _) = encode_decoded(general, &ImageConfig::default()).unwrap();
+    assert_eq!(encoded.data, png);
+    assert_eq!((encoded.width, encoded.height), (3, 2));
+    let decoded = image::load_from_memory(&encoded.data).unwrap().to_rgb8();
+    assert_eq!(decoded.into_raw(), samples);
+  }
+
+  #[test]
+  fn jpeg_output_follows_the_quality_setting() {
+    use lopdf::{Stream, dictionary};
+
+    let doc = Document::with_version("1.7");
+    // A busy 64x64 photo-like pattern, so quality makes a visible difference
+    let samples: Vec<u8> = (0..64 * 64 * 3u32)
+      .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+      .collect();
+    let image = Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 64,
+        "Height" => 64,
+        "ColorSpace" => "DeviceRGB",
+        "BitsPerComponent" => 8,
+      },
+      samples,
+    );
+    let jpeg = |quality| {
+      let config = ImageConfig {
+        encoding: ImageEncoding::Jpeg { quality },
+        ..Default::default()
+      };
+      encode_image_stream(&doc, &image, &config).unwrap().data
+    };
+    let (fine, coarse) = (jpeg(95), jpeg(20));
+    for data in [&fine, &coarse] {
+      assert_eq!(image::guess_format(data).unwrap(), ImageFormat::Jpeg);
+      let decoded = image::load_from_memory(data).unwrap();
+      assert_eq!((decoded.width(), decoded.height()), (64, 64));
+    }
+    assert!(coarse.len() < fine.len());
+  }
+
+  #[test]
+  fn grayscale_and_binarize_convert_before_encoding() {
+    use lopdf::{Stream, dictionary};
+
+    let doc = Document::with_version("1.7");
+    let image = Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 3,
+        "Height" => 1,
+        "ColorSpace" => "DeviceRGB",
+        "BitsPerComponent" => 8,
+      },
+      vec![255, 255, 255, 200, 100, 50, 10, 20, 30],
+    );
+    let encode = |config: ImageConfig| {
+      let encoded = encode_image_stream(&doc, &image, &config).unwrap();
+      image::load_from_memory(&encoded.data).unwrap()
+    };
+    let gray = encode(ImageConfig {
+      grayscale: true,
+      ..ImageConfig::default()
+    });
+    assert_eq!(gray.color(), image::ColorType::L8);
+    assert_eq!(gray.to_luma8().into_raw(), [255, 118, 19]);
+
+    let bw = encode(ImageConfig {
+      binarize: Some(128),
+      ..ImageConfig::default()
+    });
+    assert_eq!(bw.color(), image::ColorType::L8);
+    assert_eq!(bw.to_luma8().into_raw(), [255, 0, 0]);
+  }
+
+  #[test]
+  fn compute_stats_reports_mean_color_and_grayscale() {
+    use lopdf::{Stream, dictionary};
+
+    let doc = Document::with_version("1.7");
+    let image = |color_space: &str, width: i64, samples: Vec<u8>| {
+      Stream::new(
+        dictionary! {
+          "Type" => "XObject",
+          "Subtype" => "Image",
+          "Width" => width,
+          "Height" => 1,
+          "ColorSpace" => color_space,
+          "BitsPerComponent" => 8,
+        },
+        samples,
+      )
+    };
+    let stats = |stream: &Stream, config: ImageConfig| {
+      let stats = encode_image_stream(&doc, stream, &config)
+        .unwrap()
+        .stats
+        .unwrap();
+      (stats.mean_color, stats.is_grayscale)
+    };
+    let with_stats = || ImageConfig {
+      compute_stats: true,
+      ..ImageConfig::default()
+    };
+
+    let color = image(
+      "DeviceRGB",
+      3,
+      vec![255, 255, 255, 200, 100, 50, 10, 20, 30],
+    );
+    assert_eq!(stats(&color, with_stats()), (vec![155, 125, 111], false));
+    // Downscaling takes the general path; stats still describe the original
+    let downscaled = ImageConfig {
+      max_dimension: Some(1),
+      ..with_stats()
+    };
+    assert_eq!(stats(&color, downscaled), (vec![155, 125, 111], false));
+
+    let near_gray = image("DeviceRGB", 2, vec![100, 104, 98, 30, 30, 30]);
+    assert_eq!(stats(&near_gray, with_stats()), (vec![65, 67, 64], true));
+    let gray = image("DeviceGray", 2, vec![0, 100]);
+    assert_eq!(stats(&gray, with_stats()), (vec![50, 50, 50], true));
+
+    let plain = encode_image_stream(&doc, &gray, &ImageConfig::default()).unwrap();
+    assert!(plain.stats.is_none());
+  }
+
+  #[test]
+  fn exif_orientation_is_applied_to_jpegs() {
+    use image::codecs::jpeg::JpegEncoder;
+    use lopdf::{Stream, dictionary};
+
+    let mut jpeg = Vec::new();
+    JpegEncoder::new(&mut jpeg)
+      .write_image(&[0; 8], 4, 2, ExtendedColorType::L8)
+      .unwrap();
+    // APP1 segment with a single IFD entry: Orientation (0x0112) = 6, i.e.
+    // rotate 90° clockwise to display
+    let mut exif = b"Exif\0\0II*\0\x08\0\0\0\x01\0".to_vec();
+    exif.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0]);
+    let mut app1 = vec![0xFF, 0xE1];
+    app1.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+    app1.extend_from_slice(&exif);
+    jpeg.splice(2..2, app1);
+
+    let doc = Document::with_version("1.7");
+    let image = Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 4,
+        "Height" => 2,
+        "ColorSpace" => "DeviceGray",
+        "BitsPerComponent" => 8,
+        "Filter" => "DCTDecode",
+      },
+      jpeg,
+    );
+    let size = |config: ImageConfig| {
+      let encoded = encode_image_stream(&doc, &image, &config).unwrap();
+      (encoded.width, encoded.height)
+    };
+    assert_eq!(size(ImageConfig::default()), (2, 4));
+    let as_stored = ImageConfig {
+      apply_exif_orientation: false,
+      ..ImageConfig::default()
+    };
+    assert_eq!(size(as_stored), (4, 2));
+  }
+
+  #[test]
+  fn color_key_masks_make_matching_pixels_transparent() {
+    use lopdf::{Stream, dictionary};
+
+    let doc = Document::with_version("1.7");
+    let image = Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 3,
+        "Height" => 1,
+        "ColorSpace" => "DeviceRGB",
+        "BitsPerComponent" => 8,
+        "Mask" => vec![250.into(), 255.into(), 0.into(), 5.into(), 250.into(), 255.into()],
+      },
+      vec![255, 0, 255, 10, 20, 30, 252, 3, 251],
+    );
+    let encoded = encode_image_stream(&doc, &image, &ImageConfig::default()).unwrap();
+    let rgba = image::load_from_memory(&encoded.data).unwrap().to_rgba8();
+    let alpha: Vec<u8> = (0..3).map(|x| rgba.get_pixel(x, 0)[3]).collect();
+    assert_eq!(alpha, [0, 255, 0]);
+  }
+
+  #[test]
+  fn stencil_masks_are_scaled_to_the_image() {
+    use lopdf::{Stream, dictionary};
+
+    let mut doc = Document::with_version("1.7");
+    // 2×1 stencil over a 4×2 image: the right half is masked out
+    let mask = doc.add_object(Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 2,
+        "Height" => 1,
+        "ImageMask" => true,
+        "BitsPerComponent" => 1,
+      },
+      vec![0b0100_0000],
+    ));
+    let image = Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 4,
+        "Height" => 2,
+        "ColorSpace" => "DeviceGray",
+        "BitsPerComponent" => 8,
+        "Mask" => mask,
+      },
+      vec![128; 8],
+    );
+    let encoded = encode_image_stream(&doc, &image, &ImageConfig::default()).unwrap();
+    let rgba = image::load_from_memory(&encoded.data).unwrap().to_rgba8();
+    let alpha: Vec<u8> = rgba.pixels().map(|p| p[3]).collect();
+    assert_eq!(alpha, [255, 255, 0, 0, 255, 255, 0, 0]);
+
+    if let Ok(Object::Stream(stream)) = doc.get_object_mut(mask) {
+      stream.dict.set("Decode", vec![1.into(), 0.into()]);
+    }
+    let encoded = encode_image_stream(&doc, &image, &ImageConfig::default()).unwrap();
+    let rgba = image::load_from_memory(&encoded.data).unwrap().to_rgba8();
+    assert_eq!(rgba.get_pixel(0, 0)[3], 0);
+    assert_eq!(rgba.get_pixel(3, 1)[3], 255);
+  }
+
+  #[test]
+  fn sampled_functions_interpolate_between_grid_points() {
+    use lopdf::{Stream, dictionary};
+
+    let doc = Document::with_version("1.7");
+    // 2x2 grid, first input varying fastest
+    let function = Object::Stream(Stream::new(
+      dictionary! {
+        "FunctionType" => 0,
+        "Domain" => vec![0.into(), 1.into(), 0.into(), 1.into()],
+        "Range" => vec![0.into(), 1.into()],
+        "Size" => vec![2.into(), 2.into()],
+        "BitsPerSample" => 8,
+      },
+      vec![0, 51, 102, 255],
+    ));
+    let function = PdfFunction::parse(&doc, &function).unwrap();
+    let eval = |input: &[f64]| (function.eval(input)[0] * 100.0).round() / 100.0;
+    assert_eq!(eval(&[1.0, 0.0]), 0.2);
+    assert_eq!(eval(&[0.0, 1.0]), 0.4);
+    assert_eq!(eval(&[0.5, 0.5]), 0.4);
+    assert_eq!(eval(&[2.0, 2.0]), 1.0);
+  }
+
+  #[test]
+  fn contact_sheets_tile_page_images_into_a_grid() {
+    let (doc, page_id) = bilevel_image_doc();
+    let png = page_contact_sheet(&doc, page_id, 4).unwrap();
+    let sheet = image::load_from_memory(&png).unwrap().to_luma8();
+    // One image: the grid shrinks to a single cell
+    let side = CONTACT_SHEET_CELL + 2 * CONTACT_SHEET_GAP;
+    assert_eq!(sheet.dimensions(), (side, side));
+    // The 10x2 image is centred in its cell on a white background
+    let (x, y) = (
+      CONTACT_SHEET_GAP + (CONTACT_SHEET_CELL - 10) / 2,
+      CONTACT_SHEET_GAP + (CONTACT_SHEET_CELL - 2) / 2,
+    );
+    let first_row: Vec<u8> = (0..10).map(|dx| sheet.get_pixel(x + dx, y)[0]).collect();
+    assert_eq!(first_row, [0, 255, 0, 255, 0, 255, 0, 255, 0, 255]);
+    assert_eq!(sheet.get_pixel(0, 0)[0], 255);
+
+    let mut without_images = doc.clone();
+    without_images
+      .get_dictionary_mut(page_id)
+      .unwrap()
+      .remove(b"Resources");
+    assert!(page_contact_sheet(&without_images, page_id, 4).is_none());
+  }
+
+  #[test]
+  fn contact_sheets_include_images_inside_forms() {
+    let (mut doc, page_id) = form_wrapped_image_doc();
+    let cell_row = |sheet: &image::GrayImage, column: u32| -> Vec<u8> {
+      let x = CONTACT_SHEET_GAP
+        + column * (CONTACT_SHEET_CELL + CONTACT_SHEET_GAP)
+        + (CONTACT_SHEET_CELL - 10) / 2;
+      let y = CONTACT_SHEET_GAP + (CONTACT_SHEET_CELL - 2) / 2;
+      (0..10).map(|dx| sheet.get_pixel(x + dx, y)[0]).collect()
+    };
+    let checkerboard = [0, 255, 0, 255, 0, 255, 0, 255, 0, 255];
+
+    // The page paints its only image through Fm1 -> Fm2
+    let png = page_contact_sheet(&doc, page_id, 4).unwrap();
+    let sheet = image::load_from_memory(&png).unwrap().to_luma8();
+    let side = CONTACT_SHEET_CELL + 2 * CONTACT_SHEET_GAP;
+    assert_eq!(sheet.dimensions(), (side, side));
+    assert_eq!(cell_row(&sheet, 0), checkerboard);
+
+    // A second image painted directly gets a cell before the wrapped one
+    let photo = doc
+      .objects
+      .values()
+      .find(|obj| obj.as_stream().is_ok_and(|s| s.dict.has(b"Width")))
+      .cloned()
+      .unwrap();
+    let photo = doc.add_object(photo);
+    let page = doc.get_dictionary_mut(page_id).unwrap();
+    page
+      .get_mut(b"Resources")
+      .and_then(Object::as_dict_mut)
+      .and_then(|r| r.get_mut(b"XObject"))
+      .and_then(Object::as_dict_mut)
+      .unwrap()
+      .set("Im1", photo);
+    let content_id = doc.get_page_contents(page_id)[0];
+    if let Ok(Object::Stream(content)) = doc.get_object_mut(content_id) {
+      content.set_content(b"/Fm1 Do /Im1 Do".to_vec());
+    }
+    let png = page_contact_sheet(&doc, page_id, 4).unwrap();
+    let sheet = image::load_from_memory(&png).unwrap().to_luma8();
+    assert_eq!(
+      sheet.width(),
+      CONTACT_SHEET_GAP + 2 * (CONTACT_SHEET_CELL + CONTACT_SHEET_GAP)
+    );
+    assert_eq!(cell_row(&sheet, 0), checkerboard);
+    assert_eq!(cell_row(&sheet, 1), checkerboard);
+  }
+
+  #[test]
+  fn images_spill_to_disk_past_the_memory_budget() {
+    let (doc, _) = bilevel_image_doc();
+    let dir = std::env::temp_dir().join(format!("pdfdown-spill-{}", std::process::id()));
+    let spill = |threshold| {
+      extract_images_with_config(
+        &doc,
+        &ImageConfig {
+          spill_dir: Some(dir.clone()),
+          spill_threshold_bytes: threshold,
+          ..Default::default()
+        },
+      )
+      .unwrap()
+      .images
+    };
+
+    let in_memory = spill(u64::MAX);
+    assert!(in_memory[0].file_path.is_none());
+    assert!(!in_memory[0].data.is_empty());
+
+    let spilled = spill(0);
+    assert!(spilled[0].data.is_empty());
+    let path = spilled[0].file_path.as_deref().unwrap();
+    assert!(path.ends_with("page-1-image-0.png"));
+    let png = image::open(path).unwrap();
+    assert_bilevel_pixels(&png);
+
+    // Another extraction into the same directory leaves the first's files be
+    let again = spill(0);
+    let other = again[0].file_path.as_deref().unwrap();
+    assert_ne!(other, path);
+    assert!(Path::new(other).starts_with(&dir));
+    assert!(Path::new(path).exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[cfg(any(feature = "ocr", feature = "render"))]
+  #[test]
+  fn one_bit_gray_images_reach_ocr_decoding() {
+    let (doc, page_id) = bilevel_image_doc();
+    let decoded = collect_page_decoded_images(&doc, page_id);
+    assert_eq!(decoded.len(), 1);
+    assert_bilevel_pixels(&decoded[0]);
+  }
+
+  #[test]
+  fn page_thumbnails_are_decoded() {
+    use lopdf::{Stream, dictionary};
+
+    let (mut doc, page_id) = bilevel_image_doc();
+    assert!(extract_thumbnails_raw(&doc).is_empty());
+
+    // Thumbnails are image streams without /Type or /Subtype
+    let thumb_id = doc.add_object(Stream::new(
+      dictionary! {
+        "Width" => 2,
+        "Height" => 1,
+        "ColorSpace" => "DeviceGray",
+        "BitsPerComponent" => 8,
+      },
+      vec![0, 255],
+    ));
+    doc
+      .get_dictionary_mut(page_id)
+      .unwrap()
+      .set("Thumb", thumb_id);
+    let thumbs = extract_thumbnails_raw(&doc);
+    assert_eq!(thumbs.len(), 1);
+    assert_eq!(
+      (thumbs[0].page, thumbs[0].width, thumbs[0].height),
+      (1, 2, 1)
+    );
+    let png = image::load_from_memory(&thumbs[0].data).unwrap().to_luma8();
+    assert_eq!(png.as_raw(), &[0, 255]);
+  }
+
+  #[test]
+  fn sized_thumbnails_downscale_the_largest_image_per_page() {
+    use lopdf::{Stream, dictionary};
+
+    let (mut doc, page_id) = bilevel_image_doc();
+    let large_id = doc.add_object(Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 40,
+        "Height" => 20,
+        "ColorSpace" => "DeviceGray",
+        "BitsPerComponent" => 8,
+      },
+      vec![128; 40 * 20],
+    ));
+    let content_id = doc.add_object(Stream::new(
+      dictionary! {},
+      b"q 10 0 0 2 0 0 cm /Im1 Do Q q 40 0 0 20 0 0 cm /Im2 Do Q".to_vec(),
+    ));
+    let page = doc.get_dictionary_mut(page_id).unwrap();
+    page.set("Contents", content_id);
+    let resources = page.get_mut(b"Resources").unwrap().as_dict_mut().unwrap();
+    let xobjects = resources
+      .get_mut(b"XObject")
+      .unwrap()
+      .as_dict_mut()
+      .unwrap();
+    xobjects.set("Im2", large_id);
+
+    let thumbs = extract_sized_thumbnails_raw(&doc, 10);
+    assert_eq!(thumbs.len(), 1);
+    assert_eq!(
+      (
+        thumbs[0].xobject_name.as_str(),
+        thumbs[0].image_index,
+        thumbs[0].width,
+        thumbs[0].height
+      ),
+      ("Im2", 1, 10, 5)
+    );
+    let png = image::load_from_memory(&thumbs[0].data).unwrap();
+    assert_eq!((png.width(), png.height()), (10, 5));
+  }
+
+  #[test]
+  fn run_length_tolerates_truncated_input() {
+    assert_eq!(run_length_decode(&[4, b'a', b'b']), b"ab");
+    assert_eq!(run_length_decode(&[0, b'a', 200]), b"a");
+    assert!(run_length_decode(&[]).is_empty());
+  }
+}
@@ -0,0 +1,332 @@
+use crate::core::document::format_object_id;
+use crate::core::layout::page_resources;
+use crate::core::objects::deref_dict;
+use crate::types::{FileStats, ObjectSize, PageComplexity, TrailerRefs};
+use lopdf::content::Content;
+use lopdf::xref::XrefEntry;
+use lopdf::{Document, Object, ObjectId};
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+/// Map a byte offset in `raw`, the file `doc` was parsed from, to the
+/// 1-based page that owns the object stored at that position.
+///
+/// Uses the cross-reference table to find the object whose body starts at or
+/// before `offset`, then checks it against each page's own dictionary and its
+/// `/Contents` streams. An object is taken to run until the next object or
+/// cross-reference section starts, so offsets in an `xref` table, its trailer
+/// or past the end of the file match nothing. Objects packed inside object
+/// streams have no file offset of their own and are therefore never matched.
+pub(crate) fn find_page_at_offset(doc: &Document, raw: &[u8], offset: u32) -> Option<u32> {
+  if offset as usize >= raw.len() {
+    return None;
+  }
+  let mut starts: Vec<(u32, Option<u32>)> = doc
+    .reference_table
+    .entries
+    .iter()
+    .filter_map(|(&id, entry)| match entry {
+      XrefEntry::Normal { offset, .. } => Some((*offset, Some(id))),
+      _ => None,
+    })
+    .chain(
+      xref_section_starts(doc, raw)
+        .into_iter()
+        .map(|start| (start, None)),
+    )
+    .collect();
+  starts.sort_unstable();
+
+  let idx = starts.partition_point(|&(start, _)| start <= offset);
+  let obj_num = starts.get(idx.checked_sub(1)?)?.1?;
+
+  doc
+    .get_pages()
+    .into_iter()
+    .find(|&(_, page_id)| {
+      page_id.0 == obj_num
+        || doc
+          .get_page_contents(page_id)
+          .iter()
+          .any(|id| id.0 == obj_num)
+    })
+    .map(|(page_num, _)| page_num)
+}
+
+/// Offsets of the classic `xref` tables in `raw`, found by following the
+/// chain from the section `startxref` points to through each trailer's
+/// `/Prev` (and a hybrid file's `/XRefStm`), so incremental updates are
+/// covered. Cross-reference streams are objects and already have an entry of
+/// their own; their dictionaries are only read to continue the chain.
+fn xref_section_starts(doc: &Document, raw: &[u8]) -> Vec<u32> {
+  let object_starts: HashSet<usize> = doc
+    .reference_table
+    .entries
+    .values()
+    .filter_map(|entry| match entry {
+      XrefEntry::Normal { offset, .. } => Some(*offset as usize),
+      _ => None,
+    })
+    .collect();
+  let mut starts = Vec::new();
+  let mut pending = vec![doc.xref_start];
+  let mut seen = HashSet::new();
+  while let Some(at) = pending.pop() {
+    if at >= raw.len() || !seen.insert(at) {
+      continue;
+    }
+    let section = &raw[at..];
+    // A classic table's trailer follows it and ends at `startxref`; a
+    // stream's dictionary precedes its data
+    let dict = if section.starts_with(b"xref") {
+      starts.extend(u32::try_from(at).ok());
+      let Some(trailer) = find(section, b"trailer").map(|t| &section[t..]) else {
+        continue;
+      };
+      &trailer[..find(trailer, b"startxref").unwrap_or(trailer.len())]
+    } else if object_starts.contains(&at) {
+      &section[..find(section, b"stream").unwrap_or(section.len())]
+    } else {
+      continue;
+    };
+    pending.extend(
+      [&b"/Prev"[..], b"/XRefStm"]
+        .iter()
+        .filter_map(|key| dict_offset(dict, key)),
+    );
+  }
+  starts
+}
+
+/// Position of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// The integer after `key` in the dictionary text `dict`, e.g. `/Prev 1234`.
+fn dict_offset(dict: &[u8], key: &[u8]) -> Option<usize> {
+  let after = &dict[find(dict, key)? + key.len()..];
+  let digits = after.trim_ascii_start();
+  // `/Prev` must not match the start of a longer name
+  if digits.len() == after.len() {
+    return None;
+  }
+  let end = digits
+    .iter()
+    .position(|b| !b.is_ascii_digit())
+    .unwrap_or(digits.len());
+  std::str::from_utf8(&digits[..end]).ok()?.parse().ok()
+}
+
+/// The objects the trailer refers to: the catalog (`/Root`), the document
+/// information dictionary (`/Info`) and the encryption dictionary
+/// (`/Encrypt`). Entries that are missing or not indirect references are
+/// `None`.
+pub(crate) fn collect_trailer_refs(doc: &Document) -> TrailerRefs {
+  let reference = |key: &[u8]| {
+    doc
+      .trailer
+      .get(key)
+      .and_then(Object::as_reference)
+      .ok()
+      .map(format_object_id)
+  };
+  TrailerRefs {
+    root: reference(b"Root"),
+    info: reference(b"Info"),
+    encrypt: reference(b"Encrypt"),
+  }
+}
+
+/// Object, stream, image and font counts from one pass over the parsed
+/// objects; nothing is decoded. A composite font counts once: its CIDFont
+/// descendants are not counted separately.
+pub(crate) fn collect_file_stats(doc: &Document, file_bytes: usize) -> FileStats {
+  let mut stats = FileStats {
+    file_bytes: file_bytes as u32,
+    object_count: doc.objects.len() as u32,
+    stream_count: 0,
+    image_count: 0,
+    font_count: 0,
+  };
+  for obj in doc.objects.values() {
+    match obj {
+      Object::Stream(stream) => {
+        stats.stream_count += 1;
+        if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image".as_slice()) {
+          stats.image_count += 1;
+        }
+      }
+      Object::Dictionary(dict) if dict.has_type(b"Font") => {
+        let subtype = dict.get(b"Subtype").and_then(Object::as_name).ok();
+        if !matches!(subtype, Some(b"CIDFontType0" | b"CIDFontType2")) {
+          stats.font_count += 1;
+        }
+      }
+      _ => {}
+    }
+  }
+  stats
+}
+
+/// Size breakdown of every stream object in the document, largest first.
+///
+/// `compressed_size` is the stored (encoded) length; `uncompressed_size` is the
+/// decoded length, or `None` when the filter chain can't be decoded here (e.g.
+/// `DCTDecode`, `JPXDecode`). Streams are categorized as `image`, `font`,
+/// `content` (page contents and form XObjects), `metadata` or `other`.
+pub(crate) fn collect_object_sizes(doc: &Document) -> Vec<ObjectSize> {
+  let mut content_ids: HashSet<ObjectId> = HashSet::new();
+  for page_id in doc.get_pages().into_values() {
+    content_ids.extend(doc.get_page_contents(page_id));
+  }
+
+  let mut font_file_ids: HashSet<ObjectId> = HashSet::new();
+  for obj in doc.objects.values() {
+    if let Object::Dictionary(dict) = obj
+      && dict.has_type(b"FontDescriptor")
+    {
+      for key in [b"FontFile".as_slice(), b"FontFile2", b"FontFile3"] {
+        if let Ok(id) = dict.get(key).and_then(Object::as_reference) {
+          font_file_ids.insert(id);
+        }
+      }
+    }
+  }
+
+  let mut report: Vec<ObjectSize> = doc
+    .objects
+    .iter()
+    .filter_map(|(&id, obj)| {
+      let stream = obj.as_stream().ok()?;
+      let subtype = stream.dict.get(b"Subtype").and_then(Object::as_name).ok();
+      let object_type = if subtype == Some(b"Image".as_slice()) {
+        "image"
+      } else if font_file_ids.contains(&id) {
+        "font"
+      } else if content_ids.contains(&id) || subtype == Some(b"Form".as_slice()) {
+        "content"
+      } else if stream.dict.has_type(b"Metadata") {
+        "metadata"
+      } else {
+        "other"
+      };
+      let compressed_size = stream.content.len() as u32;
+      let uncompressed_size = if stream.dict.get(b"Filter").is_err() {
+        Some(compressed_size)
+      } else {
+        stream
+          .decompressed_content()
+          .ok()
+          .map(|data| data.len() as u32)
+      };
+      Some(ObjectSize {
+        object_id: format!("{} {} obj", id.0, id.1),
+        object_type: object_type.to_string(),
+        compressed_size,
+        uncompressed_size,
+      })
+    })
+    .collect();
+  report.sort_by_key(|o| std::cmp::Reverse(o.compressed_size));
+  report
+}
+
+/// Per page, the decoded size of its content streams, the number of
+/// operators in them and the number of XObjects its resources define — a
+/// rough measure of how expensive the page is to render or extract. Content
+/// that fails to parse counts no operators. Form XObjects are not followed.
+pub(crate) fn collect_page_complexity(doc: &Document) -> Vec<PageComplexity> {
+  let pages: Vec<(u32, ObjectId)> = doc.get_pages().into_iter().collect();
+  pages
+    .par_iter()
+    .map(|&(page, page_id)| {
+      let content = doc.get_page_content(page_id).unwrap_or_default();
+      let operator_count = Content::decode(&content).map_or(0, |c| c.operations.len());
+      let xobject_count = page_resources(doc, page_id)
+        .and_then(|res| res.get(b"XObject").ok())
+        .and_then(|x| deref_dict(doc, x))
+        .map_or(0, |x| x.len());
+      PageComplexity {
+        page,
+        content_bytes: content.len() as u32,
+        operator_count: operator_count as u32,
+        xobject_count: xobject_count as u32,
+      }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::test_docs::doc_with_pages;
+  use lopdf::{Stream, dictionary};
+
+  #[test]
+  fn offsets_map_to_their_page_until_the_xref_table() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    // Added last, so the content stream is written right before the xref table
+    let contents = doc.add_object(Stream::new(dictionary! {}, b"BT ET".to_vec()));
+    doc
+      .get_dictionary_mut(pages[0])
+      .unwrap()
+      .set("Contents", contents);
+    let mut raw = Vec::new();
+    doc.save_to(&mut raw).unwrap();
+    let doc = Document::load_mem(&raw).unwrap();
+
+    let start = |id: u32| match doc.reference_table.get(id) {
+      Some(XrefEntry::Normal { offset, .. }) => *offset,
+      _ => panic!("object {id} has no file offset"),
+    };
+    assert_eq!(find_page_at_offset(&doc, &raw, start(pages[0].0)), Some(1));
+    assert_eq!(
+      find_page_at_offset(&doc, &raw, start(contents.0) + 1),
+      Some(1)
+    );
+
+    let xref = doc.xref_start as u32;
+    assert_eq!(find_page_at_offset(&doc, &raw, xref - 1), Some(1));
+    assert_eq!(find_page_at_offset(&doc, &raw, xref), None);
+    assert_eq!(find_page_at_offset(&doc, &raw, raw.len() as u32 - 1), None);
+    assert_eq!(find_page_at_offset(&doc, &raw, raw.len() as u32), None);
+  }
+
+  #[test]
+  fn offsets_follow_the_xref_chain_of_incremental_updates() {
+    use lopdf::IncrementalDocument;
+    use lopdf::xref::XrefType;
+
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    // Stream data that happens to hold a line starting with `xref`
+    let contents = doc.add_object(Stream::new(
+      dictionary! {},
+      b"BT ET\nxref 0 1\n% not a table".to_vec(),
+    ));
+    doc
+      .get_dictionary_mut(pages[0])
+      .unwrap()
+      .set("Contents", contents);
+    doc.reference_table.cross_reference_type = XrefType::CrossReferenceTable;
+    let mut original = Vec::new();
+    doc.save_to(&mut original).unwrap();
+    let parsed = Document::load_mem(&original).unwrap();
+    let old_xref = parsed.xref_start as u32;
+
+    let mut update = IncrementalDocument::create_from(original.clone(), parsed);
+    update
+      .new_document
+      .add_object(dictionary! { "Type" => "Metadata" });
+    let mut raw = Vec::new();
+    update.save_to(&mut raw).unwrap();
+    let doc = Document::load_mem(&raw).unwrap();
+    assert_ne!(doc.xref_start as u32, old_xref);
+
+    let fake_xref = find(&original, b"\nxref 0 1").unwrap() as u32 + 1;
+    assert_eq!(find_page_at_offset(&doc, &raw, fake_xref + 2), Some(1));
+    // The original table, superseded but still in the file, ends the stream
+    assert_eq!(find_page_at_offset(&doc, &raw, old_xref - 1), Some(1));
+    assert_eq!(find_page_at_offset(&doc, &raw, old_xref), None);
+  }
+}
@@ -0,0 +1,1735 @@
+use crate::core::cmap::FontDecoder;
+use crate::core::meta::{page_rect, page_rotation};
+use crate::core::objects::{deref_array, deref_dict};
+use crate::core::text::extract_page_text_raw;
+use crate::types::{
+  DocumentClassification, LineBox, PageLines, PageParagraphs, PageStyledLines, PageVectorRegions,
+  StyledLine, VectorRegion,
+};
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Nesting limit for form XObjects painted from inside other forms.
+pub(crate) const MAX_FORM_DEPTH: u32 = 8;
+
+/// Font descriptor flags (`/Flags`, ISO 32000-1 table 123).
+const FLAG_ITALIC: i64 = 1 << 6;
+const FLAG_FORCE_BOLD: i64 = 1 << 18;
+
+/// Affine transform `[a b c d e f]` using PDF's row-vector convention.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Matrix(pub [f64; 6]);
+
+impl Matrix {
+  pub(crate) const IDENTITY: Matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+  fn translate(tx: f64, ty: f64) -> Matrix {
+    Matrix([1.0, 0.0, 0.0, 1.0, tx, ty])
+  }
+
+  fn from_operands(ops: &[Object]) -> Option<Matrix> {
+    if ops.len() < 6 {
+      return None;
+    }
+    let mut m = [0.0; 6];
+    for (slot, op) in m.iter_mut().zip(ops) {
+      *slot = number(op)?;
+    }
+    Some(Matrix(m))
+  }
+
+  /// Apply `self` first, then `other`.
+  pub(crate) fn then(&self, other: &Matrix) -> Matrix {
+    let [a, b, c, d, e, f] = self.0;
+    let [a2, b2, c2, d2, e2, f2] = other.0;
+    Matrix([
+      a * a2 + b * c2,
+      a * b2 + b * d2,
+      c * a2 + d * c2,
+      c * b2 + d * d2,
+      e * a2 + f * c2 + e2,
+      e * b2 + f * d2 + f2,
+    ])
+  }
+
+  pub(crate) fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+    let [a, b, c, d, e, f] = self.0;
+    (a * x + c * y + e, b * x + d * y + f)
+  }
+}
+
+fn number(obj: &Object) -> Option<f64> {
+  match obj {
+    Object::Integer(i) => Some(*i as f64),
+    Object::Real(r) => Some(*r as f64),
+    _ => None,
+  }
+}
+
+/// One positioned word in default user space (origin bottom-left). `x`/`y` is
+/// the lower-left corner of the box.
+pub(crate) struct WordBox {
+  pub text: String,
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+  /// y of the first glyph's baseline.
+  pub baseline: f64,
+  /// Largest effective (CTM-scaled) font size among the word's glyphs.
+  pub font_size: f64,
+  /// Written top to bottom, as in vertical CJK text.
+  pub vertical: bool,
+  /// Every glyph is in a bold font.
+  pub bold: bool,
+  /// Every glyph is in an italic font.
+  pub italic: bool,
+}
+
+/// A single decoded glyph as painted on the page.
+#[derive(Clone)]
+pub(crate) struct Glyph {
+  pub text: String,
+  pub bbox: [f64; 4],
+  pub baseline: f64,
+  pub size: f64,
+  /// Horizontal scaling (`Tz`) the glyph was shown with, 1.0 for 100%.
+  pub h_scale: f64,
+  /// MCID of the innermost enclosing marked-content sequence that has one.
+  mcid: Option<i64>,
+  /// The content stream (or form XObject) that showed the glyph, and the
+  /// index of the text-showing operator within its decoded operations.
+  source: (ObjectId, u32),
+  /// Position of the showing operator among all operations run for the
+  /// page, forms included, so repeated paints of one form tell apart.
+  step: u32,
+  /// The text advances downwards on the page: a vertical writing mode
+  /// (`/WMode 1`) font, or a text matrix turned that way.
+  pub vertical: bool,
+  bold: bool,
+  italic: bool,
+}
+
+enum Widths {
+  Simple {
+    first_char: u32,
+    widths: Vec<f64>,
+    missing: f64,
+  },
+  Cid {
+    widths: HashMap<u32, f64>,
+    default: f64,
+  },
+}
+
+impl Widths {
+  fn get(&self, code: u32) -> f64 {
+    match self {
+      Widths::Simple {
+        first_char,
+        widths,
+        missing,
+      } => code
+        .checked_sub(*first_char)
+        .and_then(|i| widths.get(i as usize))
+        .copied()
+        .unwrap_or(*missing),
+      Widths::Cid { widths, default } => widths.get(&code).copied().unwrap_or(*default),
+    }
+  }
+}
+
+/// What the layout pass needs from a font: how to split and decode string
+/// operands, and how far each glyph advances.
+struct FontMetrics<'a> {
+  decoder: Option<FontDecoder<'a>>,
+  code_len: usize,
+  widths: Widths,
+  /// Glyph space → text space (1/1000 except for Type3 fonts).
+  width_scale: f64,
+  ascent: f64,
+  descent: f64,
+  /// Vertical writing mode (`/WMode 1`): glyphs advance down by `v_advance`
+  /// instead of right by their width.
+  vertical: bool,
+  /// Vertical displacement per glyph in text space (`/DW2` second entry,
+  /// normally -1).
+  v_advance: f64,
+  bold: bool,
+  italic: bool,
+}
+
+/// Bold and italic hints for a font, from its descriptor's `/Flags`,
+/// `/FontWeight` and `/ItalicAngle`. Fonts without those (the standard 14 have
+/// no descriptor at all) fall back to style words in the `/BaseFont` name.
+fn font_style(font: &Dictionary, descriptor: Option<&Dictionary>) -> (bool, bool) {
+  let entry = |key: &[u8]| descriptor.and_then(|d| d.get(key).ok());
+  let flags = entry(b"Flags").and_then(|o| o.as_i64().ok()).unwrap_or(0);
+  let weight = entry(b"FontWeight").and_then(number);
+  let italic_angle = entry(b"ItalicAngle").and_then(number).unwrap_or(0.0);
+  let base_font = font
+    .get(b"BaseFont")
+    .and_then(Object::as_name)
+    .map(|name| String::from_utf8_lossy(name).to_ascii_lowercase())
+    .unwrap_or_default();
+  let named = |styles: &[&str]| styles.iter().any(|s| base_font.contains(s));
+
+  let bold = flags & FLAG_FORCE_BOLD != 0
+    || weight.is_some_and(|w| w >= 600.0)
+    || (weight.is_none() && named(&["bold", "black", "heavy"]));
+  let italic = flags & FLAG_ITALIC != 0 || italic_angle != 0.0 || named(&["italic", "oblique"]);
+  (bold, italic)
+}
+
+/// Whether a Type0 font's `/Encoding` CMap is in vertical writing mode: a
+/// predefined `-V` CMap, or an embedded one with `/WMode 1`.
+fn is_vertical_encoding(doc: &Document, font: &Dictionary) -> bool {
+  match font.get(b"Encoding") {
+    Ok(Object::Name(name)) => name.ends_with(b"-V"),
+    Ok(Object::Reference(id)) => {
+      doc
+        .get_object(*id)
+        .ok()
+        .and_then(|o| o.as_stream().ok())
+        .and_then(|s| s.dict.get(b"WMode").ok())
+        .and_then(|o| o.as_i64().ok())
+        == Some(1)
+    }
+    _ => false,
+  }
+}
+
+/// Parse a CIDFont `/W` array: `c [w1 w2 …]` and `c_first c_last w` forms.
+fn parse_cid_widths(doc: &Document, arr: &[Object]) -> HashMap<u32, f64> {
+  let mut out = HashMap::new();
+  let mut i = 0;
+  while i + 1 < arr.len() {
+    let Some(first) = number(&arr[i]) else {
+      i += 1;
+      continue;
+    };
+    let first = first as u32;
+    if let Some(list) = deref_array(doc, &arr[i + 1]) {
+      for (offset, w) in list.iter().enumerate() {
+        if let Some(w) = number(w) {
+          out.insert(first + offset as u32, w);
+        }
+      }
+      i += 2;
+    } else if let (Some(last), Some(w)) = (number(&arr[i + 1]), arr.get(i + 2).and_then(number)) {
+      // Cap so a corrupt range can't blow up the table
+      for cid in first..=(last as u32).min(first.saturating_add(0xFFFF)) {
+        out.insert(cid, w);
+      }
+      i += 3;
+    } else {
+      i += 2;
+    }
+  }
+  out
+}
+
+fn load_font_metrics<'a>(doc: &'a Document, font: &'a Dictionary) -> FontMetrics<'a> {
+  let subtype = font.get(b"Subtype").and_then(Object::as_name).ok();
+  let descendant = if subtype == Some(b"Type0".as_slice()) {
+    font
+      .get(b"DescendantFonts")
+      .ok()
+      .and_then(|o| deref_array(doc, o))
+      .and_then(|arr| arr.first())
+      .and_then(|o| deref_dict(doc, o))
+  } else {
+    None
+  };
+
+  let vertical = descendant.is_some() && is_vertical_encoding(doc, font);
+  let v_advance = descendant
+    .and_then(|d| d.get(b"DW2").ok())
+    .and_then(|o| deref_array(doc, o))
+    .and_then(|arr| arr.get(1))
+    .and_then(number)
+    .map(|w1| w1 * 0.001)
+    .filter(|&w1| w1 < 0.0)
+    .unwrap_or(-1.0);
+
+  let (code_len, widths) = match descendant {
+    Some(cid_font) => {
+      let default = cid_font.get(b"DW").ok().and_then(number).unwrap_or(1000.0);
+      let widths = cid_font
+        .get(b"W")
+        .ok()
+        .and_then(|o| deref_array(doc, o))
+        .map(|arr| parse_cid_widths(doc, arr))
+        .unwrap_or_default();
+      (2, Widths::Cid { widths, default })
+    }
+    None => {
+      let first_char = font.get(b"FirstChar").ok().and_then(number).unwrap_or(0.0) as u32;
+      let widths: Vec<f64> = font
+        .get(b"Widths")
+        .ok()
+        .and_then(|o| deref_array(doc, o))
+        .map(|arr| arr.iter().map(|w| number(w).unwrap_or(0.0)).collect())
+        .unwrap_or_default();
+      let descriptor = font
+        .get(b"FontDescriptor")
+        .ok()
+        .and_then(|o| deref_dict(doc, o));
+      // Standard 14 fonts may omit /Widths entirely — assume an average glyph
+      let missing = if widths.is_empty() {
+        500.0
+      } else {
+        descriptor
+          .and_then(|d| d.get(b"MissingWidth").ok())
+          .and_then(number)
+          .unwrap_or(0.0)
+      };
+      (
+        1,
+        Widths::Simple {
+          first_char,
+          widths,
+          missing,
+        },
+      )
+    }
+  };
+
+  let width_scale = if subtype == Some(b"Type3".as_slice()) {
+    font
+      .get(b"FontMatrix")
+      .ok()
+      .and_then(|o| deref_array(doc, o))
+      .and_then(|m| m.first())
+      .and_then(number)
+      .unwrap_or(0.001)
+  } else {
+    0.001
+  };
+
+  let descriptor = descendant
+    .unwrap_or(font)
+    .get(b"FontDescriptor")
+    .ok()
+    .and_then(|o| deref_dict(doc, o));
+  let ascent = descriptor
+    .and_then(|d| d.get(b"Ascent").ok())
+    .and_then(number)
+    .map(|a| a * 0.001)
+    .filter(|&a| a > 0.0)
+    .unwrap_or(0.8);
+  let descent = descriptor
+    .and_then(|d| d.get(b"Descent").ok())
+    .and_then(number)
+    .map(|d| d * 0.001)
+    .filter(|&d| d < 0.0)
+    .unwrap_or(-0.2);
+  let (bold, italic) = font_style(font, descriptor);
+
+  FontMetrics {
+    decoder: FontDecoder::for_font(doc, font),
+    code_len,
+    widths,
+    width_scale,
+    ascent,
+    descent,
+    vertical,
+    v_advance,
+    bold,
+    italic,
+  }
+}
+
+#[derive(Clone)]
+struct GraphicsState<'a> {
+  ctm: Matrix,
+  font: Option<Rc<FontMetrics<'a>>>,
+  font_size: f64,
+  char_spacing: f64,
+  word_spacing: f64,
+  h_scale: f64,
+  leading: f64,
+  rise: f64,
+}
+
+impl GraphicsState<'_> {
+  fn new(ctm: Matrix) -> Self {
+    GraphicsState {
+      ctm,
+      font: None,
+      font_size: 0.0,
+      char_spacing: 0.0,
+      word_spacing: 0.0,
+      h_scale: 1.0,
+      leading: 0.0,
+      rise: 0.0,
+    }
+  }
+}
+
+struct Interpreter<'a> {
+  doc: &'a Document,
+  font_cache: HashMap<ObjectId, Rc<FontMetrics<'a>>>,
+  glyphs: Vec<Glyph>,
+  /// Bounding boxes of painted image XObjects, in the same space as glyphs.
+  images: Vec<[f64; 4]>,
+  /// Bounding boxes of stroked or filled paths, in the same space as glyphs.
+  paths: Vec<[f64; 4]>,
+  /// Open marked-content sequences (BMC/BDC), with their MCID if any.
+  marked: Vec<Option<i64>>,
+  /// Innermost MCID in effect at each `Do` of the page's own content, by
+  /// XObject name. The first painting of a name wins.
+  do_mcids: HashMap<Vec<u8>, i64>,
+  /// Stream and operator index of the operation being run.
+  source: (ObjectId, u32),
+  /// Operations run so far.
+  step: u32,
+}
+
+impl<'a> Interpreter<'a> {
+  /// MCID of the innermost open marked-content sequence that has one.
+  fn mcid(&self) -> Option<i64> {
+    self.marked.iter().rev().find_map(|m| *m)
+  }
+
+  fn font(
+    &mut self,
+    resources: Option<&'a Dictionary>,
+    name: &[u8],
+  ) -> Option<Rc<FontMetrics<'a>>> {
+    let fonts = deref_dict(self.doc, resources?.get(b"Font").ok()?)?;
+    match fonts.get(name).ok()? {
+      Object::Reference(id) => {
+        if let Some(cached) = self.font_cache.get(id) {
+          return Some(Rc::clone(cached));
+        }
+        let dict = self.doc.get_dictionary(*id).ok()?;
+        let metrics = Rc::new(load_font_metrics(self.doc, dict));
+        self.font_cache.insert(*id, Rc::clone(&metrics));
+        Some(metrics)
+      }
+      Object::Dictionary(dict) => Some(Rc::new(load_font_metrics(self.doc, dict))),
+      _ => None,
+    }
+  }
+
+  fn show(&mut self, gs: &GraphicsState, tm: &mut Matrix, bytes: &[u8]) {
+    let Some(font) = gs.font.as_ref() else {
+      return;
+    };
+    let size = gs.font_size;
+    let user = tm.then(&gs.ctm);
+    let effective_size = size * user.0[2].hypot(user.0[3]);
+    // Direction the text advances in, on the page
+    let (dx, dy) = if font.vertical {
+      (-user.0[2], -user.0[3])
+    } else {
+      (user.0[0], user.0[1])
+    };
+    let vertical = dy < 0.0 && dy.abs() > dx.abs();
+    for chunk in bytes.chunks(font.code_len) {
+      let code = chunk.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+      let w = font.widths.get(code) * font.width_scale;
+      let trm = Matrix([size * gs.h_scale, 0.0, 0.0, size, 0.0, gs.rise])
+        .then(tm)
+        .then(&gs.ctm);
+      let text = font
+        .decoder
+        .as_ref()
+        .map(|d| d.decode(chunk))
+        .unwrap_or_default();
+      if !text.is_empty() {
+        // Vertical-mode glyphs hang below their origin, centred on it
+        let corners = if font.vertical {
+          [
+            trm.apply(-w / 2.0, font.v_advance),
+            trm.apply(w / 2.0, font.v_advance),
+            trm.apply(-w / 2.0, 0.0),
+            trm.apply(w / 2.0, 0.0),
+          ]
+        } else {
+          [
+            trm.apply(0.0, font.descent),
+            trm.apply(w, font.descent),
+            trm.apply(0.0, font.ascent),
+            trm.apply(w, font.ascent),
+          ]
+        };
+        self.glyphs.push(Glyph {
+          text,
+          bbox: bounds(corners),
+          baseline: trm.apply(0.0, 0.0).1,
+          size: effective_size,
+          h_scale: gs.h_scale,
+          mcid: self.mcid(),
+          source: self.source,
+          step: self.step,
+          vertical,
+          bold: font.bold,
+          italic: font.italic,
+        });
+      }
+      let word_spacing = if font.code_len == 1 && code == 32 {
+        gs.word_spacing
+      } else {
+        0.0
+      };
+      if font.vertical {
+        let ty = font.v_advance * size + gs.char_spacing + word_spacing;
+        *tm = Matrix::translate(0.0, ty).then(tm);
+      } else {
+        let tx = (w * size + gs.char_spacing + word_spacing) * gs.h_scale;
+        *tm = Matrix::translate(tx, 0.0).then(tm);
+      }
+    }
+  }
+
+  /// Run `operations`, which come from the content streams in `streams`:
+  /// each stream's id with the index of its first operation.
+  fn run(
+    &mut self,
+    operations: &[Operation],
+    streams: &[(ObjectId, usize)],
+    resources: Option<&'a Dictionary>,
+    ctm: Matrix,
+    depth: u32,
+  ) {
+    let mut gs = GraphicsState::new(ctm);
+    let mut stack: Vec<GraphicsState> = Vec::new();
+    let mut tm = Matrix::IDENTITY;
+    let mut tlm = Matrix::IDENTITY;
+    // Bounds of the path under construction (control points included)
+    let mut path: Option<[f64; 4]> = None;
+
+    for (index, op) in operations.iter().enumerate() {
+      let stream = streams.partition_point(|&(_, start)| start <= index);
+      if let Some(&(id, start)) = stream.checked_sub(1).and_then(|i| streams.get(i)) {
+        self.source = (id, (index - start) as u32);
+      }
+      self.step += 1;
+      let operands = &op.operands;
+      let num = |i: usize| operands.get(i).and_then(number).unwrap_or(0.0);
+      match op.operator.as_str() {
+        "q" => stack.push(gs.clone()),
+        "Q" => {
+          if let Some(saved) = stack.pop() {
+            gs = saved;
+          }
+        }
+        "cm" => {
+          if let Some(m) = Matrix::from_operands(operands) {
+            gs.ctm = m.then(&gs.ctm);
+          }
+        }
+        "m" | "l" | "c" | "v" | "y" => {
+          for pair in operands.chunks_exact(2) {
+            let (Some(x), Some(y)) = (number(&pair[0]), number(&pair[1])) else {
+              continue;
+            };
+            let (x, y) = gs.ctm.apply(x, y);
+            let b = path.get_or_insert([x, y, x, y]);
+            *b = [b[0].min(x), b[1].min(y), b[2].max(x), b[3].max(y)];
+          }
+        }
+        "re" => {
+          let (x, y, w, h) = (num(0), num(1), num(2), num(3));
+          let ctm = gs.ctm;
+          let r = bounds([
+            ctm.apply(x, y),
+            ctm.apply(x + w, y),
+            ctm.apply(x, y + h),
+            ctm.apply(x + w, y + h),
+          ]);
+          let b = path.get_or_insert(r);
+          *b = [
+            b[0].min(r[0]),
+            b[1].min(r[1]),
+            b[2].max(r[2]),
+            b[3].max(r[3]),
+          ];
+        }
+        "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" => {
+          if let Some(b) = path.take() {
+            self.paths.push(b);
+          }
+        }
+        // End the path without painting it (clipping paths)
+        "n" => path = None,
+        "BMC" => self.marked.push(None),
+        "BDC" => {
+          // Properties are inline or a name in the /Properties resource
+          let props = match operands.get(1) {
+            Some(Object::Name(name)) => resources
+              .and_then(|r| r.get(b"Properties").ok())
+              .and_then(|o| deref_dict(self.doc, o))
+              .and_then(|p| p.get(name).ok())
+              .and_then(|o| deref_dict(self.doc, o)),
+            Some(other) => deref_dict(self.doc, other),
+            None => None,
+          };
+          let mcid = props
+            .and_then(|p| p.get(b"MCID").ok())
+            .and_then(|o| o.as_i64().ok());
+          self.marked.push(mcid);
+        }
+        "EMC" => {
+          self.marked.pop();
+        }
+        "BT" => {
+          tm = Matrix::IDENTITY;
+          tlm = Matrix::IDENTITY;
+        }
+        "Tf" => {
+          gs.font = operands
+            .first()
+            .and_then(|o| o.as_name().ok())
+            .and_then(|name| self.font(resources, name));
+          gs.font_size = num(1);
+        }
+        "Tc" => gs.char_spacing = num(0),
+        "Tw" => gs.word_spacing = num(0),
+        "Tz" => gs.h_scale = num(0) / 100.0,
+        "TL" => gs.leading = num(0),
+        "Ts" => gs.rise = num(0),
+        "Td" | "TD" => {
+          if op.operator == "TD" {
+            gs.leading = -num(1);
+          }
+          tlm = Matrix::translate(num(0), num(1)).then(&tlm);
+          tm = tlm;
+        }
+        "Tm" => {
+          if let Some(m) = Matrix::from_operands(operands) {
+            tlm = m;
+            tm = m;
+          }
+        }
+        "T*" => {
+          tlm = Matrix::translate(0.0, -gs.leading).then(&tlm);
+          tm = tlm;
+        }
+        "Tj" | "'" | "\"" => {
+          if op.operator != "Tj" {
+            if op.operator == "\"" {
+              gs.word_spacing = num(0);
+              gs.char_spacing = num(1);
+            }
+            tlm = Matrix::translate(0.0, -gs.leading).then(&tlm);
+            tm = tlm;
+          }
+          if let Some(Object::String(bytes, _)) = operands.last() {
+            self.show(&gs, &mut tm, bytes);
+          }
+        }
+        "TJ" => {
+          let Some(Object::Array(items)) = operands.first() else {
+            continue;
+          };
+          for item in items {
+            match item {
+              Object::String(bytes, _) => self.show(&gs, &mut tm, bytes),
+              other => {
+                if let Some(adjust) = number(other) {
+                  let shift = -adjust / 1000.0 * gs.font_size;
+                  tm = if gs.font.as_ref().is_some_and(|f| f.vertical) {
+                    Matrix::translate(0.0, shift).then(&tm)
+                  } else {
+                    Matrix::translate(shift * gs.h_scale, 0.0).then(&tm)
+                  };
+                }
+              }
+            }
+          }
+        }
+        "Do" if depth < MAX_FORM_DEPTH => {
+          let Some(name) = operands.first().and_then(|o| o.as_name().ok()) else {
+            continue;
+          };
+          if depth == 0
+            && let Some(mcid) = self.mcid()
+          {
+            self.do_mcids.entry(name.to_vec()).or_insert(mcid);
+          }
+          let Some(form_id) = resources
+            .and_then(|r| r.get(b"XObject").ok())
+            .and_then(|o| deref_dict(self.doc, o))
+            .and_then(|x| x.get(name).ok())
+            .and_then(|o| o.as_reference().ok())
+          else {
+            continue;
+          };
+          let Some(stream) = self
+            .doc
+            .get_object(form_id)
+            .ok()
+            .and_then(|o| o.as_stream().ok())
+          else {
+            continue;
+          };
+          match stream.dict.get(b"Subtype").and_then(Object::as_name).ok() {
+            Some(b"Form") => {}
+            Some(b"Image") => {
+              // Images are painted into the unit square of the current CTM
+              let ctm = gs.ctm;
+              self.images.push(bounds([
+                ctm.apply(0.0, 0.0),
+                ctm.apply(1.0, 0.0),
+                ctm.apply(0.0, 1.0),
+                ctm.apply(1.0, 1.0),
+              ]));
+              continue;
+            }
+            _ => continue,
+          }
+          let matrix = stream
+            .dict
+            .get(b"Matrix")
+            .ok()
+            .and_then(|o| deref_array(self.doc, o))
+            .and_then(|arr| Matrix::from_operands(arr))
+            .unwrap_or(Matrix::IDENTITY);
+          let form_resources = stream
+            .dict
+            .get(b"Resources")
+            .ok()
+            .and_then(|o| deref_dict(self.doc, o))
+            .or(resources);
+          let data = stream
+            .decompressed_content()
+            .unwrap_or_else(|_| stream.content.clone());
+          let Ok(form) = Content::decode(&data) else {
+            continue;
+          };
+          self.run(
+            &form.operations,
+            &[(form_id, 0)],
+            form_resources,
+            matrix.then(&gs.ctm),
+            depth + 1,
+          );
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+/// Axis-aligned `[left, bottom, right, top]` box around four points.
+fn bounds(corners: [(f64, f64); 4]) -> [f64; 4] {
+  let mut bbox = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+  for (x, y) in corners {
+    bbox[0] = bbox[0].min(x);
+    bbox[1] = bbox[1].min(y);
+    bbox[2] = bbox[2].max(x);
+    bbox[3] = bbox[3].max(y);
+  }
+  bbox
+}
+
+pub(crate) fn page_resources(doc: &Document, page_id: ObjectId) -> Option<&Dictionary> {
+  let mut current = Some(page_id);
+  while let Some(id) = current {
+    let dict = doc.get_dictionary(id).ok()?;
+    if let Ok(res) = dict.get(b"Resources") {
+      return deref_dict(doc, res);
+    }
+    current = dict.get(b"Parent").and_then(Object::as_reference).ok();
+  }
+  None
+}
+
+/// Group glyphs (in content-stream order) into words. A word ends at a
+/// whitespace glyph, a baseline change, or a horizontal gap wider than a
+/// fraction of the font size. Vertical glyphs group the same way along their
+/// column: by horizontal centre and the gap below the previous glyph.
+fn group_words(glyphs: Vec<Glyph>) -> Vec<WordBox> {
+  let mut words = Vec::new();
+  // Merged glyph + last glyph's trailing edge (right, or bottom if vertical)
+  let mut current: Option<(Glyph, f64)> = None;
+
+  let flush = |current: &mut Option<(Glyph, f64)>, words: &mut Vec<WordBox>| {
+    if let Some((g, _)) = current.take() {
+      words.push(WordBox {
+        text: g.text,
+        x: g.bbox[0],
+        y: g.bbox[1],
+        width: g.bbox[2] - g.bbox[0],
+        height: g.bbox[3] - g.bbox[1],
+        baseline: g.baseline,
+        font_size: g.size,
+        vertical: g.vertical,
+        bold: g.bold,
+        italic: g.italic,
+      });
+    }
+  };
+
+  for glyph in glyphs {
+    if glyph.text.chars().all(char::is_whitespace) {
+      flush(&mut current, &mut words);
+      continue;
+    }
+    if let Some((word, last_edge)) = current.as_mut() {
+      let tolerance = word.size.max(glyph.size);
+      let (gap, same_line) = if glyph.vertical {
+        let centre = |b: &[f64; 4]| (b[0] + b[2]) / 2.0;
+        (
+          *last_edge - glyph.bbox[3],
+          (centre(&glyph.bbox) - centre(&word.bbox)).abs() <= tolerance * 0.5,
+        )
+      } else {
+        (
+          glyph.bbox[0] - *last_edge,
+          (glyph.baseline - word.baseline).abs() <= tolerance * 0.5,
+        )
+      };
+      if glyph.vertical == word.vertical
+        && same_line
+        && gap <= tolerance * 0.25
+        && gap >= -tolerance * 0.5
+      {
+        word.bbox[0] = word.bbox[0].min(glyph.bbox[0]);
+        word.bbox[1] = word.bbox[1].min(glyph.bbox[1]);
+        word.bbox[2] = word.bbox[2].max(glyph.bbox[2]);
+        word.bbox[3] = word.bbox[3].max(glyph.bbox[3]);
+        word.size = word.size.max(glyph.size);
+        word.bold &= glyph.bold;
+        word.italic &= glyph.italic;
+        word.text.push_str(&glyph.text);
+        *last_edge = if glyph.vertical {
+          glyph.bbox[1]
+        } else {
+          glyph.bbox[2]
+        };
+        continue;
+      }
+      flush(&mut current, &mut words);
+    }
+    let edge = if glyph.vertical {
+      glyph.bbox[1]
+    } else {
+      glyph.bbox[2]
+    };
+    current = Some((glyph, edge));
+  }
+  flush(&mut current, &mut words);
+  words
+}
+
+/// The text of the glyphs whose centre lies inside any of `regions`
+/// (`[left, bottom, right, top]` in the glyphs' space), grouped into words and
+/// lines and joined with spaces in reading order. Highlights often start or
+/// end mid-word, so this selects glyphs rather than whole words.
+pub(crate) fn text_within(glyphs: &[Glyph], regions: &[[f64; 4]]) -> String {
+  let inside: Vec<Glyph> = glyphs
+    .iter()
+    .filter(|g| {
+      let (cx, cy) = ((g.bbox[0] + g.bbox[2]) / 2.0, (g.bbox[1] + g.bbox[3]) / 2.0);
+      regions
+        .iter()
+        .any(|r| cx >= r[0] && cx <= r[2] && cy >= r[1] && cy <= r[3])
+    })
+    .cloned()
+    .collect();
+  group_lines(group_words(inside))
+    .into_iter()
+    .map(|line| line.text)
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// What a page paints, positioned by the base matrix passed to
+/// `interpret_page`.
+struct PageContents {
+  glyphs: Vec<Glyph>,
+  images: Vec<[f64; 4]>,
+  paths: Vec<[f64; 4]>,
+  do_mcids: HashMap<Vec<u8>, i64>,
+}
+
+/// Run a page's content streams, collecting what they paint. The streams
+/// are decoded one by one and run as a single sequence of operations, so
+/// graphics state carries over from one stream to the next.
+fn interpret_page(doc: &Document, page_id: ObjectId, base: Matrix) -> PageContents {
+  let mut operations = Vec::new();
+  let mut streams = Vec::new();
+  for stream_id in doc.get_page_contents(page_id) {
+    let Ok(Object::Stream(stream)) = doc.get_object(stream_id) else {
+      continue;
+    };
+    let data = stream
+      .decompressed_content()
+      .unwrap_or_else(|_| stream.content.clone());
+    if let Ok(content) = Content::decode(&data) {
+      streams.push((stream_id, operations.len()));
+      operations.extend(content.operations);
+    }
+  }
+  let mut interp = Interpreter {
+    doc,
+    font_cache: HashMap::new(),
+    glyphs: Vec::new(),
+    images: Vec::new(),
+    paths: Vec::new(),
+    marked: Vec::new(),
+    do_mcids: HashMap::new(),
+    source: ((0, 0), 0),
+    step: 0,
+  };
+  interp.run(&operations, &streams, page_resources(doc, page_id), base, 0);
+  PageContents {
+    glyphs: interp.glyphs,
+    images: interp.images,
+    paths: interp.paths,
+    do_mcids: interp.do_mcids,
+  }
+}
+
+fn collect_words(doc: &Document, page_id: ObjectId, base: Matrix) -> Vec<WordBox> {
+  group_words(interpret_page(doc, page_id, base).glyphs)
+}
+
+/// A page's glyphs in content-stream order, positioned in default user space
+/// with the text state (`Tc`, `Tw`, `Tz`, `TJ` adjustments) applied.
+pub(crate) fn extract_page_glyphs(doc: &Document, page_id: ObjectId) -> Vec<Glyph> {
+  interpret_page(doc, page_id, Matrix::IDENTITY).glyphs
+}
+
+/// Interpret a page's content streams (following form XObjects) and return
+/// its words with their boxes in default user space.
+pub(crate) fn extract_page_words(doc: &Document, page_id: ObjectId) -> Vec<WordBox> {
+  collect_words(doc, page_id, Matrix::IDENTITY)
+}
+
+/// Maps default user space onto the page as displayed after its `/Rotate`,
+/// with the origin at the displayed bottom-left corner.
+fn display_matrix(rotation: u32, [left, bottom, right, top]: [f64; 4]) -> Matrix {
+  match rotation {
+    90 => Matrix([0.0, -1.0, 1.0, 0.0, -bottom, right]),
+    180 => Matrix([-1.0, 0.0, 0.0, -1.0, right, top]),
+    270 => Matrix([0.0, 1.0, -1.0, 0.0, top, -left]),
+    _ => Matrix::IDENTITY,
+  }
+}
+
+/// Like `extract_page_words`, but for rotated pages the boxes are in the
+/// displayed orientation, so "top to bottom" and "left to right" match what a
+/// viewer shows. Unrotated pages keep their user-space coordinates.
+pub(crate) fn extract_display_words(doc: &Document, page_id: ObjectId) -> Vec<WordBox> {
+  collect_words(doc, page_id, display_base(doc, page_id))
+}
+
+/// `display_matrix` for the page, or the identity when it isn't rotated.
+fn display_base(doc: &Document, page_id: ObjectId) -> Matrix {
+  let rotation = page_rotation(doc, page_id);
+  match page_rect(doc, page_id) {
+    Some(rect) if rotation != 0 => display_matrix(rotation, rect),
+    _ => Matrix::IDENTITY,
+  }
+}
+
+/// Font size given to overlay text at most; smaller boxes use their height.
+const OVERLAY_FONT_SIZE: f64 = 12.0;
+
+/// A page's lines as `group_lines` returns them for `extract_display_words`,
+/// with extra text placed on the page: each `(rect, text)` — `rect` in
+/// default user space, such as a form widget's `/Rect` — becomes a word set
+/// in that box, so it joins the line of any label beside it. Text in boxes
+/// taller than two lines sits on the first line.
+pub(crate) fn page_lines_with_overlays(
+  doc: &Document,
+  page_id: ObjectId,
+  overlays: &[([f64; 4], String)],
+) -> Vec<LineBox> {
+  let base = display_base(doc, page_id);
+  let mut words = collect_words(doc, page_id, base);
+  for ([x1, y1, x2, y2], text) in overlays {
+    let ((ax, ay), (bx, by)) = (base.apply(*x1, *y1), base.apply(*x2, *y2));
+    let (left, right, bottom, top) = (ax.min(bx), ax.max(bx), ay.min(by), ay.max(by));
+    let height = top - bottom;
+    let font_size = height.min(OVERLAY_FONT_SIZE);
+    let baseline = if height > font_size * 2.0 {
+      top - font_size
+    } else {
+      bottom + (height - font_size) / 2.0
+    };
+    words.push(WordBox {
+      text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+      x: left,
+      y: baseline,
+      width: right - left,
+      height: font_size,
+      baseline,
+      font_size,
+      vertical: false,
+      bold: false,
+      italic: false,
+    });
+  }
+  group_lines(words)
+}
+
+/// Text of each marked-content sequence on a page, keyed by MCID. Glyphs are
+/// grouped into words as for `extract_page_words` and joined with spaces.
+pub(crate) fn page_marked_text(doc: &Document, page_id: ObjectId) -> HashMap<i64, String> {
+  let mut by_mcid: HashMap<i64, Vec<Glyph>> = HashMap::new();
+  for glyph in interpret_page(doc, page_id, Matrix::IDENTITY).glyphs {
+    if let Some(mcid) = glyph.mcid {
+      by_mcid.entry(mcid).or_default().push(glyph);
+    }
+  }
+  by_mcid
+    .into_iter()
+    .map(|(mcid, glyphs)| {
+      let words: Vec<String> = group_words(glyphs).into_iter().map(|w| w.text).collect();
+      (mcid, words.join(" "))
+    })
+    .collect()
+}
+
+/// Text shown by each text-showing operator on a page, keyed by the content
+/// stream (or form XObject) and the operator's index within it, in the order
+/// first painted. A form painted more than once is reported once. Glyphs are
+/// grouped into words as for `extract_page_words` and joined with spaces.
+pub(crate) fn page_source_text(doc: &Document, page_id: ObjectId) -> Vec<(ObjectId, u32, String)> {
+  let mut runs: Vec<(u32, Vec<Glyph>)> = Vec::new();
+  let mut seen = HashSet::new();
+  for glyph in interpret_page(doc, page_id, Matrix::IDENTITY).glyphs {
+    match runs.last_mut() {
+      Some((step, glyphs)) if *step == glyph.step => glyphs.push(glyph),
+      _ if seen.insert(glyph.source) => runs.push((glyph.step, vec![glyph])),
+      // The operator was already shown by an earlier paint of its form
+      _ => {}
+    }
+  }
+  runs
+    .into_iter()
+    .filter_map(|(_, glyphs)| {
+      let (stream_id, index) = glyphs[0].source;
+      let words: Vec<String> = group_words(glyphs).into_iter().map(|w| w.text).collect();
+      (!words.is_empty()).then(|| (stream_id, index, words.join(" ")))
+    })
+    .collect()
+}
+
+/// Innermost MCID in effect at each `Do` of a page's content stream, keyed
+/// by XObject name. The first painting of a name wins.
+pub(crate) fn page_do_mcids(doc: &Document, page_id: ObjectId) -> HashMap<Vec<u8>, i64> {
+  interpret_page(doc, page_id, Matrix::IDENTITY).do_mcids
+}
+
+/// Group a page's words into lines: words whose baselines are within half a
+/// font size of each other form one line, ordered left to right. Lines are
+/// returned top to bottom. Vertical words form columns instead, read top to
+/// bottom; the columns follow the horizontal lines, right to left.
+pub(crate) fn group_lines(words: Vec<WordBox>) -> Vec<LineBox> {
+  group_line_words(words)
+    .iter()
+    .map(|line| line_box(line))
+    .collect()
+}
+
+/// The words of each line, in the order described on `group_lines`.
+fn group_line_words(words: Vec<WordBox>) -> Vec<Vec<WordBox>> {
+  let (mut columns, mut words): (Vec<WordBox>, Vec<WordBox>) =
+    words.into_iter().partition(|w| w.vertical);
+  words.sort_by(|a, b| b.baseline.total_cmp(&a.baseline));
+  let mut lines: Vec<Vec<WordBox>> = Vec::new();
+  for word in words {
+    match lines.last_mut() {
+      Some(line)
+        if (line[0].baseline - word.baseline).abs()
+          <= line[0].font_size.min(word.font_size) * 0.5 =>
+      {
+        line.push(word)
+      }
+      _ => lines.push(vec![word]),
+    }
+  }
+  for line in &mut lines {
+    line.sort_by(|a, b| a.x.total_cmp(&b.x));
+  }
+
+  let centre = |w: &WordBox| w.x + w.width / 2.0;
+  columns.sort_by(|a, b| centre(b).total_cmp(&centre(a)));
+  let first_column = lines.len();
+  for word in columns {
+    match lines[first_column..].last_mut() {
+      Some(column)
+        if (centre(&column[0]) - centre(&word)).abs()
+          <= column[0].font_size.min(word.font_size) * 0.5 =>
+      {
+        column.push(word)
+      }
+      _ => lines.push(vec![word]),
+    }
+  }
+  for column in &mut lines[first_column..] {
+    column.sort_by(|a, b| (b.y + b.height).total_cmp(&(a.y + a.height)));
+  }
+  lines
+}
+
+/// Text and bounding box of one grouped line.
+fn line_box(line: &[WordBox]) -> LineBox {
+  let left = line.iter().map(|w| w.x).fold(f64::MAX, f64::min);
+  let bottom = line.iter().map(|w| w.y).fold(f64::MAX, f64::min);
+  let right = line.iter().map(|w| w.x + w.width).fold(f64::MIN, f64::max);
+  let top = line.iter().map(|w| w.y + w.height).fold(f64::MIN, f64::max);
+  let text = line
+    .iter()
+    .map(|w| w.text.as_str())
+    .collect::<Vec<_>>()
+    .join(" ");
+  LineBox {
+    text,
+    x: left,
+    y: bottom,
+    width: right - left,
+    height: top - bottom,
+    vertical: line[0].vertical,
+  }
+}
+
+/// Group a page's words into lines as `group_lines` does, tagging each line
+/// with its largest font size and whether all of its words are bold or italic.
+pub(crate) fn group_styled_lines(words: Vec<WordBox>) -> Vec<StyledLine> {
+  group_line_words(words)
+    .into_iter()
+    .map(|line| {
+      let LineBox {
+        text,
+        x,
+        y,
+        width,
+        height,
+        vertical,
+      } = line_box(&line);
+      StyledLine {
+        text,
+        x,
+        y,
+        width,
+        height,
+        vertical,
+        font_size: line.iter().map(|w| w.font_size).fold(0.0, f64::max),
+        bold: line.iter().all(|w| w.bold),
+        italic: line.iter().all(|w| w.italic),
+      }
+    })
+    .collect()
+}
+
+/// Styled lines for every page, in page order.
+pub(crate) fn collect_styled_lines(doc: &Document) -> Vec<PageStyledLines> {
+  let pages = doc.get_pages();
+  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
+  page_entries
+    .par_iter()
+    .map(|&(page_num, page_id)| PageStyledLines {
+      page: page_num,
+      lines: group_styled_lines(extract_display_words(doc, page_id)),
+    })
+    .collect()
+}
+
+/// Positioned lines for every page, in page order.
+pub(crate) fn extract_positioned_lines(doc: &Document) -> Vec<PageLines> {
+  let pages = doc.get_pages();
+  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
+  page_entries
+    .par_iter()
+    .map(|&(page_num, page_id)| PageLines {
+      page: page_num,
+      lines: group_lines(extract_display_words(doc, page_id)),
+    })
+    .collect()
+}
+
+/// Join a page's lines (top to bottom) into paragraphs. A paragraph ends where
+/// the vertical gap to the next line exceeds the page's median line gap by
+/// more than half a line height. Line breaks inside a paragraph become spaces.
+pub(crate) fn group_paragraphs(lines: &[LineBox]) -> Vec<String> {
+  let gap = |above: &LineBox, below: &LineBox| above.y - (below.y + below.height);
+  let mut gaps: Vec<f64> = lines.windows(2).map(|w| gap(&w[0], &w[1])).collect();
+  gaps.sort_by(f64::total_cmp);
+  let typical = gaps.get(gaps.len() / 2).copied().unwrap_or(0.0).max(0.0);
+
+  let mut paragraphs: Vec<String> = Vec::new();
+  let mut prev: Option<&LineBox> = None;
+  for line in lines {
+    match (prev, paragraphs.last_mut()) {
+      (Some(above), Some(current))
+        if gap(above, line) <= typical + above.height.min(line.height) * 0.5 =>
+      {
+        current.push(' ');
+        current.push_str(&line.text);
+      }
+      _ => paragraphs.push(line.text.clone()),
+    }
+    prev = Some(line);
+  }
+  paragraphs
+}
+
+/// Paragraphs for every page, in page order.
+pub(crate) fn extract_grouped_paragraphs(doc: &Document) -> Vec<PageParagraphs> {
+  extract_positioned_lines(doc)
+    .into_iter()
+    .map(|p| PageParagraphs {
+      page: p.page,
+      paragraphs: group_paragraphs(&p.lines),
+    })
+    .collect()
+}
+
+/// Painted paths closer than this (in points) belong to the same region.
+const REGION_MERGE_GAP: f64 = 2.0;
+
+/// Paths covering at least this fraction of the page are backgrounds.
+const BACKGROUND_COVERAGE: f64 = 0.9;
+
+fn boxes_touch(a: &[f64; 4], b: &[f64; 4]) -> bool {
+  a[0] <= b[2] + REGION_MERGE_GAP
+    && b[0] <= a[2] + REGION_MERGE_GAP
+    && a[1] <= b[3] + REGION_MERGE_GAP
+    && b[1] <= a[3] + REGION_MERGE_GAP
+}
+
+fn union_box(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+  [
+    a[0].min(b[0]),
+    a[1].min(b[1]),
+    a[2].max(b[2]),
+    a[3].max(b[3]),
+  ]
+}
+
+/// Cluster path boxes into regions of touching (or nearly touching) paths.
+/// Returns each region's box with the number of paths in it.
+fn merge_regions(mut boxes: Vec<[f64; 4]>) -> Vec<([f64; 4], u32)> {
+  fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+      parent[i] = parent[parent[i]];
+      i = parent[i];
+    }
+    i
+  }
+
+  // Sweep in left-edge order so each box is only compared with boxes that
+  // can still reach it horizontally
+  boxes.sort_by(|a, b| a[0].total_cmp(&b[0]));
+  let mut parent: Vec<usize> = (0..boxes.len()).collect();
+  for i in 0..boxes.len() {
+    for j in i + 1..boxes.len() {
+      if boxes[j][0] > boxes[i][2] + REGION_MERGE_GAP {
+        break;
+      }
+      if boxes_touch(&boxes[i], &boxes[j]) {
+        let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+        parent[rj] = ri;
+      }
+    }
+  }
+
+  let mut by_root: HashMap<usize, ([f64; 4], u32)> = HashMap::new();
+  for (i, &b) in boxes.iter().enumerate() {
+    let root = find(&mut parent, i);
+    by_root
+      .entry(root)
+      .and_modify(|(r, n)| {
+        *r = union_box(*r, b);
+        *n += 1;
+      })
+      .or_insert((b, 1));
+  }
+
+  // A merged region can grow into one it didn't touch path by path
+  let mut regions: Vec<([f64; 4], u32)> = by_root.into_values().collect();
+  let mut merged = true;
+  while merged {
+    merged = false;
+    'outer: for i in 0..regions.len() {
+      for j in i + 1..regions.len() {
+        if boxes_touch(&regions[i].0, &regions[j].0) {
+          let (b, n) = regions.swap_remove(j);
+          regions[i] = (union_box(regions[i].0, b), regions[i].1 + n);
+          merged = true;
+          break 'outer;
+        }
+      }
+    }
+  }
+  regions
+}
+
+/// Regions of stroked or filled vector content on a page, clipped to the
+/// visible page area and ordered top to bottom, then left to right.
+/// Page-sized background fills are left out. Uses the
+/// displayed orientation, like `extract_display_words`.
+pub(crate) fn page_vector_regions(doc: &Document, page_id: ObjectId) -> Vec<VectorRegion> {
+  let base = display_base(doc, page_id);
+  let clip = page_rect(doc, page_id).map(|[l, b, r, t]| {
+    bounds([
+      base.apply(l, b),
+      base.apply(r, b),
+      base.apply(l, t),
+      base.apply(r, t),
+    ])
+  });
+  let mut paths = interpret_page(doc, page_id, base).paths;
+  // Page-sized fills are backgrounds, not figures, and would swallow
+  // every other region
+  if let Some(c) = clip {
+    let page_area = (c[2] - c[0]) * (c[3] - c[1]);
+    paths.retain(|p| {
+      let w = p[2].min(c[2]) - p[0].max(c[0]);
+      let h = p[3].min(c[3]) - p[1].max(c[1]);
+      w.max(0.0) * h.max(0.0) < page_area * BACKGROUND_COVERAGE
+    });
+  }
+  let mut regions: Vec<VectorRegion> = merge_regions(paths)
+    .into_iter()
+    .filter_map(|(r, path_count)| {
+      let [left, bottom, right, top] = match clip {
+        Some(c) => [
+          r[0].max(c[0]),
+          r[1].max(c[1]),
+          r[2].min(c[2]),
+          r[3].min(c[3]),
+        ],
+        None => r,
+      };
+      (right >= left && top >= bottom).then_some(VectorRegion {
+        x: left,
+        y: bottom,
+        width: right - left,
+        height: top - bottom,
+        path_count,
+      })
+    })
+    .collect();
+  regions.sort_by(|a, b| {
+    (b.y + b.height)
+      .total_cmp(&(a.y + a.height))
+      .then(a.x.total_cmp(&b.x))
+  });
+  regions
+}
+
+/// Vector regions for every page, in page order.
+pub(crate) fn collect_vector_regions(doc: &Document) -> Vec<PageVectorRegions> {
+  let pages = doc.get_pages();
+  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
+  page_entries
+    .par_iter()
+    .map(|&(page_num, page_id)| PageVectorRegions {
+      page: page_num,
+      regions: page_vector_regions(doc, page_id),
+    })
+    .collect()
+}
+
+/// Fraction (0.0–1.0) of the page's visible area covered by word boxes.
+/// Overlapping boxes are counted once per word, so the result is an estimate
+/// capped at 1.0. `None` when the page has no usable CropBox/MediaBox.
+pub(crate) fn page_text_coverage(doc: &Document, page_id: ObjectId) -> Option<f64> {
+  let [left, bottom, right, top] = page_rect(doc, page_id)?;
+  let page_area = (right - left) * (top - bottom);
+  if page_area <= 0.0 {
+    return None;
+  }
+  let covered = extract_page_words(doc, page_id).iter().fold(0.0, |acc, w| {
+    let cw = (w.x + w.width).min(right) - w.x.max(left);
+    let ch = (w.y + w.height).min(top) - w.y.max(bottom);
+    acc + cw.max(0.0) * ch.max(0.0)
+  });
+  Some((covered / page_area).min(1.0))
+}
+
+/// Pages with fewer non-whitespace characters of native text than this have
+/// no usable text layer.
+const SCANNED_MAX_TEXT_CHARS: usize = 10;
+
+/// Fraction of the page area a single image must cover to count as a scan.
+const FULL_PAGE_IMAGE_COVERAGE: f64 = 0.8;
+
+/// Whether a page looks like a scan: (almost) no extractable text, but an
+/// image painted over most of its visible area.
+pub(crate) fn page_looks_scanned(doc: &Document, page_num: u32, page_id: ObjectId) -> bool {
+  let text = extract_page_text_raw(doc, page_num, page_id);
+  if text.chars().filter(|c| !c.is_whitespace()).count() >= SCANNED_MAX_TEXT_CHARS {
+    return false;
+  }
+  let Some([left, bottom, right, top]) = page_rect(doc, page_id) else {
+    return false;
+  };
+  let page_area = (right - left) * (top - bottom);
+  if page_area <= 0.0 {
+    return false;
+  }
+  let images = interpret_page(doc, page_id, Matrix::IDENTITY).images;
+  images.iter().any(|&[x0, y0, x1, y1]| {
+    let cw = x1.min(right) - x0.max(left);
+    let ch = y1.min(top) - y0.max(bottom);
+    cw.max(0.0) * ch.max(0.0) / page_area >= FULL_PAGE_IMAGE_COVERAGE
+  })
+}
+
+/// Share of scanned-looking pages above which the whole document counts as
+/// scanned.
+const LIKELY_SCANNED_RATIO: f64 = 0.5;
+
+/// The fraction (0.0–1.0) of pages that look scanned (see
+/// `page_looks_scanned`) and whether they are a majority. A document without
+/// pages has a ratio of 0.0 and is not scanned.
+pub(crate) fn collect_document_classification(doc: &Document) -> DocumentClassification {
+  let pages = doc.get_pages();
+  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
+  let scanned = page_entries
+    .par_iter()
+    .filter(|&&(page_num, page_id)| page_looks_scanned(doc, page_num, page_id))
+    .count();
+  let scanned_page_ratio = if page_entries.is_empty() {
+    0.0
+  } else {
+    scanned as f64 / page_entries.len() as f64
+  };
+  DocumentClassification {
+    likely_scanned: scanned_page_ratio > LIKELY_SCANNED_RATIO,
+    scanned_page_ratio,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lopdf::{Stream, dictionary};
+
+  fn single_page_doc(content: &[u8]) -> (Document, ObjectId) {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type1",
+      "BaseFont" => "Helvetica",
+    });
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.to_vec()));
+    let page_id = doc.add_object(dictionary! {
+      "Type" => "Page",
+      "Parent" => pages_id,
+      "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+      "Contents" => content_id,
+      "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+    });
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => vec![page_id.into()],
+        "Count" => 1,
+      }),
+    );
+    (doc, page_id)
+  }
+
+  #[test]
+  fn words_split_on_spaces_and_follow_text_matrix() {
+    let (doc, page_id) = single_page_doc(b"BT /F1 10 Tf 10 20 Td (AB CD) Tj ET");
+    let words = extract_page_words(&doc, page_id);
+    assert_eq!(words.len(), 2);
+    assert_eq!(
+      (words[0].text.as_str(), words[1].text.as_str()),
+      ("AB", "CD")
+    );
+    // Standard 14 font without /Widths: 500/1000 em per glyph
+    assert_eq!((words[0].x, words[0].width), (10.0, 10.0));
+    assert_eq!((words[0].y, words[0].height), (18.0, 10.0));
+    assert_eq!(words[1].x, 25.0);
+  }
+
+  #[test]
+  fn coverage_is_word_area_over_page_area() {
+    let (doc, page_id) = single_page_doc(b"BT /F1 10 Tf 10 20 Td (AB CD) Tj ET");
+    let coverage = page_text_coverage(&doc, page_id).unwrap();
+    assert!((coverage - 0.02).abs() < 1e-9);
+
+    let (empty, empty_id) = single_page_doc(b"");
+    assert_eq!(page_text_coverage(&empty, empty_id), Some(0.0));
+  }
+
+  #[test]
+  fn lines_group_words_by_baseline_top_to_bottom() {
+    let (doc, page_id) = single_page_doc(
+      b"BT /F1 10 Tf 40 20 Td (world) Tj -30 0 Td (hello) Tj 0 30 Td (title) Tj ET",
+    );
+    let lines = group_lines(extract_page_words(&doc, page_id));
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].text, "title");
+    assert_eq!(lines[1].text, "hello world");
+    assert_eq!((lines[1].x, lines[1].y), (10.0, 18.0));
+    assert_eq!((lines[1].width, lines[1].height), (55.0, 10.0));
+  }
+
+  #[test]
+  fn rotated_pages_read_in_display_order() {
+    // Two lines written bottom-to-top along x, as on a landscape scan stored
+    // in portrait and shown with /Rotate 90
+    let (mut doc, page_id) = single_page_doc(
+      b"BT /F1 10 Tf 0 1 -1 0 30 10 Tm (first) Tj 0 1 -1 0 50 10 Tm (second) Tj ET",
+    );
+    let texts =
+      |lines: &[LineBox]| -> Vec<String> { lines.iter().map(|l| l.text.clone()).collect() };
+    // In unrotated user space the glyphs stack vertically and don't form lines
+    let unrotated = group_lines(extract_page_words(&doc, page_id));
+    assert_ne!(texts(&unrotated), ["first", "second"]);
+
+    doc.get_dictionary_mut(page_id).unwrap().set("Rotate", 90);
+    let lines = group_lines(extract_display_words(&doc, page_id));
+    assert_eq!(texts(&lines), ["first", "second"]);
+    // Displayed page is 100 wide; "first" starts 10pt from the displayed left
+    assert!((lines[0].x - 10.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn vertical_writing_mode_reads_columns_right_to_left() {
+    // The left column is painted first; the header is horizontal
+    let (mut doc, page_id) = single_page_doc(
+      b"BT /F2 10 Tf 60 90 Td <00040005> Tj ET \
+        BT /F2 10 Tf 80 90 Td <000100020003> Tj ET \
+        BT /F1 8 Tf 10 94 Td (title) Tj ET",
+    );
+    let to_unicode = b"/CIDInit /ProcSet findresource begin 12 dict begin begincmap \
+      1 begincodespacerange <0000> <FFFF> endcodespacerange \
+      5 beginbfchar <0001> <7E26> <0002> <66F8> <0003> <304D> <0004> <6587> <0005> <5B57> \
+      endbfchar endcmap end end";
+    let to_unicode_id = doc.add_object(Stream::new(dictionary! {}, to_unicode.to_vec()));
+    let cid_font = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "CIDFontType0",
+      "BaseFont" => "KozMinPr6N-Regular",
+      "DW" => 1000,
+    });
+    let font_id = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type0",
+      "BaseFont" => "KozMinPr6N-Regular",
+      "Encoding" => "Identity-V",
+      "DescendantFonts" => vec![cid_font.into()],
+      "ToUnicode" => to_unicode_id,
+    });
+    doc
+      .get_dictionary_mut(page_id)
+      .and_then(|page| page.get_mut(b"Resources"))
+      .and_then(Object::as_dict_mut)
+      .and_then(|res| res.get_mut(b"Font"))
+      .and_then(Object::as_dict_mut)
+      .unwrap()
+      .set("F2", font_id);
+
+    let lines = group_lines(extract_page_words(&doc, page_id));
+    let summary: Vec<_> = lines
+      .iter()
+      .map(|l| (l.text.as_str(), l.vertical))
+      .collect();
+    assert_eq!(
+      summary,
+      [("title", false), ("縦書き", true), ("文字", true)]
+    );
+    // Glyphs hang below the origin, one em per glyph, centred on x = 80
+    let column = &lines[1];
+    assert_eq!((column.x, column.y), (75.0, 60.0));
+    assert_eq!((column.width, column.height), (10.0, 30.0));
+  }
+
+  #[test]
+  fn styled_lines_carry_font_size_and_weight() {
+    let (mut doc, page_id) = single_page_doc(
+      b"BT /F2 18 Tf 10 80 Td (Heading) Tj /F1 10 Tf 0 -20 Td (Body) Tj /F3 10 Tf ( text) Tj \
+        0 -20 Td (Quote) Tj ET",
+    );
+    let bold_id = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type1",
+      "BaseFont" => "Helvetica-Bold",
+    });
+    let descriptor_id = doc.add_object(dictionary! {
+      "Type" => "FontDescriptor",
+      "Flags" => FLAG_ITALIC | FLAG_FORCE_BOLD,
+      "FontWeight" => 700,
+    });
+    let bold_italic_id = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type1",
+      "BaseFont" => "ABCDEF+Custom",
+      "FontDescriptor" => descriptor_id,
+    });
+    let fonts = doc
+      .get_dictionary_mut(page_id)
+      .and_then(|p| p.get_mut(b"Resources"))
+      .and_then(Object::as_dict_mut)
+      .and_then(|r| r.get_mut(b"Font"))
+      .and_then(Object::as_dict_mut)
+      .unwrap();
+    fonts.set("F2", bold_id);
+    fonts.set("F3", bold_italic_id);
+
+    let lines = group_styled_lines(extract_page_words(&doc, page_id));
+    let summary: Vec<_> = lines
+      .iter()
+      .map(|l| (l.text.as_str(), l.font_size, l.bold, l.italic))
+      .collect();
+    assert_eq!(
+      summary,
+      [
+        ("Heading", 18.0, true, false),
+        // Only part of the line is bold italic
+        ("Body text", 10.0, false, false),
+        ("Quote", 10.0, true, true),
+      ]
+    );
+  }
+
+  #[test]
+  fn paragraphs_break_on_large_vertical_gaps() {
+    let line = |text: &str, y: f64| LineBox {
+      text: text.to_string(),
+      x: 10.0,
+      y,
+      width: 50.0,
+      height: 10.0,
+      vertical: false,
+    };
+    // 2pt between lines within a paragraph, 16pt before the second one
+    let lines = [
+      line("The quick", 100.0),
+      line("brown fox", 88.0),
+      line("jumps.", 76.0),
+      line("A new", 50.0),
+      line("paragraph.", 38.0),
+    ];
+    assert_eq!(
+      group_paragraphs(&lines),
+      ["The quick brown fox jumps.", "A new paragraph."]
+    );
+    assert!(group_paragraphs(&[]).is_empty());
+  }
+
+  /// `single_page_doc` with a 1x1 image XObject available as `/Im1`.
+  fn page_with_image(content: &[u8]) -> (Document, ObjectId) {
+    let (mut doc, page_id) = single_page_doc(content);
+    let image_id = doc.add_object(Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 1,
+        "Height" => 1,
+        "ColorSpace" => "DeviceGray",
+        "BitsPerComponent" => 8,
+      },
+      vec![0],
+    ));
+    doc
+      .get_dictionary_mut(page_id)
+      .and_then(|page| page.get_mut(b"Resources"))
+      .and_then(Object::as_dict_mut)
+      .unwrap()
+      .set("XObject", dictionary! { "Im1" => image_id });
+    (doc, page_id)
+  }
+
+  #[test]
+  fn full_page_image_without_text_looks_scanned() {
+    let (doc, page_id) = page_with_image(b"q 100 0 0 100 0 0 cm /Im1 Do Q");
+    assert!(page_looks_scanned(&doc, 1, page_id));
+
+    // A small figure is not a scan
+    let (doc, page_id) = page_with_image(b"q 40 0 0 40 0 0 cm /Im1 Do Q");
+    assert!(!page_looks_scanned(&doc, 1, page_id));
+
+    // Nor is a full-page background behind a real text layer
+    let (doc, page_id) = page_with_image(
+      b"q 100 0 0 100 0 0 cm /Im1 Do Q BT /F1 10 Tf 10 50 Td (Quarterly report) Tj ET",
+    );
+    assert!(!page_looks_scanned(&doc, 1, page_id));
+  }
+
+  #[test]
+  fn documents_are_scanned_when_most_pages_are() {
+    use crate::core::test_docs::{doc_with_pages, helvetica};
+
+    let classify = |contents: &[&[u8]]| {
+      let (doc, _) = doc_with_pages(|doc| {
+        let font = helvetica(doc);
+        let image = doc.add_object(Stream::new(
+          dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => 1,
+            "Height" => 1,
+            "ColorSpace" => "DeviceGray",
+            "BitsPerComponent" => 8,
+          },
+          vec![0],
+        ));
+        contents
+          .iter()
+          .map(|content| {
+            dictionary! {
+              "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+              "Contents" => doc.add_object(Stream::new(dictionary! {}, content.to_vec())),
+              "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => font },
+                "XObject" => dictionary! { "Im1" => image },
+              },
+            }
+          })
+          .collect()
+      });
+      collect_document_classification(&doc)
+    };
+    let scan: &[u8] = b"q 100 0 0 100 0 0 cm /Im1 Do Q";
+    let text: &[u8] = b"BT /F1 10 Tf 10 50 Td (Quarterly report) Tj ET";
+
+    let found = classify(&[scan, scan, text]);
+    assert!((found.scanned_page_ratio - 2.0 / 3.0).abs() < 1e-9);
+    assert!(found.likely_scanned);
+    // Half is not a majority
+    let found = classify(&[scan, text]);
+    assert_eq!(found.scanned_page_ratio, 0.5);
+    assert!(!found.likely_scanned);
+    let found = classify(&[]);
+    assert_eq!(found.scanned_page_ratio, 0.0);
+    assert!(!found.likely_scanned);
+  }
+
+  #[test]
+  fn vector_regions_merge_touching_paths() {
+    // A page background, a two-rectangle figure (sharing an edge), a separate
+    // stroked rule, and a clipping path that is never painted
+    let (doc, page_id) = single_page_doc(
+      b"0 0 100 100 re f 10 60 20 20 re f 30 60 20 20 re S 10 20 m 90 20 l S 0 0 50 50 re W n",
+    );
+    let regions = page_vector_regions(&doc, page_id);
+    let boxes: Vec<(f64, f64, f64, f64, u32)> = regions
+      .iter()
+      .map(|r| (r.x, r.y, r.width, r.height, r.path_count))
+      .collect();
+    assert_eq!(
+      boxes,
+      [(10.0, 60.0, 40.0, 20.0, 2), (10.0, 20.0, 80.0, 0.0, 1)]
+    );
+  }
+
+  #[test]
+  fn display_matrix_maps_page_corners() {
+    let rect = [0.0, 0.0, 200.0, 100.0];
+    // Rotate 90: user bottom-left becomes displayed top-left
+    assert_eq!(display_matrix(90, rect).apply(0.0, 0.0), (0.0, 200.0));
+    assert_eq!(display_matrix(180, rect).apply(0.0, 0.0), (200.0, 100.0));
+    assert_eq!(display_matrix(270, rect).apply(0.0, 0.0), (100.0, 0.0));
+  }
+}
@@ -1,66 +1,286 @@
-use crate::types::{BoxType, PageBox, PdfMeta};
+use crate::core::document::format_object_id;
+use crate::core::images::color_space_name;
+use crate::core::objects::{deref, deref_dict};
+use crate::types::{
+  BoxType, EditEvent, LinearizationInfo, Measurement, OutputIntent, PageBox, PageSizeRange,
+  PageTreeStats, PdfMeta, ReindexedPage, ViewerPreferences,
+};
+use lopdf::xref::XrefEntry;
 use lopdf::{Document, Object, ObjectId};
-use std::collections::{BTreeMap, HashMap};
+use napi::bindgen_prelude::Buffer;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Decode a PDF text string: UTF-16BE after a `FE FF` byte-order mark,
+/// UTF-8 after an `EF BB BF` mark (PDF 2.0), otherwise the bytes are read as
+/// UTF-8 with invalid sequences replaced.
+pub(crate) fn decode_pdf_string(bytes: &[u8]) -> String {
+  if let Some(utf16) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+    let units: Vec<u16> = utf16
+      .chunks_exact(2)
+      .map(|c| u16::from_be_bytes([c[0], c[1]]))
+      .collect();
+    return String::from_utf16_lossy(&units);
+  }
+  let utf8 = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+  String::from_utf8_lossy(utf8).to_string()
+}
 
 fn extract_info_string(dict: &lopdf::Dictionary, key: &[u8]) -> Option<String> {
   match dict.get(key).ok()? {
-    Object::String(bytes, _) => {
-      if bytes.len() >= 2 && bytes[0] == 0xFE && bytes[1] == 0xFF {
-        // UTF-16 BOM
-        let utf16: Vec<u16> = bytes[2..]
-          .chunks_exact(2)
-          .map(|c| u16::from_be_bytes([c[0], c[1]]))
-          .collect();
-        Some(String::from_utf16_lossy(&utf16))
-      } else {
-        Some(String::from_utf8_lossy(bytes).to_string())
+    Object::String(bytes, _) => Some(decode_pdf_string(bytes)),
+    _ => None,
+  }
+}
+
+/// The document's declared natural language (`/Lang` on the catalog, e.g.
+/// `"en-US"`). Blank values count as absent.
+fn extract_language(doc: &Document) -> Option<String> {
+  let lang = extract_info_string(doc.catalog().ok()?, b"Lang")?;
+  let lang = lang.trim();
+  (!lang.is_empty()).then(|| lang.to_string())
+}
+
+/// The first entry of the catalog's `/OutputIntents`: the print condition
+/// the document was prepared for and its ICC profile. The condition falls
+/// back to the identifier when `/OutputCondition` isn't given.
+fn extract_output_intent(doc: &Document) -> Option<OutputIntent> {
+  let intents = deref(doc, doc.catalog().ok()?.get(b"OutputIntents").ok()?)?
+    .as_array()
+    .ok()?;
+  let intent = deref(doc, intents.first()?)?.as_dict().ok()?;
+  let identifier = extract_info_string(intent, b"OutputConditionIdentifier").unwrap_or_default();
+  let condition = extract_info_string(intent, b"OutputCondition")
+    .filter(|c| !c.trim().is_empty())
+    .unwrap_or_else(|| identifier.clone());
+  let icc_profile = intent
+    .get(b"DestOutputProfile")
+    .ok()
+    .and_then(|o| deref(doc, o))
+    .and_then(|o| o.as_stream().ok())
+    .map(|s| {
+      s.decompressed_content()
+        .unwrap_or_else(|_| s.content.clone())
+    });
+  Some(OutputIntent {
+    condition,
+    identifier,
+    icc_profile: icc_profile.map(Buffer::from),
+  })
+}
+
+/// The linearization parameter dictionary: the object holding a
+/// `/Linearized` entry, which a linearized file writes first.
+fn extract_linearization(doc: &Document) -> Option<LinearizationInfo> {
+  let params = doc
+    .objects
+    .values()
+    .filter_map(|o| o.as_dict().ok())
+    .find(|d| d.has(b"Linearized"))?;
+  let file_length = params
+    .get(b"L")
+    .and_then(Object::as_i64)
+    .ok()
+    .and_then(|n| u32::try_from(n).ok());
+  let first_page_object = params
+    .get(b"O")
+    .and_then(Object::as_i64)
+    .ok()
+    .and_then(|n| u32::try_from(n).ok())
+    .map(|n| {
+      let generation = doc.objects.keys().find(|id| id.0 == n).map_or(0, |id| id.1);
+      format_object_id((n, generation))
+    });
+  Some(LinearizationInfo {
+    file_length,
+    first_page_object,
+    hint_stream_valid: hint_stream_valid(doc, params, file_length),
+  })
+}
+
+/// Whether `/H` locates the primary hint stream: an offset and length that
+/// fit within `/L`, where the cross-reference table has a stream object.
+fn hint_stream_valid(doc: &Document, params: &lopdf::Dictionary, file_length: Option<u32>) -> bool {
+  let Some(hint) = params.get(b"H").and_then(Object::as_array).ok() else {
+    return false;
+  };
+  let numbers: Vec<i64> = hint.iter().filter_map(|o| o.as_i64().ok()).collect();
+  if !matches!(hint.len(), 2 | 4) || numbers.len() != hint.len() {
+    return false;
+  }
+  let (offset, length) = (numbers[0], numbers[1]);
+  if offset < 0
+    || length <= 0
+    || file_length.is_some_and(|l| {
+      offset
+        .checked_add(length)
+        .is_none_or(|end| end > i64::from(l))
+    })
+  {
+    return false;
+  }
+  doc
+    .reference_table
+    .entries
+    .iter()
+    .any(|(&number, entry)| match entry {
+      XrefEntry::Normal {
+        offset: at,
+        generation,
+      } => {
+        i64::from(*at) == offset
+          && doc
+            .get_object((number, *generation))
+            .is_ok_and(|o| o.as_stream().is_ok())
       }
-    }
+      _ => false,
+    })
+}
+
+/// The catalog's `/ViewerPreferences` together with its `/PageLayout` and
+/// `/PageMode`; `None` when the catalog sets none of them.
+fn extract_viewer_preferences(doc: &Document) -> Option<ViewerPreferences> {
+  let catalog = doc.catalog().ok()?;
+  let name = |dict: &lopdf::Dictionary, key: &[u8]| {
+    let name = deref(doc, dict.get(key).ok()?)?.as_name().ok()?;
+    Some(String::from_utf8_lossy(name).to_string())
+  };
+  let prefs = catalog
+    .get(b"ViewerPreferences")
+    .ok()
+    .and_then(|p| deref(doc, p))
+    .and_then(|p| p.as_dict().ok());
+  let page_layout = name(catalog, b"PageLayout");
+  let page_mode = name(catalog, b"PageMode");
+  if prefs.is_none() && page_layout.is_none() && page_mode.is_none() {
+    return None;
+  }
+  let empty = lopdf::Dictionary::new();
+  let prefs = prefs.unwrap_or(&empty);
+  let flag = |key: &[u8]| {
+    prefs
+      .get(key)
+      .ok()
+      .and_then(|v| deref(doc, v))
+      .and_then(|v| v.as_bool().ok())
+      .unwrap_or(false)
+  };
+  Some(ViewerPreferences {
+    hide_toolbar: flag(b"HideToolbar"),
+    hide_menubar: flag(b"HideMenubar"),
+    hide_window_ui: flag(b"HideWindowUI"),
+    fit_window: flag(b"FitWindow"),
+    center_window: flag(b"CenterWindow"),
+    display_doc_title: flag(b"DisplayDocTitle"),
+    non_full_screen_page_mode: name(prefs, b"NonFullScreenPageMode"),
+    direction: name(prefs, b"Direction"),
+    print_scaling: name(prefs, b"PrintScaling"),
+    duplex: name(prefs, b"Duplex"),
+    page_layout,
+    page_mode,
+  })
+}
+
+/// The catalog's `/Version` (e.g. `/1.7`), which an incremental update can
+/// use to raise the version declared in the file header.
+fn extract_catalog_version(doc: &Document) -> Option<String> {
+  match doc.catalog().ok()?.get(b"Version").ok()? {
+    Object::Name(name) => Some(String::from_utf8_lossy(name).to_string()),
+    Object::String(bytes, _) => Some(String::from_utf8_lossy(bytes).to_string()),
     _ => None,
   }
 }
 
 /// Convert a PDF date string to ISO 8601.
-/// Input format:  `D:YYYYMMDDHHmmSS+HH'mm'` (D: prefix optional, timezone optional)
-/// Output format: `YYYY-MM-DDTHH:mm:SS+HH:mm` or `…Z`
-fn pdf_date_to_iso8601(raw: &str) -> String {
-  let s = raw.strip_prefix("D:").unwrap_or(raw);
-
-  // Need at least YYYY (4 chars)
-  if s.len() < 4 {
-    return raw.to_string();
-  }
-
-  let yyyy = &s[..4];
-  let mm = s.get(4..6).unwrap_or("01");
-  let dd = s.get(6..8).unwrap_or("01");
-  let hh = s.get(8..10).unwrap_or("00");
-  let min = s.get(10..12).unwrap_or("00");
-  let sec = s.get(12..14).unwrap_or("00");
-
-  let tz_part = &s[14.min(s.len())..];
-  let tz = if tz_part.is_empty() {
-    String::new()
-  } else if tz_part.starts_with('Z') {
-    "Z".to_string()
-  } else {
-    // e.g. +05'30' or -06'00' → +05:30 or -06:00
-    let cleaned = tz_part.replace('\'', "");
-    if cleaned.len() >= 3 {
-      let sign = &cleaned[..1];
-      let tzh = &cleaned[1..3];
-      let tzm = if cleaned.len() >= 5 {
-        &cleaned[3..5]
-      } else {
-        "00"
-      };
-      format!("{sign}{tzh}:{tzm}")
-    } else {
-      String::new()
+/// Input format:  `D:YYYYMMDDHHmmSS+HH'mm'` (D: prefix optional, everything
+/// after the year optional)
+/// Output format: `YYYY-MM-DDTHH:mm:SS[.sss]+HH:mm` or `…Z`
+///
+/// Returns `None` for strings that aren't a date in that format, or whose
+/// fields are out of range, rather than guessing; callers still have the
+/// untouched string in `PdfMeta`'s `*_raw` fields.
+fn pdf_date_to_iso8601(raw: &str) -> Option<String> {
+  let s = raw.trim();
+  let s = s.strip_prefix("D:").unwrap_or(s);
+  let digits_end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+  let (digits, tz_part) = s.split_at(digits_end);
+
+  // YYYY, then whole two-digit fields up to seconds
+  if !(4..=14).contains(&digits.len()) || digits.len() % 2 != 0 {
+    return None;
+  }
+  let field = |start: usize, default: &'static str, max: u32| -> Option<&str> {
+    match digits.get(start..start + 2) {
+      Some(d) => (d.parse::<u32>().ok()? <= max).then_some(d),
+      None => Some(default),
     }
   };
+  let yyyy = &digits[..4];
+  let mm = field(4, "01", 12)?;
+  let dd = field(6, "01", 31)?;
+  if mm == "00" || dd == "00" {
+    return None;
+  }
+  let hh = field(8, "00", 23)?;
+  let min = field(10, "00", 59)?;
+  let sec = field(12, "00", 59)?;
+
+  // Some producers append fractional seconds (`…SS.sss`), which ISO 8601 keeps
+  let (frac, tz_part) = match tz_part.strip_prefix('.') {
+    Some(after) if digits.len() == 14 => {
+      let end = after
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(after.len());
+      if end == 0 {
+        return None;
+      }
+      (&tz_part[..end + 1], &after[end..])
+    }
+    Some(_) => return None,
+    None => ("", tz_part),
+  };
+  let tz = parse_pdf_tz(tz_part)?;
+
+  Some(format!("{yyyy}-{mm}-{dd}T{hh}:{min}:{sec}{frac}{tz}"))
+}
 
-  format!("{yyyy}-{mm}-{dd}T{hh}:{min}:{sec}{tz}")
+/// Parse the timezone suffix of a PDF date into ISO 8601 form (`""`, `"Z"`
+/// or `"±HH:mm"`). Accepts `+05'30'`, `+05'30`, `-0500`, `+05` and `Z`, and a
+/// `Z` followed by an explicit offset (`Z+01'00'`), in which case the offset
+/// wins. `None` for anything else.
+fn parse_pdf_tz(tz: &str) -> Option<String> {
+  match tz.chars().next() {
+    None => Some(String::new()),
+    Some('Z') => {
+      let rest = &tz[1..];
+      if rest.starts_with(['+', '-']) {
+        return parse_pdf_tz(rest);
+      }
+      // `Z00'00'` restates UTC
+      rest
+        .chars()
+        .all(|c| c == '0' || c == '\'')
+        .then(|| "Z".to_string())
+    }
+    Some(sign @ ('+' | '-')) => {
+      // e.g. +05'30' or -0600 → +05:30 or -06:00
+      let cleaned: String = tz[1..].chars().filter(|&c| c != '\'' && c != ':').collect();
+      if !cleaned.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+      }
+      let (tzh, tzm) = match cleaned.len() {
+        // A bare sign carries no offset
+        0 => return Some(String::new()),
+        2 => (cleaned.as_str(), "00"),
+        4 => cleaned.split_at(2),
+        _ => return None,
+      };
+      if tzh.parse::<u32>().ok()? > 23 || tzm.parse::<u32>().ok()? > 59 {
+        return None;
+      }
+      Some(format!("{sign}{tzh}:{tzm}"))
+    }
+    Some(_) => None,
+  }
 }
 
 fn parse_page_box(obj: &Object) -> Option<[f64; 4]> {
@@ -84,8 +304,15 @@ fn parse_page_box(obj: &Object) -> Option<[f64; 4]> {
 
 /// Walk the page tree to find an inheritable page box (e.g., /MediaBox, /CropBox).
 /// Resolves indirect references — some PDFs store the box array via `Object::Reference`.
-fn get_inherited_page_box(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<[f64; 4]> {
+/// Also returns the tree level the box was found at: 0 is the page itself, 1 its
+/// parent, and so on.
+fn get_inherited_page_box(
+  doc: &Document,
+  page_id: ObjectId,
+  key: &[u8],
+) -> Option<([f64; 4], u32)> {
   let mut current_id = Some(page_id);
+  let mut depth = 0u32;
   while let Some(id) = current_id {
     let dict = doc.get_dictionary(id).ok()?;
     if let Ok(obj) = dict.get(key) {
@@ -97,7 +324,7 @@ fn get_inherited_page_box(doc: &Document, page_id: ObjectId, key: &[u8]) -> Opti
       if let Some(ref val) = resolved
         && let Some(rect) = parse_page_box(val)
       {
-        return Some(rect);
+        return Some((rect, depth));
       }
     }
     // Walk up to /Parent
@@ -105,10 +332,74 @@ fn get_inherited_page_box(doc: &Document, page_id: ObjectId, key: &[u8]) -> Opti
       Object::Reference(ref_id) => Some(*ref_id),
       _ => None,
     });
+    depth += 1;
   }
   None
 }
 
+/// The page's visible area — CropBox, falling back to MediaBox — normalized to
+/// `[left, bottom, right, top]`.
+pub(crate) fn page_rect(doc: &Document, page_id: ObjectId) -> Option<[f64; 4]> {
+  let (r, _) = get_inherited_page_box(doc, page_id, b"CropBox")
+    .or_else(|| get_inherited_page_box(doc, page_id, b"MediaBox"))?;
+  Some([
+    r[0].min(r[2]),
+    r[1].min(r[3]),
+    r[0].max(r[2]),
+    r[1].max(r[3]),
+  ])
+}
+
+/// Look up a numeric page attribute, walking up `/Parent` until one is found.
+fn inherited_number(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<f64> {
+  let mut current_id = Some(page_id);
+  while let Some(id) = current_id {
+    let dict = doc.get_dictionary(id).ok()?;
+    if let Ok(obj) = dict.get(key) {
+      let resolved = match obj {
+        Object::Reference(ref_id) => doc.get_object(*ref_id).ok()?,
+        other => other,
+      };
+      return match resolved {
+        Object::Integer(v) => Some(*v as f64),
+        Object::Real(v) => Some(*v as f64),
+        _ => None,
+      };
+    }
+    current_id = dict.get(b"Parent").and_then(Object::as_reference).ok();
+  }
+  None
+}
+
+/// The page's `/Rotate` (inheritable) normalized to 0, 90, 180 or 270 degrees
+/// clockwise. Values that aren't a multiple of 90 are treated as 0.
+pub(crate) fn page_rotation(doc: &Document, page_id: ObjectId) -> u32 {
+  match inherited_number(doc, page_id, b"Rotate").map(|v| (v as i64).rem_euclid(360)) {
+    Some(r @ (90 | 180 | 270)) => r as u32,
+    _ => 0,
+  }
+}
+
+/// The page's `/UserUnit` — the size of one user-space unit in 1/72 inch.
+/// Defaults to 1.0; non-positive values are ignored.
+pub(crate) fn page_user_unit(doc: &Document, page_id: ObjectId) -> f64 {
+  inherited_number(doc, page_id, b"UserUnit")
+    .filter(|&u| u > 0.0 && u.is_finite())
+    .unwrap_or(1.0)
+}
+
+/// Blending color space of the page's transparency group: `/CS` of a `/Group`
+/// whose `/S` is `/Transparency`. `None` when the page has no such group or
+/// the group leaves the color space to the output device.
+pub(crate) fn page_transparency_group(doc: &Document, page_id: ObjectId) -> Option<String> {
+  let page = doc.get_dictionary(page_id).ok()?;
+  let group = deref_dict(doc, page.get(b"Group").ok()?)?;
+  if group.get(b"S").and_then(Object::as_name).ok()? != b"Transparency" {
+    return None;
+  }
+  group.get(b"CS").ok().map(|cs| color_space_name(cs, doc))
+}
+
 /// Key type for grouping page boxes by geometry.
 /// Uses `to_bits()` so NaN/negative-zero edge cases hash correctly.
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -118,6 +409,9 @@ struct PageBoxKey {
   right: u64,
   top: u64,
   box_type: u8, // 0=CropBox, 1=MediaBox, 2=Unknown
+  inherited: bool,
+  user_unit: u64,
+  transparency_group: Option<String>,
 }
 
 /// Intermediate representation before we decide which group is dominant.
@@ -127,9 +421,250 @@ struct PageBoxGroup {
   right: f64,
   top: f64,
   box_type: BoxType,
+  inherited: bool,
+  user_unit: f64,
+  transparency_group: Option<String>,
   page_nums: Vec<u32>,
 }
 
+/// A page's effective box (CropBox, else MediaBox) with its corners
+/// normalized to `[left, bottom, right, top]`, and whether it was inherited.
+fn resolve_page_box(doc: &Document, page_id: ObjectId) -> (BoxType, [f64; 4], bool) {
+  let (box_type, rect, depth) =
+    if let Some((rect, depth)) = get_inherited_page_box(doc, page_id, b"CropBox") {
+      (BoxType::CropBox, rect, depth)
+    } else if let Some((rect, depth)) = get_inherited_page_box(doc, page_id, b"MediaBox") {
+      (BoxType::MediaBox, rect, depth)
+    } else {
+      (BoxType::Unknown, [0.0, 0.0, 0.0, 0.0], 0)
+    };
+  let normalized = [
+    rect[0].min(rect[2]),
+    rect[1].min(rect[3]),
+    rect[0].max(rect[2]),
+    rect[1].max(rect[3]),
+  ];
+  (box_type, normalized, depth > 0)
+}
+
+/// Ungrouped box for a single page: `page_count` is 1 and `pages` is `[page_num]`.
+pub(crate) fn single_page_box(doc: &Document, page_num: u32, page_id: ObjectId) -> PageBox {
+  let (box_type, [left, bottom, right, top], inherited) = resolve_page_box(doc, page_id);
+  PageBox {
+    page_count: 1,
+    left,
+    bottom,
+    right,
+    top,
+    width: right - left,
+    height: top - bottom,
+    box_type,
+    inherited,
+    user_unit: page_user_unit(doc, page_id),
+    transparency_group: page_transparency_group(doc, page_id),
+    pages: Some(vec![page_num]),
+  }
+}
+
+/// One `PageBox` per page, in page order.
+pub(crate) fn extract_per_page_boxes(doc: &Document) -> Vec<PageBox> {
+  doc
+    .get_pages()
+    .into_iter()
+    .map(|(page_num, page_id)| single_page_box(doc, page_num, page_id))
+    .collect()
+}
+
+/// Depth, widest fan-out and node count of the `/Pages` tree. A node with a
+/// `/Kids` array counts as an intermediate node; nodes reached twice (a
+/// cyclic or shared subtree) are only walked once. All zero when the
+/// catalog has no page tree.
+pub(crate) fn collect_page_tree_stats(doc: &Document) -> PageTreeStats {
+  let mut stats = PageTreeStats {
+    depth: 0,
+    max_kids: 0,
+    node_count: 0,
+  };
+  let Some(root) = doc
+    .catalog()
+    .ok()
+    .and_then(|catalog| catalog.get(b"Pages").ok())
+    .and_then(|pages| pages.as_reference().ok())
+  else {
+    return stats;
+  };
+  let mut visited = HashSet::new();
+  let mut stack = vec![(root, 1u32)];
+  while let Some((id, level)) = stack.pop() {
+    if !visited.insert(id) {
+      continue;
+    }
+    let kids = match doc.get_dictionary(id).and_then(|node| node.get(b"Kids")) {
+      Ok(Object::Array(kids)) => kids,
+      Ok(Object::Reference(kids_id)) => match doc.get_object(*kids_id) {
+        Ok(Object::Array(kids)) => kids,
+        _ => continue,
+      },
+      _ => continue,
+    };
+    stats.node_count += 1;
+    stats.depth = stats.depth.max(level);
+    stats.max_kids = stats.max_kids.max(kids.len() as u32);
+    stack.extend(
+      kids
+        .iter()
+        .filter_map(|kid| kid.as_reference().ok())
+        .map(|kid| (kid, level + 1)),
+    );
+  }
+  stats
+}
+
+/// Page tree levels followed before giving up, as in `Document::get_pages`.
+const MAX_PAGE_TREE_DEPTH: usize = 256;
+
+/// Fixes to make a `/Pages` tree walk the way `walk_page_tree` reads it.
+#[derive(Default)]
+pub(crate) struct PageTreeRepairs {
+  /// Pages in tree order, each once.
+  pages: Vec<ObjectId>,
+  /// Nodes whose `/Kids` lost a repeated page or node.
+  kids: Vec<(ObjectId, Vec<Object>)>,
+  /// Nodes and pages whose `/Type` is missing or wrong.
+  types: Vec<(ObjectId, &'static str)>,
+}
+
+fn walk_page_tree(
+  doc: &Document,
+  node: ObjectId,
+  depth: usize,
+  visited: &mut HashSet<ObjectId>,
+  out: &mut PageTreeRepairs,
+) {
+  let Ok(dict) = doc.get_dictionary(node) else {
+    return;
+  };
+  let kids = match dict.get(b"Kids") {
+    Ok(Object::Array(kids)) => Some(kids),
+    Ok(Object::Reference(id)) => doc.get_object(*id).and_then(Object::as_array).ok(),
+    _ => None,
+  };
+  let wanted = if kids.is_some() { "Pages" } else { "Page" };
+  if dict.get(b"Type").and_then(Object::as_name).ok() != Some(wanted.as_bytes()) {
+    out.types.push((node, wanted));
+  }
+  let Some(kids) = kids else {
+    out.pages.push(node);
+    return;
+  };
+  if depth >= MAX_PAGE_TREE_DEPTH {
+    return;
+  }
+  let mut kept = Vec::with_capacity(kids.len());
+  for kid in kids {
+    match kid.as_reference() {
+      Ok(id) if !visited.insert(id) => continue,
+      Ok(id) => walk_page_tree(doc, id, depth + 1, visited, out),
+      Err(_) => {}
+    }
+    kept.push(kid.clone());
+  }
+  if kept.len() < kids.len() {
+    out.kids.push((node, kept));
+  }
+}
+
+/// Renumber pages sequentially in `/Pages` tree order when
+/// `Document::get_pages` would skip or repeat some: a kid with a `/Kids`
+/// array is read as an intermediate node and any other kid as a page,
+/// whatever its `/Type`, and a page or node listed again is dropped. Returns
+/// the repairs that make `get_pages` yield that order, or `None` when the
+/// tree needs no repair.
+pub(crate) fn page_tree_repairs(doc: &Document) -> Option<PageTreeRepairs> {
+  let root = doc
+    .catalog()
+    .ok()?
+    .get(b"Pages")
+    .ok()?
+    .as_reference()
+    .ok()?;
+  let mut repairs = PageTreeRepairs::default();
+  walk_page_tree(doc, root, 0, &mut HashSet::from([root]), &mut repairs);
+  let previous = doc.get_pages();
+  if repairs.pages.iter().eq(previous.values()) {
+    return None;
+  }
+  Some(repairs)
+}
+
+impl PageTreeRepairs {
+  /// Rewrite the `/Kids` and `/Type` entries the walk found wrong. Returns
+  /// each page's new number with the one `get_pages` gave it before.
+  pub(crate) fn apply(self, doc: &mut Document) -> Vec<ReindexedPage> {
+    // A repeated page keeps the first number it had
+    let mut previous: HashMap<ObjectId, u32> = HashMap::new();
+    for (page, id) in doc.get_pages() {
+      previous.entry(id).or_insert(page);
+    }
+    for (id, kids) in self.kids {
+      if let Ok(node) = doc.get_dictionary_mut(id) {
+        node.set("Kids", kids);
+      }
+    }
+    for (id, wanted) in self.types {
+      if let Ok(node) = doc.get_dictionary_mut(id) {
+        node.set("Type", wanted);
+      }
+    }
+    self
+      .pages
+      .iter()
+      .enumerate()
+      .map(|(i, id)| ReindexedPage {
+        page: i as u32 + 1,
+        object_id: format_object_id(*id),
+        previous_page: previous.get(id).copied(),
+      })
+      .collect()
+  }
+}
+
+/// Smallest and largest displayed page width and height in points: each
+/// page's CropBox/MediaBox, swapped for `/Rotate` 90 or 270 and scaled by
+/// `/UserUnit`. Pages without a box are ignored; `None` when none has one.
+pub(crate) fn page_size_range(doc: &Document) -> Option<PageSizeRange> {
+  let sizes: Vec<(f64, f64)> = doc
+    .get_pages()
+    .into_values()
+    .filter_map(|page_id| {
+      let (box_type, [left, bottom, right, top], _) = resolve_page_box(doc, page_id);
+      if matches!(box_type, BoxType::Unknown) {
+        return None;
+      }
+      let unit = page_user_unit(doc, page_id);
+      let (width, height) = ((right - left) * unit, (top - bottom) * unit);
+      Some(match page_rotation(doc, page_id) {
+        90 | 270 => (height, width),
+        _ => (width, height),
+      })
+    })
+    .collect();
+  let (&first, rest) = sizes.split_first()?;
+  let mut range = PageSizeRange {
+    min_width: first.0,
+    min_height: first.1,
+    max_width: first.0,
+    max_height: first.1,
+  };
+  for &(width, height) in rest {
+    range.min_width = range.min_width.min(width);
+    range.min_height = range.min_height.min(height);
+    range.max_width = range.max_width.max(width);
+    range.max_height = range.max_height.max(height);
+  }
+  Some(range)
+}
+
 pub(crate) fn extract_page_boxes(doc: &Document, pages: &BTreeMap<u32, ObjectId>) -> Vec<PageBox> {
   let mut page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
   page_entries.sort_unstable_by_key(|(page, _)| *page);
@@ -139,24 +674,9 @@ pub(crate) fn extract_page_boxes(doc: &Document, pages: &BTreeMap<u32, ObjectId>
   let mut key_to_idx: HashMap<PageBoxKey, usize> = HashMap::new();
 
   for (page_num, page_id) in page_entries {
-    let (box_type, rect) = if let Some(rect) = get_inherited_page_box(doc, page_id, b"CropBox") {
-      (BoxType::CropBox, rect)
-    } else if let Some(rect) = get_inherited_page_box(doc, page_id, b"MediaBox") {
-      (BoxType::MediaBox, rect)
-    } else {
-      (BoxType::Unknown, [0.0, 0.0, 0.0, 0.0])
-    };
-
-    let (left, right) = if rect[0] <= rect[2] {
-      (rect[0], rect[2])
-    } else {
-      (rect[2], rect[0])
-    };
-    let (bottom, top) = if rect[1] <= rect[3] {
-      (rect[1], rect[3])
-    } else {
-      (rect[3], rect[1])
-    };
+    let (box_type, [left, bottom, right, top], inherited) = resolve_page_box(doc, page_id);
+    let user_unit = page_user_unit(doc, page_id);
+    let transparency_group = page_transparency_group(doc, page_id);
 
     let key = PageBoxKey {
       left: left.to_bits(),
@@ -168,6 +688,9 @@ pub(crate) fn extract_page_boxes(doc: &Document, pages: &BTreeMap<u32, ObjectId>
         BoxType::MediaBox => 1,
         BoxType::Unknown => 2,
       },
+      inherited,
+      user_unit: user_unit.to_bits(),
+      transparency_group: transparency_group.clone(),
     };
 
     if let Some(&idx) = key_to_idx.get(&key) {
@@ -181,6 +704,9 @@ pub(crate) fn extract_page_boxes(doc: &Document, pages: &BTreeMap<u32, ObjectId>
         right,
         top,
         box_type,
+        inherited,
+        user_unit,
+        transparency_group,
         page_nums: vec![page_num],
       });
     }
@@ -213,50 +739,283 @@ pub(crate) fn extract_page_boxes(doc: &Document, pages: &BTreeMap<u32, ObjectId>
         width: g.right - g.left,
         height: g.top - g.bottom,
         box_type: g.box_type,
+        inherited: g.inherited,
+        user_unit: g.user_unit,
+        transparency_group: g.transparency_group,
         pages,
       }
     })
     .collect()
 }
 
+fn info_dictionary(doc: &Document) -> Option<&lopdf::Dictionary> {
+  match doc.trailer.get(b"Info").ok()? {
+    Object::Reference(id) => doc.get_dictionary(*id).ok(),
+    _ => None,
+  }
+}
+
+/// A text entry of the trailer's Info dictionary, e.g. `b"Producer"`.
+pub(crate) fn info_entry(doc: &Document, key: &[u8]) -> Option<String> {
+  extract_info_string(info_dictionary(doc)?, key)
+}
+
 pub(crate) fn extract_metadata(doc: &Document) -> PdfMeta {
   let pages = doc.get_pages();
   let page_count = pages.len() as u32;
   let version = doc.version.clone();
-  let is_linearized = doc.trailer.get(b"Linearized").is_ok();
+  let catalog_version = extract_catalog_version(doc);
+  let linearization = extract_linearization(doc);
+  let is_linearized = linearization.is_some() || doc.trailer.get(b"Linearized").is_ok();
 
-  let info_dict = doc.trailer.get(b"Info").ok().and_then(|obj| match obj {
-    Object::Reference(id) => doc.get_dictionary(*id).ok(),
-    _ => None,
-  });
+  let info_dict = info_dictionary(doc);
 
-  let (creator, producer, creation_date, modification_date) = match info_dict {
+  let (creator, producer, creation_date_raw, modification_date_raw) = match info_dict {
     Some(d) => (
       extract_info_string(d, b"Creator"),
       extract_info_string(d, b"Producer"),
-      extract_info_string(d, b"CreationDate").map(|s| pdf_date_to_iso8601(&s)),
-      extract_info_string(d, b"ModDate").map(|s| pdf_date_to_iso8601(&s)),
+      extract_info_string(d, b"CreationDate"),
+      extract_info_string(d, b"ModDate"),
     ),
     None => (None, None, None, None),
   };
+  let creation_date = creation_date_raw.as_deref().and_then(pdf_date_to_iso8601);
+  let modification_date = modification_date_raw
+    .as_deref()
+    .and_then(pdf_date_to_iso8601);
 
+  let language = extract_language(doc);
   let page_boxes = extract_page_boxes(doc, &pages);
+  let edit_history = xmp_packet(doc)
+    .map(|xmp| parse_xmp_history(&xmp))
+    .unwrap_or_default();
+  let usage_rights = extract_usage_rights(doc);
+  let output_intent = extract_output_intent(doc);
+  let viewer_preferences = extract_viewer_preferences(doc);
 
   PdfMeta {
     page_count,
     version,
+    catalog_version,
     is_linearized,
     creator,
     producer,
     creation_date,
     modification_date,
+    creation_date_raw,
+    modification_date_raw,
+    language,
     page_boxes,
+    edit_history,
+    usage_rights,
+    output_intent,
+    linearization,
+    viewer_preferences,
+  }
+}
+
+/// Rights categories of a UR transform's `/TransformParams` (ISO 32000-1
+/// table 255), each an array of right names.
+const USAGE_RIGHT_CATEGORIES: &[&str] = &[
+  "Document",
+  "Msg",
+  "Annots",
+  "Form",
+  "FormEx",
+  "Signature",
+  "EF",
+];
+
+/// Rights enabled by the catalog's `/Perms` usage rights signature (`/UR3`,
+/// else `/UR`), read from the `/TransformParams` of its UR signature
+/// references. A signature without readable parameters grants no listed
+/// rights, giving an empty list.
+fn extract_usage_rights(doc: &Document) -> Option<Vec<String>> {
+  let perms = deref(doc, doc.catalog().ok()?.get(b"Perms").ok()?)?
+    .as_dict()
+    .ok()?;
+  let signature = perms.get(b"UR3").or_else(|_| perms.get(b"UR")).ok()?;
+  let signature = deref(doc, signature)?.as_dict().ok()?;
+  let references = signature
+    .get(b"Reference")
+    .ok()
+    .and_then(|r| deref(doc, r))
+    .and_then(|r| r.as_array().ok())
+    .map(Vec::as_slice)
+    .unwrap_or_default();
+  let mut rights = Vec::new();
+  for reference in references {
+    let Some(params) = deref(doc, reference)
+      .and_then(|r| r.as_dict().ok())
+      .and_then(|r| r.get(b"TransformParams").ok())
+      .and_then(|p| deref(doc, p))
+      .and_then(|p| p.as_dict().ok())
+    else {
+      continue;
+    };
+    for category in USAGE_RIGHT_CATEGORIES {
+      let names = params
+        .get(category.as_bytes())
+        .ok()
+        .and_then(|n| deref(doc, n))
+        .and_then(|n| n.as_array().ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|n| n.as_name().ok());
+      for name in names {
+        let right = format!("{category}.{}", String::from_utf8_lossy(name));
+        if !rights.contains(&right) {
+          rights.push(right);
+        }
+      }
+    }
+  }
+  Some(rights)
+}
+
+/// The `/Metadata` stream of `dict` (the catalog, a page, ...) as text.
+fn metadata_stream_text(doc: &Document, dict: &lopdf::Dictionary) -> Option<String> {
+  let id = dict.get(b"Metadata").ok()?.as_reference().ok()?;
+  let stream = doc.get_object(id).ok()?.as_stream().ok()?;
+  let bytes = stream
+    .decompressed_content()
+    .unwrap_or_else(|_| stream.content.clone());
+  Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// The catalog's `/Metadata` XMP packet as text.
+fn xmp_packet(doc: &Document) -> Option<String> {
+  metadata_stream_text(doc, doc.catalog().ok()?)
+}
+
+/// A page's own `/Metadata` XMP packet as text, when it has one. Page
+/// metadata isn't inherited from the page tree.
+pub(crate) fn page_xmp_packet(doc: &Document, page_id: ObjectId) -> Option<String> {
+  metadata_stream_text(doc, doc.get_dictionary(page_id).ok()?)
+}
+
+/// Events of the `xmpMM:History` sequence. Each `rdf:li` may carry its
+/// `stEvt` fields as attributes or as child elements; entries without an
+/// action are skipped.
+fn parse_xmp_history(xmp: &str) -> Vec<EditEvent> {
+  let Some(start) = xmp.find("<xmpMM:History") else {
+    return Vec::new();
+  };
+  let history = &xmp[start..];
+  let history = &history[..history.find("</xmpMM:History>").unwrap_or(history.len())];
+  history
+    .split("<rdf:li")
+    .skip(1)
+    .filter_map(|item| {
+      Some(EditEvent {
+        action: xmp_field(item, "stEvt:action")?,
+        software_agent: xmp_field(item, "stEvt:softwareAgent"),
+        when: xmp_field(item, "stEvt:when"),
+      })
+    })
+    .collect()
+}
+
+/// Value of `name` in an XMP fragment, written either as an attribute
+/// (`name="…"`) or as an element (`<name>…</name>`), with XML entities
+/// unescaped. Blank values count as absent.
+fn xmp_field(fragment: &str, name: &str) -> Option<String> {
+  let attribute = fragment.find(&format!("{name}=")).and_then(|at| {
+    let rest = &fragment[at + name.len() + 1..];
+    let quote = rest.chars().next().filter(|&q| q == '"' || q == '\'')?;
+    let rest = &rest[1..];
+    Some(&rest[..rest.find(quote)?])
+  });
+  let element = || {
+    let open = format!("<{name}>");
+    let rest = &fragment[fragment.find(&open)? + open.len()..];
+    Some(&rest[..rest.find(&format!("</{name}>"))?])
+  };
+  let value = xml_unescape(attribute.or_else(element)?.trim());
+  (!value.is_empty()).then_some(value)
+}
+
+fn xml_unescape(text: &str) -> String {
+  text
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&quot;", "\"")
+    .replace("&apos;", "'")
+    .replace("&amp;", "&")
+}
+
+/// Real-world scales of the pages' viewports: each `/VP` entry whose
+/// `/Measure` is rectilinear (`/Subtype /RL`, the default), in page order.
+/// The unit and conversion factor come from the first `/X` number format.
+pub(crate) fn collect_measurements(doc: &Document) -> Vec<Measurement> {
+  let mut measurements = Vec::new();
+  for (page_num, page_id) in doc.get_pages() {
+    let viewports = doc
+      .get_dictionary(page_id)
+      .ok()
+      .and_then(|p| p.get(b"VP").ok())
+      .and_then(|o| deref(doc, o))
+      .and_then(|o| o.as_array().ok());
+    for viewport in viewports.into_iter().flatten() {
+      let Some(viewport) = deref(doc, viewport).and_then(|o| o.as_dict().ok()) else {
+        continue;
+      };
+      let Some(measure) = viewport
+        .get(b"Measure")
+        .ok()
+        .and_then(|o| deref(doc, o))
+        .and_then(|o| o.as_dict().ok())
+      else {
+        continue;
+      };
+      let subtype = measure.get(b"Subtype").and_then(Object::as_name).ok();
+      if subtype.is_some_and(|s| s != b"RL") {
+        continue;
+      }
+      let Ok(Object::String(ratio, _)) = measure.get(b"R") else {
+        continue;
+      };
+      let x_format = measure
+        .get(b"X")
+        .ok()
+        .and_then(|o| deref(doc, o))
+        .and_then(|o| o.as_array().ok())
+        .and_then(|formats| formats.first())
+        .and_then(|o| deref(doc, o))
+        .and_then(|o| o.as_dict().ok());
+      let unit = match x_format.and_then(|f| f.get(b"U").ok()) {
+        Some(Object::String(unit, _)) => decode_pdf_string(unit),
+        _ => String::new(),
+      };
+      let conversion_factor = x_format
+        .and_then(|f| f.get(b"C").ok())
+        .and_then(|c| c.as_float().ok())
+        .map(f64::from);
+      let rect = viewport
+        .get(b"BBox")
+        .ok()
+        .and_then(|o| deref(doc, o))
+        .and_then(parse_page_box)
+        .map(Vec::from)
+        .unwrap_or_default();
+
+      measurements.push(Measurement {
+        page: page_num,
+        rect,
+        scale: decode_pdf_string(ratio),
+        unit,
+        conversion_factor,
+      });
+    }
   }
+  measurements
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::core::test_docs::{doc_with_pages, page_tree_root};
+  use lopdf::dictionary;
 
   #[test]
   fn parse_page_box_valid_integers() {
@@ -329,4 +1088,590 @@ mod tests {
     // Only first 4 used
     assert_eq!(parse_page_box(&obj), Some([0.0, 0.0, 612.0, 792.0]));
   }
+
+  #[test]
+  fn inherited_page_box_reports_tree_level() {
+    let (mut doc, pages) = doc_with_pages(|_| {
+      vec![
+        dictionary! { "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()] },
+        dictionary! {},
+      ]
+    });
+    let root = page_tree_root(&doc);
+    doc
+      .get_dictionary_mut(root)
+      .unwrap()
+      .set("MediaBox", vec![0.into(), 0.into(), 612.into(), 792.into()]);
+    let (own_id, inheriting_id) = (pages[0], pages[1]);
+
+    assert_eq!(
+      get_inherited_page_box(&doc, own_id, b"MediaBox"),
+      Some(([0.0, 0.0, 595.0, 842.0], 0))
+    );
+    assert_eq!(
+      get_inherited_page_box(&doc, inheriting_id, b"MediaBox"),
+      Some(([0.0, 0.0, 612.0, 792.0], 1))
+    );
+    assert_eq!(get_inherited_page_box(&doc, own_id, b"CropBox"), None);
+  }
+
+  #[test]
+  fn per_page_boxes_are_ungrouped_and_normalized() {
+    let (mut doc, pages) = doc_with_pages(|_| {
+      vec![
+        dictionary! {},
+        dictionary! {},
+        dictionary! { "CropBox" => vec![612.into(), 792.into(), 0.into(), 0.into()] },
+      ]
+    });
+    let root = page_tree_root(&doc);
+    doc
+      .get_dictionary_mut(root)
+      .unwrap()
+      .set("MediaBox", vec![0.into(), 0.into(), 612.into(), 792.into()]);
+
+    let boxes = extract_per_page_boxes(&doc);
+    assert_eq!(boxes.len(), 3);
+    for (i, b) in boxes.iter().enumerate() {
+      assert_eq!(b.page_count, 1);
+      assert_eq!(b.pages, Some(vec![i as u32 + 1]));
+      assert_eq!(
+        (b.left, b.bottom, b.width, b.height),
+        (0.0, 0.0, 612.0, 792.0)
+      );
+    }
+    assert!(boxes[0].inherited);
+    assert!(matches!(boxes[0].box_type, BoxType::MediaBox));
+    assert!(!boxes[2].inherited);
+    assert!(matches!(boxes[2].box_type, BoxType::CropBox));
+    // Grouping still collapses the pages that share geometry and box type
+    assert_eq!(extract_page_boxes(&doc, &doc.get_pages()).len(), 2);
+    assert!(boxes.iter().all(|b| b.user_unit == 1.0));
+
+    // A different UserUnit splits otherwise identical pages into their own group
+    doc
+      .get_dictionary_mut(pages[1])
+      .unwrap()
+      .set("UserUnit", 2.5);
+    assert_eq!(page_user_unit(&doc, pages[1]), 2.5);
+    assert_eq!(extract_page_boxes(&doc, &doc.get_pages()).len(), 3);
+  }
+
+  #[test]
+  fn page_size_range_accounts_for_rotation_and_user_unit() {
+    let (mut doc, _) = doc_with_pages(|_| {
+      vec![
+        // Letter, inherited from the tree
+        dictionary! {},
+        // A3 landscape via /Rotate
+        dictionary! {
+          "MediaBox" => vec![0.into(), 0.into(), 842.into(), 1191.into()],
+          "Rotate" => 90,
+        },
+        dictionary! {
+          "MediaBox" => vec![0.into(), 0.into(), 100.into(), 50.into()],
+          "UserUnit" => 2,
+        },
+      ]
+    });
+    let root = page_tree_root(&doc);
+    doc
+      .get_dictionary_mut(root)
+      .unwrap()
+      .set("MediaBox", vec![0.into(), 0.into(), 612.into(), 792.into()]);
+
+    let range = page_size_range(&doc).unwrap();
+    assert_eq!(
+      (
+        range.min_width,
+        range.min_height,
+        range.max_width,
+        range.max_height
+      ),
+      (200.0, 100.0, 1191.0, 842.0)
+    );
+  }
+
+  #[test]
+  fn pdf_dates_convert_to_iso8601() {
+    assert_eq!(
+      pdf_date_to_iso8601("D:20230415093000+05'30'").as_deref(),
+      Some("2023-04-15T09:30:00+05:30")
+    );
+    assert_eq!(
+      pdf_date_to_iso8601("D:20230415093000Z").as_deref(),
+      Some("2023-04-15T09:30:00Z")
+    );
+    assert_eq!(
+      pdf_date_to_iso8601("2023").as_deref(),
+      Some("2023-01-01T00:00:00")
+    );
+    assert_eq!(
+      pdf_date_to_iso8601("D:202304-08'00").as_deref(),
+      Some("2023-04-01T00:00:00-08:00")
+    );
+  }
+
+  #[test]
+  fn pdf_date_timezone_variants() {
+    let iso = |raw: &str| pdf_date_to_iso8601(raw);
+    // Apostrophe-free and minute-less offsets
+    assert_eq!(
+      iso("D:20230101120000-0500").as_deref(),
+      Some("2023-01-01T12:00:00-05:00")
+    );
+    assert_eq!(
+      iso("D:20230101120000+05").as_deref(),
+      Some("2023-01-01T12:00:00+05:00")
+    );
+    assert_eq!(
+      iso("D:20230101120000+05'").as_deref(),
+      Some("2023-01-01T12:00:00+05:00")
+    );
+    // Z, with a redundant zero offset, or with an explicit offset that wins
+    assert_eq!(
+      iso("D:20230101120000Z00'00'").as_deref(),
+      Some("2023-01-01T12:00:00Z")
+    );
+    assert_eq!(
+      iso("D:20230101120000Z+01'00'").as_deref(),
+      Some("2023-01-01T12:00:00+01:00")
+    );
+    // Fractional seconds are kept
+    assert_eq!(
+      iso("D:20230101120000.250+01'00'").as_deref(),
+      Some("2023-01-01T12:00:00.250+01:00")
+    );
+    assert_eq!(iso("D:202301011200.5"), None);
+    assert_eq!(iso("D:20230101120000+24'00'"), None);
+    assert_eq!(iso("D:20230101120000+05'7'"), None);
+    assert_eq!(iso("D:20230101120000Z05'00'"), None);
+  }
+
+  #[test]
+  fn invalid_pdf_dates_are_rejected() {
+    for raw in [
+      "",
+      "D:",
+      "D:23",
+      "Tue Apr 15 2023",
+      "D:2023041",
+      "D:20231315",
+      "D:20230400",
+      "D:20230415250000",
+      "D:20230415093000+5x'00'",
+      "D:20230415093000 GMT",
+    ] {
+      assert_eq!(pdf_date_to_iso8601(raw), None, "{raw:?}");
+    }
+  }
+
+  #[test]
+  fn language_comes_from_the_catalog() {
+    use lopdf::StringFormat;
+
+    let (mut doc, _) = doc_with_pages(|_| Vec::new());
+    assert_eq!(extract_language(&doc), None);
+
+    doc.catalog_mut().unwrap().set(
+      "Lang",
+      Object::String(b"en-US".to_vec(), StringFormat::Literal),
+    );
+    assert_eq!(extract_language(&doc).as_deref(), Some("en-US"));
+
+    doc
+      .catalog_mut()
+      .unwrap()
+      .set("Lang", Object::String(b" ".to_vec(), StringFormat::Literal));
+    assert_eq!(extract_language(&doc), None);
+  }
+
+  #[test]
+  fn pdf_strings_decode_by_byte_order_mark() {
+    assert_eq!(decode_pdf_string(b"plain"), "plain");
+    assert_eq!(
+      decode_pdf_string(&[0xFE, 0xFF, 0x00, 0x63, 0x00, 0xE9, 0xD8, 0x3D, 0xDE, 0x00]),
+      "cé😀"
+    );
+    assert_eq!(decode_pdf_string(&[0xEF, 0xBB, 0xBF, 0xC3, 0xA9]), "é");
+    // A dangling odd byte after UTF-16 units is ignored
+    assert_eq!(decode_pdf_string(&[0xFE, 0xFF, 0x00, 0x41, 0x42]), "A");
+  }
+
+  #[test]
+  fn catalog_version_is_reported_separately_from_the_header() {
+    let (mut doc, _) = doc_with_pages(|_| Vec::new());
+    doc.version = "1.4".to_string();
+    assert_eq!(extract_catalog_version(&doc), None);
+
+    doc
+      .catalog_mut()
+      .unwrap()
+      .set("Version", Object::Name(b"1.7".to_vec()));
+    let meta = extract_metadata(&doc);
+    assert_eq!(meta.version, "1.4");
+    assert_eq!(meta.catalog_version.as_deref(), Some("1.7"));
+  }
+
+  #[test]
+  fn output_intent_reads_condition_and_profile() {
+    use lopdf::{Stream, StringFormat};
+
+    let (mut doc, _) = doc_with_pages(|_| Vec::new());
+    assert!(extract_output_intent(&doc).is_none());
+
+    let text = |s: &str| Object::String(s.as_bytes().to_vec(), StringFormat::Literal);
+    let profile = doc.add_object(Stream::new(dictionary! { "N" => 4 }, b"icc".to_vec()));
+    let intent = doc.add_object(dictionary! {
+      "Type" => "OutputIntent",
+      "S" => "GTS_PDFX",
+      "OutputConditionIdentifier" => text("FOGRA39"),
+      "DestOutputProfile" => profile,
+    });
+    doc
+      .catalog_mut()
+      .unwrap()
+      .set("OutputIntents", vec![intent.into()]);
+    let found = extract_output_intent(&doc).unwrap();
+    assert_eq!(
+      (found.condition.as_str(), found.identifier.as_str()),
+      ("FOGRA39", "FOGRA39")
+    );
+    assert_eq!(found.icc_profile.as_deref(), Some(&b"icc"[..]));
+
+    doc
+      .get_dictionary_mut(intent)
+      .unwrap()
+      .set("OutputCondition", text("Coated FOGRA39 (ISO 12647-2:2004)"));
+    let found = extract_output_intent(&doc).unwrap();
+    assert_eq!(found.condition, "Coated FOGRA39 (ISO 12647-2:2004)");
+  }
+
+  #[test]
+  fn linearization_reads_parameters_and_checks_hint_stream() {
+    use lopdf::Stream;
+    use lopdf::xref::XrefEntry;
+
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}]);
+    assert!(extract_linearization(&doc).is_none());
+
+    let page = pages[0];
+    let hints = doc.add_object(Stream::new(dictionary! { "S" => 36 }, vec![0; 64]));
+    let params = doc.add_object(dictionary! {
+      "Linearized" => 1,
+      "L" => 5000,
+      "O" => page.0 as i64,
+      "H" => vec![600.into(), 64.into()],
+      "E" => 900,
+      "N" => 1,
+      "T" => 4800,
+    });
+    // Nothing in the xref table sits at the hint offset yet
+    let info = extract_linearization(&doc).unwrap();
+    assert_eq!(info.file_length, Some(5000));
+    assert_eq!(info.first_page_object, Some(format_object_id(page)));
+    assert!(!info.hint_stream_valid);
+
+    doc.reference_table.insert(
+      hints.0,
+      XrefEntry::Normal {
+        offset: 600,
+        generation: 0,
+      },
+    );
+    assert!(extract_linearization(&doc).unwrap().hint_stream_valid);
+
+    // A hint stream reaching past the declared file length is rejected
+    doc.get_dictionary_mut(params).unwrap().set("L", 620);
+    assert!(!extract_linearization(&doc).unwrap().hint_stream_valid);
+
+    // ...as is one whose length overflows, rather than wrapping
+    let params = doc.get_dictionary_mut(params).unwrap();
+    params.set("L", 5000);
+    params.set("H", vec![600.into(), i64::MAX.into()]);
+    assert!(!extract_linearization(&doc).unwrap().hint_stream_valid);
+  }
+
+  #[test]
+  fn viewer_preferences_read_the_catalog() {
+    let (mut doc, _) = doc_with_pages(|_| Vec::new());
+    assert!(extract_viewer_preferences(&doc).is_none());
+
+    let prefs = doc.add_object(dictionary! {
+      "HideToolbar" => true,
+      "FitWindow" => true,
+      "DisplayDocTitle" => false,
+      "Direction" => "R2L",
+    });
+    let catalog = doc.catalog_mut().unwrap();
+    catalog.set("ViewerPreferences", prefs);
+    catalog.set("PageLayout", "TwoColumnRight");
+    let found = extract_viewer_preferences(&doc).unwrap();
+    assert!(found.hide_toolbar && found.fit_window);
+    assert!(!found.hide_menubar && !found.display_doc_title);
+    assert_eq!(found.direction.as_deref(), Some("R2L"));
+    assert_eq!(found.page_layout.as_deref(), Some("TwoColumnRight"));
+    assert_eq!(found.page_mode, None);
+
+    // A page mode alone is still a preference
+    let catalog = doc.catalog_mut().unwrap();
+    catalog.remove(b"ViewerPreferences");
+    catalog.remove(b"PageLayout");
+    catalog.set("PageMode", "UseOutlines");
+    let found = extract_viewer_preferences(&doc).unwrap();
+    assert!(!found.hide_toolbar);
+    assert_eq!(found.page_mode.as_deref(), Some("UseOutlines"));
+  }
+
+  #[test]
+  fn measurements_read_rectilinear_viewports() {
+    use lopdf::StringFormat;
+
+    let text = |s: &str| Object::String(s.as_bytes().to_vec(), StringFormat::Literal);
+    let (doc, _) = doc_with_pages(|doc| {
+      let measure = doc.add_object(dictionary! {
+        "Type" => "Measure",
+        "Subtype" => "RL",
+        "R" => text("1 in = 10 ft"),
+        "X" => vec![dictionary! { "U" => text("ft"), "C" => Object::Real(10.0 / 72.0) }.into()],
+      });
+      vec![dictionary! {
+        "VP" => vec![
+          dictionary! {
+            "Type" => "Viewport",
+            "BBox" => vec![36.into(), 36.into(), 576.into(), 400.into()],
+            "Measure" => measure,
+          }
+          .into(),
+          // Geospatial measures aren't scales
+          dictionary! {
+            "BBox" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+            "Measure" => dictionary! { "Subtype" => "GEO" },
+          }
+          .into(),
+        ],
+      }]
+    });
+
+    let found = collect_measurements(&doc);
+    assert_eq!(found.len(), 1);
+    let m = &found[0];
+    assert_eq!(
+      (m.page, m.scale.as_str(), m.unit.as_str()),
+      (1, "1 in = 10 ft", "ft")
+    );
+    assert_eq!(m.rect, [36.0, 36.0, 576.0, 400.0]);
+    assert!((m.conversion_factor.unwrap() - 10.0 / 72.0).abs() < 1e-6);
+  }
+
+  #[test]
+  fn transparency_group_reports_blend_color_space() {
+    let (mut doc, _) = doc_with_pages(|doc| {
+      [
+        Some(dictionary! { "S" => "Transparency", "CS" => "DeviceCMYK" }),
+        Some(dictionary! { "S" => "Transparency" }),
+        None,
+      ]
+      .into_iter()
+      .map(|group| match group {
+        Some(group) => dictionary! { "Group" => doc.add_object(group) },
+        None => dictionary! {},
+      })
+      .collect()
+    });
+    let root = page_tree_root(&doc);
+    doc
+      .get_dictionary_mut(root)
+      .unwrap()
+      .set("MediaBox", vec![0.into(), 0.into(), 612.into(), 792.into()]);
+
+    let per_page: Vec<Option<String>> = extract_per_page_boxes(&doc)
+      .into_iter()
+      .map(|b| b.transparency_group)
+      .collect();
+    assert_eq!(per_page, [Some("DeviceCMYK".to_string()), None, None]);
+
+    let grouped = extract_page_boxes(&doc, &doc.get_pages());
+    assert_eq!(grouped.len(), 2);
+    assert_eq!(grouped[0].transparency_group.as_deref(), Some("DeviceCMYK"));
+    assert_eq!(grouped[0].pages, Some(vec![1]));
+    assert_eq!(
+      (grouped[1].page_count, grouped[1].pages.as_ref()),
+      (2, None)
+    );
+  }
+
+  #[test]
+  fn page_tree_stats_measure_depth_and_fan_out() {
+    let (mut doc, _) = doc_with_pages(|_| vec![dictionary! {}]);
+    let root_id = page_tree_root(&doc);
+    // A second level holding three more pages
+    let inner_id = doc.new_object_id();
+    let inner_kids: Vec<Object> = (0..3)
+      .map(|_| {
+        doc
+          .add_object(dictionary! { "Type" => "Page", "Parent" => inner_id })
+          .into()
+      })
+      .collect();
+    doc.objects.insert(
+      inner_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Parent" => root_id,
+        "Kids" => inner_kids,
+        "Count" => 3,
+      }),
+    );
+    let root = doc.get_dictionary_mut(root_id).unwrap();
+    root.set("Count", 4);
+    if let Ok(Object::Array(kids)) = root.get_mut(b"Kids") {
+      kids.push(inner_id.into());
+    }
+
+    let stats = collect_page_tree_stats(&doc);
+    assert_eq!((stats.depth, stats.max_kids, stats.node_count), (2, 3, 2));
+
+    // A subtree pointing back at the root is not walked again
+    if let Ok(Object::Array(kids)) = doc.get_dictionary_mut(inner_id).unwrap().get_mut(b"Kids") {
+      kids.push(root_id.into());
+    }
+    let stats = collect_page_tree_stats(&doc);
+    assert_eq!((stats.depth, stats.max_kids, stats.node_count), (2, 4, 2));
+  }
+
+  #[test]
+  fn reindex_pages_recovers_untyped_and_repeated_pages() {
+    let (mut doc, pages) = doc_with_pages(|_| vec![dictionary! {}, dictionary! {}, dictionary! {}]);
+    let (first, untyped, last) = (pages[0], pages[1], pages[2]);
+    // No /Type: Document::get_pages skips it
+    doc.get_dictionary_mut(untyped).unwrap().remove(b"Type");
+    assert_eq!(doc.get_pages().len(), 2);
+
+    let mapping = page_tree_repairs(&doc).unwrap().apply(&mut doc);
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    assert_eq!(pages, [first, untyped, last]);
+    let numbering: Vec<(u32, Option<u32>)> =
+      mapping.iter().map(|p| (p.page, p.previous_page)).collect();
+    assert_eq!(numbering, [(1, Some(1)), (2, None), (3, Some(2))]);
+    assert_eq!(mapping[1].object_id, format_object_id(untyped));
+
+    // A page listed twice keeps its first position only
+    let root_id = page_tree_root(&doc);
+    if let Ok(Object::Array(kids)) = doc.get_dictionary_mut(root_id).unwrap().get_mut(b"Kids") {
+      kids.push(first.into());
+    }
+    assert_eq!(doc.get_pages().len(), 4);
+    let mapping = page_tree_repairs(&doc).unwrap().apply(&mut doc);
+    assert_eq!(doc.get_pages().len(), 3);
+    assert_eq!(mapping.len(), 3);
+
+    // A sound tree is left alone
+    assert!(page_tree_repairs(&doc).is_none());
+  }
+
+  #[test]
+  fn page_xmp_packet_reads_the_page_stream() {
+    use lopdf::Stream;
+
+    let (doc, pages) = doc_with_pages(|doc| {
+      let xmp = doc.add_object(Stream::new(
+        dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+        b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>".to_vec(),
+      ));
+      vec![dictionary! { "Metadata" => xmp }, dictionary! {}]
+    });
+    assert_eq!(
+      page_xmp_packet(&doc, pages[0]).as_deref(),
+      Some("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>")
+    );
+    assert_eq!(page_xmp_packet(&doc, pages[1]), None);
+  }
+
+  #[test]
+  fn xmp_history_reads_attribute_and_element_events() {
+    use lopdf::Stream;
+
+    let xmp = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description>
+      <xmpMM:History><rdf:Seq>
+        <rdf:li stEvt:action="created" stEvt:softwareAgent="Adobe InDesign 18.0"
+          stEvt:when="2023-04-15T09:30:00+05:30"/>
+        <rdf:li rdf:parseType="Resource">
+          <stEvt:action>converted</stEvt:action>
+          <stEvt:softwareAgent>Acrobat Distiller &amp; Co</stEvt:softwareAgent>
+        </rdf:li>
+        <rdf:li stEvt:when="2023-05-01"/>
+      </rdf:Seq></xmpMM:History>
+    </rdf:Description></rdf:RDF></x:xmpmeta>"#;
+    let (mut doc, _) = doc_with_pages(|_| Vec::new());
+    let metadata = doc.add_object(Stream::new(
+      dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+      xmp.as_bytes().to_vec(),
+    ));
+    doc.catalog_mut().unwrap().set("Metadata", metadata);
+
+    let history = extract_metadata(&doc).edit_history;
+    let events: Vec<(&str, Option<&str>, Option<&str>)> = history
+      .iter()
+      .map(|e| {
+        (
+          e.action.as_str(),
+          e.software_agent.as_deref(),
+          e.when.as_deref(),
+        )
+      })
+      .collect();
+    assert_eq!(
+      events,
+      [
+        (
+          "created",
+          Some("Adobe InDesign 18.0"),
+          Some("2023-04-15T09:30:00+05:30")
+        ),
+        ("converted", Some("Acrobat Distiller & Co"), None),
+      ]
+    );
+  }
+
+  #[test]
+  fn usage_rights_list_ur3_transform_params() {
+    let (mut doc, _) = doc_with_pages(|_| Vec::new());
+    assert_eq!(extract_metadata(&doc).usage_rights, None);
+
+    let names = |names: &[&str]| -> Vec<Object> {
+      names
+        .iter()
+        .map(|n| Object::Name(n.as_bytes().to_vec()))
+        .collect()
+    };
+    let ur3 = doc.add_object(dictionary! {
+      "Type" => "Sig",
+      "Filter" => "Adobe.PPKLite",
+      "Reference" => vec![Object::Dictionary(dictionary! {
+        "Type" => "SigRef",
+        "TransformMethod" => "UR3",
+        "TransformParams" => dictionary! {
+          "Type" => "TransformParams",
+          "Document" => names(&["FullSave"]),
+          "Form" => names(&["FillIn", "Import"]),
+          "Annots" => names(&["Create"]),
+          "V" => "2.2",
+        },
+      })],
+    });
+    doc
+      .catalog_mut()
+      .unwrap()
+      .set("Perms", dictionary! { "UR3" => ur3 });
+    assert_eq!(
+      extract_metadata(&doc).usage_rights,
+      Some(vec![
+        "Document.FullSave".to_string(),
+        "Annots.Create".to_string(),
+        "Form.FillIn".to_string(),
+        "Form.Import".to_string(),
+      ])
+    );
+  }
 }
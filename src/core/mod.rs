@@ -1,8 +1,18 @@
+pub(crate) mod cmap;
 pub(crate) mod document;
+pub(crate) mod forms;
 pub(crate) mod images;
+pub(crate) mod inspect;
+pub(crate) mod layout;
 pub(crate) mod meta;
+pub(crate) mod objects;
+pub(crate) mod outline;
+pub(crate) mod tagged;
 pub(crate) mod text;
 
+#[cfg(test)]
+pub(crate) mod test_docs;
+
 #[cfg(feature = "ocr")]
 pub(crate) mod ocr;
 
@@ -0,0 +1,20 @@
+use lopdf::{Dictionary, Document, Object};
+
+/// `obj` itself, or the object it refers to when it is an indirect
+/// reference. `None` for a dangling reference.
+pub(crate) fn deref<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Object> {
+  match obj {
+    Object::Reference(id) => doc.get_object(*id).ok(),
+    other => Some(other),
+  }
+}
+
+/// [`deref`], when the result is a dictionary.
+pub(crate) fn deref_dict<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Dictionary> {
+  deref(doc, obj)?.as_dict().ok()
+}
+
+/// [`deref`], when the result is an array.
+pub(crate) fn deref_array<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Vec<Object>> {
+  deref(doc, obj)?.as_array().ok()
+}
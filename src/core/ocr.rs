@@ -1,6 +1,8 @@
 use crate::core::images::collect_page_decoded_images;
-use crate::core::text::{detect_headers_footers, strip_footer_artifacts};
-use crate::types::{OcrPageText, OcrStructuredPageText, PageText, TextSource};
+use crate::core::text::{
+  detect_headers_footers, extract_page_text_raw, extract_text, strip_footer_artifacts,
+};
+use crate::types::{OcrPageText, OcrStreamedPage, OcrStructuredPageText, PageText, TextSource};
 use image::DynamicImage;
 use lopdf::{Document, ObjectId};
 use napi::Result;
@@ -67,48 +69,96 @@ fn get_tessdata_prefix() -> Option<&'static str> {
     .as_deref()
 }
 
-/// OCR a single DynamicImage and return extracted text.
-fn ocr_dynamic_image(img: &DynamicImage, lang: &str) -> String {
-  let datapath = get_tessdata_prefix().unwrap_or("");
+/// The tessdata directory for this call: the explicit override, else the
+/// process-wide auto-detected path.
+fn tessdata_dir(tessdata_path: Option<&str>) -> &str {
+  match tessdata_path {
+    Some(path) => path,
+    None => get_tessdata_prefix().unwrap_or(""),
+  }
+}
+
+/// Whether Tesseract can start with `lang` from the resolved tessdata
+/// directory. Probed once per call so a missing or partial install degrades to
+/// native text instead of empty OCR pages.
+pub(crate) fn ocr_available(lang: &str, tessdata_path: Option<&str>) -> bool {
+  tesseract_rs::TesseractAPI::new()
+    .init(tessdata_dir(tessdata_path), lang)
+    .is_ok()
+}
+
+/// Text Tesseract recognised, with its mean word confidence (0–100).
+struct Recognized {
+  text: String,
+  confidence: Option<f64>,
+}
+
+/// OCR a single DynamicImage and return extracted text. The confidence is
+/// only set when some text was recognised.
+///
+/// `tessdata_path` overrides the process-wide auto-detected tessdata directory
+/// for this call only.
+fn ocr_dynamic_image(img: &DynamicImage, lang: &str, tessdata_path: Option<&str>) -> Recognized {
+  let none = || Recognized {
+    text: String::new(),
+    confidence: None,
+  };
+  let datapath = tessdata_dir(tessdata_path);
   let rgb = img.to_rgb8();
   let (w, h) = rgb.dimensions();
   let pixels = rgb.as_raw();
 
   let tess = tesseract_rs::TesseractAPI::new();
   if tess.init(datapath, lang).is_err() {
-    return String::new();
+    return none();
   }
   if tess
     .set_image(pixels, w as i32, h as i32, 3, (w * 3) as i32)
     .is_err()
   {
-    return String::new();
+    return none();
   }
   match tess.get_utf8_text() {
     Ok(text) => {
       let trimmed = text.trim();
       if trimmed.is_empty() {
-        String::new()
+        none()
       } else {
-        trimmed.to_string()
+        Recognized {
+          text: trimmed.to_string(),
+          confidence: tess.mean_text_conf().ok().map(f64::from),
+        }
       }
     }
-    Err(_) => String::new(),
+    Err(_) => none(),
   }
 }
 
-fn ocr_page_images(doc: &Document, page_id: ObjectId, lang: &str) -> String {
+/// OCR every raster image on the page, joining their text with newlines.
+/// The confidence is the mean over the images that yielded text.
+fn ocr_page_images(
+  doc: &Document,
+  page_id: ObjectId,
+  lang: &str,
+  tessdata_path: Option<&str>,
+) -> Recognized {
   let images: Vec<DynamicImage> = collect_page_decoded_images(doc, page_id);
   let mut texts = Vec::new();
+  let mut confidences = Vec::new();
 
   for dyn_img in &images {
-    let text = ocr_dynamic_image(dyn_img, lang);
-    if !text.is_empty() {
-      texts.push(text);
+    let recognized = ocr_dynamic_image(dyn_img, lang, tessdata_path);
+    if !recognized.text.is_empty() {
+      texts.push(recognized.text);
+      confidences.extend(recognized.confidence);
     }
   }
 
-  texts.join("\n")
+  Recognized {
+    text: texts.join("\n"),
+    confidence: (!confidences.is_empty())
+      .then(|| confidences.iter().sum::<f64>() / confidences.len() as f64),
+  }
 }
 
 // Re-export render-mode constants so existing `use crate::core::ocr::RENDER_MODE_*`
@@ -125,119 +175,231 @@ pub(crate) const RENDER_MODE_NEVER: u8 = 1;
 #[cfg(not(feature = "render"))]
 pub(crate) const RENDER_MODE_ALWAYS: u8 = 2;
 
-/// Extract text with OCR fallback, with optional render tier.
+/// Per-page text from `extract_text_with_ocr`, plus whether Tesseract could
+/// actually run. When it couldn't, every page carries its native text.
+pub(crate) struct OcrText {
+  pub pages: Vec<OcrPageText>,
+  pub ocr_available: bool,
+}
+
+/// Native text for every page, labelled as such — the result when OCR can't
+/// run at all.
+pub(crate) fn native_ocr_pages(doc: &Document) -> Vec<OcrPageText> {
+  extract_text(doc)
+    .unwrap_or_default()
+    .into_iter()
+    .map(|p| OcrPageText {
+      page: p.page,
+      text: p.text,
+      source: TextSource::Native,
+    })
+    .collect()
+}
+
+/// Per-call OCR settings shared by every page of one extraction.
 ///
-/// `render_dpi` and `render_mode` are always accepted as params.
-/// When the `render` feature is disabled they are silently ignored.
-pub(crate) fn extract_text_with_ocr(
-  doc: &Document,
-  pdf_bytes: &[u8],
-  lang: &str,
+/// `render_dpi` and `render_mode` are always accepted. When the `render`
+/// feature is disabled they are silently ignored.
+struct OcrRun<'a> {
+  doc: &'a Document,
+  #[cfg_attr(not(feature = "render"), allow(dead_code))]
+  pdf_bytes: &'a [u8],
+  lang: &'a str,
+  tessdata_path: Option<&'a str>,
   min_len: u32,
-  max_threads: u32,
+  #[cfg_attr(not(feature = "render"), allow(dead_code))]
   render_dpi: u32,
+  #[cfg_attr(not(feature = "render"), allow(dead_code))]
   render_mode: u8,
-) -> Result<Vec<OcrPageText>> {
-  let pages = doc.get_pages();
-  let page_count_str = pages.len().to_string();
-  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
-
-  let pool = get_ocr_pool(max_threads as usize);
+  ocr_ready: bool,
+  /// Every page with its object id, in page order.
+  pages: Vec<(u32, ObjectId)>,
+  page_count_str: String,
+}
 
-  #[cfg(feature = "render")]
-  let pdf_arc: Arc<Vec<u8>> = Arc::new(pdf_bytes.to_vec());
-  #[cfg(not(feature = "render"))]
-  let _ = (pdf_bytes, render_dpi, render_mode);
+impl OcrRun<'_> {
+  /// Text for one page through the tiers: native text when it has at least
+  /// `min_len` non-whitespace characters (or Tesseract can't run), else OCR
+  /// of the page's raster images, else (render feature) OCR of the rendered
+  /// page. Comes with the OCR confidence when OCR produced the text.
+  fn page(&self, page_num: u32, page_id: ObjectId) -> (OcrPageText, Option<f64>) {
+    let (doc, lang, tessdata_path) = (self.doc, self.lang, self.tessdata_path);
+    let page = |text: String, source: TextSource| OcrPageText {
+      page: page_num,
+      text,
+      source,
+    };
 
-  let mut results: Vec<OcrPageText> = pool.install(|| {
-    page_entries
-      .par_iter()
-      .map(|&(page_num, page_id)| {
-        // Tier 1: Native text extraction
-        let raw = doc.extract_text(&[page_num]).unwrap_or_default();
-        let native = strip_footer_artifacts(&raw, &page_count_str);
-        let non_ws: usize = native.chars().filter(|c| !c.is_whitespace()).count();
-        if non_ws >= min_len as usize {
-          #[cfg(feature = "render")]
-          {
-            // In Always mode, render every page regardless
-            if render_mode == RENDER_MODE_ALWAYS {
-              if let Some(rendered_text) = try_render_ocr_page(&pdf_arc, page_num, render_dpi, lang)
-              {
-                if !rendered_text.is_empty() {
-                  return OcrPageText {
-                    page: page_num,
-                    text: rendered_text,
-                    source: TextSource::Rendered,
-                  };
-                }
-              }
+    // Tier 1: Native text extraction
+    let raw = extract_page_text_raw(doc, page_num, page_id);
+    let native = strip_footer_artifacts(&raw, &self.page_count_str).into_owned();
+    let non_ws: usize = native.chars().filter(|c| !c.is_whitespace()).count();
+    if non_ws >= self.min_len as usize || !self.ocr_ready {
+      #[cfg(feature = "render")]
+      {
+        // In Always mode, render every page regardless
+        if self.ocr_ready && self.render_mode == RENDER_MODE_ALWAYS {
+          if let Some(rendered) = try_render_ocr_page(
+            self.pdf_bytes,
+            page_num,
+            self.render_dpi,
+            lang,
+            tessdata_path,
+          ) {
+            if !rendered.text.is_empty() {
+              return (
+                page(rendered.text, TextSource::Rendered),
+                rendered.confidence,
+              );
             }
           }
-          return OcrPageText {
-            page: page_num,
-            text: native,
-            source: TextSource::Native,
-          };
         }
+      }
+      return (page(native, TextSource::Native), None);
+    }
 
-        // Tier 2: OCR extracted raster images
-        let ocr_text = ocr_page_images(doc, page_id, lang);
-        if !ocr_text.is_empty() {
-          return OcrPageText {
-            page: page_num,
-            text: ocr_text,
-            source: TextSource::Ocr,
-          };
-        }
+    // Tier 2: OCR extracted raster images
+    let ocr = ocr_page_images(doc, page_id, lang, tessdata_path);
+    if !ocr.text.is_empty() {
+      return (page(ocr.text, TextSource::Ocr), ocr.confidence);
+    }
 
-        // Tier 3: Render page + OCR (render feature only)
-        #[cfg(feature = "render")]
-        {
-          if render_mode != RENDER_MODE_NEVER {
-            let should_render = render_mode == RENDER_MODE_ALWAYS
-              || page_has_form_xobjects(doc, page_id)
-              || page_has_vector_content(doc, page_id);
-
-            if should_render {
-              if let Some(rendered_text) = try_render_ocr_page(&pdf_arc, page_num, render_dpi, lang)
-              {
-                if !rendered_text.is_empty() {
-                  return OcrPageText {
-                    page: page_num,
-                    text: rendered_text,
-                    source: TextSource::Rendered,
-                  };
-                }
-              }
+    // Tier 3: Render page + OCR (render feature only)
+    #[cfg(feature = "render")]
+    {
+      if self.render_mode != RENDER_MODE_NEVER {
+        let should_render = self.render_mode == RENDER_MODE_ALWAYS
+          || page_has_form_xobjects(doc, page_id)
+          || page_has_vector_content(doc, page_id);
+
+        if should_render {
+          if let Some(rendered) = try_render_ocr_page(
+            self.pdf_bytes,
+            page_num,
+            self.render_dpi,
+            lang,
+            tessdata_path,
+          ) {
+            if !rendered.text.is_empty() {
+              return (
+                page(rendered.text, TextSource::Rendered),
+                rendered.confidence,
+              );
             }
           }
         }
+      }
+    }
 
-        // Fallback: empty
-        OcrPageText {
-          page: page_num,
-          text: String::new(),
-          source: TextSource::Ocr,
-        }
-      })
+    // Fallback: empty
+    (page(String::new(), TextSource::Ocr), None)
+  }
+}
+
+/// Extract text with OCR fallback, with optional render tier.
+///
+/// `render_dpi` and `render_mode` are always accepted as params.
+/// When the `render` feature is disabled they are silently ignored.
+/// If Tesseract can't be initialised, pages keep their native text and
+/// `ocr_available` is `false`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn extract_text_with_ocr(
+  doc: &Document,
+  pdf_bytes: &[u8],
+  lang: &str,
+  tessdata_path: Option<&str>,
+  min_len: u32,
+  max_threads: u32,
+  render_dpi: u32,
+  render_mode: u8,
+) -> Result<OcrText> {
+  let pages: Vec<(u32, ObjectId)> = doc.get_pages().into_iter().collect();
+  let run = OcrRun {
+    doc,
+    pdf_bytes,
+    lang,
+    tessdata_path,
+    min_len,
+    render_dpi,
+    render_mode,
+    ocr_ready: ocr_available(lang, tessdata_path),
+    page_count_str: pages.len().to_string(),
+    pages,
+  };
+  let pool = get_ocr_pool(max_threads as usize);
+  let mut results: Vec<OcrPageText> = pool.install(|| {
+    run
+      .pages
+      .par_iter()
+      .map(|&(page_num, page_id)| run.page(page_num, page_id).0)
       .collect()
   });
   results.sort_unstable_by_key(|r| r.page);
-  Ok(results)
+  Ok(OcrText {
+    pages: results,
+    ocr_available: run.ocr_ready,
+  })
+}
+
+/// Like `extract_text_with_ocr`, but hand each page to `emit` as soon as it
+/// is done, in completion order, with its OCR confidence. Returns whether
+/// Tesseract could run.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn stream_text_with_ocr(
+  doc: &Document,
+  pdf_bytes: &[u8],
+  lang: &str,
+  tessdata_path: Option<&str>,
+  min_len: u32,
+  max_threads: u32,
+  render_dpi: u32,
+  render_mode: u8,
+  emit: impl Fn(OcrStreamedPage) + Sync,
+) -> bool {
+  let pages: Vec<(u32, ObjectId)> = doc.get_pages().into_iter().collect();
+  let run = OcrRun {
+    doc,
+    pdf_bytes,
+    lang,
+    tessdata_path,
+    min_len,
+    render_dpi,
+    render_mode,
+    ocr_ready: ocr_available(lang, tessdata_path),
+    page_count_str: pages.len().to_string(),
+    pages,
+  };
+  let pool = get_ocr_pool(max_threads as usize);
+  pool.install(|| {
+    run.pages.par_iter().for_each(|&(page_num, page_id)| {
+      let (page, confidence) = run.page(page_num, page_id);
+      emit(OcrStreamedPage {
+        page: page.page,
+        text: page.text,
+        source: page.source,
+        confidence,
+      });
+    })
+  });
+  run.ocr_ready
 }
 
 /// Render a page to an image and OCR the result.
 #[cfg(feature = "render")]
-fn try_render_ocr_page(pdf_bytes: &[u8], page_num: u32, dpi: u32, lang: &str) -> Option<String> {
+fn try_render_ocr_page(
+  pdf_bytes: &[u8],
+  page_num: u32,
+  dpi: u32,
+  lang: &str,
+  tessdata_path: Option<&str>,
+) -> Option<Recognized> {
   if !render::is_pdfium_available() {
     return None;
   }
   // page_num is 1-based, PDFium page index is 0-based
   let page_index = (page_num - 1) as u16;
   let img = render::render_page_to_image_from_bytes(pdf_bytes, page_index, dpi)?;
-  let text = ocr_dynamic_image(&img, lang);
-  Some(text)
+  Some(ocr_dynamic_image(&img, lang, tessdata_path))
 }
 
 pub(crate) fn detect_headers_footers_ocr(pages: &[OcrPageText]) -> Vec<OcrStructuredPageText> {
@@ -246,6 +408,7 @@ pub(crate) fn detect_headers_footers_ocr(pages: &[OcrPageText]) -> Vec<OcrStruct
     .map(|p| PageText {
       page: p.page,
       text: p.text.clone(),
+      footer_stripped: false,
     })
     .collect();
   let structured = detect_headers_footers(&as_page_text);
@@ -0,0 +1,445 @@
+use crate::core::document::{destination_page, named_destinations};
+use crate::core::layout::collect_styled_lines;
+use crate::core::meta::decode_pdf_string;
+use crate::types::{OutlineItem, PageStyledLines};
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Bookmarks nested deeper than this are dropped.
+const MAX_OUTLINE_DEPTH: u32 = 32;
+
+/// Heading levels a synthesized outline distinguishes (H1–H3).
+const MAX_HEADING_LEVELS: usize = 3;
+
+/// A line is set larger than body text when its font size exceeds the body
+/// size by this factor.
+const HEADING_SIZE_RATIO: f64 = 1.15;
+
+/// Lines longer than this are body text, however they are styled.
+const MAX_HEADING_CHARS: usize = 120;
+
+/// Font sizes are compared at this granularity (points), so sizes that only
+/// differ by rounding in the text matrix share a level.
+const SIZE_STEP: f64 = 0.5;
+
+fn size_key(size: f64) -> i64 {
+  (size / SIZE_STEP).round() as i64
+}
+
+/// Body text size: the median font size over all characters of horizontal
+/// lines, as a `size_key`.
+fn body_size(pages: &[PageStyledLines]) -> Option<i64> {
+  let mut sizes: Vec<(i64, usize)> = pages
+    .iter()
+    .flat_map(|p| &p.lines)
+    .filter(|l| !l.vertical)
+    .map(|l| (size_key(l.font_size), l.text.chars().count()))
+    .collect();
+  sizes.sort_unstable();
+  let total: usize = sizes.iter().map(|(_, chars)| chars).sum();
+  let mut seen = 0;
+  sizes.into_iter().find_map(|(size, chars)| {
+    seen += chars;
+    (seen * 2 >= total).then_some(size)
+  })
+}
+
+fn could_be_heading(text: &str) -> bool {
+  text.chars().count() <= MAX_HEADING_CHARS && text.chars().any(char::is_alphabetic)
+}
+
+/// Build a nested outline from the largest text in a document without one.
+///
+/// The body size is the median font size of all text. Distinct sizes at least
+/// 15% larger become heading levels, largest first; if fewer than three exist,
+/// bold lines at body size form the next level. Consecutive heading lines of
+/// the same level are joined into one title, and each heading nests under the
+/// closest preceding heading of a higher level.
+pub(crate) fn outline_from_headings(doc: &Document) -> Vec<OutlineItem> {
+  let mut roots: Vec<OutlineItem> = Vec::new();
+  for (level, page, title) in detect_headings(doc) {
+    let mut siblings = &mut roots;
+    for _ in 0..level {
+      if siblings.is_empty() {
+        break;
+      }
+      let last = siblings.len() - 1;
+      siblings = &mut siblings[last].children;
+    }
+    siblings.push(OutlineItem {
+      title,
+      page: Some(page),
+      level: level as u32 + 1,
+      children: Vec::new(),
+    });
+  }
+  roots
+}
+
+/// The last heading on each page that has one, as `outline_from_headings`
+/// detects them.
+pub(crate) fn last_heading_per_page(doc: &Document) -> BTreeMap<u32, String> {
+  detect_headings(doc)
+    .into_iter()
+    .map(|(_, page, title)| (page, title))
+    .collect()
+}
+
+/// Headings as (level, page, title) in reading order, level 0 the largest.
+fn detect_headings(doc: &Document) -> Vec<(usize, u32, String)> {
+  let pages = collect_styled_lines(doc);
+  let Some(body) = body_size(&pages) else {
+    return Vec::new();
+  };
+  let threshold = body as f64 * HEADING_SIZE_RATIO;
+  let lines = || {
+    pages
+      .iter()
+      .flat_map(|p| p.lines.iter().map(move |l| (p.page, l)))
+      .filter(|(_, l)| !l.vertical && could_be_heading(&l.text))
+  };
+
+  let mut sizes: Vec<i64> = lines()
+    .map(|(_, l)| size_key(l.font_size))
+    .filter(|&size| size as f64 > threshold)
+    .collect();
+  sizes.sort_unstable_by(|a, b| b.cmp(a));
+  sizes.dedup();
+  sizes.truncate(MAX_HEADING_LEVELS);
+  let bold_level = (sizes.len() < MAX_HEADING_LEVELS).then_some(sizes.len());
+  let level_of = |size: i64, bold: bool| {
+    sizes
+      .iter()
+      .position(|&s| s == size)
+      .or(bold_level.filter(|_| bold && size == body))
+  };
+
+  // (level, page, title) in reading order. A body line in between ends a
+  // multi-line heading.
+  let mut headings: Vec<(usize, u32, String)> = Vec::new();
+  let mut continues = false;
+  for (page, line) in pages
+    .iter()
+    .flat_map(|p| p.lines.iter().map(move |l| (p.page, l)))
+  {
+    let level = (!line.vertical && could_be_heading(&line.text))
+      .then(|| level_of(size_key(line.font_size), line.bold))
+      .flatten();
+    let Some(level) = level else {
+      continues = false;
+      continue;
+    };
+    match headings.last_mut() {
+      Some((last_level, last_page, title))
+        if continues && *last_level == level && *last_page == page =>
+      {
+        title.push(' ');
+        title.push_str(&line.text);
+      }
+      _ => headings.push((level, page, line.text.clone())),
+    }
+    continues = true;
+  }
+  headings
+}
+
+/// The document's bookmarks (`/Outlines`), nested as a viewer shows them.
+/// Documents without bookmarks fall back to their named destinations
+/// (`/Dests`), listed flat in target-page order and titled by name.
+pub(crate) fn collect_outline(doc: &Document) -> Vec<OutlineItem> {
+  let page_nums: HashMap<ObjectId, u32> = doc
+    .get_pages()
+    .into_iter()
+    .map(|(num, id)| (id, num))
+    .collect();
+  let first = doc
+    .catalog()
+    .and_then(|catalog| doc.get_dict_in_dict(catalog, b"Outlines"))
+    .and_then(|outlines| outlines.get(b"First"))
+    .and_then(Object::as_reference)
+    .ok();
+  let bookmarks = outline_items(doc, first, 1, &page_nums, &mut HashSet::new());
+  if !bookmarks.is_empty() {
+    return bookmarks;
+  }
+
+  let mut destinations: Vec<(u32, String)> = named_destinations(doc)
+    .into_iter()
+    .filter_map(|(name, page_id)| Some((*page_nums.get(&page_id)?, name)))
+    .collect();
+  destinations.sort();
+  destinations
+    .into_iter()
+    .map(|(page, title)| OutlineItem {
+      title,
+      page: Some(page),
+      level: 1,
+      children: Vec::new(),
+    })
+    .collect()
+}
+
+/// The sibling chain starting at `first` (following `/Next`), with each
+/// item's `/First` chain nested under it.
+fn outline_items(
+  doc: &Document,
+  first: Option<ObjectId>,
+  level: u32,
+  page_nums: &HashMap<ObjectId, u32>,
+  seen: &mut HashSet<ObjectId>,
+) -> Vec<OutlineItem> {
+  let mut items = Vec::new();
+  let mut next = first;
+  while let Some(id) = next {
+    if level > MAX_OUTLINE_DEPTH || !seen.insert(id) {
+      break;
+    }
+    let Ok(item) = doc.get_dictionary(id) else {
+      break;
+    };
+    let title = match item.get(b"Title") {
+      Ok(Object::String(bytes, _)) => decode_pdf_string(bytes),
+      _ => String::new(),
+    };
+    let page = outline_target(doc, item).and_then(|id| page_nums.get(&id).copied());
+    let children = item.get(b"First").and_then(Object::as_reference).ok();
+    items.push(OutlineItem {
+      title,
+      page,
+      level,
+      children: outline_items(doc, children, level + 1, page_nums, seen),
+    });
+    next = item.get(b"Next").and_then(Object::as_reference).ok();
+  }
+  items
+}
+
+/// The page an outline item jumps to: its `/Dest`, else the destination of a
+/// `GoTo` action.
+fn outline_target(doc: &Document, item: &Dictionary) -> Option<ObjectId> {
+  let dest = item.get(b"Dest").ok().or_else(|| {
+    let action = doc.get_dict_in_dict(item, b"A").ok()?;
+    if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" {
+      return None;
+    }
+    action.get(b"D").ok()
+  })?;
+  destination_page(doc, dest)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lopdf::{Object, Stream, dictionary};
+
+  fn doc_with_pages(contents: &[&[u8]]) -> Document {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let font = |doc: &mut Document, base: &str| {
+      doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => base,
+      })
+    };
+    let regular = font(&mut doc, "Helvetica");
+    let bold = font(&mut doc, "Helvetica-Bold");
+    let kids: Vec<Object> = contents
+      .iter()
+      .map(|content| {
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.to_vec()));
+        doc
+          .add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 600.into(), 800.into()],
+            "Contents" => content_id,
+            "Resources" => dictionary! {
+              "Font" => dictionary! { "F1" => regular, "F2" => bold },
+            },
+          })
+          .into()
+      })
+      .collect();
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Count" => kids.len() as i64,
+        "Kids" => kids,
+      }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+    doc
+  }
+
+  fn titles(items: &[OutlineItem]) -> Vec<String> {
+    items
+      .iter()
+      .map(|item| {
+        let children = titles(&item.children);
+        if children.is_empty() {
+          item.title.clone()
+        } else {
+          format!("{} [{}]", item.title, children.join(", "))
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn headings_nest_by_font_size_and_weight() {
+    let doc = doc_with_pages(&[
+      b"BT /F1 24 Tf 50 750 Td (Getting) Tj 0 -26 Td (Started) Tj \
+        /F1 16 Tf 0 -40 Td (Install) Tj \
+        /F1 10 Tf 0 -20 Td (Run the installer and follow the prompts.) Tj \
+        0 -12 Td (It takes a minute or two to finish.) Tj \
+        /F2 10 Tf 0 -20 Td (Requirements) Tj \
+        /F1 10 Tf 0 -12 Td (Any recent operating system will do.) Tj ET",
+      b"BT /F1 24 Tf 50 750 Td (Reference) Tj \
+        /F1 10 Tf 0 -30 Td (Every option is listed here in order.) Tj ET",
+    ]);
+    let outline = outline_from_headings(&doc);
+    assert_eq!(
+      titles(&outline),
+      ["Getting Started [Install [Requirements]]", "Reference"]
+    );
+    assert_eq!(
+      (outline[0].page, outline[0].level, outline[1].page),
+      (Some(1), 1, Some(2))
+    );
+    assert_eq!(outline[0].children[0].children[0].level, 3);
+  }
+
+  #[test]
+  fn pages_take_the_nearest_preceding_heading_as_section_title() {
+    use crate::core::text::{StructuredTextConfig, extract_structured_text_with_config};
+
+    let doc = doc_with_pages(&[
+      b"BT /F1 10 Tf 50 780 Td (Preface text before any heading.) Tj         /F1 20 Tf 0 -30 Td (Introduction) Tj         /F1 10 Tf 0 -20 Td (Why this matters.) Tj ET",
+      b"BT /F1 10 Tf 50 750 Td (More of the introduction.) Tj ET",
+      b"BT /F1 20 Tf 50 750 Td (Methods) Tj         /F1 10 Tf 0 -20 Td (How it was done.) Tj ET",
+    ]);
+    assert_eq!(
+      last_heading_per_page(&doc).into_iter().collect::<Vec<_>>(),
+      [(1, "Introduction".to_string()), (3, "Methods".to_string())]
+    );
+
+    let config = StructuredTextConfig {
+      section_titles: true,
+      ..StructuredTextConfig::default()
+    };
+    let result = extract_structured_text_with_config(&doc, config).unwrap();
+    let sections: Vec<Option<&str>> = result
+      .pages
+      .iter()
+      .map(|p| p.section_title.as_deref())
+      .collect();
+    assert_eq!(
+      sections,
+      [Some("Introduction"), Some("Introduction"), Some("Methods")]
+    );
+    let plain = extract_structured_text_with_config(&doc, StructuredTextConfig::default()).unwrap();
+    assert!(plain.pages.iter().all(|p| p.section_title.is_none()));
+  }
+
+  #[test]
+  fn uniform_text_has_no_outline() {
+    let doc = doc_with_pages(&[b"BT /F1 10 Tf 50 750 Td (Just body text.) Tj ET"]);
+    assert!(outline_from_headings(&doc).is_empty());
+  }
+
+  #[test]
+  fn bookmarks_are_read_and_named_destinations_are_the_fallback() {
+    use lopdf::StringFormat;
+
+    let mut doc = doc_with_pages(&[b"", b"", b""]);
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    let fit = |page: ObjectId| Object::Array(vec![page.into(), "Fit".into()]);
+    let literal = |s: &str| Object::String(s.as_bytes().to_vec(), StringFormat::Literal);
+    let leaf = doc.add_object(dictionary! {
+      "Title" => literal("Install"),
+      "A" => dictionary! { "S" => "GoTo", "D" => literal("setup") },
+    });
+    let second = doc.add_object(dictionary! { "Title" => literal("Reference") });
+    let first = doc.add_object(dictionary! {
+      "Title" => literal("Getting Started"),
+      "Dest" => fit(pages[0]),
+      "First" => leaf,
+      "Next" => second,
+    });
+    let tree = doc.add_object(dictionary! {
+      "Kids" => vec![Object::Dictionary(dictionary! {
+        "Limits" => vec![literal("appendix"), literal("intro")],
+        "Names" => vec![literal("appendix"), fit(pages[2]), literal("intro"), fit(pages[0])],
+      })],
+    });
+    let catalog = doc.catalog_mut().unwrap();
+    catalog.set(
+      "Outlines",
+      dictionary! { "First" => first, "Last" => second },
+    );
+    catalog.set("Dests", dictionary! { "setup" => fit(pages[1]) });
+    catalog.set("Names", dictionary! { "Dests" => tree });
+
+    // Depth-first, so nesting shows up in `level`
+    fn entries(items: &[OutlineItem]) -> Vec<(String, Option<u32>, u32)> {
+      items
+        .iter()
+        .flat_map(|item| {
+          let mut flat = vec![(item.title.clone(), item.page, item.level)];
+          flat.extend(entries(&item.children));
+          flat
+        })
+        .collect()
+    }
+    let entry = |title: &str, page, level| (title.to_string(), page, level);
+    assert_eq!(
+      entries(&collect_outline(&doc)),
+      [
+        entry("Getting Started", Some(1), 1),
+        entry("Install", Some(2), 2),
+        entry("Reference", None, 1),
+      ]
+    );
+
+    doc.catalog_mut().unwrap().remove(b"Outlines");
+    assert_eq!(
+      entries(&collect_outline(&doc)),
+      [
+        entry("intro", Some(1), 1),
+        entry("setup", Some(2), 1),
+        entry("appendix", Some(3), 1),
+      ]
+    );
+  }
+
+  #[test]
+  fn cyclic_name_trees_are_walked_once() {
+    use lopdf::StringFormat;
+
+    let mut doc = doc_with_pages(&[b""]);
+    let page = *doc.get_pages().values().next().unwrap();
+    let node = doc.new_object_id();
+    // The kid lists itself among its own kids
+    doc.objects.insert(
+      node,
+      Object::Dictionary(dictionary! {
+        "Names" => vec![
+          Object::String(b"intro".to_vec(), StringFormat::Literal),
+          Object::Array(vec![page.into(), "Fit".into()]),
+        ],
+        "Kids" => vec![node.into()],
+      }),
+    );
+    let tree = doc.add_object(dictionary! { "Kids" => vec![node.into()] });
+    doc
+      .catalog_mut()
+      .unwrap()
+      .set("Names", dictionary! { "Dests" => tree });
+
+    let outline = collect_outline(&doc);
+    assert_eq!(titles(&outline), ["intro"]);
+  }
+}
@@ -0,0 +1,575 @@
+use crate::core::layout::{page_do_mcids, page_marked_text};
+use crate::core::meta::decode_pdf_string;
+use crate::core::objects::deref;
+use crate::types::TaggedBlock;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+/// Structure elements nested deeper than this are not followed.
+const MAX_STRUCT_DEPTH: u32 = 256;
+
+/// Standard inline-level structure types (ISO 32000-1 §14.8.4.4). Their text
+/// flows into the enclosing block instead of forming blocks of their own.
+const INLINE_ROLES: &[&str] = &[
+  "Span",
+  "Quote",
+  "Note",
+  "Reference",
+  "BibEntry",
+  "Code",
+  "Link",
+  "Annot",
+  "Ruby",
+  "RB",
+  "RT",
+  "RP",
+  "Warichu",
+  "WT",
+  "WP",
+  "Em",
+  "Strong",
+  "Sub",
+];
+
+/// Walks the structure tree, turning each block-level element into a
+/// `TaggedBlock`.
+struct Walker<'a> {
+  doc: &'a Document,
+  role_map: Option<&'a Dictionary>,
+  page_nums: HashMap<ObjectId, u32>,
+  /// MCID → text for every page.
+  marked: HashMap<ObjectId, HashMap<i64, String>>,
+  /// Elements already seen, so shared or cyclic references are read once.
+  visited: HashSet<ObjectId>,
+  blocks: Vec<TaggedBlock>,
+}
+
+/// A structure element's `/K` entry as a list of kids.
+fn kids<'a>(doc: &'a Document, elem: &'a Dictionary) -> Vec<&'a Object> {
+  let Ok(k) = elem.get(b"K") else {
+    return Vec::new();
+  };
+  match deref(doc, k) {
+    Some(Object::Array(items)) => items.iter().collect(),
+    _ => vec![k],
+  }
+}
+
+/// The `/Pg` page of a structure element or marked-content reference.
+fn own_page(dict: &Dictionary) -> Option<ObjectId> {
+  dict.get(b"Pg").and_then(Object::as_reference).ok()
+}
+
+/// A kid that refers to marked content: a bare MCID, or an `/MCR` dictionary
+/// (whose `/Pg` overrides the element's). Content inside form XObjects
+/// (`/Stm`) isn't resolved.
+fn marked_content_ref(kid: &Object, page: Option<ObjectId>) -> Option<(Option<ObjectId>, i64)> {
+  match kid {
+    Object::Integer(mcid) => Some((page, *mcid)),
+    Object::Dictionary(d) if d.has(b"MCID") => {
+      if d.has(b"Stm") {
+        return None;
+      }
+      let mcid = d.get(b"MCID").and_then(Object::as_i64).ok()?;
+      Some((own_page(d).or(page), mcid))
+    }
+    _ => None,
+  }
+}
+
+fn is_struct_elem(dict: &Dictionary) -> bool {
+  dict.has(b"S")
+}
+
+impl<'a> Walker<'a> {
+  /// Standard structure type for `elem`, following the `/RoleMap`.
+  fn role(&self, elem: &Dictionary) -> String {
+    let mut role = elem
+      .get(b"S")
+      .and_then(Object::as_name)
+      .map(|n| n.to_vec())
+      .unwrap_or_default();
+    for _ in 0..8 {
+      let mapped = self
+        .role_map
+        .and_then(|m| m.get(&role).ok())
+        .and_then(|o| o.as_name().ok());
+      match mapped {
+        Some(next) if next != role.as_slice() => role = next.to_vec(),
+        _ => break,
+      }
+    }
+    String::from_utf8_lossy(&role).to_string()
+  }
+
+  /// Resolve a kid to a structure element, skipping ones already visited.
+  fn element(&mut self, kid: &'a Object) -> Option<&'a Dictionary> {
+    if let Object::Reference(id) = kid
+      && !self.visited.insert(*id)
+    {
+      return None;
+    }
+    match deref(self.doc, kid)? {
+      Object::Dictionary(d) if is_struct_elem(d) => Some(d),
+      _ => None,
+    }
+  }
+
+  /// Whether `elem` forms one block: it has `/ActualText`, marked content of
+  /// its own, or only inline-level children.
+  fn is_block(&self, elem: &Dictionary, page: Option<ObjectId>) -> bool {
+    if elem.has(b"ActualText") {
+      return true;
+    }
+    let mut has_children = false;
+    for kid in kids(self.doc, elem) {
+      if marked_content_ref(kid, page).is_some() {
+        return true;
+      }
+      if let Some(Object::Dictionary(child)) = deref(self.doc, kid)
+        && is_struct_elem(child)
+      {
+        if !INLINE_ROLES.contains(&self.role(child).as_str()) {
+          return false;
+        }
+        has_children = true;
+      }
+    }
+    has_children
+  }
+
+  fn walk(&mut self, elem: &'a Dictionary, page: Option<ObjectId>, depth: u32) {
+    if depth > MAX_STRUCT_DEPTH {
+      return;
+    }
+    let page = own_page(elem).or(page);
+    if is_struct_elem(elem) && self.is_block(elem, page) {
+      let mut parts = Vec::new();
+      let mut first_page = None;
+      self.collect(elem, page, depth, &mut parts, &mut first_page);
+      let text = parts
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+      if !text.is_empty() {
+        self.blocks.push(TaggedBlock {
+          page: first_page
+            .or(page)
+            .and_then(|id| self.page_nums.get(&id).copied()),
+          role: self.role(elem),
+          text,
+        });
+      }
+      return;
+    }
+    for kid in kids(self.doc, elem) {
+      if let Some(child) = self.element(kid) {
+        self.walk(child, page, depth + 1);
+      }
+    }
+  }
+
+  /// Append the text of `elem`'s subtree, in structure order, to `parts`.
+  fn collect(
+    &mut self,
+    elem: &'a Dictionary,
+    page: Option<ObjectId>,
+    depth: u32,
+    parts: &mut Vec<String>,
+    first_page: &mut Option<ObjectId>,
+  ) {
+    if depth > MAX_STRUCT_DEPTH {
+      return;
+    }
+    if let Ok(Object::String(bytes, _)) = elem.get(b"ActualText") {
+      parts.push(decode_pdf_string(bytes));
+      if let Some(page) = page {
+        first_page.get_or_insert(page);
+      }
+      return;
+    }
+    for kid in kids(self.doc, elem) {
+      if let Some((Some(kid_page), mcid)) = marked_content_ref(kid, page) {
+        if let Some(text) = self.marked.get(&kid_page).and_then(|m| m.get(&mcid)) {
+          parts.push(text.clone());
+          first_page.get_or_insert(kid_page);
+        }
+      } else if let Some(child) = self.element(kid) {
+        let child_page = own_page(child).or(page);
+        self.collect(child, child_page, depth + 1, parts, first_page);
+      }
+    }
+  }
+}
+
+/// The document's structure tree root, if it is tagged.
+fn struct_tree_root(doc: &Document) -> Option<&Dictionary> {
+  doc
+    .catalog()
+    .ok()?
+    .get(b"StructTreeRoot")
+    .ok()
+    .and_then(|o| deref(doc, o))?
+    .as_dict()
+    .ok()
+}
+
+/// Objects referenced from the structure tree by `/OBJR` kids (annotations,
+/// XObjects), in structure order. Untagged documents return an empty list.
+pub(crate) fn structure_object_order(doc: &Document) -> Vec<ObjectId> {
+  let Some(root) = struct_tree_root(doc) else {
+    return Vec::new();
+  };
+  let mut order = Vec::new();
+  let mut visited = HashSet::new();
+  let mut stack: Vec<(&Object, u32)> = kids(doc, root).into_iter().rev().map(|k| (k, 0)).collect();
+  while let Some((kid, depth)) = stack.pop() {
+    if depth > MAX_STRUCT_DEPTH {
+      continue;
+    }
+    if let Object::Reference(id) = kid
+      && !visited.insert(*id)
+    {
+      continue;
+    }
+    let Some(Object::Dictionary(dict)) = deref(doc, kid) else {
+      continue;
+    };
+    if !is_struct_elem(dict) {
+      if let Ok(id) = dict.get(b"Obj").and_then(Object::as_reference) {
+        order.push(id);
+      }
+      continue;
+    }
+    stack.extend(kids(doc, dict).into_iter().rev().map(|k| (k, depth + 1)));
+  }
+  order
+}
+
+/// Look up `key` in a number tree (ISO 32000-1 §7.9.7), skipping subtrees
+/// whose `/Limits` exclude it.
+fn number_tree_lookup<'a>(
+  doc: &'a Document,
+  node: &'a Dictionary,
+  key: i64,
+  depth: u32,
+) -> Option<&'a Object> {
+  if depth > MAX_STRUCT_DEPTH {
+    return None;
+  }
+  if let Some(Object::Array(nums)) = node.get(b"Nums").ok().and_then(|o| deref(doc, o)) {
+    return nums
+      .chunks_exact(2)
+      .find(|pair| pair[0].as_i64().is_ok_and(|k| k == key))
+      .and_then(|pair| deref(doc, &pair[1]));
+  }
+  let kids = node.get(b"Kids").ok().and_then(|o| deref(doc, o))?;
+  kids.as_array().ok()?.iter().find_map(|kid| {
+    let kid = deref(doc, kid)?.as_dict().ok()?;
+    if let Ok(limits) = kid.get(b"Limits").and_then(Object::as_array)
+      && let (Some(Ok(lo)), Some(Ok(hi))) = (
+        limits.first().map(Object::as_i64),
+        limits.get(1).map(Object::as_i64),
+      )
+      && !(lo..=hi).contains(&key)
+    {
+      return None;
+    }
+    number_tree_lookup(doc, kid, key, depth + 1)
+  })
+}
+
+/// The `/Alt` text of a structure element, if it has a non-empty one.
+fn alt_text(doc: &Document, elem: &Object) -> Option<String> {
+  let Object::String(bytes, _) = deref(doc, elem)?.as_dict().ok()?.get(b"Alt").ok()? else {
+    return None;
+  };
+  Some(decode_pdf_string(bytes)).filter(|alt| !alt.trim().is_empty())
+}
+
+/// Alternate descriptions (`/Alt`) of the image XObjects painted on a page,
+/// keyed by XObject name. An image's structure element is found through the
+/// structure tree's `/ParentTree`: by the image's own `/StructParent` when it
+/// is a content item in its own right, otherwise by the page's
+/// `/StructParents` and the MCID of the marked-content sequence around its
+/// `Do`. Untagged documents return an empty map.
+pub(crate) fn page_image_alt_texts(
+  doc: &Document,
+  page_id: ObjectId,
+  images: &[(Vec<u8>, ObjectId, &Stream)],
+) -> HashMap<Vec<u8>, String> {
+  let mut alts = HashMap::new();
+  let Some(parent_tree) = struct_tree_root(doc)
+    .and_then(|root| root.get(b"ParentTree").ok())
+    .and_then(|o| deref(doc, o))
+    .and_then(|o| o.as_dict().ok())
+  else {
+    return alts;
+  };
+
+  let mut unresolved = Vec::new();
+  for (name, _, stream) in images {
+    let alt = stream
+      .dict
+      .get(b"StructParent")
+      .and_then(Object::as_i64)
+      .ok()
+      .and_then(|key| number_tree_lookup(doc, parent_tree, key, 0))
+      .and_then(|elem| alt_text(doc, elem));
+    match alt {
+      Some(alt) => {
+        alts.insert(name.clone(), alt);
+      }
+      None => unresolved.push(name),
+    }
+  }
+  if unresolved.is_empty() {
+    return alts;
+  }
+
+  let Some(Object::Array(by_mcid)) = doc
+    .get_dictionary(page_id)
+    .ok()
+    .and_then(|page| page.get(b"StructParents").ok())
+    .and_then(|o| o.as_i64().ok())
+    .and_then(|key| number_tree_lookup(doc, parent_tree, key, 0))
+  else {
+    return alts;
+  };
+  let mcids = page_do_mcids(doc, page_id);
+  for name in unresolved {
+    let alt = mcids
+      .get(name)
+      .and_then(|&mcid| by_mcid.get(usize::try_from(mcid).ok()?))
+      .and_then(|elem| alt_text(doc, elem));
+    if let Some(alt) = alt {
+      alts.insert(name.clone(), alt);
+    }
+  }
+  alts
+}
+
+/// Text of a tagged PDF in the logical reading order declared by its
+/// structure tree (`/StructTreeRoot`), one entry per block-level element.
+/// Marked-content references (`/MCID`) are resolved to the text painted
+/// inside the matching marked-content sequence of the page's content stream.
+/// Untagged documents return an empty list.
+pub(crate) fn collect_tagged_blocks(doc: &Document) -> Vec<TaggedBlock> {
+  let Some(root) = struct_tree_root(doc) else {
+    return Vec::new();
+  };
+
+  let pages = doc.get_pages();
+  let marked: HashMap<ObjectId, HashMap<i64, String>> = pages
+    .values()
+    .copied()
+    .collect::<Vec<_>>()
+    .par_iter()
+    .map(|&page_id| (page_id, page_marked_text(doc, page_id)))
+    .collect();
+
+  let mut walker = Walker {
+    doc,
+    role_map: root
+      .get(b"RoleMap")
+      .ok()
+      .and_then(|o| deref(doc, o))
+      .and_then(|o| o.as_dict().ok()),
+    page_nums: pages.iter().map(|(&num, &id)| (id, num)).collect(),
+    marked,
+    visited: HashSet::new(),
+    blocks: Vec::new(),
+  };
+  for kid in kids(doc, root) {
+    if let Some(elem) = walker.element(kid) {
+      walker.walk(elem, None, 0);
+    }
+  }
+  walker.blocks
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use lopdf::{Stream, dictionary};
+
+  #[test]
+  fn blocks_follow_structure_order_not_content_order() {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type1",
+      "BaseFont" => "Helvetica",
+    });
+    // The body is painted before the title, and a footer is an artifact
+    let content = b"/P <</MCID 0>> BDC BT /F1 10 Tf 10 50 Td (Body) Tj ET EMC \
+      /Span <</MCID 2>> BDC BT /F1 10 Tf 40 50 Td (text) Tj ET EMC \
+      /H1 <</MCID 1>> BDC BT /F1 20 Tf 10 80 Td (Title) Tj ET EMC \
+      /Artifact BMC BT /F1 8 Tf 10 5 Td (Page 1) Tj ET EMC";
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.to_vec()));
+    let page_id = doc.add_object(dictionary! {
+      "Type" => "Page",
+      "Parent" => pages_id,
+      "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+      "Contents" => content_id,
+      "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+    });
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => vec![page_id.into()],
+        "Count" => 1,
+      }),
+    );
+
+    let heading = doc.add_object(dictionary! { "S" => "Heading", "Pg" => page_id, "K" => 1 });
+    let span = doc.add_object(dictionary! { "S" => "Span", "Pg" => page_id, "K" => 2 });
+    let para = doc.add_object(dictionary! {
+      "S" => "P",
+      "Pg" => page_id,
+      "K" => vec![0.into(), span.into()],
+    });
+    let document = doc.add_object(dictionary! {
+      "S" => "Document",
+      "K" => vec![heading.into(), para.into()],
+    });
+    let root = doc.add_object(dictionary! {
+      "Type" => "StructTreeRoot",
+      "K" => document,
+      "RoleMap" => dictionary! { "Heading" => "H1" },
+    });
+    let catalog_id = doc.add_object(dictionary! {
+      "Type" => "Catalog",
+      "Pages" => pages_id,
+      "StructTreeRoot" => root,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let blocks: Vec<(Option<u32>, String, String)> = collect_tagged_blocks(&doc)
+      .into_iter()
+      .map(|b| (b.page, b.role, b.text))
+      .collect();
+    assert_eq!(
+      blocks,
+      [
+        (Some(1), "H1".to_string(), "Title".to_string()),
+        (Some(1), "P".to_string(), "Body text".to_string()),
+      ]
+    );
+
+    // Untagged documents yield nothing
+    doc
+      .get_dictionary_mut(catalog_id)
+      .unwrap()
+      .remove(b"StructTreeRoot");
+    assert!(collect_tagged_blocks(&doc).is_empty());
+  }
+
+  #[test]
+  fn image_alt_text_resolves_through_the_parent_tree() {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let image = |struct_parent: Option<i64>| {
+      let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => 1,
+        "Height" => 1,
+      };
+      if let Some(key) = struct_parent {
+        dict.set("StructParent", key);
+      }
+      Stream::new(dict, vec![0])
+    };
+    // Im1 is its own content item, Im2 sits in a marked-content sequence
+    // referenced through a named property list, Im3 is untagged
+    let im1 = doc.add_object(image(Some(7)));
+    let im2 = doc.add_object(image(None));
+    let im3 = doc.add_object(image(None));
+    let content = b"q /Im1 Do Q /Figure /Fig0 BDC q /Im2 Do Q EMC q /Im3 Do Q";
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.to_vec()));
+    let page_id = doc.add_object(dictionary! {
+      "Type" => "Page",
+      "Parent" => pages_id,
+      "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+      "Contents" => content_id,
+      "StructParents" => 0,
+      "Resources" => dictionary! {
+        "XObject" => dictionary! { "Im1" => im1, "Im2" => im2, "Im3" => im3 },
+        "Properties" => dictionary! { "Fig0" => dictionary! { "MCID" => 0 } },
+      },
+    });
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => vec![page_id.into()],
+        "Count" => 1,
+      }),
+    );
+
+    let alt = |s: &str| Object::string_literal(s);
+    let figure1 = doc.add_object(dictionary! {
+      "S" => "Figure",
+      "Pg" => page_id,
+      "Alt" => alt("A lighthouse at dusk"),
+      "K" => dictionary! { "Type" => "OBJR", "Obj" => im1 },
+    });
+    let figure2 = doc.add_object(dictionary! {
+      "S" => "Figure",
+      "Pg" => page_id,
+      "Alt" => alt("Quarterly revenue chart"),
+      "K" => 0,
+    });
+    // A two-leaf number tree, so the lookup has to honour /Limits
+    let leaf_low = doc.add_object(dictionary! {
+      "Limits" => vec![0.into(), 0.into()],
+      "Nums" => vec![0.into(), vec![figure2.into()].into()],
+    });
+    let leaf_high = doc.add_object(dictionary! {
+      "Limits" => vec![5.into(), 9.into()],
+      "Nums" => vec![7.into(), figure1.into()],
+    });
+    let parent_tree = doc.add_object(dictionary! {
+      "Kids" => vec![leaf_low.into(), leaf_high.into()],
+    });
+    let root = doc.add_object(dictionary! {
+      "Type" => "StructTreeRoot",
+      "K" => vec![figure1.into(), figure2.into()],
+      "ParentTree" => parent_tree,
+    });
+    let catalog_id = doc.add_object(dictionary! {
+      "Type" => "Catalog",
+      "Pages" => pages_id,
+      "StructTreeRoot" => root,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    let images: Vec<_> = [(b"Im1", im1), (b"Im2", im2), (b"Im3", im3)]
+      .into_iter()
+      .map(|(name, id)| {
+        (
+          name.to_vec(),
+          id,
+          doc.get_object(id).unwrap().as_stream().unwrap(),
+        )
+      })
+      .collect();
+    let alts = page_image_alt_texts(&doc, page_id, &images);
+    assert_eq!(alts.len(), 2);
+    assert_eq!(alts[&b"Im1".to_vec()], "A lighthouse at dusk");
+    assert_eq!(alts[&b"Im2".to_vec()], "Quarterly revenue chart");
+
+    // Without a structure tree no alt text is reported
+    let mut untagged = doc.clone();
+    untagged
+      .get_dictionary_mut(catalog_id)
+      .unwrap()
+      .remove(b"StructTreeRoot");
+    assert!(page_image_alt_texts(&untagged, page_id, &images).is_empty());
+  }
+}
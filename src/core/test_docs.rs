@@ -0,0 +1,50 @@
+use lopdf::{Dictionary, Document, Object, ObjectId, dictionary};
+
+/// A 1.7 document with a catalog and a single-level page tree. `build` adds
+/// the objects the pages need and returns one dictionary per page; each
+/// becomes a kid of the `/Pages` root, in order, with `/Type /Page` and
+/// `/Parent` filled in. Returns the document with its page ids.
+pub(crate) fn doc_with_pages(
+  build: impl FnOnce(&mut Document) -> Vec<Dictionary>,
+) -> (Document, Vec<ObjectId>) {
+  let mut doc = Document::with_version("1.7");
+  let pages_id = doc.new_object_id();
+  let page_ids: Vec<ObjectId> = build(&mut doc)
+    .into_iter()
+    .map(|mut page| {
+      page.set("Type", "Page");
+      page.set("Parent", pages_id);
+      doc.add_object(page)
+    })
+    .collect();
+  doc.objects.insert(
+    pages_id,
+    Object::Dictionary(dictionary! {
+      "Type" => "Pages",
+      "Count" => page_ids.len() as i64,
+      "Kids" => page_ids.iter().map(|&id| id.into()).collect::<Vec<Object>>(),
+    }),
+  );
+  let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+  doc.trailer.set("Root", catalog_id);
+  (doc, page_ids)
+}
+
+/// The `/Pages` root of a document from [`doc_with_pages`], e.g. to set
+/// entries its pages inherit.
+pub(crate) fn page_tree_root(doc: &Document) -> ObjectId {
+  doc
+    .catalog()
+    .and_then(|catalog| catalog.get(b"Pages"))
+    .and_then(Object::as_reference)
+    .unwrap()
+}
+
+/// A Type1 Helvetica font without `/Widths`.
+pub(crate) fn helvetica(doc: &mut Document) -> ObjectId {
+  doc.add_object(dictionary! {
+    "Type" => "Font",
+    "Subtype" => "Type1",
+    "BaseFont" => "Helvetica",
+  })
+}
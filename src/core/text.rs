@@ -1,30 +1,435 @@
-use crate::types::{PageText, StructuredPageText};
-use lopdf::Document;
-use napi::Result;
+use crate::core::cmap::FontDecoder;
+use crate::core::document::format_object_id;
+use crate::core::forms::collect_widget_values;
+use crate::core::layout::{
+  Glyph, extract_page_glyphs, page_lines_with_overlays, page_source_text, page_text_coverage,
+};
+use crate::core::meta::info_entry;
+use crate::core::objects::deref_dict;
+use crate::core::outline::last_heading_per_page;
+use crate::types::{
+  PageDiff, PageText, PageWordFreq, ScriptCount, SourceTextRun, StructuredPageText,
+  StructuredTextResult, TermCount, TextChunk,
+};
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use napi::{Error, Result};
 use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Per-call text post-processing, resolved from `TextOptions`.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct TextConfig {
+  pub strip_soft_hyphens: bool,
+  pub exact_spacing: bool,
+  pub form_values: bool,
+  pub strict: bool,
+  pub profile: Option<CleanupProfile>,
+}
+
+/// Producer-specific cleanups, resolved from `TextOptions.producerProfile`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum CleanupProfile {
+  /// Pick one of the others from the Info `/Producer` or `/Creator`.
+  Auto,
+  Latex,
+  Chromium,
+  Word,
+}
+
+/// The profile matching a `/Producer` or `/Creator` value, e.g.
+/// `"pdfTeX-1.40.25"`, `"Skia/PDF m120"` or `"Microsoft® Word for Microsoft 365"`.
+fn cleanup_profile_for(tool: &str) -> Option<CleanupProfile> {
+  let tool = tool.to_lowercase();
+  let has = |needles: &[&str]| needles.iter().any(|n| tool.contains(n));
+  if has(&["pdftex", "xetex", "luatex", "latex", "dvipdfm", "dvips"]) {
+    Some(CleanupProfile::Latex)
+  } else if has(&["skia/pdf", "chromium", "chrome"]) {
+    Some(CleanupProfile::Chromium)
+  } else if has(&["microsoft"]) && has(&["word"]) {
+    Some(CleanupProfile::Word)
+  } else {
+    None
+  }
+}
+
+/// Resolve `Auto` from the document's Info dictionary; `None` when neither
+/// the producer nor the creator is recognized.
+fn resolve_cleanup_profile(
+  doc: &Document,
+  profile: Option<CleanupProfile>,
+) -> Option<CleanupProfile> {
+  match profile? {
+    CleanupProfile::Auto => [b"Producer".as_slice(), b"Creator"]
+      .iter()
+      .filter_map(|key| info_entry(doc, key))
+      .find_map(|tool| cleanup_profile_for(&tool)),
+    profile => Some(profile),
+  }
+}
+
+/// Per-call structured-text settings, resolved from `StructuredTextOptions`.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct StructuredTextConfig {
+  pub dedupe_headers: bool,
+  pub matching: HeaderFooterMatching,
+  pub section_titles: bool,
+}
+
+/// How loosely lines are compared when looking for repeated headers and
+/// footers. Digit runs always match each other.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct HeaderFooterMatching {
+  /// Month and weekday names match each other, so "March 2024" repeats as
+  /// "April 2024".
+  pub dates: bool,
+  pub ignore_case: bool,
+}
+
+/// Month names, their abbreviations, and weekday names, lowercase.
+const DATE_WORDS: &[&str] = &[
+  "january",
+  "february",
+  "march",
+  "april",
+  "may",
+  "june",
+  "july",
+  "august",
+  "september",
+  "october",
+  "november",
+  "december",
+  "jan",
+  "feb",
+  "mar",
+  "apr",
+  "jun",
+  "jul",
+  "aug",
+  "sep",
+  "sept",
+  "oct",
+  "nov",
+  "dec",
+  "monday",
+  "tuesday",
+  "wednesday",
+  "thursday",
+  "friday",
+  "saturday",
+  "sunday",
+  "mon",
+  "tue",
+  "tues",
+  "wed",
+  "thu",
+  "thurs",
+  "fri",
+  "sat",
+  "sun",
+];
 
 pub(crate) fn extract_text(doc: &Document) -> Result<Vec<PageText>> {
+  extract_text_with_config(doc, TextConfig::default())
+}
+
+/// Page numbers come from `Document::get_pages`, which counts pages in
+/// `/Kids` traversal order of the page tree, independent of object numbers.
+pub(crate) fn extract_text_with_config(
+  doc: &Document,
+  config: TextConfig,
+) -> Result<Vec<PageText>> {
   let pages = doc.get_pages();
   let page_count_str = pages.len().to_string();
-  let page_nums: Vec<u32> = pages.keys().copied().collect();
-  let mut results: Vec<PageText> = page_nums
+  let page_entries: Vec<(u32, ObjectId)> = pages.iter().map(|(&k, &v)| (k, v)).collect();
+  let widget_values = if config.form_values {
+    collect_widget_values(doc)
+  } else {
+    HashMap::new()
+  };
+  let profile = resolve_cleanup_profile(doc, config.profile);
+  // Indexed parallel collect keeps page order, so the first error reported
+  // in strict mode is the first failing page
+  let results: Vec<Result<PageText>> = page_entries
     .par_iter()
-    .map(|&page_num| {
-      let raw = doc.extract_text(&[page_num]).unwrap_or_default();
-      let text = strip_footer_artifacts(&raw, &page_count_str);
-      PageText {
+    .map(|&(page_num, page_id)| {
+      // Pages without filled-in widgets keep the regular extraction path
+      let overlays = widget_values.get(&page_num).filter(|v| !v.is_empty());
+      let raw = if let Some(overlays) = overlays {
+        page_lines_with_overlays(doc, page_id, overlays)
+          .into_iter()
+          .map(|line| line.text)
+          .collect::<Vec<_>>()
+          .join("\n")
+      } else if config.exact_spacing {
+        extract_page_text_spaced(doc, page_num, page_id)
+      } else if config.strict {
+        try_extract_page_text_raw(doc, page_num, page_id).map_err(|e| {
+          Error::from_reason(format!("Failed to extract text from page {page_num}: {e}"))
+        })?
+      } else {
+        extract_page_text_raw(doc, page_num, page_id)
+      };
+      let stripped = strip_footer_artifacts(&raw, &page_count_str);
+      let footer_stripped = matches!(stripped, Cow::Owned(_));
+      let mut text = stripped.into_owned();
+      if config.strip_soft_hyphens {
+        text = strip_soft_hyphens(&text);
+      }
+      match profile {
+        Some(CleanupProfile::Latex) => text = expand_ligatures(&text),
+        Some(CleanupProfile::Word) => text = straighten_quotes(&text),
+        _ => {}
+      }
+      Ok(PageText {
         page: page_num,
         text,
-      }
+        footer_stripped,
+      })
     })
     .collect();
-  results.sort_unstable_by_key(|p| p.page);
-  Ok(results)
+  results.into_iter().collect()
+}
+
+/// Each page's text exactly as lopdf's `extract_text` returns it: no CID
+/// fallback, footer stripping or other cleanup. Pages lopdf fails on are
+/// empty.
+pub(crate) fn extract_text_unprocessed(doc: &Document) -> Vec<PageText> {
+  let pages: Vec<u32> = doc.get_pages().into_keys().collect();
+  // Indexed parallel collect keeps page order without sorting
+  pages
+    .par_iter()
+    .map(|&page| PageText {
+      page,
+      text: doc.extract_text(&[page]).unwrap_or_default(),
+      footer_stripped: false,
+    })
+    .collect()
+}
+
+/// Native text for one page, before footer cleanup.
+///
+/// Uses lopdf's extractor first. When that fails or yields mostly replacement
+/// and control characters — typically composite (CID) fonts whose encoding
+/// lopdf can't resolve — the page is re-walked with [`extract_page_text_with_cmaps`]
+/// and whichever result carries more readable characters wins.
+pub(crate) fn extract_page_text_raw(doc: &Document, page_num: u32, page_id: ObjectId) -> String {
+  try_extract_page_text_raw(doc, page_num, page_id).unwrap_or_default()
+}
+
+/// [`extract_page_text_raw`], but returning lopdf's error when it fails on
+/// the page and the CMap re-walk recovers no readable text either.
+fn try_extract_page_text_raw(
+  doc: &Document,
+  page_num: u32,
+  page_id: ObjectId,
+) -> lopdf::Result<String> {
+  let native = doc.extract_text(&[page_num]);
+  let native_text = native.as_deref().unwrap_or_default();
+  if !looks_unmapped(native_text) {
+    return native;
+  }
+  match extract_page_text_with_cmaps(doc, page_id) {
+    Some(fallback) if readable_chars(&fallback) > readable_chars(native_text) => Ok(fallback),
+    _ => native,
+  }
+}
+
+/// A gap between two glyphs wider than this fraction of the font size (after
+/// horizontal scaling) is copied as a space, as Acrobat's clipboard does.
+const COPY_SPACE_GAP: f64 = 0.15;
+
+/// Glyphs whose baselines differ by more than this fraction of the font size
+/// are on different lines.
+const COPY_LINE_SHIFT: f64 = 0.5;
+
+/// Page text as a viewer's copy-paste would produce it: glyphs in
+/// content-stream order, with spaces only where the painted gap between two
+/// glyphs is wide enough to read as one. Gaps come from the glyph positions,
+/// so character and word spacing (`Tc`, `Tw`), horizontal scaling (`Tz`) and
+/// `TJ` offsets are all accounted for. Falls back to
+/// [`extract_page_text_raw`] when no glyph on the page can be decoded.
+pub(crate) fn extract_page_text_spaced(doc: &Document, page_num: u32, page_id: ObjectId) -> String {
+  let text = join_glyphs(&extract_page_glyphs(doc, page_id));
+  if readable_chars(&text) == 0 {
+    return extract_page_text_raw(doc, page_num, page_id);
+  }
+  text
+}
+
+fn join_glyphs(glyphs: &[Glyph]) -> String {
+  let mut text = String::new();
+  let mut previous: Option<&Glyph> = None;
+  for glyph in glyphs {
+    if let Some(prev) = previous {
+      let size = prev.size.max(glyph.size);
+      let (gap, shift) = if glyph.vertical {
+        let centre = |b: &[f64; 4]| (b[0] + b[2]) / 2.0;
+        (
+          prev.bbox[1] - glyph.bbox[3],
+          centre(&glyph.bbox) - centre(&prev.bbox),
+        )
+      } else {
+        (glyph.bbox[0] - prev.bbox[2], glyph.baseline - prev.baseline)
+      };
+      let at_break =
+        text.ends_with(char::is_whitespace) || glyph.text.starts_with(char::is_whitespace);
+      if glyph.vertical != prev.vertical || shift.abs() > size * COPY_LINE_SHIFT {
+        if !text.ends_with('\n') {
+          text.push('\n');
+        }
+      } else if !at_break && (gap > size * glyph.h_scale * COPY_SPACE_GAP || gap < -size) {
+        // A wide gap, or a jump back along the line to start another run
+        text.push(' ');
+      }
+    }
+    text.push_str(&glyph.text);
+    previous = Some(glyph);
+  }
+  if !text.is_empty() && !text.ends_with('\n') {
+    text.push('\n');
+  }
+  text
+}
+
+fn readable_chars(text: &str) -> usize {
+  text
+    .chars()
+    .filter(|&c| !c.is_whitespace() && c != '\u{FFFD}' && !c.is_control())
+    .count()
+}
+
+/// True when the text is empty or at least 30% of its non-whitespace
+/// characters are U+FFFD or control characters.
+fn looks_unmapped(text: &str) -> bool {
+  let total = text.chars().filter(|c| !c.is_whitespace()).count();
+  if total == 0 {
+    return true;
+  }
+  let bad = total - readable_chars(text);
+  bad * 10 >= total * 3
+}
+
+fn push_text_operands(out: &mut String, decoder: &FontDecoder, operands: &[Object]) {
+  for operand in operands {
+    match operand {
+      Object::String(bytes, _) => out.push_str(&decoder.decode(bytes)),
+      Object::Array(arr) => {
+        push_text_operands(out, decoder, arr);
+        out.push(' ');
+      }
+      // Same word-gap heuristic lopdf applies to TJ kerning offsets
+      Object::Integer(i) if *i < -100 => out.push(' '),
+      Object::Real(r) if *r < -100.0 => out.push(' '),
+      _ => {}
+    }
+  }
+}
+
+/// Re-extract a page's text, decoding CID fonts through their ToUnicode CMap or
+/// predefined CMap and everything else through lopdf's font encodings.
+/// Returns `None` when no font on the page can be decoded at all.
+fn extract_page_text_with_cmaps(doc: &Document, page_id: ObjectId) -> Option<String> {
+  let fonts = doc.get_page_fonts(page_id).ok()?;
+  content_text_with_cmaps(doc, fonts, &doc.get_page_content(page_id).ok()?)
+}
+
+/// Text shown by a form XObject such as an annotation's appearance stream,
+/// decoded through the fonts of its own `/Resources` like
+/// [`extract_page_text_with_cmaps`]. Nested XObjects aren't followed.
+/// Returns `None` when it shows no text.
+pub(crate) fn form_xobject_text(doc: &Document, form: &lopdf::Stream) -> Option<String> {
+  let resources = deref_dict(doc, form.dict.get(b"Resources").ok()?)?;
+  let fonts = deref_dict(doc, resources.get(b"Font").ok()?)?
+    .iter()
+    .filter_map(|(name, font)| Some((name.clone(), deref_dict(doc, font)?)))
+    .collect();
+  let content = form
+    .decompressed_content()
+    .unwrap_or_else(|_| form.content.clone());
+  let text = content_text_with_cmaps(doc, fonts, &content)?;
+  let text = text.trim();
+  (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Text shown by `content`, with `fonts` mapping the resource names used by
+/// `Tf` to font dictionaries.
+fn content_text_with_cmaps(
+  doc: &Document,
+  fonts: BTreeMap<Vec<u8>, &Dictionary>,
+  content: &[u8],
+) -> Option<String> {
+  let decoders: BTreeMap<Vec<u8>, FontDecoder> = fonts
+    .into_iter()
+    .filter_map(|(name, font)| Some((name, FontDecoder::for_font(doc, font)?)))
+    .collect();
+  if decoders.is_empty() {
+    return None;
+  }
+
+  let content = Content::decode(content).ok()?;
+  let mut current: Option<&FontDecoder> = None;
+  let mut text = String::new();
+  for op in &content.operations {
+    match op.operator.as_str() {
+      "Tf" => {
+        current = op
+          .operands
+          .first()
+          .and_then(|o| o.as_name().ok())
+          .and_then(|name| decoders.get(name));
+      }
+      "Tj" | "TJ" | "'" | "\"" => {
+        if matches!(op.operator.as_str(), "'" | "\"") && !text.ends_with('\n') {
+          text.push('\n');
+        }
+        if let Some(decoder) = current {
+          // `"` carries word and char spacing before the string
+          let operands = if op.operator == "\"" {
+            op.operands.get(2..).unwrap_or_default()
+          } else {
+            &op.operands
+          };
+          push_text_operands(&mut text, decoder, operands);
+        }
+      }
+      "ET" if !text.ends_with('\n') => text.push('\n'),
+      _ => {}
+    }
+  }
+  Some(text)
+}
+
+/// Text runs of every page tagged with where they come from: the content
+/// stream (or form XObject) object and the index of the text-showing operator
+/// within its decoded operations. Each content stream of a page is indexed on
+/// its own, so an index is only meaningful together with its `object_id`.
+pub(crate) fn collect_source_text_runs(doc: &Document) -> Vec<SourceTextRun> {
+  let pages: Vec<(u32, ObjectId)> = doc.get_pages().into_iter().collect();
+  pages
+    .par_iter()
+    .flat_map(|&(page_num, page_id)| {
+      page_source_text(doc, page_id)
+        .into_iter()
+        .map(|(stream_id, index, text)| SourceTextRun {
+          page: page_num,
+          object_id: format_object_id(stream_id),
+          operator_index: index,
+          text,
+        })
+        .collect::<Vec<_>>()
+    })
+    .collect()
 }
 
 /// Normalize a line for header/footer comparison: trim whitespace and replace
 /// contiguous digit sequences with `<NUM>` so "Page 1" matches "Page 42".
-fn normalize_header_footer_line(line: &str) -> String {
+/// `matching` can also fold case and replace month/weekday names with `<DATE>`.
+fn normalize_header_footer_line(line: &str, matching: HeaderFooterMatching) -> String {
   let trimmed = line.trim();
   let mut out = String::with_capacity(trimmed.len());
   let mut in_digits = false;
@@ -39,9 +444,108 @@ fn normalize_header_footer_line(line: &str) -> String {
       out.push(ch);
     }
   }
+  if matching.ignore_case {
+    out = out.to_lowercase();
+  }
+  if matching.dates {
+    out = replace_date_words(&out);
+  }
+  out
+}
+
+/// Replace whole alphabetic words naming a month or weekday with `<DATE>`.
+fn replace_date_words(line: &str) -> String {
+  let mut out = String::with_capacity(line.len());
+  let mut rest = line;
+  while let Some(start) = rest.find(char::is_alphabetic) {
+    out.push_str(&rest[..start]);
+    rest = &rest[start..];
+    let end = rest
+      .find(|c: char| !c.is_alphabetic())
+      .unwrap_or(rest.len());
+    let word = &rest[..end];
+    if DATE_WORDS.contains(&word.to_lowercase().as_str()) {
+      out.push_str("<DATE>");
+    } else {
+      out.push_str(word);
+    }
+    rest = &rest[end..];
+  }
+  out.push_str(rest);
   out
 }
 
+/// Remove soft hyphens (U+00AD). A soft hyphen at the end of a line also
+/// joins the two fragments, dropping the line break and the next line's
+/// indentation: `"exam\u{AD}\n  ple"` → `"example"`.
+pub(crate) fn strip_soft_hyphens(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    if c != '\u{AD}' {
+      out.push(c);
+      continue;
+    }
+    let mut lookahead = chars.clone();
+    while lookahead.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+    let cr = lookahead.next_if_eq(&'\r').is_some();
+    let lf = lookahead.next_if_eq(&'\n').is_some();
+    if cr || lf {
+      while lookahead.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+      chars = lookahead;
+    }
+  }
+  out
+}
+
+/// Spell out ligatures: the Unicode presentation forms (U+FB00–U+FB06), and
+/// the control characters that TeX's OT1 font encoding puts ff, fi, fl, ffi
+/// and ffl at (0x0B–0x0F) when a font has no `/ToUnicode` map. Those only
+/// count inside a word, so a line's `\r` is left alone.
+pub(crate) fn expand_ligatures(text: &str) -> String {
+  let chars: Vec<char> = text.chars().collect();
+  let mut out = String::with_capacity(text.len());
+  for (i, &c) in chars.iter().enumerate() {
+    let expansion = match c {
+      '\u{FB00}' => "ff",
+      '\u{FB01}' => "fi",
+      '\u{FB02}' => "fl",
+      '\u{FB03}' => "ffi",
+      '\u{FB04}' => "ffl",
+      '\u{FB05}' | '\u{FB06}' => "st",
+      // 0x0D is also a carriage return, so it is never read as "fl"
+      '\u{0B}'..='\u{0F}' if c != '\r' => {
+        let next_alpha = chars.get(i + 1).is_some_and(|c| c.is_alphabetic());
+        let prev_alpha = i > 0 && chars[i - 1].is_alphabetic();
+        if next_alpha || prev_alpha {
+          ["ff", "fi", "fl", "ffi", "ffl"][c as usize - 0x0B]
+        } else {
+          out.push(c);
+          continue;
+        }
+      }
+      _ => {
+        out.push(c);
+        continue;
+      }
+    };
+    out.push_str(expansion);
+  }
+  out
+}
+
+/// Replace curly quotes, as Word's AutoFormat types them, with straight ones.
+pub(crate) fn straighten_quotes(text: &str) -> String {
+  text
+    .chars()
+    .map(|c| match c {
+      '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => '\'',
+      '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => '"',
+      c => c,
+    })
+    .collect()
+}
+
 /// Strip Chromium footer artifacts from extracted text.
 ///
 /// Chromium's Skia PDF renderer writes page footers (e.g., `1 / 38`) as 2-3
@@ -49,10 +553,11 @@ fn normalize_header_footer_line(line: &str) -> String {
 /// content-stream order, causing orphaned fragments like ` / \n38\n` to appear
 /// mid-text on every page. This function removes the known pattern: a line
 /// containing just `/` followed by a line containing just the total page count.
-pub(crate) fn strip_footer_artifacts(text: &str, page_count_str: &str) -> String {
+/// Borrowed when nothing was removed.
+pub(crate) fn strip_footer_artifacts<'a>(text: &'a str, page_count_str: &str) -> Cow<'a, str> {
   let lines: Vec<&str> = text.lines().collect();
   if lines.len() < 2 {
-    return text.to_string();
+    return Cow::Borrowed(text);
   }
   let mut skip = vec![false; lines.len()];
   for i in 0..lines.len() - 1 {
@@ -62,20 +567,29 @@ pub(crate) fn strip_footer_artifacts(text: &str, page_count_str: &str) -> String
     }
   }
   if !skip.iter().any(|&s| s) {
-    return text.to_string();
+    return Cow::Borrowed(text);
   }
-  lines
-    .iter()
-    .zip(skip.iter())
-    .filter(|&(_, &s)| !s)
-    .map(|(&line, _)| line)
-    .collect::<Vec<_>>()
-    .join("\n")
+  Cow::Owned(
+    lines
+      .iter()
+      .zip(skip.iter())
+      .filter(|&(_, &s)| !s)
+      .map(|(&line, _)| line)
+      .collect::<Vec<_>>()
+      .join("\n"),
+  )
 }
 
 /// Detect repeated header/footer lines across pages and split each page's text
 /// into header, body, and footer sections.
 pub(crate) fn detect_headers_footers(pages: &[PageText]) -> Vec<StructuredPageText> {
+  detect_headers_footers_with(pages, HeaderFooterMatching::default())
+}
+
+pub(crate) fn detect_headers_footers_with(
+  pages: &[PageText],
+  matching: HeaderFooterMatching,
+) -> Vec<StructuredPageText> {
   // For fewer than 3 pages, no meaningful detection — return everything as body
   if pages.len() < 3 {
     return pages
@@ -85,6 +599,8 @@ pub(crate) fn detect_headers_footers(pages: &[PageText]) -> Vec<StructuredPageTe
         header: String::new(),
         body: p.text.clone(),
         footer: String::new(),
+        text_coverage: None,
+        section_title: None,
       })
       .collect();
   }
@@ -102,7 +618,7 @@ pub(crate) fn detect_headers_footers(pages: &[PageText]) -> Vec<StructuredPageTe
     let mut freq = std::collections::HashMap::<String, usize>::new();
     for lines in &page_lines {
       if let Some(&line) = lines.get(pos) {
-        let norm = normalize_header_footer_line(line);
+        let norm = normalize_header_footer_line(line, matching);
         if !norm.is_empty() {
           *freq.entry(norm).or_insert(0) += 1;
         }
@@ -124,7 +640,7 @@ pub(crate) fn detect_headers_footers(pages: &[PageText]) -> Vec<StructuredPageTe
         let idx = lines.len() - 1 - pos;
         // Don't overlap with headers
         if idx >= header_count {
-          let norm = normalize_header_footer_line(lines[idx]);
+          let norm = normalize_header_footer_line(lines[idx], matching);
           if !norm.is_empty() {
             *freq.entry(norm).or_insert(0) += 1;
           }
@@ -159,25 +675,464 @@ pub(crate) fn detect_headers_footers(pages: &[PageText]) -> Vec<StructuredPageTe
         header,
         body,
         footer,
+        text_coverage: None,
+        section_title: None,
       }
     })
     .collect()
 }
 
+pub(crate) const DEFAULT_PAGE_MARKER: &str = "\n\n--- Page {page} ---\n\n";
+
+/// Concatenate page texts into one string, inserting `template` between
+/// consecutive pages. `{page}` in the template is replaced with the 1-based
+/// number of the page that follows the marker.
+pub(crate) fn join_pages_with_markers(pages: &[PageText], template: &str) -> String {
+  let mut out = String::new();
+  for (i, p) in pages.iter().enumerate() {
+    if i > 0 {
+      out.push_str(&template.replace("{page}", &p.page.to_string()));
+    }
+    out.push_str(&p.text);
+  }
+  out
+}
+
+pub(crate) fn extract_full_text_with_markers(doc: &Document, template: &str) -> Result<String> {
+  let pages = extract_text(doc)?;
+  Ok(join_pages_with_markers(&pages, template))
+}
+
+/// The opening text of the document. Pages are extracted one at a time, in
+/// order, until `max_chars` non-whitespace characters have been collected, so
+/// the rest of the document is never decoded. Pages are separated by a blank
+/// line and the text ends at the `max_chars`-th non-whitespace character.
+pub(crate) fn extract_text_preview(doc: &Document, max_chars: u32) -> String {
+  let pages = doc.get_pages();
+  let page_count_str = pages.len().to_string();
+  let mut preview = String::new();
+  let mut remaining = max_chars as usize;
+  for (&page_num, &page_id) in &pages {
+    if remaining == 0 {
+      break;
+    }
+    let raw = extract_page_text_raw(doc, page_num, page_id);
+    let stripped = strip_footer_artifacts(&raw, &page_count_str);
+    let text = stripped.trim();
+    if text.is_empty() {
+      continue;
+    }
+    if !preview.is_empty() {
+      preview.push_str("\n\n");
+    }
+    for c in text.chars() {
+      preview.push(c);
+      if !c.is_whitespace() {
+        remaining -= 1;
+        if remaining == 0 {
+          break;
+        }
+      }
+    }
+  }
+  preview
+}
+
+/// Fill in `text_coverage` for each page from its positional text.
+pub(crate) fn attach_text_coverage(doc: &Document, pages: &mut [StructuredPageText]) {
+  let page_ids = doc.get_pages();
+  pages.par_iter_mut().for_each(|p| {
+    p.text_coverage = page_ids
+      .get(&p.page)
+      .and_then(|&id| page_text_coverage(doc, id));
+  });
+}
+
+/// Mark which pages' text differs between two page lists by comparing a hash
+/// of each page's text. Pages present in only one list count as changed.
+pub(crate) fn diff_pages(old: &[PageText], new: &[PageText]) -> Vec<PageDiff> {
+  let hash = |text: &str| {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+  };
+  let old_hashes: HashMap<u32, u64> = old.iter().map(|p| (p.page, hash(&p.text))).collect();
+  let new_hashes: HashMap<u32, u64> = new.iter().map(|p| (p.page, hash(&p.text))).collect();
+  let last_page = old.iter().chain(new).map(|p| p.page).max().unwrap_or(0);
+  (1..=last_page)
+    .map(|page| {
+      let changed = match (old_hashes.get(&page), new_hashes.get(&page)) {
+        (Some(a), Some(b)) => a != b,
+        _ => true,
+      };
+      PageDiff { page, changed }
+    })
+    .collect()
+}
+
+/// Per-page text diff between two versions of a document.
+pub(crate) fn diff_document_text(old: &Document, new: &Document) -> Result<Vec<PageDiff>> {
+  let (old_pages, new_pages) = rayon::join(|| extract_text(old), || extract_text(new));
+  Ok(diff_pages(&old_pages?, &new_pages?))
+}
+
+/// Put between consecutive pages' text before chunking, so a page break is
+/// also a paragraph break.
+const CHUNK_PAGE_SEPARATOR: &str = "\n\n";
+
+/// Split the document's text into chunks of at most `max_chars` characters,
+/// each starting `overlap` characters before the previous one ended.
+pub(crate) fn collect_text_chunks(
+  doc: &Document,
+  max_chars: u32,
+  overlap: u32,
+) -> Result<Vec<TextChunk>> {
+  if max_chars == 0 {
+    return Err(Error::from_reason("maxChars must be at least 1"));
+  }
+  if overlap >= max_chars {
+    return Err(Error::from_reason(format!(
+      "overlap ({overlap}) must be smaller than maxChars ({max_chars})"
+    )));
+  }
+  Ok(chunk_pages(
+    &extract_text(doc)?,
+    max_chars as usize,
+    overlap as usize,
+  ))
+}
+
+/// Chunk the concatenated page text. Each chunk ends at the last paragraph
+/// break in its window, else the last line break, else the last whitespace,
+/// else at exactly `max_chars`; breaks in the first half of the window (or
+/// inside the overlap) are not used, so chunks stay reasonably full and the
+/// text always advances. Chunks are trimmed, whitespace-only ones dropped.
+fn chunk_pages(pages: &[PageText], max_chars: usize, overlap: usize) -> Vec<TextChunk> {
+  let mut chars: Vec<char> = Vec::new();
+  // (offset in `chars`, page number) where each page's text starts
+  let mut page_starts: Vec<(usize, u32)> = Vec::with_capacity(pages.len());
+  for (i, page) in pages.iter().enumerate() {
+    if i > 0 {
+      chars.extend(CHUNK_PAGE_SEPARATOR.chars());
+    }
+    page_starts.push((chars.len(), page.page));
+    chars.extend(page.text.chars());
+  }
+  let page_at = |pos: usize| page_starts[page_starts.partition_point(|&(s, _)| s <= pos) - 1].1;
+  let min_len = (max_chars / 2).max(overlap + 1);
+
+  let mut chunks = Vec::new();
+  let mut start = 0;
+  while start < chars.len() {
+    let end = if chars.len() - start <= max_chars {
+      chars.len()
+    } else {
+      start + chunk_break(&chars[start..start + max_chars], min_len)
+    };
+    let (mut first, mut last) = (start, end);
+    while first < last && chars[first].is_whitespace() {
+      first += 1;
+    }
+    while last > first && chars[last - 1].is_whitespace() {
+      last -= 1;
+    }
+    if first < last {
+      chunks.push(TextChunk {
+        text: chars[first..last].iter().collect(),
+        start_page: page_at(first),
+        end_page: page_at(last - 1),
+      });
+    }
+    if end == chars.len() {
+      break;
+    }
+    start = end - overlap;
+  }
+  chunks
+}
+
+/// Length of the chunk cut from `window`: the latest break at or after
+/// `min_len`, preferring a paragraph break, then a line break, then any
+/// whitespace.
+fn chunk_break(window: &[char], min_len: usize) -> usize {
+  let last_break =
+    |is_break: &dyn Fn(usize) -> bool| (min_len..=window.len()).rev().find(|&end| is_break(end));
+  last_break(&|end| end >= 2 && window[end - 2] == '\n' && window[end - 1] == '\n')
+    .or_else(|| last_break(&|end| window[end - 1] == '\n'))
+    .or_else(|| last_break(&|end| window[end - 1].is_whitespace()))
+    .unwrap_or(window.len())
+}
+
+/// Unicode script blocks, sorted by start. Letters outside these fall back to
+/// `"Other"`; everything else not covered is `"Common"` (digits, punctuation,
+/// symbols).
+const SCRIPT_RANGES: &[(u32, u32, &str)] = &[
+  (0x0041, 0x005A, "Latin"),
+  (0x0061, 0x007A, "Latin"),
+  (0x00AA, 0x00AA, "Latin"),
+  (0x00BA, 0x00BA, "Latin"),
+  (0x00C0, 0x00D6, "Latin"),
+  (0x00D8, 0x00F6, "Latin"),
+  (0x00F8, 0x02AF, "Latin"),
+  (0x0300, 0x036F, "Inherited"),
+  (0x0370, 0x03FF, "Greek"),
+  (0x0400, 0x052F, "Cyrillic"),
+  (0x0530, 0x058F, "Armenian"),
+  (0x0590, 0x05FF, "Hebrew"),
+  (0x0600, 0x06FF, "Arabic"),
+  (0x0700, 0x074F, "Syriac"),
+  (0x0750, 0x077F, "Arabic"),
+  (0x0780, 0x07BF, "Thaana"),
+  (0x08A0, 0x08FF, "Arabic"),
+  (0x0900, 0x097F, "Devanagari"),
+  (0x0980, 0x09FF, "Bengali"),
+  (0x0A00, 0x0A7F, "Gurmukhi"),
+  (0x0A80, 0x0AFF, "Gujarati"),
+  (0x0B00, 0x0B7F, "Oriya"),
+  (0x0B80, 0x0BFF, "Tamil"),
+  (0x0C00, 0x0C7F, "Telugu"),
+  (0x0C80, 0x0CFF, "Kannada"),
+  (0x0D00, 0x0D7F, "Malayalam"),
+  (0x0D80, 0x0DFF, "Sinhala"),
+  (0x0E00, 0x0E7F, "Thai"),
+  (0x0E80, 0x0EFF, "Lao"),
+  (0x0F00, 0x0FFF, "Tibetan"),
+  (0x1000, 0x109F, "Myanmar"),
+  (0x10A0, 0x10FF, "Georgian"),
+  (0x1100, 0x11FF, "Hangul"),
+  (0x1200, 0x139F, "Ethiopic"),
+  (0x13A0, 0x13FF, "Cherokee"),
+  (0x1780, 0x17FF, "Khmer"),
+  (0x1800, 0x18AF, "Mongolian"),
+  (0x1C80, 0x1C8F, "Cyrillic"),
+  (0x1E00, 0x1EFF, "Latin"),
+  (0x1F00, 0x1FFF, "Greek"),
+  (0x2C60, 0x2C7F, "Latin"),
+  (0x2DE0, 0x2DFF, "Cyrillic"),
+  (0x2E80, 0x2FDF, "Han"),
+  (0x3005, 0x3005, "Han"),
+  (0x3007, 0x3007, "Han"),
+  (0x3021, 0x3029, "Han"),
+  (0x3038, 0x303B, "Han"),
+  (0x3041, 0x309F, "Hiragana"),
+  (0x30A0, 0x30FF, "Katakana"),
+  (0x3100, 0x312F, "Bopomofo"),
+  (0x3130, 0x318F, "Hangul"),
+  (0x31F0, 0x31FF, "Katakana"),
+  (0x3400, 0x4DBF, "Han"),
+  (0x4E00, 0x9FFF, "Han"),
+  (0xA640, 0xA69F, "Cyrillic"),
+  (0xA720, 0xA7FF, "Latin"),
+  (0xA960, 0xA97F, "Hangul"),
+  (0xAC00, 0xD7FF, "Hangul"),
+  (0xF900, 0xFAFF, "Han"),
+  (0xFB00, 0xFB06, "Latin"),
+  (0xFB1D, 0xFB4F, "Hebrew"),
+  (0xFB50, 0xFDFF, "Arabic"),
+  (0xFE70, 0xFEFF, "Arabic"),
+  (0xFF21, 0xFF3A, "Latin"),
+  (0xFF41, 0xFF5A, "Latin"),
+  (0xFF66, 0xFF9F, "Katakana"),
+  (0xFFA0, 0xFFDC, "Hangul"),
+  (0x20000, 0x323AF, "Han"),
+];
+
+/// The Unicode script of `c`, or `None` for whitespace and control
+/// characters, which aren't counted.
+fn char_script(c: char) -> Option<&'static str> {
+  if c.is_whitespace() || c.is_control() {
+    return None;
+  }
+  let code = c as u32;
+  let block = SCRIPT_RANGES
+    .binary_search_by(|&(start, end, _)| {
+      if end < code {
+        std::cmp::Ordering::Less
+      } else if start > code {
+        std::cmp::Ordering::Greater
+      } else {
+        std::cmp::Ordering::Equal
+      }
+    })
+    .map(|i| SCRIPT_RANGES[i].2);
+  Some(match block {
+    Ok(script) => script,
+    Err(_) if c.is_alphabetic() => "Other",
+    Err(_) => "Common",
+  })
+}
+
+/// Lowercased words of `text`, split on every non-alphanumeric character,
+/// counted. Words shorter than `min_length` characters are dropped. Most
+/// frequent first, ties in alphabetical order.
+fn term_counts(text: &str, min_length: u32) -> Vec<TermCount> {
+  let mut counts: HashMap<String, u32> = HashMap::new();
+  for word in text.split(|c: char| !c.is_alphanumeric()) {
+    if word.chars().count() < (min_length as usize).max(1) {
+      continue;
+    }
+    *counts.entry(word.to_lowercase()).or_default() += 1;
+  }
+  let mut terms: Vec<TermCount> = counts
+    .into_iter()
+    .map(|(term, count)| TermCount { term, count })
+    .collect();
+  terms.sort_unstable_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+  terms
+}
+
+/// Term frequencies of every page's text, for building a search index.
+pub(crate) fn collect_word_frequencies(
+  doc: &Document,
+  min_length: u32,
+) -> Result<Vec<PageWordFreq>> {
+  let pages = extract_text(doc)?;
+  Ok(
+    pages
+      .par_iter()
+      .map(|p| PageWordFreq {
+        page: p.page,
+        terms: term_counts(&p.text, min_length),
+      })
+      .collect(),
+  )
+}
+
+/// Count the characters of every page's text by Unicode script, most frequent
+/// first. Whitespace isn't counted.
+pub(crate) fn collect_script_histogram(doc: &Document) -> Result<Vec<ScriptCount>> {
+  let pages = extract_text(doc)?;
+  let mut counts: HashMap<&'static str, u32> = HashMap::new();
+  for c in pages.iter().flat_map(|p| p.text.chars()) {
+    if let Some(script) = char_script(c) {
+      *counts.entry(script).or_default() += 1;
+    }
+  }
+  let mut histogram: Vec<ScriptCount> = counts
+    .into_iter()
+    .map(|(script, count)| ScriptCount {
+      script: script.to_string(),
+      count,
+    })
+    .collect();
+  histogram.sort_unstable_by(|a, b| b.count.cmp(&a.count).then_with(|| a.script.cmp(&b.script)));
+  Ok(histogram)
+}
+
 pub(crate) fn extract_structured_text(doc: &Document) -> Result<Vec<StructuredPageText>> {
   let pages = extract_text(doc)?;
-  Ok(detect_headers_footers(&pages))
+  let mut structured = detect_headers_footers(&pages);
+  attach_text_coverage(doc, &mut structured);
+  Ok(structured)
+}
+
+/// Each page's text without its detected header and footer lines.
+pub(crate) fn extract_body_text(doc: &Document) -> Result<Vec<PageText>> {
+  let pages = extract_text(doc)?;
+  let structured = detect_headers_footers(&pages);
+  Ok(
+    pages
+      .into_iter()
+      .zip(structured)
+      .map(|(page, sections)| PageText {
+        page: page.page,
+        text: sections.body,
+        footer_stripped: page.footer_stripped,
+      })
+      .collect(),
+  )
+}
+
+/// The most frequent non-empty value of one section across pages, cleared
+/// from every page that has exactly that value. `None` unless at least two
+/// pages share it.
+fn hoist_common_section(
+  pages: &mut [StructuredPageText],
+  section: fn(&mut StructuredPageText) -> &mut String,
+) -> Option<String> {
+  let mut counts: HashMap<String, usize> = HashMap::new();
+  for page in pages.iter_mut() {
+    let text = section(page);
+    if !text.is_empty() {
+      *counts.entry(text.clone()).or_insert(0) += 1;
+    }
+  }
+  // Ties go to the value seen first
+  let mut common: Option<(String, usize)> = None;
+  for page in pages.iter_mut() {
+    let text = section(page);
+    let count = counts.get(text.as_str()).copied().unwrap_or(0);
+    if count >= 2 && common.as_ref().is_none_or(|(_, best)| count > *best) {
+      common = Some((text.clone(), count));
+    }
+  }
+  let (common, _) = common?;
+  for page in pages.iter_mut() {
+    let text = section(page);
+    if *text == common {
+      text.clear();
+    }
+  }
+  Some(common)
+}
+
+/// Move the header and footer shared by several pages to the document level.
+pub(crate) fn dedupe_headers_footers(
+  pages: &mut [StructuredPageText],
+) -> (Option<String>, Option<String>) {
+  let header = hoist_common_section(pages, |p| &mut p.header);
+  let footer = hoist_common_section(pages, |p| &mut p.footer);
+  (header, footer)
+}
+
+pub(crate) fn extract_structured_text_with_config(
+  doc: &Document,
+  config: StructuredTextConfig,
+) -> Result<StructuredTextResult> {
+  let text = extract_text(doc)?;
+  let mut pages = detect_headers_footers_with(&text, config.matching);
+  attach_text_coverage(doc, &mut pages);
+  if config.section_titles {
+    let headings = last_heading_per_page(doc);
+    for page in &mut pages {
+      page.section_title = headings
+        .range(..=page.page)
+        .next_back()
+        .map(|(_, title)| title.clone());
+    }
+  }
+  let (common_header, common_footer) = if config.dedupe_headers {
+    dedupe_headers_footers(&mut pages)
+  } else {
+    (None, None)
+  };
+  Ok(StructuredTextResult {
+    pages,
+    common_header,
+    common_footer,
+  })
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  #[test]
+  fn soft_hyphens_are_removed_and_join_line_breaks() {
+    assert_eq!(strip_soft_hyphens("co\u{AD}operate"), "cooperate");
+    assert_eq!(strip_soft_hyphens("exam\u{AD}\n  ple text"), "example text");
+    assert_eq!(strip_soft_hyphens("exam\u{AD} \r\nple"), "example");
+    // A soft hyphen before a space stays a word break
+    assert_eq!(strip_soft_hyphens("end\u{AD} next"), "end next");
+    assert_eq!(strip_soft_hyphens("plain\ntext"), "plain\ntext");
+  }
+
   #[test]
   fn strip_basic_footer_artifact() {
     let text = "Some content\n/\n38\nMore content";
     let result = strip_footer_artifacts(text, "38");
     assert_eq!(result, "Some content\nMore content");
+    assert!(matches!(result, Cow::Owned(_)));
   }
 
   #[test]
@@ -192,6 +1147,7 @@ mod tests {
     let text = "Some content\nNo footer here\nMore content";
     let result = strip_footer_artifacts(text, "38");
     assert_eq!(result, text);
+    assert!(matches!(result, Cow::Borrowed(_)));
   }
 
   #[test]
@@ -249,10 +1205,640 @@ mod tests {
     assert_eq!(result, text);
   }
 
+  #[test]
+  fn markers_only_between_pages() {
+    let pages = vec![
+      PageText {
+        page: 1,
+        text: "one".into(),
+        footer_stripped: false,
+      },
+      PageText {
+        page: 2,
+        text: "two".into(),
+        footer_stripped: false,
+      },
+      PageText {
+        page: 3,
+        text: "three".into(),
+        footer_stripped: false,
+      },
+    ];
+    assert_eq!(
+      join_pages_with_markers(&pages, DEFAULT_PAGE_MARKER),
+      "one\n\n--- Page 2 ---\n\ntwo\n\n--- Page 3 ---\n\nthree"
+    );
+    assert_eq!(
+      join_pages_with_markers(&pages, "<page {page}>"),
+      "one<page 2>two<page 3>three"
+    );
+    assert_eq!(join_pages_with_markers(&pages[..1], "<page {page}>"), "one");
+  }
+
+  #[test]
+  fn looks_unmapped_flags_replacement_heavy_text() {
+    assert!(looks_unmapped(""));
+    assert!(looks_unmapped("  \n "));
+    assert!(looks_unmapped("\u{FFFD}\u{FFFD}ab"));
+    assert!(looks_unmapped("\u{1}\u{2}\u{3} x"));
+    assert!(!looks_unmapped("Hello world\u{FFFD}"));
+  }
+
   #[test]
   fn count_with_extra_text_not_stripped() {
     let text = "Some content\n/\n38 pages\nMore content";
     let result = strip_footer_artifacts(text, "38");
     assert_eq!(result, text);
   }
+
+  #[test]
+  fn diff_marks_changed_added_and_removed_pages() {
+    let pages = |texts: &[&str]| -> Vec<PageText> {
+      texts
+        .iter()
+        .enumerate()
+        .map(|(i, t)| PageText {
+          page: i as u32 + 1,
+          text: t.to_string(),
+          footer_stripped: false,
+        })
+        .collect()
+    };
+    let old = pages(&["intro", "body v1", "appendix"]);
+    let new = pages(&["intro", "body v2", "appendix", "index"]);
+    let changed: Vec<(u32, bool)> = diff_pages(&old, &new)
+      .iter()
+      .map(|d| (d.page, d.changed))
+      .collect();
+    assert_eq!(changed, [(1, false), (2, true), (3, false), (4, true)]);
+    assert_eq!(diff_pages(&new, &old).len(), 4);
+  }
+
+  #[test]
+  fn pages_are_numbered_in_page_tree_order() {
+    use lopdf::{Stream, dictionary};
+
+    let mut doc = Document::with_version("1.7");
+    let font_id = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type1",
+      "BaseFont" => "Helvetica",
+    });
+    let root_id = doc.new_object_id();
+    let inner_id = doc.new_object_id();
+    // Object numbers run A, B, C; the tree visits C, B, then A
+    let mut page = |parent: ObjectId, text: &str| {
+      let content = format!("BT /F1 12 Tf 10 10 Td ({text}) Tj ET");
+      let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+      doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => parent,
+        "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+        "Contents" => content_id,
+        "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+      })
+    };
+    let a = page(root_id, "A");
+    let b = page(inner_id, "B");
+    let c = page(inner_id, "C");
+    doc.objects.insert(
+      inner_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Parent" => root_id,
+        "Kids" => vec![c.into(), b.into()],
+        "Count" => 2,
+      }),
+    );
+    doc.objects.insert(
+      root_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => vec![inner_id.into(), a.into()],
+        "Count" => 3,
+      }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => root_id });
+    doc.trailer.set("Root", catalog_id);
+
+    let pages: Vec<(u32, String)> = extract_text(&doc)
+      .unwrap()
+      .into_iter()
+      .map(|p| (p.page, p.text.trim().to_string()))
+      .collect();
+    assert_eq!(
+      pages,
+      [
+        (1, "C".to_string()),
+        (2, "B".to_string()),
+        (3, "A".to_string())
+      ]
+    );
+  }
+
+  #[test]
+  fn preview_stops_after_max_chars_of_text() {
+    use lopdf::{Stream, dictionary};
+
+    let mut doc = Document::with_version("1.7");
+    let font_id = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type1",
+      "BaseFont" => "Helvetica",
+    });
+    let pages_id = doc.new_object_id();
+    let kids: Vec<Object> = ["Hello world", "", "Second page"]
+      .iter()
+      .map(|text| {
+        let content = format!("BT /F1 12 Tf 10 10 Td ({text}) Tj ET");
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+        doc
+          .add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+          })
+          .into()
+      })
+      .collect();
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => 3 }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+
+    assert_eq!(extract_text_preview(&doc, 8), "Hello wor");
+    // The empty page adds no separator
+    assert_eq!(extract_text_preview(&doc, 14), "Hello world\n\nSeco");
+    assert_eq!(
+      extract_text_preview(&doc, 1000),
+      "Hello world\n\nSecond page"
+    );
+    assert_eq!(extract_text_preview(&doc, 0), "");
+  }
+
+  #[test]
+  fn dedupe_moves_shared_header_and_footer_to_document_level() {
+    let pages: Vec<PageText> = ["Revenue", "Costs", "Outlook", "Glossary"]
+      .iter()
+      .zip(1..)
+      .map(|(body, n)| PageText {
+        page: n,
+        text: format!("ACME Annual Report\n{body}\nPage {n} of 4"),
+        footer_stripped: false,
+      })
+      .collect();
+    let mut structured = detect_headers_footers(&pages);
+    // One page carries a different header
+    structured[3].header = "Appendix".to_string();
+    let (header, footer) = dedupe_headers_footers(&mut structured);
+    assert_eq!(header.as_deref(), Some("ACME Annual Report"));
+    // Footers all differ by page number, so none is shared
+    assert_eq!(footer, None);
+    let headers: Vec<&str> = structured.iter().map(|p| p.header.as_str()).collect();
+    assert_eq!(headers, ["", "", "", "Appendix"]);
+    assert_eq!(structured[0].footer, "Page 1 of 4");
+    assert_eq!(structured[2].body, "Outlook");
+  }
+
+  #[test]
+  fn date_and_case_matching_widen_header_detection() {
+    let headers = [
+      "Monthly Report March 2024",
+      "Monthly Report April 2024",
+      "MONTHLY REPORT May 2024",
+      "monthly report June 2024",
+    ];
+    let pages: Vec<PageText> = headers
+      .iter()
+      .zip(1..)
+      .map(|(header, n)| PageText {
+        page: n,
+        text: format!("{header}\nBody {n}\nMore"),
+        footer_stripped: false,
+      })
+      .collect();
+    let detected = |dates, ignore_case| {
+      detect_headers_footers_with(&pages, HeaderFooterMatching { dates, ignore_case })
+        .iter()
+        .filter(|p| !p.header.is_empty())
+        .count()
+    };
+    // Each flag alone matches only two of the four headers
+    assert_eq!(detected(false, false), 0);
+    assert_eq!(detected(true, false), 0);
+    assert_eq!(detected(false, true), 0);
+    assert_eq!(detected(true, true), 4);
+    assert_eq!(
+      normalize_header_footer_line(
+        "Mon, 3 Sept",
+        HeaderFooterMatching {
+          dates: true,
+          ignore_case: false
+        }
+      ),
+      "<DATE>, <NUM> <DATE>"
+    );
+    // Words that merely contain a month name are kept
+    assert_eq!(
+      normalize_header_footer_line(
+        "Marching",
+        HeaderFooterMatching {
+          dates: true,
+          ignore_case: false
+        }
+      ),
+      "Marching"
+    );
+  }
+
+  #[test]
+  fn body_text_drops_running_headers_and_footers() {
+    let contents: Vec<Vec<u8>> = ["Revenue", "Costs", "Outlook"]
+      .iter()
+      .zip(1..)
+      .map(|(body, n)| {
+        format!(
+          "BT /F1 12 Tf 72 760 Td (Quarterly Report) Tj ET \
+           BT /F1 12 Tf 72 720 Td ({body}) Tj ET \
+           BT /F1 12 Tf 72 40 Td (Page {n}) Tj ET"
+        )
+        .into_bytes()
+      })
+      .collect();
+    let (doc, _) = text_pages(&contents);
+    let bodies: Vec<String> = extract_body_text(&doc)
+      .unwrap()
+      .into_iter()
+      .map(|p| p.text)
+      .collect();
+    let structured = extract_structured_text(&doc).unwrap();
+    assert_eq!(structured[0].header, "Quarterly Report");
+    assert_eq!(structured[0].footer, "Page 1");
+    assert_eq!(bodies, ["Revenue", "Costs", "Outlook"]);
+  }
+
+  #[test]
+  fn characters_are_classified_by_script() {
+    assert!(SCRIPT_RANGES.windows(2).all(|pair| pair[0].1 < pair[1].0));
+    let scripts: Vec<Option<&str>> = "aЖ中カ한ع 7.e\u{0301}".chars().map(char_script).collect();
+    assert_eq!(
+      scripts,
+      [
+        Some("Latin"),
+        Some("Cyrillic"),
+        Some("Han"),
+        Some("Katakana"),
+        Some("Hangul"),
+        Some("Arabic"),
+        None,
+        Some("Common"),
+        Some("Common"),
+        Some("Latin"),
+        Some("Inherited"),
+      ]
+    );
+  }
+
+  #[test]
+  fn terms_are_lowercased_split_and_counted() {
+    let counts: Vec<(String, u32)> =
+      term_counts("The cat, the HAT and\nthe cat's naïve café 42", 3)
+        .into_iter()
+        .map(|t| (t.term, t.count))
+        .collect();
+    let term = |t: &str, n| (t.to_string(), n);
+    assert_eq!(
+      counts,
+      [
+        term("the", 3),
+        term("cat", 2),
+        term("and", 1),
+        term("café", 1),
+        term("hat", 1),
+        term("naïve", 1),
+      ]
+    );
+    // A zero minimum still drops the empty strings between separators
+    assert_eq!(term_counts(" a--b ", 0).len(), 2);
+  }
+
+  fn page_texts(texts: &[&str]) -> Vec<PageText> {
+    texts
+      .iter()
+      .enumerate()
+      .map(|(i, text)| PageText {
+        page: i as u32 + 1,
+        text: text.to_string(),
+        footer_stripped: false,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn chunks_prefer_paragraph_breaks_and_record_pages() {
+    let pages = page_texts(&[
+      "First paragraph here.\n\nSecond one, a bit longer.",
+      "Third paragraph on page two.",
+    ]);
+    let chunks = chunk_pages(&pages, 40, 0);
+    let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(
+      texts,
+      [
+        "First paragraph here.",
+        "Second one, a bit longer.",
+        "Third paragraph on page two."
+      ]
+    );
+    let pages: Vec<(u32, u32)> = chunks.iter().map(|c| (c.start_page, c.end_page)).collect();
+    assert_eq!(pages, [(1, 1), (1, 1), (2, 2)]);
+
+    let spanning = chunk_pages(&page_texts(&["Short page.", "Another."]), 100, 0);
+    assert_eq!(spanning.len(), 1);
+    assert_eq!((spanning[0].start_page, spanning[0].end_page), (1, 2));
+  }
+
+  #[test]
+  fn chunks_overlap_and_fall_back_to_word_and_hard_breaks() {
+    let pages = page_texts(&["alpha beta gamma delta epsilon"]);
+    let chunks = chunk_pages(&pages, 12, 4);
+    assert!(chunks.iter().all(|c| c.text.chars().count() <= 12));
+    assert_eq!(chunks[0].text, "alpha beta");
+    // The next chunk starts four characters before the previous one ended
+    assert!(chunks[1].text.starts_with("eta"));
+    assert_eq!(
+      chunks.last().unwrap().text.split(' ').next_back(),
+      Some("epsilon")
+    );
+
+    let unbroken = chunk_pages(&page_texts(&["abcdefghij"]), 4, 0);
+    let texts: Vec<&str> = unbroken.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(texts, ["abcd", "efgh", "ij"]);
+  }
+
+  fn text_page(content: &[u8]) -> (Document, ObjectId) {
+    let (doc, page_ids) = text_pages(&[content.to_vec()]);
+    (doc, page_ids[0])
+  }
+
+  /// One Courier page per content stream.
+  fn text_pages(contents: &[Vec<u8>]) -> (Document, Vec<ObjectId>) {
+    use lopdf::{Stream, dictionary};
+
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let font = doc.add_object(dictionary! {
+      "Type" => "Font",
+      "Subtype" => "Type1",
+      "BaseFont" => "Courier",
+    });
+    let page_ids: Vec<ObjectId> = contents
+      .iter()
+      .map(|content| {
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.clone()));
+        doc.add_object(dictionary! {
+          "Type" => "Page",
+          "Parent" => pages_id,
+          "MediaBox" => vec![0.into(), 0.into(), 600.into(), 800.into()],
+          "Contents" => content_id,
+          "Resources" => dictionary! { "Font" => dictionary! { "F1" => font } },
+        })
+      })
+      .collect();
+    doc.objects.insert(
+      pages_id,
+      Object::Dictionary(dictionary! {
+        "Type" => "Pages",
+        "Kids" => page_ids.iter().map(|&id| id.into()).collect::<Vec<Object>>(),
+        "Count" => page_ids.len() as i64,
+      }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+    (doc, page_ids)
+  }
+
+  #[test]
+  fn exact_spacing_follows_the_text_state() {
+    // Words set apart by TJ offsets and by character spacing, letters kerned
+    // tightly, a real space glyph widened by Tw, then a second line
+    let (doc, page_id) = text_page(
+      b"BT /F1 10 Tf 50 750 Td [(Kern) 20 (ed) -400 (gap)] TJ \
+        5 Tc (ab) Tj 0 Tc 8 Tw ( wide) Tj 0 Tw \
+        0 -14 Td 50 Tz (squeezed) Tj ET",
+    );
+    assert_eq!(
+      extract_page_text_spaced(&doc, 1, page_id),
+      "Kerned gapa b wide\nsqueezed\n"
+    );
+  }
+
+  #[test]
+  fn text_runs_point_at_their_stream_and_operator() {
+    use lopdf::{Stream, dictionary};
+
+    let (mut doc, page_id) = text_page(b"BT /F1 10 Tf 50 750 Td (Title) Tj ET");
+    let font = doc
+      .get_dictionary(page_id)
+      .unwrap()
+      .get_deref(b"Resources", &doc)
+      .unwrap()
+      .as_dict()
+      .unwrap()
+      .get(b"Font")
+      .unwrap()
+      .as_dict()
+      .unwrap()
+      .get(b"F1")
+      .unwrap()
+      .clone();
+    let form = doc.add_object(Stream::new(
+      dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Form",
+        "BBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+        "Resources" => dictionary! { "Font" => dictionary! { "F9" => font } },
+      },
+      b"BT /F9 8 Tf (Stamp) Tj ET".to_vec(),
+    ));
+    let second = doc.add_object(Stream::new(
+      dictionary! {},
+      // A form painted twice is reported once
+      b"BT /F1 10 Tf 50 700 Td [(Bo) -20 (dy)] TJ ET /Fm1 Do 1 0 0 1 0 50 cm /Fm1 Do".to_vec(),
+    ));
+    let first = doc.get_page_contents(page_id)[0];
+    let page = doc.get_dictionary_mut(page_id).unwrap();
+    page.set("Contents", vec![first.into(), second.into()]);
+    let resources = page.get_mut(b"Resources").unwrap().as_dict_mut().unwrap();
+    resources.set("XObject", dictionary! { "Fm1" => form });
+
+    let runs: Vec<(String, u32, String)> = collect_source_text_runs(&doc)
+      .into_iter()
+      .map(|r| (r.object_id, r.operator_index, r.text))
+      .collect();
+    let id = |id: ObjectId| format!("{} 0 obj", id.0);
+    assert_eq!(
+      runs,
+      [
+        (id(first), 3, "Title".to_string()),
+        (id(second), 3, "Body".to_string()),
+        (id(form), 2, "Stamp".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn form_values_are_placed_beside_their_labels() {
+    use lopdf::{StringFormat, dictionary};
+
+    let (mut doc, page_id) = text_page(
+      b"BT /F1 12 Tf 50 700 Td (Name:) Tj 0 -40 Td (Subscribe:) Tj 0 -40 Td (Footer) Tj ET",
+    );
+    let widget = |doc: &mut Document, fields: lopdf::Dictionary, rect: [i64; 4]| {
+      let mut widget = dictionary! {
+        "Subtype" => "Widget",
+        "P" => page_id,
+        "Rect" => rect.iter().map(|&n| n.into()).collect::<Vec<Object>>(),
+      };
+      widget.extend(&fields);
+      doc.add_object(widget)
+    };
+    let name = widget(
+      &mut doc,
+      dictionary! {
+        "FT" => "Tx",
+        "T" => Object::String(b"name".to_vec(), StringFormat::Literal),
+        "V" => Object::String(b"Ada Lovelace".to_vec(), StringFormat::Literal),
+      },
+      [100, 694, 250, 714],
+    );
+    let subscribe = widget(
+      &mut doc,
+      dictionary! { "FT" => "Btn", "V" => "Yes", "AS" => "Yes" },
+      [130, 656, 142, 668],
+    );
+    let hidden = widget(
+      &mut doc,
+      dictionary! {
+        "FT" => "Tx",
+        "V" => Object::String(b"secret".to_vec(), StringFormat::Literal),
+        "F" => 2,
+      },
+      [100, 500, 200, 520],
+    );
+    let widgets: Vec<Object> = vec![name.into(), subscribe.into(), hidden.into()];
+    doc
+      .get_dictionary_mut(page_id)
+      .unwrap()
+      .set("Annots", widgets.clone());
+    let catalog_id = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+    doc
+      .get_dictionary_mut(catalog_id)
+      .unwrap()
+      .set("AcroForm", dictionary! { "Fields" => widgets });
+
+    let config = TextConfig {
+      form_values: true,
+      ..TextConfig::default()
+    };
+    let pages = extract_text_with_config(&doc, config).unwrap();
+    assert_eq!(pages[0].text, "Name: Ada Lovelace\nSubscribe: [x]\nFooter");
+    let plain = extract_text(&doc).unwrap();
+    assert!(!plain[0].text.contains("Ada"));
+
+    // Without widgets on the page the other options still apply
+    let (doc, _) = text_page(b"BT /F1 12 Tf 50 700 Td (Wide) Tj 200 0 Td (gap) Tj ET");
+    let spaced = TextConfig {
+      exact_spacing: true,
+      ..TextConfig::default()
+    };
+    assert_eq!(
+      extract_text_with_config(
+        &doc,
+        TextConfig {
+          form_values: true,
+          ..spaced
+        }
+      )
+      .unwrap()[0]
+        .text,
+      extract_text_with_config(&doc, spaced).unwrap()[0].text
+    );
+  }
+
+  #[test]
+  fn ligatures_and_quotes_are_cleaned_per_profile() {
+    use lopdf::dictionary;
+
+    assert_eq!(
+      expand_ligatures("\u{FB01}nd the \u{FB02}ow"),
+      "find the flow"
+    );
+    // OT1 slots inside words, but not a line's carriage return
+    assert_eq!(
+      expand_ligatures("e\u{0E}cient sta\u{0B}"),
+      "efficient staff"
+    );
+    assert_eq!(
+      expand_ligatures("\u{0C}rst line\r\nnext"),
+      "first line\r\nnext"
+    );
+    assert_eq!(expand_ligatures("line\rnext"), "line\rnext");
+    assert_eq!(
+      straighten_quotes("\u{201C}It\u{2019}s fine,\u{201D} she said"),
+      "\"It's fine,\" she said"
+    );
+
+    assert_eq!(
+      cleanup_profile_for("pdfTeX-1.40.25"),
+      Some(CleanupProfile::Latex)
+    );
+    assert_eq!(
+      cleanup_profile_for("Skia/PDF m120"),
+      Some(CleanupProfile::Chromium)
+    );
+    assert_eq!(
+      cleanup_profile_for("Microsoft\u{AE} Word for Microsoft 365"),
+      Some(CleanupProfile::Word)
+    );
+    assert_eq!(cleanup_profile_for("Microsoft: Print To PDF"), None);
+
+    let (mut doc, _) = text_page(b"BT /F1 12 Tf 72 720 Td (Plain text) Tj ET");
+    let info = doc.add_object(dictionary! {
+      "Producer" => Object::string_literal("LuaTeX-1.17.0"),
+    });
+    doc.trailer.set("Info", info);
+    assert_eq!(
+      resolve_cleanup_profile(&doc, Some(CleanupProfile::Auto)),
+      Some(CleanupProfile::Latex)
+    );
+    assert_eq!(resolve_cleanup_profile(&doc, None), None);
+    assert_eq!(
+      resolve_cleanup_profile(&doc, Some(CleanupProfile::Word)),
+      Some(CleanupProfile::Word)
+    );
+  }
+
+  #[test]
+  fn strict_mode_names_the_page_that_failed() {
+    // lopdf rejects a `Tf` without operands
+    let (doc, _) = text_page(b"BT Tf ET");
+    let strict = TextConfig {
+      strict: true,
+      ..TextConfig::default()
+    };
+    let Err(err) = extract_text_with_config(&doc, strict) else {
+      panic!("strict extraction should fail");
+    };
+    assert!(
+      err
+        .reason
+        .starts_with("Failed to extract text from page 1:")
+    );
+    assert_eq!(extract_text(&doc).unwrap()[0].text, "");
+  }
 }
@@ -1,57 +1,258 @@
 #![deny(clippy::all)]
 
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
 use napi::{Env, Task};
 use napi_derive::napi;
 
 use lopdf::Document;
-use std::sync::Arc;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
 
 mod core;
 mod types;
 
 // Public API types (appear in generated .d.ts)
 pub use types::{
-  BoxType, Capabilities, PageAnnotation, PageBox, PageImage, PageText, PdfDocument, PdfMeta,
-  StructuredPageText,
+  AnnotationOptions, AnnotationSummary, BoxType, Capabilities, CleanReport, DocumentAction,
+  DocumentClassification, DocumentOptions, EditEvent, FileStats, FormField, FormFieldsResult,
+  HighlightedText, ImageDecodeFailure, ImageExtractionResult, ImageOptions, ImageOutputFormat,
+  LineBox, LinearizationInfo, Measurement, ObjectSize, OutlineItem, OutputIntent, PageAnnotation,
+  PageBox, PageChunk, PageComplexity, PageDiff, PageImage, PageImageBytes, PageLines,
+  PageParagraphs, PageSizeRange, PageStyledLines, PageText, PageTreeStats, PageVectorRegions,
+  PageWordFreq, PdfDocument, PdfMeta, ProducerProfile, ReindexedPage, ScriptCount, SourceTextRun,
+  StreamPagesOptions, StructuredPageText, StructuredTextOptions, StructuredTextResult, StyledLine,
+  TaggedBlock, TermCount, TextChunk, TextOptions, TrailerRefs, UriRef, VectorRegion,
+  ViewerPreferences,
 };
 
 #[cfg(feature = "ocr")]
-pub use types::{OcrOptions, OcrPageText, OcrStructuredPageText, PdfDocumentOcr, TextSource};
+pub use types::{
+  OcrOptions, OcrPageText, OcrStreamedPage, OcrStructuredPageText, PdfDocumentOcr, TextSource,
+};
 
 #[cfg(feature = "render")]
 pub use types::{RawRenderedPage, RenderMode, RenderOptions, RenderedPage};
 
 // Internal plumbing (used by Task impls in this file — must be `pub` for napi Task trait)
-pub use types::{RawPageImage, RawPdfDocument};
+pub use types::{RawImageExtraction, RawPageImage, RawPdfDocument};
 
 #[cfg(feature = "ocr")]
 pub use types::RawPdfDocumentOcr;
 
 // ── Step 1: Compile-time assertion that lopdf::Document is Send + Sync ──
-const _: () = {
+const _: fn() = || {
   fn assert_send_sync<T: Send + Sync>() {}
-  fn check() {
-    assert_send_sync::<lopdf::Document>();
-  }
+  assert_send_sync::<lopdf::Document>();
 };
 
 // ── Shared helpers ──────────────────────────────────────────────
 
-use crate::core::document::{extract_all, extract_annotations};
-use crate::core::images::extract_images_raw;
-use crate::core::meta::extract_metadata;
-use crate::core::text::{extract_structured_text, extract_text};
+use crate::core::document::{
+  AnnotationConfig, DocumentConfig, StreamPagesConfig, collect_annotation_summary,
+  collect_clean_report, collect_document_actions, collect_highlighted_text, collect_uris,
+  extract_all, extract_annotations_with_config, stream_pages,
+};
+use crate::core::forms::collect_form_fields;
+use crate::core::images::{
+  DEFAULT_JPEG_QUALITY, ImageConfig, ImageEncoding, collect_image_bytes_per_page,
+  collect_image_filters, extract_images_raw, extract_images_with_config, extract_page_images_raw,
+  extract_sized_thumbnails_raw, extract_thumbnails_raw, page_contact_sheet,
+};
+use crate::core::inspect::{
+  collect_file_stats, collect_object_sizes, collect_page_complexity, collect_trailer_refs,
+  find_page_at_offset,
+};
+use crate::core::layout::{
+  collect_document_classification, collect_styled_lines, collect_vector_regions,
+  extract_grouped_paragraphs, extract_positioned_lines,
+};
+use crate::core::meta::{
+  collect_measurements, collect_page_tree_stats, extract_metadata, extract_per_page_boxes,
+  page_size_range, page_xmp_packet, single_page_box,
+};
+use crate::core::outline::{collect_outline, outline_from_headings};
+use crate::core::tagged::collect_tagged_blocks;
+use crate::core::text::{
+  CleanupProfile, DEFAULT_PAGE_MARKER, HeaderFooterMatching, StructuredTextConfig, TextConfig,
+  collect_script_histogram, collect_source_text_runs, collect_text_chunks,
+  collect_word_frequencies, diff_document_text, extract_body_text, extract_full_text_with_markers,
+  extract_structured_text, extract_structured_text_with_config, extract_text_preview,
+  extract_text_unprocessed, extract_text_with_config,
+};
 
 #[cfg(feature = "ocr")]
 use crate::core::document::extract_all_with_ocr;
 #[cfg(feature = "ocr")]
-use crate::core::ocr::{extract_text_with_ocr, normalize_max_threads};
+use crate::core::ocr::{extract_text_with_ocr, normalize_max_threads, stream_text_with_ocr};
 
 fn load_doc(buf: &[u8]) -> Result<Document> {
   Document::load_mem(buf).map_err(|e| Error::from_reason(format!("Failed to load PDF: {e}")))
 }
 
+/// Parse a standalone function's input, or reuse the already-parsed document
+/// when a `PdfDown` instance is passed instead of a buffer.
+fn input_doc(input: &Either<Buffer, &PdfDown>) -> Result<Arc<Document>> {
+  match input {
+    Either::A(buffer) => load_doc(buffer.as_ref()).map(Arc::new),
+    Either::B(pdf) => Ok(Arc::clone(&pdf.doc)),
+  }
+}
+
+/// Raw file bytes behind a standalone function's input.
+fn input_bytes<'a>(input: &'a Either<Buffer, &PdfDown>) -> &'a [u8] {
+  match input {
+    Either::A(buffer) => buffer.as_ref(),
+    Either::B(pdf) => pdf.raw.as_slice(),
+  }
+}
+
+/// Owned form of a standalone function's input, moved into async tasks so the
+/// worker thread either parses the bytes or reuses the `PdfDown` document.
+enum PdfSource {
+  Bytes(Vec<u8>),
+  Parsed {
+    doc: Arc<Document>,
+    raw: Arc<Vec<u8>>,
+  },
+}
+
+impl PdfSource {
+  fn new(input: Either<Buffer, &PdfDown>) -> Self {
+    match input {
+      Either::A(buffer) => PdfSource::Bytes(buffer.to_vec()),
+      Either::B(pdf) => PdfSource::Parsed {
+        doc: Arc::clone(&pdf.doc),
+        raw: Arc::clone(&pdf.raw),
+      },
+    }
+  }
+
+  fn document(&self) -> Result<Arc<Document>> {
+    match self {
+      PdfSource::Bytes(data) => load_doc(data).map(Arc::new),
+      PdfSource::Parsed { doc, .. } => Ok(Arc::clone(doc)),
+    }
+  }
+
+  fn bytes(&self) -> &[u8] {
+    match self {
+      PdfSource::Bytes(data) => data,
+      PdfSource::Parsed { raw, .. } => raw,
+    }
+  }
+}
+
+/// Resolve DocumentOptions into stage toggles — unset fields keep the stage
+/// enabled and leave page numbering alone.
+fn extract_document_config(opts: &Option<DocumentOptions>) -> DocumentConfig {
+  let defaults = DocumentConfig::default();
+  match opts {
+    Some(o) => DocumentConfig {
+      include_images: o.include_images.unwrap_or(defaults.include_images),
+      include_annotations: o
+        .include_annotations
+        .unwrap_or(defaults.include_annotations),
+      include_structured_text: o
+        .include_structured_text
+        .unwrap_or(defaults.include_structured_text),
+      reindex_pages: o.reindex_pages.unwrap_or(defaults.reindex_pages),
+      include_full_text: o.include_full_text.unwrap_or(defaults.include_full_text),
+    },
+    None => defaults,
+  }
+}
+
+fn extract_text_config(opts: &Option<TextOptions>) -> TextConfig {
+  let defaults = TextConfig::default();
+  match opts {
+    Some(o) => TextConfig {
+      strip_soft_hyphens: o.strip_soft_hyphens.unwrap_or(defaults.strip_soft_hyphens),
+      exact_spacing: o.exact_spacing.unwrap_or(defaults.exact_spacing),
+      form_values: o.form_values.unwrap_or(defaults.form_values),
+      strict: o.strict.unwrap_or(defaults.strict),
+      profile: o
+        .producer_profile
+        .as_ref()
+        .map(|profile| match profile {
+          ProducerProfile::Auto => CleanupProfile::Auto,
+          ProducerProfile::Latex => CleanupProfile::Latex,
+          ProducerProfile::Chromium => CleanupProfile::Chromium,
+          ProducerProfile::Word => CleanupProfile::Word,
+        })
+        .or(defaults.profile),
+    },
+    None => defaults,
+  }
+}
+
+fn extract_structured_text_config(opts: &Option<StructuredTextOptions>) -> StructuredTextConfig {
+  let defaults = StructuredTextConfig::default();
+  match opts {
+    Some(o) => StructuredTextConfig {
+      dedupe_headers: o.dedupe_headers.unwrap_or(defaults.dedupe_headers),
+      matching: HeaderFooterMatching {
+        dates: o.normalize_dates.unwrap_or(defaults.matching.dates),
+        ignore_case: o.ignore_case.unwrap_or(defaults.matching.ignore_case),
+      },
+      section_titles: o.section_titles.unwrap_or(defaults.section_titles),
+    },
+    None => defaults,
+  }
+}
+
+fn extract_stream_pages_config(opts: &Option<StreamPagesOptions>) -> StreamPagesConfig {
+  let defaults = StreamPagesConfig::default();
+  match opts {
+    Some(o) => StreamPagesConfig {
+      include_images: o.include_images.unwrap_or(defaults.include_images),
+      include_annotations: o
+        .include_annotations
+        .unwrap_or(defaults.include_annotations),
+    },
+    None => defaults,
+  }
+}
+
+fn extract_annotation_config(opts: &Option<AnnotationOptions>) -> AnnotationConfig {
+  let defaults = AnnotationConfig::default();
+  match opts {
+    Some(o) => AnnotationConfig {
+      rendered_text: o.rendered_text.unwrap_or(defaults.rendered_text),
+    },
+    None => defaults,
+  }
+}
+
+fn extract_image_config(opts: &Option<ImageOptions>) -> ImageConfig {
+  let defaults = ImageConfig::default();
+  match opts {
+    Some(o) => ImageConfig {
+      report_failures: o.report_failures.unwrap_or(defaults.report_failures),
+      max_dimension: o.max_dimension.or(defaults.max_dimension),
+      spill_dir: o.spill_dir.as_ref().map(PathBuf::from),
+      spill_threshold_bytes: o
+        .spill_threshold_bytes
+        .map_or(defaults.spill_threshold_bytes, |bytes| bytes.max(0) as u64),
+      grayscale: o.grayscale.unwrap_or(defaults.grayscale),
+      binarize: o.binarize.or(defaults.binarize),
+      compute_stats: o.compute_stats.unwrap_or(defaults.compute_stats),
+      apply_exif_orientation: o
+        .apply_exif_orientation
+        .unwrap_or(defaults.apply_exif_orientation),
+      encoding: match o.format {
+        Some(ImageOutputFormat::Jpeg) => ImageEncoding::Jpeg {
+          quality: o.jpeg_quality.unwrap_or(DEFAULT_JPEG_QUALITY).clamp(1, 100),
+        },
+        Some(ImageOutputFormat::Png) | None => defaults.encoding,
+      },
+    },
+    None => defaults,
+  }
+}
+
 /// Extract render mode (u8) from OcrOptions when render feature is enabled.
 #[cfg(all(feature = "ocr", feature = "render"))]
 fn extract_render_mode(opts: &Option<OcrOptions>) -> u8 {
@@ -98,26 +299,96 @@ fn maybe_init_pdfium(_opts: &Option<OcrOptions>) {}
 // ── Standalone sync functions ───────────────────────────────────
 
 #[napi]
-pub fn extract_text_per_page(buffer: Buffer) -> Result<Vec<PageText>> {
-  let doc = load_doc(buffer.as_ref())?;
-  extract_text(&doc)
+pub fn extract_text_per_page(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<TextOptions>,
+) -> Result<Vec<PageText>> {
+  let doc = input_doc(&input)?;
+  extract_text_with_config(&doc, extract_text_config(&opts))
+}
+
+/// Per-page text exactly as lopdf extracts it, skipping the cleanup
+/// `extractTextPerPage` does (CID font fallback, footer stripping, options).
+/// The fastest way to get text from clean documents.
+#[napi]
+pub fn extract_raw_text_per_page(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageText>> {
+  let doc = input_doc(&input)?;
+  Ok(extract_text_unprocessed(&doc))
+}
+
+/// The document's text split into chunks of at most `maxChars` characters,
+/// breaking at paragraphs, lines or words where possible, with `overlap`
+/// characters repeated between consecutive chunks.
+#[napi]
+pub fn extract_chunks(
+  input: Either<Buffer, &PdfDown>,
+  max_chars: u32,
+  overlap: u32,
+) -> Result<Vec<TextChunk>> {
+  let doc = input_doc(&input)?;
+  collect_text_chunks(&doc, max_chars, overlap)
+}
+
+/// Compare the per-page text of two versions of a document and mark the pages
+/// whose text changed, for incremental re-indexing. Pages that exist in only
+/// one version are reported as changed.
+#[napi]
+pub fn diff_text(
+  old: Either<Buffer, &PdfDown>,
+  new: Either<Buffer, &PdfDown>,
+) -> Result<Vec<PageDiff>> {
+  let (old, new) = (input_doc(&old)?, input_doc(&new)?);
+  diff_document_text(&old, &new)
+}
+
+/// Text runs tagged with the content stream (or form XObject) object and the
+/// index of the operator that shows them, for mapping extracted text back to
+/// its source in editors and verifiers.
+#[napi]
+pub fn extract_text_with_source_refs(
+  input: Either<Buffer, &PdfDown>,
+) -> Result<Vec<SourceTextRun>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_source_text_runs(&doc))
 }
 
 #[napi]
-pub fn pdf_metadata(buffer: Buffer) -> Result<PdfMeta> {
-  let doc = load_doc(buffer.as_ref())?;
+pub fn pdf_metadata(input: Either<Buffer, &PdfDown>) -> Result<PdfMeta> {
+  let doc = input_doc(&input)?;
   Ok(extract_metadata(&doc))
 }
 
 #[napi]
-pub fn extract_annotations_per_page(buffer: Buffer) -> Result<Vec<PageAnnotation>> {
-  let doc = load_doc(buffer.as_ref())?;
-  Ok(extract_annotations(&doc))
+pub fn extract_annotations_per_page(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<AnnotationOptions>,
+) -> Result<Vec<PageAnnotation>> {
+  let doc = input_doc(&input)?;
+  Ok(extract_annotations_with_config(
+    &doc,
+    extract_annotation_config(&opts),
+  ))
+}
+
+/// Per page, the annotation count and the union of the annotations'
+/// `/Rect`s — the extent an annotation overlay has to cover.
+#[napi]
+pub fn annotation_summary(input: Either<Buffer, &PdfDown>) -> Result<Vec<AnnotationSummary>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_annotation_summary(&doc))
+}
+
+/// The page text under each `Highlight` annotation, selected by its
+/// `/QuadPoints` — what a reviewer highlighted.
+#[napi]
+pub fn extract_highlighted_text(input: Either<Buffer, &PdfDown>) -> Result<Vec<HighlightedText>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_highlighted_text(&doc))
 }
 
 #[napi]
-pub fn extract_images_per_page(buffer: Buffer) -> Result<Vec<PageImage>> {
-  let doc = load_doc(buffer.as_ref())?;
+pub fn extract_images_per_page(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageImage>> {
+  let doc = input_doc(&input)?;
   Ok(
     extract_images_raw(&doc)
       .into_iter()
@@ -126,62 +397,332 @@ pub fn extract_images_per_page(buffer: Buffer) -> Result<Vec<PageImage>> {
   )
 }
 
+/// Decode the pre-rendered `/Thumb` thumbnail of each page that has one — a
+/// cheap preview compared to rendering the page. Pages without a thumbnail
+/// are omitted.
+#[napi]
+pub fn extract_thumbnails(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageImage>> {
+  let doc = input_doc(&input)?;
+  Ok(
+    extract_thumbnails_raw(&doc)
+      .into_iter()
+      .map(PageImage::from)
+      .collect(),
+  )
+}
+
+/// One small PNG preview per page, made from the page's largest image and
+/// downscaled so its longest side is at most `maxEdge` pixels. Only that image
+/// is decoded. Pages without images are omitted.
+#[napi]
+pub fn extract_thumbnails_sized(
+  input: Either<Buffer, &PdfDown>,
+  max_edge: u32,
+) -> Result<Vec<PageImage>> {
+  let doc = input_doc(&input)?;
+  Ok(
+    extract_sized_thumbnails_raw(&doc, max_edge)
+      .into_iter()
+      .map(PageImage::from)
+      .collect(),
+  )
+}
+
+/// Like `extractImagesPerPage`, but configurable; with `reportFailures` set,
+/// image XObjects that couldn't be decoded are listed in `failures`.
+#[napi]
+pub fn extract_images_with_options(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<ImageOptions>,
+) -> Result<ImageExtractionResult> {
+  let doc = input_doc(&input)?;
+  Ok(ImageExtractionResult::from(extract_images_with_config(
+    &doc,
+    &extract_image_config(&opts),
+  )?))
+}
+
+/// Distinct image `/Filter` names in the document (e.g. `DCTDecode`,
+/// `JBIG2Decode`) without decoding any pixels.
+#[napi]
+pub fn image_filters(input: Either<Buffer, &PdfDown>) -> Result<Vec<String>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_image_filters(&doc))
+}
+
+/// Per page, how many image XObjects it paints and their total stored stream
+/// size, without decoding — for estimating extraction cost up front.
+#[napi]
+pub fn image_bytes_per_page(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageImageBytes>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_image_bytes_per_page(&doc))
+}
+
+/// Depth, widest `/Kids` fan-out and node count of the `/Pages` tree, for
+/// diagnosing documents whose page tree is slow to walk.
 #[napi]
-pub fn pdf_document(buffer: Buffer) -> Result<PdfDocument> {
-  let doc = load_doc(buffer.as_ref())?;
-  Ok(PdfDocument::from(extract_all(&doc)?))
+pub fn page_tree_stats(input: Either<Buffer, &PdfDown>) -> Result<PageTreeStats> {
+  let doc = input_doc(&input)?;
+  Ok(collect_page_tree_stats(&doc))
+}
+
+#[napi]
+pub fn pdf_document(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<DocumentOptions>,
+) -> Result<PdfDocument> {
+  Ok(PdfDocument::from(extract_all(
+    input_doc(&input)?,
+    extract_document_config(&opts),
+  )?))
 }
 
 #[napi]
-pub fn extract_structured_text_per_page(buffer: Buffer) -> Result<Vec<StructuredPageText>> {
-  let doc = load_doc(buffer.as_ref())?;
+pub fn extract_structured_text_per_page(
+  input: Either<Buffer, &PdfDown>,
+) -> Result<Vec<StructuredPageText>> {
+  let doc = input_doc(&input)?;
   extract_structured_text(&doc)
 }
 
+/// Like `extractStructuredTextPerPage`, but configurable; with
+/// `dedupeHeaders` set, the header and footer shared by several pages are
+/// returned once instead of on every page.
+#[napi]
+pub fn extract_structured_text_with_options(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<StructuredTextOptions>,
+) -> Result<StructuredTextResult> {
+  let doc = input_doc(&input)?;
+  extract_structured_text_with_config(&doc, extract_structured_text_config(&opts))
+}
+
+/// Text lines per page with their bounding boxes, built from positioned words
+/// that share a baseline.
+#[napi]
+pub fn extract_lines_with_position(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageLines>> {
+  let doc = input_doc(&input)?;
+  Ok(extract_positioned_lines(&doc))
+}
+
+/// Text lines per page as in `extractLinesWithPosition`, each tagged with its
+/// font size and whether it is bold or italic (for telling headings from body
+/// text).
+#[napi]
+pub fn extract_styled_lines(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageStyledLines>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_styled_lines(&doc))
+}
+
+/// Text per page grouped into paragraphs: consecutive lines are joined with
+/// spaces until a large vertical gap.
+#[napi]
+pub fn extract_paragraphs(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageParagraphs>> {
+  let doc = input_doc(&input)?;
+  Ok(extract_grouped_paragraphs(&doc))
+}
+
+/// Bounding boxes of painted vector content (paths drawn with m/l/c/re and
+/// stroked or filled) per page, with touching paths merged into regions.
+#[napi]
+pub fn extract_vector_regions(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageVectorRegions>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_vector_regions(&doc))
+}
+
+/// Text of a tagged PDF in the logical reading order of its structure tree,
+/// one entry per block-level element. Returns an empty array for untagged
+/// documents; use `extractText` for those.
+#[napi]
+pub fn extract_tagged_text(input: Either<Buffer, &PdfDown>) -> Result<Vec<TaggedBlock>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_tagged_blocks(&doc))
+}
+
+/// The document's bookmarks, nested as a viewer shows them. PDFs without an
+/// `/Outlines` tree fall back to their named destinations (`/Dests`), listed
+/// flat in page order with each name as the title.
+#[napi]
+pub fn extract_outline(input: Either<Buffer, &PdfDown>) -> Result<Vec<OutlineItem>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_outline(&doc))
+}
+
+/// An outline synthesized from the document's headings, for PDFs without
+/// bookmarks: lines set larger than the body text (or bold at body size) are
+/// classified as H1–H3 and nested by level.
+#[napi]
+pub fn synthesize_outline(input: Either<Buffer, &PdfDown>) -> Result<Vec<OutlineItem>> {
+  let doc = input_doc(&input)?;
+  Ok(outline_from_headings(&doc))
+}
+
+/// Terminal AcroForm fields with their values, and the order a viewer tabs
+/// through them. Checkboxes and radio groups also report `checked`. Returns
+/// empty arrays when there's no form.
+#[napi]
+pub fn extract_form_fields(input: Either<Buffer, &PdfDown>) -> Result<FormFieldsResult> {
+  let doc = input_doc(&input)?;
+  Ok(collect_form_fields(&doc))
+}
+
+/// Every URI the document references — on link annotations, in other URI
+/// actions (outlines, open actions, name trees) and as URLs in page text —
+/// each with its page and where it was found.
+#[napi]
+pub fn extract_all_uris(input: Either<Buffer, &PdfDown>) -> Result<Vec<UriRef>> {
+  let doc = input_doc(&input)?;
+  collect_uris(&doc)
+}
+
+/// What the document does by itself: its `/OpenAction` and the catalog's
+/// additional actions (on close, save and print), with `/Next` chains
+/// expanded — e.g. scripts that run on open or print dialogs it triggers.
+#[napi]
+pub fn document_actions(input: Either<Buffer, &PdfDown>) -> Result<Vec<DocumentAction>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_document_actions(&doc))
+}
+
+/// Whether the document is free of active content: JavaScript, `Launch`
+/// actions and embedded files anywhere in it, plus whether it has an
+/// `/OpenAction` — one call for "is this safe to render?".
+#[napi]
+pub fn clean_report(input: Either<Buffer, &PdfDown>) -> Result<CleanReport> {
+  let doc = input_doc(&input)?;
+  Ok(collect_clean_report(&doc))
+}
+
+/// Real-world scales of page viewports (`/VP` with a rectilinear `/Measure`)
+/// in engineering drawings and maps, for converting distances on the page to
+/// real units.
+#[napi]
+pub fn extract_measurements(input: Either<Buffer, &PdfDown>) -> Result<Vec<Measurement>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_measurements(&doc))
+}
+
+/// Count the characters of the document's text by Unicode script (Latin,
+/// Han, Cyrillic, Arabic, …), most frequent first — a cheap way to route
+/// "mostly CJK" documents without full language detection.
+#[napi]
+pub fn script_histogram(input: Either<Buffer, &PdfDown>) -> Result<Vec<ScriptCount>> {
+  let doc = input_doc(&input)?;
+  collect_script_histogram(&doc)
+}
+
+/// Term frequencies per page for a lightweight search index: the text is
+/// lowercased, split on every non-alphanumeric character, and words shorter
+/// than `minLength` characters are dropped.
+#[napi]
+pub fn word_frequencies(
+  input: Either<Buffer, &PdfDown>,
+  min_length: u32,
+) -> Result<Vec<PageWordFreq>> {
+  let doc = input_doc(&input)?;
+  collect_word_frequencies(&doc, min_length)
+}
+
+/// Return the 1-based page whose page object or content stream is stored at
+/// `offset` in the raw file, or `null` if the offset doesn't fall inside one.
+#[napi]
+pub fn page_at_offset(input: Either<Buffer, &PdfDown>, offset: u32) -> Result<Option<u32>> {
+  let doc = input_doc(&input)?;
+  Ok(find_page_at_offset(&doc, input_bytes(&input), offset))
+}
+
+/// List every stream object with its stored and decoded size, largest first.
+#[napi]
+pub fn object_size_report(input: Either<Buffer, &PdfDown>) -> Result<Vec<ObjectSize>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_object_sizes(&doc))
+}
+
+/// The object ids the trailer's `/Root`, `/Info` and `/Encrypt` entries
+/// point at, for debugging malformed files.
+#[napi]
+pub fn trailer_refs(input: Either<Buffer, &PdfDown>) -> Result<TrailerRefs> {
+  let doc = input_doc(&input)?;
+  Ok(collect_trailer_refs(&doc))
+}
+
+/// File size and object, stream, image and font counts, without extracting
+/// anything.
+#[napi]
+pub fn file_stats(input: Either<Buffer, &PdfDown>) -> Result<FileStats> {
+  let doc = input_doc(&input)?;
+  Ok(collect_file_stats(&doc, input_bytes(&input).len()))
+}
+
+/// Whether the document is likely scanned, with the fraction (0–1) of pages
+/// that have negligible native text but a full-page image. Every page's text
+/// and layout is read, so this costs about as much as extracting the text.
+#[napi]
+pub fn classify_document(input: Either<Buffer, &PdfDown>) -> Result<DocumentClassification> {
+  let doc = input_doc(&input)?;
+  Ok(collect_document_classification(&doc))
+}
+
+/// Content size, operator count and XObject count of every page, for
+/// predicting which pages are slow to render or extract.
+#[napi]
+pub fn page_complexity(input: Either<Buffer, &PdfDown>) -> Result<Vec<PageComplexity>> {
+  let doc = input_doc(&input)?;
+  Ok(collect_page_complexity(&doc))
+}
+
 #[cfg(feature = "ocr")]
 #[napi]
 pub fn extract_text_with_ocr_per_page(
-  buffer: Buffer,
+  input: Either<Buffer, &PdfDown>,
   opts: Option<OcrOptions>,
 ) -> Result<Vec<OcrPageText>> {
   maybe_init_pdfium(&opts);
-  let doc = load_doc(buffer.as_ref())?;
+  let doc = input_doc(&input)?;
   let lang = opts
     .as_ref()
     .and_then(|o| o.lang.as_deref())
     .unwrap_or("eng");
+  let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.as_deref());
   let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
   let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
   let render_dpi = extract_render_dpi(&opts);
   let render_mode = extract_render_mode(&opts);
   extract_text_with_ocr(
     &doc,
-    buffer.as_ref(),
+    input_bytes(&input),
     lang,
+    tessdata_path,
     min_len,
     max_threads,
     render_dpi,
     render_mode,
   )
+  .map(|t| t.pages)
 }
 
 #[cfg(feature = "ocr")]
 #[napi]
-pub fn pdf_document_ocr(buffer: Buffer, opts: Option<OcrOptions>) -> Result<PdfDocumentOcr> {
+pub fn pdf_document_ocr(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<OcrOptions>,
+) -> Result<PdfDocumentOcr> {
   maybe_init_pdfium(&opts);
-  let doc = load_doc(buffer.as_ref())?;
+  let doc = input_doc(&input)?;
   let lang = opts
     .as_ref()
     .and_then(|o| o.lang.as_deref())
     .unwrap_or("eng");
+  let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.as_deref());
   let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
   let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
   let render_dpi = extract_render_dpi(&opts);
   let render_mode = extract_render_mode(&opts);
   Ok(PdfDocumentOcr::from(extract_all_with_ocr(
     &doc,
-    buffer.as_ref(),
+    input_bytes(&input),
     lang,
+    tessdata_path,
     min_len,
     max_threads,
     render_dpi,
@@ -193,7 +734,7 @@ pub fn pdf_document_ocr(buffer: Buffer, opts: Option<OcrOptions>) -> Result<PdfD
 
 #[cfg(feature = "render")]
 pub struct RenderPagesTask {
-  data: Vec<u8>,
+  source: PdfSource,
   dpi: u32,
   mode: u8,
 }
@@ -209,7 +750,7 @@ impl Task for RenderPagesTask {
 
     let dpi = self.dpi;
     let mode = self.mode;
-    let pdf_bytes = &self.data;
+    let pdf_bytes = self.source.bytes();
 
     if mode == RENDER_MODE_NEVER {
       return Ok(Vec::new());
@@ -218,7 +759,7 @@ impl Task for RenderPagesTask {
     crate::core::render::ensure_pdfium_with_path(None)
       .map_err(|e| Error::from_reason(e.to_string()))?;
 
-    let doc = load_doc(pdf_bytes)?;
+    let doc = self.source.document()?;
     let pages = doc.get_pages();
     let page_count = pages.len() as u16;
 
@@ -266,7 +807,7 @@ impl Task for RenderPagesTask {
 #[cfg(feature = "render")]
 #[napi]
 pub fn render_pages_async(
-  buffer: Buffer,
+  input: Either<Buffer, &PdfDown>,
   opts: Option<RenderOptions>,
 ) -> AsyncTask<RenderPagesTask> {
   use crate::core::render::{RENDER_MODE_ALWAYS, RENDER_MODE_AUTO, RENDER_MODE_NEVER};
@@ -281,7 +822,7 @@ pub fn render_pages_async(
     })
     .unwrap_or(RENDER_MODE_ALWAYS);
   AsyncTask::new(RenderPagesTask {
-    data: buffer.to_vec(),
+    source: PdfSource::new(input),
     dpi,
     mode,
   })
@@ -302,7 +843,10 @@ pub fn capabilities() -> Capabilities {
 
 // ── Standalone async functions (libuv thread pool via AsyncTask) ─
 
-pub struct ExtractTextTask(Vec<u8>);
+pub struct ExtractTextTask {
+  source: PdfSource,
+  config: TextConfig,
+}
 
 #[napi]
 impl Task for ExtractTextTask {
@@ -310,8 +854,64 @@ impl Task for ExtractTextTask {
   type JsValue = Vec<PageText>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    let doc = load_doc(&self.0)?;
-    extract_text(&doc)
+    let doc = self.source.document()?;
+    extract_text_with_config(&doc, self.config)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_text_per_page_async(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<TextOptions>,
+) -> AsyncTask<ExtractTextTask> {
+  AsyncTask::new(ExtractTextTask {
+    source: PdfSource::new(input),
+    config: extract_text_config(&opts),
+  })
+}
+
+pub struct ExtractRawTextTask(PdfSource);
+
+#[napi]
+impl Task for ExtractRawTextTask {
+  type Output = Vec<PageText>;
+  type JsValue = Vec<PageText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(extract_text_unprocessed(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_raw_text_per_page_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractRawTextTask> {
+  AsyncTask::new(ExtractRawTextTask(PdfSource::new(input)))
+}
+
+pub struct ExtractChunksTask {
+  source: PdfSource,
+  max_chars: u32,
+  overlap: u32,
+}
+
+#[napi]
+impl Task for ExtractChunksTask {
+  type Output = Vec<TextChunk>;
+  type JsValue = Vec<TextChunk>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.source.document()?;
+    collect_text_chunks(&doc, self.max_chars, self.overlap)
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -320,11 +920,19 @@ impl Task for ExtractTextTask {
 }
 
 #[napi]
-pub fn extract_text_per_page_async(buffer: Buffer) -> AsyncTask<ExtractTextTask> {
-  AsyncTask::new(ExtractTextTask(buffer.to_vec()))
+pub fn extract_chunks_async(
+  input: Either<Buffer, &PdfDown>,
+  max_chars: u32,
+  overlap: u32,
+) -> AsyncTask<ExtractChunksTask> {
+  AsyncTask::new(ExtractChunksTask {
+    source: PdfSource::new(input),
+    max_chars,
+    overlap,
+  })
 }
 
-pub struct ExtractImagesTask(Vec<u8>);
+pub struct ExtractImagesTask(PdfSource);
 
 #[napi]
 impl Task for ExtractImagesTask {
@@ -332,7 +940,7 @@ impl Task for ExtractImagesTask {
   type JsValue = Vec<PageImage>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    let doc = load_doc(&self.0)?;
+    let doc = self.0.document()?;
     Ok(extract_images_raw(&doc))
   }
 
@@ -342,86 +950,108 @@ impl Task for ExtractImagesTask {
 }
 
 #[napi]
-pub fn extract_images_per_page_async(buffer: Buffer) -> AsyncTask<ExtractImagesTask> {
-  AsyncTask::new(ExtractImagesTask(buffer.to_vec()))
+pub fn extract_images_per_page_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractImagesTask> {
+  AsyncTask::new(ExtractImagesTask(PdfSource::new(input)))
 }
 
-pub struct PdfMetaTask(Vec<u8>);
+pub struct ExtractThumbnailsTask(PdfSource);
 
 #[napi]
-impl Task for PdfMetaTask {
-  type Output = PdfMeta;
-  type JsValue = PdfMeta;
+impl Task for ExtractThumbnailsTask {
+  type Output = Vec<RawPageImage>;
+  type JsValue = Vec<PageImage>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    let doc = load_doc(&self.0)?;
-    Ok(extract_metadata(&doc))
+    let doc = self.0.document()?;
+    Ok(extract_thumbnails_raw(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    Ok(output)
+    Ok(output.into_iter().map(PageImage::from).collect())
   }
 }
 
-pub struct ExtractAnnotationsTask(Vec<u8>);
+#[napi]
+pub fn extract_thumbnails_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractThumbnailsTask> {
+  AsyncTask::new(ExtractThumbnailsTask(PdfSource::new(input)))
+}
+
+pub struct ExtractSizedThumbnailsTask {
+  source: PdfSource,
+  max_edge: u32,
+}
 
 #[napi]
-impl Task for ExtractAnnotationsTask {
-  type Output = Vec<PageAnnotation>;
-  type JsValue = Vec<PageAnnotation>;
+impl Task for ExtractSizedThumbnailsTask {
+  type Output = Vec<RawPageImage>;
+  type JsValue = Vec<PageImage>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    let doc = load_doc(&self.0)?;
-    Ok(extract_annotations(&doc))
+    let doc = self.source.document()?;
+    Ok(extract_sized_thumbnails_raw(&doc, self.max_edge))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    Ok(output)
+    Ok(output.into_iter().map(PageImage::from).collect())
   }
 }
 
 #[napi]
-pub fn extract_annotations_per_page_async(buffer: Buffer) -> AsyncTask<ExtractAnnotationsTask> {
-  AsyncTask::new(ExtractAnnotationsTask(buffer.to_vec()))
+pub fn extract_thumbnails_sized_async(
+  input: Either<Buffer, &PdfDown>,
+  max_edge: u32,
+) -> AsyncTask<ExtractSizedThumbnailsTask> {
+  AsyncTask::new(ExtractSizedThumbnailsTask {
+    source: PdfSource::new(input),
+    max_edge,
+  })
 }
 
-#[napi]
-pub fn pdf_metadata_async(buffer: Buffer) -> AsyncTask<PdfMetaTask> {
-  AsyncTask::new(PdfMetaTask(buffer.to_vec()))
+pub struct ExtractImagesWithOptionsTask {
+  source: PdfSource,
+  config: ImageConfig,
 }
 
-pub struct PdfDocumentTask(Vec<u8>);
-
 #[napi]
-impl Task for PdfDocumentTask {
-  type Output = RawPdfDocument;
-  type JsValue = PdfDocument;
+impl Task for ExtractImagesWithOptionsTask {
+  type Output = RawImageExtraction;
+  type JsValue = ImageExtractionResult;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    let doc = load_doc(&self.0)?;
-    extract_all(&doc)
+    let doc = self.source.document()?;
+    extract_images_with_config(&doc, &self.config)
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    Ok(PdfDocument::from(output))
+    Ok(ImageExtractionResult::from(output))
   }
 }
 
 #[napi]
-pub fn pdf_document_async(buffer: Buffer) -> AsyncTask<PdfDocumentTask> {
-  AsyncTask::new(PdfDocumentTask(buffer.to_vec()))
+pub fn extract_images_with_options_async(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<ImageOptions>,
+) -> AsyncTask<ExtractImagesWithOptionsTask> {
+  AsyncTask::new(ExtractImagesWithOptionsTask {
+    source: PdfSource::new(input),
+    config: extract_image_config(&opts),
+  })
 }
 
-pub struct ExtractStructuredTextTask(Vec<u8>);
+pub struct ImageFiltersTask(PdfSource);
 
 #[napi]
-impl Task for ExtractStructuredTextTask {
-  type Output = Vec<StructuredPageText>;
-  type JsValue = Vec<StructuredPageText>;
+impl Task for ImageFiltersTask {
+  type Output = Vec<String>;
+  type JsValue = Vec<String>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    let doc = load_doc(&self.0)?;
-    extract_structured_text(&doc)
+    let doc = self.0.document()?;
+    Ok(collect_image_filters(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -430,39 +1060,20 @@ impl Task for ExtractStructuredTextTask {
 }
 
 #[napi]
-pub fn extract_structured_text_per_page_async(
-  buffer: Buffer,
-) -> AsyncTask<ExtractStructuredTextTask> {
-  AsyncTask::new(ExtractStructuredTextTask(buffer.to_vec()))
+pub fn image_filters_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<ImageFiltersTask> {
+  AsyncTask::new(ImageFiltersTask(PdfSource::new(input)))
 }
 
-#[cfg(feature = "ocr")]
-pub struct ExtractTextOcrTask {
-  data: Vec<u8>,
-  lang: String,
-  min_len: u32,
-  max_threads: u32,
-  render_dpi: u32,
-  render_mode: u8,
-}
+pub struct ImageBytesPerPageTask(PdfSource);
 
-#[cfg(feature = "ocr")]
 #[napi]
-impl Task for ExtractTextOcrTask {
-  type Output = Vec<OcrPageText>;
-  type JsValue = Vec<OcrPageText>;
+impl Task for ImageBytesPerPageTask {
+  type Output = Vec<PageImageBytes>;
+  type JsValue = Vec<PageImageBytes>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    let doc = load_doc(&self.data)?;
-    extract_text_with_ocr(
-      &doc,
-      &self.data,
-      &self.lang,
-      self.min_len,
-      self.max_threads,
-      self.render_dpi,
-      self.render_mode,
-    )
+    let doc = self.0.document()?;
+    Ok(collect_image_bytes_per_page(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -470,103 +1081,54 @@ impl Task for ExtractTextOcrTask {
   }
 }
 
-#[cfg(feature = "ocr")]
 #[napi]
-pub fn extract_text_with_ocr_per_page_async(
-  buffer: Buffer,
-  opts: Option<OcrOptions>,
-) -> AsyncTask<ExtractTextOcrTask> {
-  maybe_init_pdfium(&opts);
-  let lang = opts
-    .as_ref()
-    .and_then(|o| o.lang.clone())
-    .unwrap_or_else(|| "eng".to_string());
-  let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
-  let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
-  let render_dpi = extract_render_dpi(&opts);
-  let render_mode = extract_render_mode(&opts);
-  AsyncTask::new(ExtractTextOcrTask {
-    data: buffer.to_vec(),
-    lang,
-    min_len,
-    max_threads,
-    render_dpi,
-    render_mode,
-  })
+pub fn image_bytes_per_page_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ImageBytesPerPageTask> {
+  AsyncTask::new(ImageBytesPerPageTask(PdfSource::new(input)))
 }
 
-#[cfg(feature = "ocr")]
-pub struct PdfDocumentOcrTask {
-  data: Vec<u8>,
-  lang: String,
-  min_len: u32,
-  max_threads: u32,
-  render_dpi: u32,
-  render_mode: u8,
+pub struct DiffTextTask {
+  old: PdfSource,
+  new: PdfSource,
 }
 
-#[cfg(feature = "ocr")]
 #[napi]
-impl Task for PdfDocumentOcrTask {
-  type Output = RawPdfDocumentOcr;
-  type JsValue = PdfDocumentOcr;
+impl Task for DiffTextTask {
+  type Output = Vec<PageDiff>;
+  type JsValue = Vec<PageDiff>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    let doc = load_doc(&self.data)?;
-    extract_all_with_ocr(
-      &doc,
-      &self.data,
-      &self.lang,
-      self.min_len,
-      self.max_threads,
-      self.render_dpi,
-      self.render_mode,
-    )
+    let (old, new) = (self.old.document()?, self.new.document()?);
+    diff_document_text(&old, &new)
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    Ok(PdfDocumentOcr::from(output))
+    Ok(output)
   }
 }
 
-#[cfg(feature = "ocr")]
 #[napi]
-pub fn pdf_document_ocr_async(
-  buffer: Buffer,
-  opts: Option<OcrOptions>,
-) -> AsyncTask<PdfDocumentOcrTask> {
-  maybe_init_pdfium(&opts);
-  let lang = opts
-    .as_ref()
-    .and_then(|o| o.lang.clone())
-    .unwrap_or_else(|| "eng".to_string());
-  let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
-  let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
-  let render_dpi = extract_render_dpi(&opts);
-  let render_mode = extract_render_mode(&opts);
-  AsyncTask::new(PdfDocumentOcrTask {
-    data: buffer.to_vec(),
-    lang,
-    min_len,
-    max_threads,
-    render_dpi,
-    render_mode,
+pub fn diff_text_async(
+  old: Either<Buffer, &PdfDown>,
+  new: Either<Buffer, &PdfDown>,
+) -> AsyncTask<DiffTextTask> {
+  AsyncTask::new(DiffTextTask {
+    old: PdfSource::new(old),
+    new: PdfSource::new(new),
   })
 }
 
-// ── Step 3: Class-based API with Arc<Document> ──────────────────
-
-/// Shared-document task types for class async methods.
-/// These use Arc<Document> instead of raw bytes, avoiding re-parsing.
-pub struct SharedExtractTextTask(Arc<Document>);
+pub struct SourceTextRunsTask(PdfSource);
 
 #[napi]
-impl Task for SharedExtractTextTask {
-  type Output = Vec<PageText>;
-  type JsValue = Vec<PageText>;
+impl Task for SourceTextRunsTask {
+  type Output = Vec<SourceTextRun>;
+  type JsValue = Vec<SourceTextRun>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    extract_text(&self.0)
+    let doc = self.0.document()?;
+    Ok(collect_source_text_runs(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -574,31 +1136,43 @@ impl Task for SharedExtractTextTask {
   }
 }
 
-pub struct SharedExtractImagesTask(Arc<Document>);
+#[napi]
+pub fn extract_text_with_source_refs_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<SourceTextRunsTask> {
+  AsyncTask::new(SourceTextRunsTask(PdfSource::new(input)))
+}
+
+pub struct PdfMetaTask(PdfSource);
 
 #[napi]
-impl Task for SharedExtractImagesTask {
-  type Output = Vec<RawPageImage>;
-  type JsValue = Vec<PageImage>;
+impl Task for PdfMetaTask {
+  type Output = PdfMeta;
+  type JsValue = PdfMeta;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    Ok(extract_images_raw(&self.0))
+    let doc = self.0.document()?;
+    Ok(extract_metadata(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    Ok(output.into_iter().map(PageImage::from).collect())
+    Ok(output)
   }
 }
 
-pub struct SharedExtractAnnotationsTask(Arc<Document>);
+pub struct ExtractAnnotationsTask {
+  source: PdfSource,
+  config: AnnotationConfig,
+}
 
 #[napi]
-impl Task for SharedExtractAnnotationsTask {
+impl Task for ExtractAnnotationsTask {
   type Output = Vec<PageAnnotation>;
   type JsValue = Vec<PageAnnotation>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    Ok(extract_annotations(&self.0))
+    let doc = self.source.document()?;
+    Ok(extract_annotations_with_config(&doc, self.config))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -606,15 +1180,27 @@ impl Task for SharedExtractAnnotationsTask {
   }
 }
 
-pub struct SharedPdfMetaTask(Arc<Document>);
+#[napi]
+pub fn extract_annotations_per_page_async(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<AnnotationOptions>,
+) -> AsyncTask<ExtractAnnotationsTask> {
+  AsyncTask::new(ExtractAnnotationsTask {
+    source: PdfSource::new(input),
+    config: extract_annotation_config(&opts),
+  })
+}
+
+pub struct AnnotationSummaryTask(PdfSource);
 
 #[napi]
-impl Task for SharedPdfMetaTask {
-  type Output = PdfMeta;
-  type JsValue = PdfMeta;
+impl Task for AnnotationSummaryTask {
+  type Output = Vec<AnnotationSummary>;
+  type JsValue = Vec<AnnotationSummary>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    Ok(extract_metadata(&self.0))
+    let doc = self.0.document()?;
+    Ok(collect_annotation_summary(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -622,31 +1208,52 @@ impl Task for SharedPdfMetaTask {
   }
 }
 
-pub struct SharedPdfDocumentTask(Arc<Document>);
+#[napi]
+pub fn annotation_summary_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<AnnotationSummaryTask> {
+  AsyncTask::new(AnnotationSummaryTask(PdfSource::new(input)))
+}
+
+pub struct ExtractHighlightedTextTask(PdfSource);
 
 #[napi]
-impl Task for SharedPdfDocumentTask {
-  type Output = RawPdfDocument;
-  type JsValue = PdfDocument;
+impl Task for ExtractHighlightedTextTask {
+  type Output = Vec<HighlightedText>;
+  type JsValue = Vec<HighlightedText>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    extract_all(&self.0)
+    let doc = self.0.document()?;
+    Ok(collect_highlighted_text(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    Ok(PdfDocument::from(output))
+    Ok(output)
   }
 }
 
-pub struct SharedStructuredTextTask(Arc<Document>);
+#[napi]
+pub fn extract_highlighted_text_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractHighlightedTextTask> {
+  AsyncTask::new(ExtractHighlightedTextTask(PdfSource::new(input)))
+}
 
 #[napi]
-impl Task for SharedStructuredTextTask {
-  type Output = Vec<StructuredPageText>;
-  type JsValue = Vec<StructuredPageText>;
+pub fn pdf_metadata_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<PdfMetaTask> {
+  AsyncTask::new(PdfMetaTask(PdfSource::new(input)))
+}
+
+pub struct ObjectSizeReportTask(PdfSource);
+
+#[napi]
+impl Task for ObjectSizeReportTask {
+  type Output = Vec<ObjectSize>;
+  type JsValue = Vec<ObjectSize>;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    extract_structured_text(&self.0)
+    let doc = self.0.document()?;
+    Ok(collect_object_sizes(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -654,33 +1261,23 @@ impl Task for SharedStructuredTextTask {
   }
 }
 
-#[cfg(feature = "ocr")]
-pub struct SharedExtractTextOcrTask {
-  doc: Arc<Document>,
-  raw: Arc<Vec<u8>>,
-  lang: String,
-  min_len: u32,
-  max_threads: u32,
-  render_dpi: u32,
-  render_mode: u8,
+#[napi]
+pub fn object_size_report_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ObjectSizeReportTask> {
+  AsyncTask::new(ObjectSizeReportTask(PdfSource::new(input)))
 }
 
-#[cfg(feature = "ocr")]
+pub struct TrailerRefsTask(PdfSource);
+
 #[napi]
-impl Task for SharedExtractTextOcrTask {
-  type Output = Vec<OcrPageText>;
-  type JsValue = Vec<OcrPageText>;
+impl Task for TrailerRefsTask {
+  type Output = TrailerRefs;
+  type JsValue = TrailerRefs;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    extract_text_with_ocr(
-      &self.doc,
-      &self.raw,
-      &self.lang,
-      self.min_len,
-      self.max_threads,
-      self.render_dpi,
-      self.render_mode,
-    )
+    let doc = self.0.document()?;
+    Ok(collect_trailer_refs(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
@@ -688,160 +1285,1737 @@ impl Task for SharedExtractTextOcrTask {
   }
 }
 
-#[cfg(feature = "ocr")]
-pub struct SharedPdfDocumentOcrTask {
-  doc: Arc<Document>,
-  raw: Arc<Vec<u8>>,
-  lang: String,
-  min_len: u32,
-  max_threads: u32,
-  render_dpi: u32,
-  render_mode: u8,
+#[napi]
+pub fn trailer_refs_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<TrailerRefsTask> {
+  AsyncTask::new(TrailerRefsTask(PdfSource::new(input)))
 }
 
-#[cfg(feature = "ocr")]
+pub struct FileStatsTask(PdfSource);
+
 #[napi]
-impl Task for SharedPdfDocumentOcrTask {
-  type Output = RawPdfDocumentOcr;
-  type JsValue = PdfDocumentOcr;
+impl Task for FileStatsTask {
+  type Output = FileStats;
+  type JsValue = FileStats;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    extract_all_with_ocr(
-      &self.doc,
-      &self.raw,
-      &self.lang,
-      self.min_len,
-      self.max_threads,
-      self.render_dpi,
-      self.render_mode,
-    )
+    let doc = self.0.document()?;
+    Ok(collect_file_stats(&doc, self.0.bytes().len()))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    Ok(PdfDocumentOcr::from(output))
+    Ok(output)
   }
 }
 
-#[cfg(feature = "render")]
-pub struct SharedRenderPagesTask {
-  raw: Arc<Vec<u8>>,
-  dpi: u32,
-  mode: u8,
+#[napi]
+pub fn file_stats_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<FileStatsTask> {
+  AsyncTask::new(FileStatsTask(PdfSource::new(input)))
 }
 
-#[cfg(feature = "render")]
+pub struct ClassifyDocumentTask(PdfSource);
+
 #[napi]
-impl Task for SharedRenderPagesTask {
-  type Output = Vec<RawRenderedPage>;
-  type JsValue = Vec<RenderedPage>;
+impl Task for ClassifyDocumentTask {
+  type Output = DocumentClassification;
+  type JsValue = DocumentClassification;
 
   fn compute(&mut self) -> Result<Self::Output> {
-    use crate::core::render::{RENDER_MODE_AUTO, RENDER_MODE_NEVER};
-
-    let dpi = self.dpi;
-    let mode = self.mode;
-    let pdf_bytes = &self.raw;
+    let doc = self.0.document()?;
+    Ok(collect_document_classification(&doc))
+  }
 
-    if mode == RENDER_MODE_NEVER {
-      return Ok(Vec::new());
-    }
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
 
-    crate::core::render::ensure_pdfium_with_path(None)
-      .map_err(|e| Error::from_reason(e.to_string()))?;
+#[napi]
+pub fn classify_document_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<ClassifyDocumentTask> {
+  AsyncTask::new(ClassifyDocumentTask(PdfSource::new(input)))
+}
 
-    let doc = load_doc(pdf_bytes)?;
-    let pages = doc.get_pages();
-    let page_count = pages.len() as u16;
+pub struct PageComplexityTask(PdfSource);
 
-    let indices: Vec<u16> = if mode == RENDER_MODE_AUTO {
-      pages
-        .iter()
-        .filter_map(|(&page_num, &page_id)| {
-          let raw = doc.extract_text(&[page_num]).unwrap_or_default();
-          let non_ws: usize = raw.chars().filter(|c| !c.is_whitespace()).count();
-          if non_ws > 0 {
-            return None;
-          }
-          let has_images =
-            !crate::core::images::collect_page_decoded_images(&doc, page_id).is_empty();
-          if has_images {
-            return None;
-          }
-          Some((page_num - 1) as u16)
-        })
-        .collect()
-    } else {
-      (0..page_count).collect()
-    };
+#[napi]
+impl Task for PageComplexityTask {
+  type Output = Vec<PageComplexity>;
+  type JsValue = Vec<PageComplexity>;
 
-    let rendered = crate::core::render::render_pages_to_png(pdf_bytes, &indices, dpi);
-    Ok(
-      rendered
-        .into_iter()
-        .map(|(idx, w, h, data)| RawRenderedPage {
-          page: (idx as u32) + 1,
-          width: w,
-          height: h,
-          dpi,
-          data,
-        })
-        .collect(),
-    )
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_page_complexity(&doc))
   }
 
   fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
-    Ok(output.into_iter().map(RenderedPage::from).collect())
+    Ok(output)
   }
 }
 
 #[napi]
-pub struct PdfDown {
-  doc: Arc<Document>,
-  #[allow(dead_code)] // used when ocr or render features are enabled
-  raw: Arc<Vec<u8>>,
+pub fn page_complexity_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<PageComplexityTask> {
+  AsyncTask::new(PageComplexityTask(PdfSource::new(input)))
+}
+
+pub struct PdfDocumentTask {
+  source: PdfSource,
+  config: DocumentConfig,
 }
 
 #[napi]
-impl PdfDown {
-  #[napi(constructor)]
-  pub fn new(buffer: Buffer) -> Result<Self> {
-    let bytes = buffer.to_vec();
-    let doc = Document::load_mem(&bytes)
-      .map_err(|e| Error::from_reason(format!("Failed to load PDF: {e}")))?;
-    Ok(PdfDown {
-      doc: Arc::new(doc),
-      raw: Arc::new(bytes),
-    })
-  }
+impl Task for PdfDocumentTask {
+  type Output = RawPdfDocument;
+  type JsValue = PdfDocument;
 
-  #[napi]
-  pub fn text_per_page(&self) -> Result<Vec<PageText>> {
-    extract_text(&self.doc)
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_all(self.source.document()?, self.config)
   }
 
-  #[napi]
-  pub fn images_per_page(&self) -> Result<Vec<PageImage>> {
-    Ok(
-      extract_images_raw(&self.doc)
-        .into_iter()
-        .map(PageImage::from)
-        .collect(),
-    )
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(PdfDocument::from(output))
   }
+}
 
-  #[napi]
-  pub fn annotations_per_page(&self) -> Vec<PageAnnotation> {
-    extract_annotations(&self.doc)
-  }
+#[napi]
+pub fn pdf_document_async(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<DocumentOptions>,
+) -> AsyncTask<PdfDocumentTask> {
+  AsyncTask::new(PdfDocumentTask {
+    source: PdfSource::new(input),
+    config: extract_document_config(&opts),
+  })
+}
+
+pub struct ExtractStructuredTextTask(PdfSource);
+
+#[napi]
+impl Task for ExtractStructuredTextTask {
+  type Output = Vec<StructuredPageText>;
+  type JsValue = Vec<StructuredPageText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    extract_structured_text(&doc)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_structured_text_per_page_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractStructuredTextTask> {
+  AsyncTask::new(ExtractStructuredTextTask(PdfSource::new(input)))
+}
+
+pub struct ExtractStructuredTextWithOptionsTask {
+  source: PdfSource,
+  config: StructuredTextConfig,
+}
+
+#[napi]
+impl Task for ExtractStructuredTextWithOptionsTask {
+  type Output = StructuredTextResult;
+  type JsValue = StructuredTextResult;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.source.document()?;
+    extract_structured_text_with_config(&doc, self.config)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_structured_text_with_options_async(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<StructuredTextOptions>,
+) -> AsyncTask<ExtractStructuredTextWithOptionsTask> {
+  AsyncTask::new(ExtractStructuredTextWithOptionsTask {
+    source: PdfSource::new(input),
+    config: extract_structured_text_config(&opts),
+  })
+}
+
+pub struct ExtractLinesTask(PdfSource);
+
+#[napi]
+impl Task for ExtractLinesTask {
+  type Output = Vec<PageLines>;
+  type JsValue = Vec<PageLines>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(extract_positioned_lines(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_lines_with_position_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractLinesTask> {
+  AsyncTask::new(ExtractLinesTask(PdfSource::new(input)))
+}
+
+pub struct ExtractStyledLinesTask(PdfSource);
+
+#[napi]
+impl Task for ExtractStyledLinesTask {
+  type Output = Vec<PageStyledLines>;
+  type JsValue = Vec<PageStyledLines>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_styled_lines(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_styled_lines_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractStyledLinesTask> {
+  AsyncTask::new(ExtractStyledLinesTask(PdfSource::new(input)))
+}
+
+pub struct ExtractParagraphsTask(PdfSource);
+
+#[napi]
+impl Task for ExtractParagraphsTask {
+  type Output = Vec<PageParagraphs>;
+  type JsValue = Vec<PageParagraphs>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(extract_grouped_paragraphs(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_paragraphs_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractParagraphsTask> {
+  AsyncTask::new(ExtractParagraphsTask(PdfSource::new(input)))
+}
+
+pub struct ExtractVectorRegionsTask(PdfSource);
+
+#[napi]
+impl Task for ExtractVectorRegionsTask {
+  type Output = Vec<PageVectorRegions>;
+  type JsValue = Vec<PageVectorRegions>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_vector_regions(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_vector_regions_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractVectorRegionsTask> {
+  AsyncTask::new(ExtractVectorRegionsTask(PdfSource::new(input)))
+}
+
+pub struct ExtractTaggedTextTask(PdfSource);
+
+#[napi]
+impl Task for ExtractTaggedTextTask {
+  type Output = Vec<TaggedBlock>;
+  type JsValue = Vec<TaggedBlock>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_tagged_blocks(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_tagged_text_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractTaggedTextTask> {
+  AsyncTask::new(ExtractTaggedTextTask(PdfSource::new(input)))
+}
+
+pub struct ExtractOutlineTask(PdfSource);
+
+#[napi]
+impl Task for ExtractOutlineTask {
+  type Output = Vec<OutlineItem>;
+  type JsValue = Vec<OutlineItem>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_outline(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_outline_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<ExtractOutlineTask> {
+  AsyncTask::new(ExtractOutlineTask(PdfSource::new(input)))
+}
+
+pub struct SynthesizeOutlineTask(PdfSource);
+
+#[napi]
+impl Task for SynthesizeOutlineTask {
+  type Output = Vec<OutlineItem>;
+  type JsValue = Vec<OutlineItem>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(outline_from_headings(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn synthesize_outline_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<SynthesizeOutlineTask> {
+  AsyncTask::new(SynthesizeOutlineTask(PdfSource::new(input)))
+}
+
+pub struct ExtractFormFieldsTask(PdfSource);
+
+#[napi]
+impl Task for ExtractFormFieldsTask {
+  type Output = FormFieldsResult;
+  type JsValue = FormFieldsResult;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_form_fields(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_form_fields_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractFormFieldsTask> {
+  AsyncTask::new(ExtractFormFieldsTask(PdfSource::new(input)))
+}
+
+pub struct ExtractAllUrisTask(PdfSource);
+
+#[napi]
+impl Task for ExtractAllUrisTask {
+  type Output = Vec<UriRef>;
+  type JsValue = Vec<UriRef>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    collect_uris(&doc)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_all_uris_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<ExtractAllUrisTask> {
+  AsyncTask::new(ExtractAllUrisTask(PdfSource::new(input)))
+}
+
+pub struct DocumentActionsTask(PdfSource);
+
+#[napi]
+impl Task for DocumentActionsTask {
+  type Output = Vec<DocumentAction>;
+  type JsValue = Vec<DocumentAction>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_document_actions(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn document_actions_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<DocumentActionsTask> {
+  AsyncTask::new(DocumentActionsTask(PdfSource::new(input)))
+}
+
+pub struct CleanReportTask(PdfSource);
+
+#[napi]
+impl Task for CleanReportTask {
+  type Output = CleanReport;
+  type JsValue = CleanReport;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_clean_report(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn clean_report_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<CleanReportTask> {
+  AsyncTask::new(CleanReportTask(PdfSource::new(input)))
+}
+
+pub struct ExtractMeasurementsTask(PdfSource);
+
+#[napi]
+impl Task for ExtractMeasurementsTask {
+  type Output = Vec<Measurement>;
+  type JsValue = Vec<Measurement>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    Ok(collect_measurements(&doc))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn extract_measurements_async(
+  input: Either<Buffer, &PdfDown>,
+) -> AsyncTask<ExtractMeasurementsTask> {
+  AsyncTask::new(ExtractMeasurementsTask(PdfSource::new(input)))
+}
+
+pub struct ScriptHistogramTask(PdfSource);
+
+#[napi]
+impl Task for ScriptHistogramTask {
+  type Output = Vec<ScriptCount>;
+  type JsValue = Vec<ScriptCount>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.0.document()?;
+    collect_script_histogram(&doc)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn script_histogram_async(input: Either<Buffer, &PdfDown>) -> AsyncTask<ScriptHistogramTask> {
+  AsyncTask::new(ScriptHistogramTask(PdfSource::new(input)))
+}
+
+pub struct WordFrequenciesTask {
+  source: PdfSource,
+  min_length: u32,
+}
+
+#[napi]
+impl Task for WordFrequenciesTask {
+  type Output = Vec<PageWordFreq>;
+  type JsValue = Vec<PageWordFreq>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.source.document()?;
+    collect_word_frequencies(&doc, self.min_length)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[napi]
+pub fn word_frequencies_async(
+  input: Either<Buffer, &PdfDown>,
+  min_length: u32,
+) -> AsyncTask<WordFrequenciesTask> {
+  AsyncTask::new(WordFrequenciesTask {
+    source: PdfSource::new(input),
+    min_length,
+  })
+}
+
+#[cfg(feature = "ocr")]
+pub struct ExtractTextOcrTask {
+  source: PdfSource,
+  lang: String,
+  tessdata_path: Option<String>,
+  min_len: u32,
+  max_threads: u32,
+  render_dpi: u32,
+  render_mode: u8,
+}
+
+#[cfg(feature = "ocr")]
+#[napi]
+impl Task for ExtractTextOcrTask {
+  type Output = Vec<OcrPageText>;
+  type JsValue = Vec<OcrPageText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.source.document()?;
+    extract_text_with_ocr(
+      &doc,
+      self.source.bytes(),
+      &self.lang,
+      self.tessdata_path.as_deref(),
+      self.min_len,
+      self.max_threads,
+      self.render_dpi,
+      self.render_mode,
+    )
+    .map(|t| t.pages)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[cfg(feature = "ocr")]
+#[napi]
+pub fn extract_text_with_ocr_per_page_async(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<OcrOptions>,
+) -> AsyncTask<ExtractTextOcrTask> {
+  maybe_init_pdfium(&opts);
+  let lang = opts
+    .as_ref()
+    .and_then(|o| o.lang.clone())
+    .unwrap_or_else(|| "eng".to_string());
+  let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.clone());
+  let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
+  let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
+  let render_dpi = extract_render_dpi(&opts);
+  let render_mode = extract_render_mode(&opts);
+  AsyncTask::new(ExtractTextOcrTask {
+    source: PdfSource::new(input),
+    lang,
+    tessdata_path,
+    min_len,
+    max_threads,
+    render_dpi,
+    render_mode,
+  })
+}
+
+#[cfg(feature = "ocr")]
+pub struct PdfDocumentOcrTask {
+  source: PdfSource,
+  lang: String,
+  tessdata_path: Option<String>,
+  min_len: u32,
+  max_threads: u32,
+  render_dpi: u32,
+  render_mode: u8,
+}
+
+#[cfg(feature = "ocr")]
+#[napi]
+impl Task for PdfDocumentOcrTask {
+  type Output = RawPdfDocumentOcr;
+  type JsValue = PdfDocumentOcr;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let doc = self.source.document()?;
+    extract_all_with_ocr(
+      &doc,
+      self.source.bytes(),
+      &self.lang,
+      self.tessdata_path.as_deref(),
+      self.min_len,
+      self.max_threads,
+      self.render_dpi,
+      self.render_mode,
+    )
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(PdfDocumentOcr::from(output))
+  }
+}
+
+#[cfg(feature = "ocr")]
+#[napi]
+pub fn pdf_document_ocr_async(
+  input: Either<Buffer, &PdfDown>,
+  opts: Option<OcrOptions>,
+) -> AsyncTask<PdfDocumentOcrTask> {
+  maybe_init_pdfium(&opts);
+  let lang = opts
+    .as_ref()
+    .and_then(|o| o.lang.clone())
+    .unwrap_or_else(|| "eng".to_string());
+  let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.clone());
+  let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
+  let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
+  let render_dpi = extract_render_dpi(&opts);
+  let render_mode = extract_render_mode(&opts);
+  AsyncTask::new(PdfDocumentOcrTask {
+    source: PdfSource::new(input),
+    lang,
+    tessdata_path,
+    min_len,
+    max_threads,
+    render_dpi,
+    render_mode,
+  })
+}
+
+// ── Step 3: Class-based API with Arc<Document> ──────────────────
+
+/// Shared-document task types for class async methods.
+/// These use Arc<Document> instead of raw bytes, avoiding re-parsing.
+pub struct SharedExtractTextTask {
+  doc: Arc<Document>,
+  config: TextConfig,
+}
+
+#[napi]
+impl Task for SharedExtractTextTask {
+  type Output = Vec<PageText>;
+  type JsValue = Vec<PageText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_text_with_config(&self.doc, self.config)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedRawTextTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedRawTextTask {
+  type Output = Vec<PageText>;
+  type JsValue = Vec<PageText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(extract_text_unprocessed(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedChunksTask {
+  doc: Arc<Document>,
+  max_chars: u32,
+  overlap: u32,
+}
+
+#[napi]
+impl Task for SharedChunksTask {
+  type Output = Vec<TextChunk>;
+  type JsValue = Vec<TextChunk>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    collect_text_chunks(&self.doc, self.max_chars, self.overlap)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedExtractImagesTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedExtractImagesTask {
+  type Output = Vec<RawPageImage>;
+  type JsValue = Vec<PageImage>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(extract_images_raw(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output.into_iter().map(PageImage::from).collect())
+  }
+}
+
+pub struct SharedThumbnailsTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedThumbnailsTask {
+  type Output = Vec<RawPageImage>;
+  type JsValue = Vec<PageImage>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(extract_thumbnails_raw(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output.into_iter().map(PageImage::from).collect())
+  }
+}
+
+pub struct SharedSizedThumbnailsTask {
+  doc: Arc<Document>,
+  max_edge: u32,
+}
+
+#[napi]
+impl Task for SharedSizedThumbnailsTask {
+  type Output = Vec<RawPageImage>;
+  type JsValue = Vec<PageImage>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(extract_sized_thumbnails_raw(&self.doc, self.max_edge))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output.into_iter().map(PageImage::from).collect())
+  }
+}
+
+pub struct SharedContactSheetTask {
+  doc: Arc<Document>,
+  page: u32,
+  columns: u32,
+}
+
+#[napi]
+impl Task for SharedContactSheetTask {
+  type Output = Option<Vec<u8>>;
+  type JsValue = Option<Buffer>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    let Some(&page_id) = self.doc.get_pages().get(&self.page) else {
+      return Ok(None);
+    };
+    Ok(page_contact_sheet(&self.doc, page_id, self.columns))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output.map(Buffer::from))
+  }
+}
+
+pub struct SharedImagesWithOptionsTask {
+  doc: Arc<Document>,
+  config: ImageConfig,
+}
+
+#[napi]
+impl Task for SharedImagesWithOptionsTask {
+  type Output = RawImageExtraction;
+  type JsValue = ImageExtractionResult;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_images_with_config(&self.doc, &self.config)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(ImageExtractionResult::from(output))
+  }
+}
+
+pub struct SharedImageFiltersTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedImageFiltersTask {
+  type Output = Vec<String>;
+  type JsValue = Vec<String>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_image_filters(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedImageBytesPerPageTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedImageBytesPerPageTask {
+  type Output = Vec<PageImageBytes>;
+  type JsValue = Vec<PageImageBytes>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_image_bytes_per_page(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedExtractAnnotationsTask {
+  doc: Arc<Document>,
+  config: AnnotationConfig,
+}
+
+#[napi]
+impl Task for SharedExtractAnnotationsTask {
+  type Output = Vec<PageAnnotation>;
+  type JsValue = Vec<PageAnnotation>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(extract_annotations_with_config(&self.doc, self.config))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedAnnotationSummaryTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedAnnotationSummaryTask {
+  type Output = Vec<AnnotationSummary>;
+  type JsValue = Vec<AnnotationSummary>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_annotation_summary(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedHighlightedTextTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedHighlightedTextTask {
+  type Output = Vec<HighlightedText>;
+  type JsValue = Vec<HighlightedText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_highlighted_text(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedPdfMetaTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedPdfMetaTask {
+  type Output = PdfMeta;
+  type JsValue = PdfMeta;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(extract_metadata(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedClassifyDocumentTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedClassifyDocumentTask {
+  type Output = DocumentClassification;
+  type JsValue = DocumentClassification;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_document_classification(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedPdfDocumentTask {
+  doc: Arc<Document>,
+  config: DocumentConfig,
+}
+
+#[napi]
+impl Task for SharedPdfDocumentTask {
+  type Output = RawPdfDocument;
+  type JsValue = PdfDocument;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_all(Arc::clone(&self.doc), self.config)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(PdfDocument::from(output))
+  }
+}
+
+/// JS callback for `PdfDown.streamPages`, called with one `PageChunk` per page.
+type PageCallback = ThreadsafeFunction<PageChunk, Unknown<'static>, PageChunk, Status, false>;
+
+/// Pages queued for the JS callback that it hasn't returned from yet, and the
+/// first error it threw.
+#[derive(Default)]
+struct PendingPages {
+  count: usize,
+  error: Option<String>,
+}
+
+/// Run `produce`, passing every page it emits to `callback`, and wait until
+/// JS has returned from each call. Fails with the first error the callback
+/// threw.
+fn call_for_each_page<T: 'static + JsValuesTupleIntoVec>(
+  callback: &ThreadsafeFunction<T, Unknown<'static>, T, Status, false>,
+  produce: impl FnOnce(&(dyn Fn(T) + Sync)),
+) -> Result<()> {
+  let pending = Arc::new((Mutex::new(PendingPages::default()), Condvar::new()));
+  let settle = |pending: &(Mutex<PendingPages>, Condvar), error: Option<String>| {
+    let (state, done) = pending;
+    let mut state = state.lock().unwrap();
+    state.count -= 1;
+    if state.error.is_none() {
+      state.error = error;
+    }
+    done.notify_all();
+  };
+  produce(&|page| {
+    pending.0.lock().unwrap().count += 1;
+    let on_return = Arc::clone(&pending);
+    let status = callback.call_with_return_value(
+      page,
+      ThreadsafeFunctionCallMode::NonBlocking,
+      move |result, _env| {
+        settle(&on_return, result.err().map(|e| e.reason));
+        Ok(())
+      },
+    );
+    if status != Status::Ok {
+      settle(
+        &pending,
+        Some(format!("Failed to queue page callback: {status}")),
+      );
+    }
+  });
+
+  // Resolve only after JS has seen every page
+  let (state, done) = &*pending;
+  let state = done
+    .wait_while(state.lock().unwrap(), |s| s.count > 0)
+    .unwrap();
+  match &state.error {
+    Some(reason) => Err(Error::from_reason(reason.clone())),
+    None => Ok(()),
+  }
+}
+
+pub struct StreamPagesTask {
+  doc: Arc<Document>,
+  config: StreamPagesConfig,
+  callback: PageCallback,
+}
+
+#[napi]
+impl Task for StreamPagesTask {
+  type Output = ();
+  type JsValue = ();
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    call_for_each_page(&self.callback, |emit| {
+      stream_pages(&self.doc, self.config, |chunk| emit(PageChunk::from(chunk)))
+    })
+  }
+
+  fn resolve(&mut self, _env: Env, _output: Self::Output) -> Result<Self::JsValue> {
+    Ok(())
+  }
+}
+
+/// JS callback for `PdfDown.streamTextWithOcr`, called with one
+/// `OcrStreamedPage` per page.
+#[cfg(feature = "ocr")]
+type OcrPageCallback =
+  ThreadsafeFunction<OcrStreamedPage, Unknown<'static>, OcrStreamedPage, Status, false>;
+
+#[cfg(feature = "ocr")]
+pub struct StreamTextOcrTask {
+  doc: Arc<Document>,
+  raw: Arc<Vec<u8>>,
+  lang: String,
+  tessdata_path: Option<String>,
+  min_len: u32,
+  max_threads: u32,
+  render_dpi: u32,
+  render_mode: u8,
+  callback: OcrPageCallback,
+}
+
+#[cfg(feature = "ocr")]
+#[napi]
+impl Task for StreamTextOcrTask {
+  type Output = ();
+  type JsValue = ();
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    call_for_each_page(&self.callback, |emit| {
+      stream_text_with_ocr(
+        &self.doc,
+        &self.raw,
+        &self.lang,
+        self.tessdata_path.as_deref(),
+        self.min_len,
+        self.max_threads,
+        self.render_dpi,
+        self.render_mode,
+        emit,
+      );
+    })
+  }
+
+  fn resolve(&mut self, _env: Env, _output: Self::Output) -> Result<Self::JsValue> {
+    Ok(())
+  }
+}
+
+pub struct SharedStructuredTextTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedStructuredTextTask {
+  type Output = Vec<StructuredPageText>;
+  type JsValue = Vec<StructuredPageText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_structured_text(&self.0)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedBodyTextTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedBodyTextTask {
+  type Output = Vec<PageText>;
+  type JsValue = Vec<PageText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_body_text(&self.0)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedStructuredTextWithOptionsTask {
+  doc: Arc<Document>,
+  config: StructuredTextConfig,
+}
+
+#[napi]
+impl Task for SharedStructuredTextWithOptionsTask {
+  type Output = StructuredTextResult;
+  type JsValue = StructuredTextResult;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_structured_text_with_config(&self.doc, self.config)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedLinesTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedLinesTask {
+  type Output = Vec<PageLines>;
+  type JsValue = Vec<PageLines>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(extract_positioned_lines(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedStyledLinesTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedStyledLinesTask {
+  type Output = Vec<PageStyledLines>;
+  type JsValue = Vec<PageStyledLines>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_styled_lines(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedParagraphsTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedParagraphsTask {
+  type Output = Vec<PageParagraphs>;
+  type JsValue = Vec<PageParagraphs>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(extract_grouped_paragraphs(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedVectorRegionsTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedVectorRegionsTask {
+  type Output = Vec<PageVectorRegions>;
+  type JsValue = Vec<PageVectorRegions>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_vector_regions(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedTaggedTextTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedTaggedTextTask {
+  type Output = Vec<TaggedBlock>;
+  type JsValue = Vec<TaggedBlock>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_tagged_blocks(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedOutlineTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedOutlineTask {
+  type Output = Vec<OutlineItem>;
+  type JsValue = Vec<OutlineItem>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_outline(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedSynthesizeOutlineTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedSynthesizeOutlineTask {
+  type Output = Vec<OutlineItem>;
+  type JsValue = Vec<OutlineItem>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(outline_from_headings(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedFormFieldsTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedFormFieldsTask {
+  type Output = FormFieldsResult;
+  type JsValue = FormFieldsResult;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_form_fields(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedAllUrisTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedAllUrisTask {
+  type Output = Vec<UriRef>;
+  type JsValue = Vec<UriRef>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    collect_uris(&self.0)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedDocumentActionsTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedDocumentActionsTask {
+  type Output = Vec<DocumentAction>;
+  type JsValue = Vec<DocumentAction>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_document_actions(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedCleanReportTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedCleanReportTask {
+  type Output = CleanReport;
+  type JsValue = CleanReport;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_clean_report(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedMeasurementsTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedMeasurementsTask {
+  type Output = Vec<Measurement>;
+  type JsValue = Vec<Measurement>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_measurements(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedSourceTextRunsTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedSourceTextRunsTask {
+  type Output = Vec<SourceTextRun>;
+  type JsValue = Vec<SourceTextRun>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    Ok(collect_source_text_runs(&self.0))
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedScriptHistogramTask(Arc<Document>);
+
+#[napi]
+impl Task for SharedScriptHistogramTask {
+  type Output = Vec<ScriptCount>;
+  type JsValue = Vec<ScriptCount>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    collect_script_histogram(&self.0)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedWordFrequenciesTask {
+  doc: Arc<Document>,
+  min_length: u32,
+}
+
+#[napi]
+impl Task for SharedWordFrequenciesTask {
+  type Output = Vec<PageWordFreq>;
+  type JsValue = Vec<PageWordFreq>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    collect_word_frequencies(&self.doc, self.min_length)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+pub struct SharedFullTextTask {
+  doc: Arc<Document>,
+  template: String,
+}
+
+#[napi]
+impl Task for SharedFullTextTask {
+  type Output = String;
+  type JsValue = String;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_full_text_with_markers(&self.doc, &self.template)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[cfg(feature = "ocr")]
+pub struct SharedExtractTextOcrTask {
+  doc: Arc<Document>,
+  raw: Arc<Vec<u8>>,
+  lang: String,
+  tessdata_path: Option<String>,
+  min_len: u32,
+  max_threads: u32,
+  render_dpi: u32,
+  render_mode: u8,
+}
+
+#[cfg(feature = "ocr")]
+#[napi]
+impl Task for SharedExtractTextOcrTask {
+  type Output = Vec<OcrPageText>;
+  type JsValue = Vec<OcrPageText>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_text_with_ocr(
+      &self.doc,
+      &self.raw,
+      &self.lang,
+      self.tessdata_path.as_deref(),
+      self.min_len,
+      self.max_threads,
+      self.render_dpi,
+      self.render_mode,
+    )
+    .map(|t| t.pages)
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output)
+  }
+}
+
+#[cfg(feature = "ocr")]
+pub struct SharedPdfDocumentOcrTask {
+  doc: Arc<Document>,
+  raw: Arc<Vec<u8>>,
+  lang: String,
+  tessdata_path: Option<String>,
+  min_len: u32,
+  max_threads: u32,
+  render_dpi: u32,
+  render_mode: u8,
+}
+
+#[cfg(feature = "ocr")]
+#[napi]
+impl Task for SharedPdfDocumentOcrTask {
+  type Output = RawPdfDocumentOcr;
+  type JsValue = PdfDocumentOcr;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    extract_all_with_ocr(
+      &self.doc,
+      &self.raw,
+      &self.lang,
+      self.tessdata_path.as_deref(),
+      self.min_len,
+      self.max_threads,
+      self.render_dpi,
+      self.render_mode,
+    )
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(PdfDocumentOcr::from(output))
+  }
+}
+
+#[cfg(feature = "render")]
+pub struct SharedRenderPagesTask {
+  raw: Arc<Vec<u8>>,
+  dpi: u32,
+  mode: u8,
+}
+
+#[cfg(feature = "render")]
+#[napi]
+impl Task for SharedRenderPagesTask {
+  type Output = Vec<RawRenderedPage>;
+  type JsValue = Vec<RenderedPage>;
+
+  fn compute(&mut self) -> Result<Self::Output> {
+    use crate::core::render::{RENDER_MODE_AUTO, RENDER_MODE_NEVER};
+
+    let dpi = self.dpi;
+    let mode = self.mode;
+    let pdf_bytes = &self.raw;
+
+    if mode == RENDER_MODE_NEVER {
+      return Ok(Vec::new());
+    }
+
+    crate::core::render::ensure_pdfium_with_path(None)
+      .map_err(|e| Error::from_reason(e.to_string()))?;
+
+    let doc = load_doc(pdf_bytes)?;
+    let pages = doc.get_pages();
+    let page_count = pages.len() as u16;
+
+    let indices: Vec<u16> = if mode == RENDER_MODE_AUTO {
+      pages
+        .iter()
+        .filter_map(|(&page_num, &page_id)| {
+          let raw = doc.extract_text(&[page_num]).unwrap_or_default();
+          let non_ws: usize = raw.chars().filter(|c| !c.is_whitespace()).count();
+          if non_ws > 0 {
+            return None;
+          }
+          let has_images =
+            !crate::core::images::collect_page_decoded_images(&doc, page_id).is_empty();
+          if has_images {
+            return None;
+          }
+          Some((page_num - 1) as u16)
+        })
+        .collect()
+    } else {
+      (0..page_count).collect()
+    };
+
+    let rendered = crate::core::render::render_pages_to_png(pdf_bytes, &indices, dpi);
+    Ok(
+      rendered
+        .into_iter()
+        .map(|(idx, w, h, data)| RawRenderedPage {
+          page: (idx as u32) + 1,
+          width: w,
+          height: h,
+          dpi,
+          data,
+        })
+        .collect(),
+    )
+  }
+
+  fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+    Ok(output.into_iter().map(RenderedPage::from).collect())
+  }
+}
+
+#[napi]
+pub struct PdfDown {
+  doc: Arc<Document>,
+  raw: Arc<Vec<u8>>,
+}
+
+/// How many parsed documents `PdfDown.fromCached` keeps.
+const DOCUMENT_CACHE_CAPACITY: usize = 8;
+
+/// Most raw PDF bytes the `PdfDown.fromCached` cache holds across its
+/// documents; a larger buffer is parsed but not cached.
+const DOCUMENT_CACHE_MAX_BYTES: usize = 256 * 1024 * 1024;
+
+struct CachedDocument {
+  sha256: [u8; 32],
+  doc: Arc<Document>,
+  raw: Arc<Vec<u8>>,
+}
+
+/// Recently parsed documents, least recently used first.
+static DOCUMENT_CACHE: Mutex<VecDeque<CachedDocument>> = Mutex::new(VecDeque::new());
+
+/// Drop every document `PdfDown.fromCached` keeps. `PdfDown` instances
+/// already handed out stay valid.
+#[napi]
+pub fn clear_document_cache() {
+  DOCUMENT_CACHE.lock().unwrap().clear();
+}
+
+#[napi]
+impl PdfDown {
+  #[napi(constructor)]
+  pub fn new(buffer: Buffer) -> Result<Self> {
+    let bytes = buffer.to_vec();
+    let doc = Document::load_mem(&bytes)
+      .map_err(|e| Error::from_reason(format!("Failed to load PDF: {e}")))?;
+    Ok(PdfDown {
+      doc: Arc::new(doc),
+      raw: Arc::new(bytes),
+    })
+  }
+
+  /// Like the constructor, but reuses the parse of a recently loaded buffer
+  /// with the same bytes (matched by SHA-256) instead of parsing it again.
+  /// The last 8 distinct documents, up to 256 MiB of PDF bytes in total,
+  /// are kept; the least recently used ones are dropped to make room.
+  #[napi(factory)]
+  pub fn from_cached(buffer: Buffer) -> Result<Self> {
+    let sha256: [u8; 32] = Sha256::digest(&buffer[..]).into();
+    let lookup = |cache: &mut VecDeque<CachedDocument>| {
+      let entry = cache.remove(cache.iter().position(|c| c.sha256 == sha256)?)?;
+      let pdf = PdfDown {
+        doc: Arc::clone(&entry.doc),
+        raw: Arc::clone(&entry.raw),
+      };
+      cache.push_back(entry);
+      Some(pdf)
+    };
+    if let Some(pdf) = lookup(&mut DOCUMENT_CACHE.lock().unwrap()) {
+      return Ok(pdf);
+    }
+
+    // Parse outside the lock; another thread may have cached it meanwhile
+    let pdf = Self::new(buffer)?;
+    let mut cache = DOCUMENT_CACHE.lock().unwrap();
+    if let Some(cached) = lookup(&mut cache) {
+      return Ok(cached);
+    }
+    if pdf.raw.len() > DOCUMENT_CACHE_MAX_BYTES {
+      return Ok(pdf);
+    }
+    let mut held: usize = cache.iter().map(|c| c.raw.len()).sum();
+    while cache.len() == DOCUMENT_CACHE_CAPACITY || held + pdf.raw.len() > DOCUMENT_CACHE_MAX_BYTES
+    {
+      let Some(evicted) = cache.pop_front() else {
+        break;
+      };
+      held -= evicted.raw.len();
+    }
+    cache.push_back(CachedDocument {
+      sha256,
+      doc: Arc::clone(&pdf.doc),
+      raw: Arc::clone(&pdf.raw),
+    });
+    Ok(pdf)
+  }
+
+  #[napi]
+  pub fn text_per_page(&self, opts: Option<TextOptions>) -> Result<Vec<PageText>> {
+    extract_text_with_config(&self.doc, extract_text_config(&opts))
+  }
+
+  #[napi]
+  pub fn raw_text_per_page(&self) -> Vec<PageText> {
+    extract_text_unprocessed(&self.doc)
+  }
+
+  #[napi]
+  pub fn raw_text_per_page_async(&self) -> AsyncTask<SharedRawTextTask> {
+    AsyncTask::new(SharedRawTextTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn chunks(&self, max_chars: u32, overlap: u32) -> Result<Vec<TextChunk>> {
+    collect_text_chunks(&self.doc, max_chars, overlap)
+  }
+
+  #[napi]
+  pub fn chunks_async(&self, max_chars: u32, overlap: u32) -> AsyncTask<SharedChunksTask> {
+    AsyncTask::new(SharedChunksTask {
+      doc: Arc::clone(&self.doc),
+      max_chars,
+      overlap,
+    })
+  }
+
+  #[napi]
+  pub fn images_per_page(&self) -> Result<Vec<PageImage>> {
+    Ok(
+      extract_images_raw(&self.doc)
+        .into_iter()
+        .map(PageImage::from)
+        .collect(),
+    )
+  }
+
+  /// Decoded images of `page` (1-based) alone, so a lazy viewer doesn't
+  /// decode the whole document. Empty when the page has no images or doesn't
+  /// exist.
+  #[napi]
+  pub fn images_on_page(&self, page: u32) -> Result<Vec<PageImage>> {
+    let Some(&page_id) = self.doc.get_pages().get(&page) else {
+      return Ok(Vec::new());
+    };
+    Ok(
+      extract_page_images_raw(&self.doc, page_id, page)
+        .into_iter()
+        .map(PageImage::from)
+        .collect(),
+    )
+  }
+
+  #[napi]
+  pub fn thumbnails(&self) -> Vec<PageImage> {
+    extract_thumbnails_raw(&self.doc)
+      .into_iter()
+      .map(PageImage::from)
+      .collect()
+  }
+
+  #[napi]
+  pub fn thumbnails_async(&self) -> AsyncTask<SharedThumbnailsTask> {
+    AsyncTask::new(SharedThumbnailsTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn thumbnails_sized(&self, max_edge: u32) -> Vec<PageImage> {
+    extract_sized_thumbnails_raw(&self.doc, max_edge)
+      .into_iter()
+      .map(PageImage::from)
+      .collect()
+  }
+
+  #[napi]
+  pub fn thumbnails_sized_async(&self, max_edge: u32) -> AsyncTask<SharedSizedThumbnailsTask> {
+    AsyncTask::new(SharedSizedThumbnailsTask {
+      doc: Arc::clone(&self.doc),
+      max_edge,
+    })
+  }
+
+  /// All images on `page` (1-based) tiled `columns` per row into one PNG,
+  /// each shrunk to fit a 256px cell. `null` when the page doesn't exist or
+  /// has no decodable images.
+  #[napi]
+  pub fn page_contact_sheet(&self, page: u32, columns: u32) -> Option<Buffer> {
+    let page_id = *self.doc.get_pages().get(&page)?;
+    page_contact_sheet(&self.doc, page_id, columns).map(Buffer::from)
+  }
+
+  #[napi]
+  pub fn page_contact_sheet_async(
+    &self,
+    page: u32,
+    columns: u32,
+  ) -> AsyncTask<SharedContactSheetTask> {
+    AsyncTask::new(SharedContactSheetTask {
+      doc: Arc::clone(&self.doc),
+      page,
+      columns,
+    })
+  }
+
+  #[napi]
+  pub fn images_with_options(&self, opts: Option<ImageOptions>) -> Result<ImageExtractionResult> {
+    Ok(ImageExtractionResult::from(extract_images_with_config(
+      &self.doc,
+      &extract_image_config(&opts),
+    )?))
+  }
+
+  #[napi]
+  pub fn image_filters(&self) -> Vec<String> {
+    collect_image_filters(&self.doc)
+  }
+
+  #[napi]
+  pub fn image_bytes_per_page(&self) -> Vec<PageImageBytes> {
+    collect_image_bytes_per_page(&self.doc)
+  }
+
+  #[napi]
+  pub fn annotations_per_page(&self, opts: Option<AnnotationOptions>) -> Vec<PageAnnotation> {
+    extract_annotations_with_config(&self.doc, extract_annotation_config(&opts))
+  }
+
+  #[napi]
+  pub fn annotation_summary(&self) -> Vec<AnnotationSummary> {
+    collect_annotation_summary(&self.doc)
+  }
+
+  #[napi]
+  pub fn highlighted_text(&self) -> Vec<HighlightedText> {
+    collect_highlighted_text(&self.doc)
+  }
 
   #[napi]
   pub fn metadata(&self) -> PdfMeta {
     extract_metadata(&self.doc)
   }
 
+  /// The XMP packet of `page`'s own `/Metadata` stream (1-based), for
+  /// page-level provenance. `null` when the page doesn't exist or has none.
+  #[napi]
+  pub fn page_metadata(&self, page: u32) -> Option<String> {
+    let page_id = *self.doc.get_pages().get(&page)?;
+    page_xmp_packet(&self.doc, page_id)
+  }
+
   #[napi]
-  pub fn text_per_page_async(&self) -> AsyncTask<SharedExtractTextTask> {
-    AsyncTask::new(SharedExtractTextTask(Arc::clone(&self.doc)))
+  pub fn text_per_page_async(&self, opts: Option<TextOptions>) -> AsyncTask<SharedExtractTextTask> {
+    AsyncTask::new(SharedExtractTextTask {
+      doc: Arc::clone(&self.doc),
+      config: extract_text_config(&opts),
+    })
   }
 
   #[napi]
@@ -850,8 +3024,45 @@ impl PdfDown {
   }
 
   #[napi]
-  pub fn annotations_per_page_async(&self) -> AsyncTask<SharedExtractAnnotationsTask> {
-    AsyncTask::new(SharedExtractAnnotationsTask(Arc::clone(&self.doc)))
+  pub fn images_with_options_async(
+    &self,
+    opts: Option<ImageOptions>,
+  ) -> AsyncTask<SharedImagesWithOptionsTask> {
+    AsyncTask::new(SharedImagesWithOptionsTask {
+      doc: Arc::clone(&self.doc),
+      config: extract_image_config(&opts),
+    })
+  }
+
+  #[napi]
+  pub fn image_filters_async(&self) -> AsyncTask<SharedImageFiltersTask> {
+    AsyncTask::new(SharedImageFiltersTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn image_bytes_per_page_async(&self) -> AsyncTask<SharedImageBytesPerPageTask> {
+    AsyncTask::new(SharedImageBytesPerPageTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn annotations_per_page_async(
+    &self,
+    opts: Option<AnnotationOptions>,
+  ) -> AsyncTask<SharedExtractAnnotationsTask> {
+    AsyncTask::new(SharedExtractAnnotationsTask {
+      doc: Arc::clone(&self.doc),
+      config: extract_annotation_config(&opts),
+    })
+  }
+
+  #[napi]
+  pub fn annotation_summary_async(&self) -> AsyncTask<SharedAnnotationSummaryTask> {
+    AsyncTask::new(SharedAnnotationSummaryTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn highlighted_text_async(&self) -> AsyncTask<SharedHighlightedTextTask> {
+    AsyncTask::new(SharedHighlightedTextTask(Arc::clone(&self.doc)))
   }
 
   #[napi]
@@ -859,14 +3070,75 @@ impl PdfDown {
     AsyncTask::new(SharedPdfMetaTask(Arc::clone(&self.doc)))
   }
 
+  /// Whether the document is likely scanned; see `classifyDocument`.
+  #[napi]
+  pub fn classify_document(&self) -> DocumentClassification {
+    collect_document_classification(&self.doc)
+  }
+
+  #[napi]
+  pub fn classify_document_async(&self) -> AsyncTask<SharedClassifyDocumentTask> {
+    AsyncTask::new(SharedClassifyDocumentTask(Arc::clone(&self.doc)))
+  }
+
+  /// The CropBox/MediaBox of one page (1-based), or `None` when the page
+  /// doesn't exist. Unlike `metadata().pageBoxes` this is never grouped.
+  #[napi]
+  pub fn page_box(&self, page: u32) -> Option<PageBox> {
+    let page_id = *self.doc.get_pages().get(&page)?;
+    Some(single_page_box(&self.doc, page, page_id))
+  }
+
+  /// One box per page in page order, each with `pageCount: 1` and
+  /// `pages: [n]`.
+  #[napi]
+  pub fn per_page_boxes(&self) -> Vec<PageBox> {
+    extract_per_page_boxes(&self.doc)
+  }
+
+  /// Smallest and largest page width and height in points, as displayed
+  /// (`/Rotate` applied) and scaled by `/UserUnit`. `None` when no page has a
+  /// CropBox or MediaBox.
+  #[napi]
+  pub fn page_size_range(&self) -> Option<PageSizeRange> {
+    page_size_range(&self.doc)
+  }
+
+  #[napi]
+  pub fn page_tree_stats(&self) -> PageTreeStats {
+    collect_page_tree_stats(&self.doc)
+  }
+
+  #[napi]
+  pub fn document(&self, opts: Option<DocumentOptions>) -> Result<PdfDocument> {
+    Ok(PdfDocument::from(extract_all(
+      Arc::clone(&self.doc),
+      extract_document_config(&opts),
+    )?))
+  }
+
   #[napi]
-  pub fn document(&self) -> Result<PdfDocument> {
-    Ok(PdfDocument::from(extract_all(&self.doc)?))
+  pub fn document_async(&self, opts: Option<DocumentOptions>) -> AsyncTask<SharedPdfDocumentTask> {
+    AsyncTask::new(SharedPdfDocumentTask {
+      doc: Arc::clone(&self.doc),
+      config: extract_document_config(&opts),
+    })
   }
 
+  /// Extract pages in parallel, calling `callback` with each one as soon as
+  /// it is done, in completion order. The promise resolves once the callback
+  /// has run for every page, and rejects with the first error it throws.
   #[napi]
-  pub fn document_async(&self) -> AsyncTask<SharedPdfDocumentTask> {
-    AsyncTask::new(SharedPdfDocumentTask(Arc::clone(&self.doc)))
+  pub fn stream_pages(
+    &self,
+    callback: PageCallback,
+    opts: Option<StreamPagesOptions>,
+  ) -> AsyncTask<StreamPagesTask> {
+    AsyncTask::new(StreamPagesTask {
+      doc: Arc::clone(&self.doc),
+      config: extract_stream_pages_config(&opts),
+      callback,
+    })
   }
 
   #[napi]
@@ -878,6 +3150,220 @@ impl PdfDown {
   pub fn structured_text_async(&self) -> AsyncTask<SharedStructuredTextTask> {
     AsyncTask::new(SharedStructuredTextTask(Arc::clone(&self.doc)))
   }
+
+  /// Each page's text without the running headers and footers that
+  /// `structuredText` detects: its `body` alone.
+  #[napi]
+  pub fn body_text_per_page(&self) -> Result<Vec<PageText>> {
+    extract_body_text(&self.doc)
+  }
+
+  #[napi]
+  pub fn body_text_per_page_async(&self) -> AsyncTask<SharedBodyTextTask> {
+    AsyncTask::new(SharedBodyTextTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn structured_text_with_options(
+    &self,
+    opts: Option<StructuredTextOptions>,
+  ) -> Result<StructuredTextResult> {
+    extract_structured_text_with_config(&self.doc, extract_structured_text_config(&opts))
+  }
+
+  #[napi]
+  pub fn structured_text_with_options_async(
+    &self,
+    opts: Option<StructuredTextOptions>,
+  ) -> AsyncTask<SharedStructuredTextWithOptionsTask> {
+    AsyncTask::new(SharedStructuredTextWithOptionsTask {
+      doc: Arc::clone(&self.doc),
+      config: extract_structured_text_config(&opts),
+    })
+  }
+
+  #[napi]
+  pub fn lines_with_position(&self) -> Vec<PageLines> {
+    extract_positioned_lines(&self.doc)
+  }
+
+  #[napi]
+  pub fn lines_with_position_async(&self) -> AsyncTask<SharedLinesTask> {
+    AsyncTask::new(SharedLinesTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn styled_lines(&self) -> Vec<PageStyledLines> {
+    collect_styled_lines(&self.doc)
+  }
+
+  #[napi]
+  pub fn styled_lines_async(&self) -> AsyncTask<SharedStyledLinesTask> {
+    AsyncTask::new(SharedStyledLinesTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn paragraphs(&self) -> Vec<PageParagraphs> {
+    extract_grouped_paragraphs(&self.doc)
+  }
+
+  #[napi]
+  pub fn paragraphs_async(&self) -> AsyncTask<SharedParagraphsTask> {
+    AsyncTask::new(SharedParagraphsTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn vector_regions(&self) -> Vec<PageVectorRegions> {
+    collect_vector_regions(&self.doc)
+  }
+
+  #[napi]
+  pub fn vector_regions_async(&self) -> AsyncTask<SharedVectorRegionsTask> {
+    AsyncTask::new(SharedVectorRegionsTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn tagged_text(&self) -> Vec<TaggedBlock> {
+    collect_tagged_blocks(&self.doc)
+  }
+
+  #[napi]
+  pub fn tagged_text_async(&self) -> AsyncTask<SharedTaggedTextTask> {
+    AsyncTask::new(SharedTaggedTextTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn outline(&self) -> Vec<OutlineItem> {
+    collect_outline(&self.doc)
+  }
+
+  #[napi]
+  pub fn outline_async(&self) -> AsyncTask<SharedOutlineTask> {
+    AsyncTask::new(SharedOutlineTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn synthesized_outline(&self) -> Vec<OutlineItem> {
+    outline_from_headings(&self.doc)
+  }
+
+  #[napi]
+  pub fn synthesized_outline_async(&self) -> AsyncTask<SharedSynthesizeOutlineTask> {
+    AsyncTask::new(SharedSynthesizeOutlineTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn form_fields(&self) -> FormFieldsResult {
+    collect_form_fields(&self.doc)
+  }
+
+  #[napi]
+  pub fn form_fields_async(&self) -> AsyncTask<SharedFormFieldsTask> {
+    AsyncTask::new(SharedFormFieldsTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn all_uris(&self) -> Result<Vec<UriRef>> {
+    collect_uris(&self.doc)
+  }
+
+  #[napi]
+  pub fn all_uris_async(&self) -> AsyncTask<SharedAllUrisTask> {
+    AsyncTask::new(SharedAllUrisTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn document_actions(&self) -> Vec<DocumentAction> {
+    collect_document_actions(&self.doc)
+  }
+
+  #[napi]
+  pub fn document_actions_async(&self) -> AsyncTask<SharedDocumentActionsTask> {
+    AsyncTask::new(SharedDocumentActionsTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn clean_report(&self) -> CleanReport {
+    collect_clean_report(&self.doc)
+  }
+
+  #[napi]
+  pub fn clean_report_async(&self) -> AsyncTask<SharedCleanReportTask> {
+    AsyncTask::new(SharedCleanReportTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn measurements(&self) -> Vec<Measurement> {
+    collect_measurements(&self.doc)
+  }
+
+  #[napi]
+  pub fn measurements_async(&self) -> AsyncTask<SharedMeasurementsTask> {
+    AsyncTask::new(SharedMeasurementsTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn text_with_source_refs(&self) -> Vec<SourceTextRun> {
+    collect_source_text_runs(&self.doc)
+  }
+
+  #[napi]
+  pub fn text_with_source_refs_async(&self) -> AsyncTask<SharedSourceTextRunsTask> {
+    AsyncTask::new(SharedSourceTextRunsTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn script_histogram(&self) -> Result<Vec<ScriptCount>> {
+    collect_script_histogram(&self.doc)
+  }
+
+  #[napi]
+  pub fn script_histogram_async(&self) -> AsyncTask<SharedScriptHistogramTask> {
+    AsyncTask::new(SharedScriptHistogramTask(Arc::clone(&self.doc)))
+  }
+
+  #[napi]
+  pub fn word_frequencies(&self, min_length: u32) -> Result<Vec<PageWordFreq>> {
+    collect_word_frequencies(&self.doc, min_length)
+  }
+
+  #[napi]
+  pub fn word_frequencies_async(&self, min_length: u32) -> AsyncTask<SharedWordFrequenciesTask> {
+    AsyncTask::new(SharedWordFrequenciesTask {
+      doc: Arc::clone(&self.doc),
+      min_length,
+    })
+  }
+
+  /// The document's opening text, at most `maxChars` non-whitespace
+  /// characters long, e.g. for a search-result snippet. Pages are extracted
+  /// in order only until enough text is collected.
+  #[napi]
+  pub fn text_preview(&self, max_chars: u32) -> Result<String> {
+    Ok(extract_text_preview(&self.doc, max_chars))
+  }
+
+  /// All page text as one string, with `template` inserted between pages.
+  /// `{page}` in the template becomes the number of the following page;
+  /// defaults to `"\n\n--- Page {page} ---\n\n"`.
+  #[napi]
+  pub fn full_text_with_markers(&self, template: Option<String>) -> Result<String> {
+    extract_full_text_with_markers(
+      &self.doc,
+      template.as_deref().unwrap_or(DEFAULT_PAGE_MARKER),
+    )
+  }
+
+  #[napi]
+  pub fn full_text_with_markers_async(
+    &self,
+    template: Option<String>,
+  ) -> AsyncTask<SharedFullTextTask> {
+    AsyncTask::new(SharedFullTextTask {
+      doc: Arc::clone(&self.doc),
+      template: template.unwrap_or_else(|| DEFAULT_PAGE_MARKER.to_string()),
+    })
+  }
 }
 
 #[cfg(feature = "ocr")]
@@ -890,6 +3376,7 @@ impl PdfDown {
       .as_ref()
       .and_then(|o| o.lang.as_deref())
       .unwrap_or("eng");
+    let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.as_deref());
     let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
     let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
     let render_dpi = extract_render_dpi(&opts);
@@ -898,11 +3385,13 @@ impl PdfDown {
       &self.doc,
       &self.raw,
       lang,
+      tessdata_path,
       min_len,
       max_threads,
       render_dpi,
       render_mode,
     )
+    .map(|t| t.pages)
   }
 
   #[napi]
@@ -915,6 +3404,7 @@ impl PdfDown {
       .as_ref()
       .and_then(|o| o.lang.clone())
       .unwrap_or_else(|| "eng".to_string());
+    let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.clone());
     let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
     let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
     let render_dpi = extract_render_dpi(&opts);
@@ -923,10 +3413,44 @@ impl PdfDown {
       doc: Arc::clone(&self.doc),
       raw: Arc::clone(&self.raw),
       lang,
+      tessdata_path,
+      min_len,
+      max_threads,
+      render_dpi,
+      render_mode,
+    })
+  }
+
+  /// OCR pages in parallel, calling `callback` with each one as soon as it
+  /// is done, in completion order, with the OCR confidence. The promise
+  /// resolves once the callback has run for every page, and rejects with the
+  /// first error it throws.
+  #[napi]
+  pub fn stream_text_with_ocr(
+    &self,
+    callback: OcrPageCallback,
+    opts: Option<OcrOptions>,
+  ) -> AsyncTask<StreamTextOcrTask> {
+    maybe_init_pdfium(&opts);
+    let lang = opts
+      .as_ref()
+      .and_then(|o| o.lang.clone())
+      .unwrap_or_else(|| "eng".to_string());
+    let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.clone());
+    let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
+    let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
+    let render_dpi = extract_render_dpi(&opts);
+    let render_mode = extract_render_mode(&opts);
+    AsyncTask::new(StreamTextOcrTask {
+      doc: Arc::clone(&self.doc),
+      raw: Arc::clone(&self.raw),
+      lang,
+      tessdata_path,
       min_len,
       max_threads,
       render_dpi,
       render_mode,
+      callback,
     })
   }
 
@@ -937,6 +3461,7 @@ impl PdfDown {
       .as_ref()
       .and_then(|o| o.lang.as_deref())
       .unwrap_or("eng");
+    let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.as_deref());
     let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
     let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
     let render_dpi = extract_render_dpi(&opts);
@@ -945,6 +3470,7 @@ impl PdfDown {
       &self.doc,
       &self.raw,
       lang,
+      tessdata_path,
       min_len,
       max_threads,
       render_dpi,
@@ -962,6 +3488,7 @@ impl PdfDown {
       .as_ref()
       .and_then(|o| o.lang.clone())
       .unwrap_or_else(|| "eng".to_string());
+    let tessdata_path = opts.as_ref().and_then(|o| o.tessdata_path.clone());
     let min_len = opts.as_ref().and_then(|o| o.min_text_length).unwrap_or(1);
     let max_threads = normalize_max_threads(opts.as_ref().and_then(|o| o.max_threads));
     let render_dpi = extract_render_dpi(&opts);
@@ -970,6 +3497,7 @@ impl PdfDown {
       doc: Arc::clone(&self.doc),
       raw: Arc::clone(&self.raw),
       lang,
+      tessdata_path,
       min_len,
       max_threads,
       render_dpi,
@@ -7,6 +7,20 @@ use napi_derive::napi;
 pub struct PageText {
   pub page: u32,
   pub text: String,
+  /// Footer artifacts (a `/` line followed by the page count) were removed
+  /// from `text`.
+  pub footer_stripped: bool,
+}
+
+/// A piece of the document's text cut to a size budget, from
+/// `extractChunks`.
+#[napi(object)]
+pub struct TextChunk {
+  pub text: String,
+  /// Page the chunk's first character comes from.
+  pub start_page: u32,
+  /// Page the chunk's last character comes from.
+  pub end_page: u32,
 }
 
 #[napi(object)]
@@ -15,6 +29,155 @@ pub struct StructuredPageText {
   pub header: String,
   pub body: String,
   pub footer: String,
+  /// Fraction of the page area (0.0–1.0) covered by text boxes. `0.0` on pages
+  /// without text; `None` when the page has no usable CropBox/MediaBox.
+  pub text_coverage: Option<f64>,
+  /// The nearest heading on this page or an earlier one, as
+  /// `synthesizeOutline` detects headings. Set only with
+  /// `StructuredTextOptions.sectionTitles`.
+  pub section_title: Option<String>,
+}
+
+/// A line of text with its bounding box in PDF user space (origin bottom-left;
+/// `x`/`y` is the lower-left corner). On pages with `/Rotate`, coordinates are
+/// in the displayed orientation, measured from the displayed bottom-left.
+#[napi(object)]
+pub struct LineBox {
+  pub text: String,
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+  /// A column of vertically written text (e.g. vertical CJK), read top to
+  /// bottom.
+  pub vertical: bool,
+}
+
+#[napi(object)]
+pub struct PageLines {
+  pub page: u32,
+  /// Top to bottom; words within a line are left to right. Vertical columns
+  /// follow, right to left.
+  pub lines: Vec<LineBox>,
+}
+
+/// A `LineBox` with the font hints that tell headings from body text.
+#[napi(object)]
+pub struct StyledLine {
+  pub text: String,
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+  pub vertical: bool,
+  /// Largest effective font size in the line, in points (text size scaled by
+  /// the text and current transformation matrices).
+  pub font_size: f64,
+  /// Every word is set in a bold font.
+  pub bold: bool,
+  /// Every word is set in an italic font.
+  pub italic: bool,
+}
+
+#[napi(object)]
+pub struct PageStyledLines {
+  pub page: u32,
+  /// In the same order as `PageLines.lines`.
+  pub lines: Vec<StyledLine>,
+}
+
+/// Bounding box of a cluster of stroked or filled vector paths, in the same
+/// coordinates as `LineBox`.
+#[napi(object)]
+pub struct VectorRegion {
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+  /// Number of painted paths merged into this region.
+  pub path_count: u32,
+}
+
+#[napi(object)]
+pub struct PageVectorRegions {
+  pub page: u32,
+  /// Top to bottom, then left to right.
+  pub regions: Vec<VectorRegion>,
+}
+
+/// One entry of a document outline, with the entries nested under it.
+#[napi(object)]
+pub struct OutlineItem {
+  pub title: String,
+  /// Page the entry points to, when it can be resolved.
+  pub page: Option<u32>,
+  /// Nesting depth: 1 for top-level entries. In a synthesized outline this is
+  /// the heading level (1 for H1, 2 for H2, ...).
+  pub level: u32,
+  pub children: Vec<OutlineItem>,
+}
+
+/// Text of one block-level structure element (`P`, `H1`, `LI`, `TD`, ...)
+/// of a tagged PDF.
+#[napi(object)]
+pub struct TaggedBlock {
+  /// Page of the block's first content, when the structure tree records it.
+  pub page: Option<u32>,
+  /// Standard structure type, after applying the document's `/RoleMap`.
+  pub role: String,
+  pub text: String,
+}
+
+/// A run of text shown by one operator, with where it sits in the file.
+#[napi(object)]
+pub struct SourceTextRun {
+  pub page: u32,
+  /// The content stream or form XObject holding the operator, e.g.
+  /// `"12 0 obj"`.
+  pub object_id: String,
+  /// Position of the `Tj`, `TJ`, `'` or `"` operator among the stream's
+  /// decoded operations, counting from 0.
+  pub operator_index: u32,
+  pub text: String,
+}
+
+#[napi(object)]
+pub struct PageDiff {
+  pub page: u32,
+  /// `true` when the page's text differs, or the page exists in only one of
+  /// the two documents.
+  pub changed: bool,
+}
+
+/// Number of characters of one Unicode script in a document's text.
+#[napi(object)]
+pub struct ScriptCount {
+  /// Script name, e.g. `"Latin"`, `"Han"`, `"Cyrillic"`, `"Arabic"`;
+  /// `"Common"` for digits, punctuation and symbols.
+  pub script: String,
+  pub count: u32,
+}
+
+/// Term frequencies of one page.
+#[napi(object)]
+pub struct PageWordFreq {
+  pub page: u32,
+  /// Most frequent first, ties in alphabetical order.
+  pub terms: Vec<TermCount>,
+}
+
+/// How often one lowercased word occurs.
+#[napi(object)]
+pub struct TermCount {
+  pub term: String,
+  pub count: u32,
+}
+
+#[napi(object)]
+pub struct PageParagraphs {
+  pub page: u32,
+  /// Top to bottom; line breaks within a paragraph are collapsed to spaces.
+  pub paragraphs: Vec<String>,
 }
 
 #[cfg(all(feature = "ocr", not(feature = "render")))]
@@ -40,10 +203,26 @@ pub struct OcrPageText {
   pub source: TextSource,
 }
 
+/// One page as handed to the `PdfDown.streamTextWithOcr` callback.
+#[cfg(feature = "ocr")]
+#[napi(object)]
+pub struct OcrStreamedPage {
+  pub page: u32,
+  pub text: String,
+  pub source: TextSource,
+  /// Tesseract's mean word confidence (0–100) when the text came from OCR
+  /// (`ocr` or `rendered`); averaged over the page's images. `None` for
+  /// native text and pages where OCR found nothing.
+  pub confidence: Option<f64>,
+}
+
 #[cfg(all(feature = "ocr", not(feature = "render")))]
 #[napi(object)]
 pub struct OcrOptions {
   pub lang: Option<String>,
+  /// Tessdata directory for this call. Overrides `TESSDATA_PREFIX` and the
+  /// auto-detected path from `tesseract --list-langs`.
+  pub tessdata_path: Option<String>,
   pub min_text_length: Option<u32>,
   pub max_threads: Option<u32>,
 }
@@ -52,6 +231,9 @@ pub struct OcrOptions {
 #[napi(object)]
 pub struct OcrOptions {
   pub lang: Option<String>,
+  /// Tessdata directory for this call. Overrides `TESSDATA_PREFIX` and the
+  /// auto-detected path from `tesseract --list-langs`.
+  pub tessdata_path: Option<String>,
   pub min_text_length: Option<u32>,
   pub max_threads: Option<u32>,
   pub render: Option<RenderMode>,
@@ -106,6 +288,117 @@ pub struct RenderOptions {
   pub mode: Option<RenderMode>,
 }
 
+/// Post-processing for `extractTextPerPage` / `PdfDown.textPerPage`.
+#[napi(object)]
+pub struct TextOptions {
+  /// Remove soft hyphens (U+00AD), joining words split across a line break by
+  /// one. Defaults to `false`.
+  pub strip_soft_hyphens: Option<bool>,
+  /// Rebuild each page's text from its glyph positions, in content-stream
+  /// order, with spaces where the painted gaps (after `Tc`, `Tw`, `Tz` and
+  /// `TJ` offsets) are wide enough — matching what copy-paste from a viewer
+  /// yields — instead of lopdf's spacing heuristics. Defaults to `false`.
+  pub exact_spacing: Option<bool>,
+  /// Show filled-in form values where a reader sees them: each page's text
+  /// is rebuilt from its positioned lines, with every visible form widget's
+  /// value placed at its `/Rect` (checkboxes and radio buttons as `[x]` or
+  /// `[ ]`). Takes precedence over `exact_spacing`. Defaults to `false`.
+  pub form_values: Option<bool>,
+  /// Fail with an error naming the first page whose text can't be extracted,
+  /// instead of returning empty text for it. Applies to the default
+  /// extraction; `exact_spacing` and `form_values` never fail per page.
+  /// Defaults to `false`.
+  pub strict: Option<bool>,
+  /// Clean up the artifacts a particular producer leaves in extracted text.
+  /// `Auto` picks a profile from the Info `/Producer` (or `/Creator`) and
+  /// leaves the text as without a profile when it recognizes neither.
+  /// Defaults to no profile.
+  pub producer_profile: Option<ProducerProfile>,
+}
+
+/// Producer-specific cleanups for `TextOptions.producerProfile`.
+#[napi(string_enum)]
+pub enum ProducerProfile {
+  /// Detect the producer from the document's Info dictionary.
+  Auto,
+  /// pdfTeX, XeTeX, LuaTeX and dvipdfmx: spell out ff/fi/fl/ffi/ffl
+  /// ligatures, including those of fonts without a `/ToUnicode` map.
+  Latex,
+  /// Chrome's Skia renderer: drop the split `N / total` page footers. Every
+  /// profile keeps this cleanup, as does extraction without one.
+  Chromium,
+  /// Microsoft Word: replace curly quotes with straight ones.
+  Word,
+}
+
+/// Options for `extractStructuredTextWithOptions` /
+/// `PdfDown.structuredTextWithOptions`.
+#[napi(object)]
+pub struct StructuredTextOptions {
+  /// Report the most common header and footer once, as `commonHeader` /
+  /// `commonFooter`, and clear them from the pages that carry them. Defaults
+  /// to `false`.
+  pub dedupe_headers: Option<bool>,
+  /// Treat month and weekday names as interchangeable when looking for
+  /// repeated headers and footers, so "March 2024" and "April 2024" count as
+  /// the same line. Numbers always match. Defaults to `false`.
+  pub normalize_dates: Option<bool>,
+  /// Compare header and footer lines case-insensitively. Defaults to `false`.
+  pub ignore_case: Option<bool>,
+  /// Set each page's `sectionTitle` to the heading it falls under, for
+  /// tracing text chunks back to their section. Detecting headings lays out
+  /// every page's styled lines as well. Defaults to `false`.
+  pub section_titles: Option<bool>,
+}
+
+#[napi(object)]
+pub struct StructuredTextResult {
+  pub pages: Vec<StructuredPageText>,
+  /// Header shared by several pages, set only with `dedupeHeaders`. Pages
+  /// whose header differs (e.g. because it carries the page number) keep
+  /// their own.
+  pub common_header: Option<String>,
+  /// Footer shared by several pages, set only with `dedupeHeaders`.
+  pub common_footer: Option<String>,
+}
+
+/// Stage toggles for `pdfDocument` / `PdfDown.document`. Every stage defaults
+/// to enabled; skipped stages come back as empty arrays with zero totals.
+#[napi(object)]
+pub struct DocumentOptions {
+  pub include_images: Option<bool>,
+  pub include_annotations: Option<bool>,
+  pub include_structured_text: Option<bool>,
+  /// Renumber pages sequentially in page tree order when the tree has pages
+  /// that would otherwise be skipped (no or wrong `/Type`) or repeated
+  /// (listed twice, or a cycle), reporting the new numbering in
+  /// `reindexedPages`. Defaults to `false`. Only this call is affected:
+  /// other functions, and a `PdfDown` the document came from, keep the
+  /// original numbering.
+  pub reindex_pages: Option<bool>,
+  /// Also return the page texts joined into one string as `fullText`, with
+  /// a `--- Page N ---` marker between pages. Defaults to `false`.
+  pub include_full_text: Option<bool>,
+}
+
+/// What `PdfDown.streamPages` includes with each page besides its text.
+/// Both default to `false`.
+#[napi(object)]
+pub struct StreamPagesOptions {
+  pub include_images: Option<bool>,
+  pub include_annotations: Option<bool>,
+}
+
+/// One page as handed to the `PdfDown.streamPages` callback. `images` and
+/// `annotations` are only set when requested in `StreamPagesOptions`.
+#[napi(object)]
+pub struct PageChunk {
+  pub page: u32,
+  pub text: String,
+  pub images: Option<Vec<PageImage>>,
+  pub annotations: Option<Vec<PageAnnotation>>,
+}
+
 #[napi(object)]
 pub struct Capabilities {
   pub ocr: bool,
@@ -116,12 +409,106 @@ pub struct Capabilities {
 pub struct PdfMeta {
   pub page_count: u32,
   pub version: String,
+  /// The catalog's `/Version`, when set. It overrides the header `version`
+  /// if later, so the effective version is the greater of the two.
+  pub catalog_version: Option<String>,
   pub is_linearized: bool,
   pub creator: Option<String>,
   pub producer: Option<String>,
   pub creation_date: Option<String>,
   pub modification_date: Option<String>,
+  /// `/CreationDate` exactly as stored in the Info dictionary (e.g.
+  /// `"D:20230415093000+05'30'"`).
+  pub creation_date_raw: Option<String>,
+  /// `/ModDate` exactly as stored in the Info dictionary.
+  pub modification_date_raw: Option<String>,
+  /// Declared document language from the catalog's `/Lang`, e.g. `"en-US"`.
+  pub language: Option<String>,
   pub page_boxes: Vec<PageBox>,
+  /// `xmpMM:History` from the catalog's XMP metadata, oldest first; empty
+  /// when the document records no history.
+  pub edit_history: Vec<EditEvent>,
+  /// Usage rights granted by a `/Perms` `/UR3` (or legacy `/UR`) signature,
+  /// i.e. a Reader-enabled document, as `"<category>.<right>"` (e.g.
+  /// `"Form.FillIn"`, `"Annots.Create"`, `"Document.FullSave"`). `None`
+  /// when the document carries no usage rights signature.
+  pub usage_rights: Option<Vec<String>>,
+  /// The print condition the document targets, from the first entry of the
+  /// catalog's `/OutputIntents`.
+  pub output_intent: Option<OutputIntent>,
+  /// The linearization parameter dictionary of a file saved for fast web
+  /// view; `None` when the file isn't linearized.
+  pub linearization: Option<LinearizationInfo>,
+  /// How the author asked viewers to present the document; `None` when the
+  /// catalog sets no viewer preferences, page layout or page mode.
+  pub viewer_preferences: Option<ViewerPreferences>,
+}
+
+/// The catalog's `/ViewerPreferences`, `/PageLayout` and `/PageMode`. Flags
+/// the document doesn't set are `false`, their PDF default.
+#[napi(object)]
+pub struct ViewerPreferences {
+  pub hide_toolbar: bool,
+  pub hide_menubar: bool,
+  /// Hide scroll bars, navigation controls and other window UI, leaving
+  /// only the page content.
+  pub hide_window_ui: bool,
+  /// Resize the window to fit the first page.
+  pub fit_window: bool,
+  pub center_window: bool,
+  /// Title the window with the Info `/Title` instead of the file name.
+  pub display_doc_title: bool,
+  /// Page mode on leaving full-screen mode, when `pageMode` is
+  /// `FullScreen`: `UseNone`, `UseOutlines`, `UseThumbs` or `UseOC`.
+  pub non_full_screen_page_mode: Option<String>,
+  /// Reading order: `L2R` or `R2L`.
+  pub direction: Option<String>,
+  /// `AppDefault` or `None` (print at actual size).
+  pub print_scaling: Option<String>,
+  /// `Simplex`, `DuplexFlipShortEdge` or `DuplexFlipLongEdge`.
+  pub duplex: Option<String>,
+  /// `/PageLayout`: `SinglePage`, `OneColumn`, `TwoColumnLeft`,
+  /// `TwoColumnRight`, `TwoPageLeft` or `TwoPageRight`.
+  pub page_layout: Option<String>,
+  /// `/PageMode`: `UseNone`, `UseOutlines`, `UseThumbs`, `FullScreen`,
+  /// `UseOC` or `UseAttachments`.
+  pub page_mode: Option<String>,
+}
+
+/// Linearization ("fast web view") parameters. A file edited after it was
+/// linearized usually keeps the dictionary but no longer matches it.
+#[napi(object)]
+pub struct LinearizationInfo {
+  /// `/L`: the file length in bytes when it was linearized.
+  pub file_length: Option<u32>,
+  /// `/O`: the first page's page object, e.g. `"12 0 obj"`.
+  pub first_page_object: Option<String>,
+  /// `true` when `/H` gives the primary hint stream's offset and length
+  /// within `/L`, and a stream object starts at that offset.
+  pub hint_stream_valid: bool,
+}
+
+/// A catalog `/OutputIntents` entry, as used by PDF/X and PDF/A.
+#[napi(object)]
+pub struct OutputIntent {
+  /// `/OutputCondition`, e.g. `"Coated FOGRA39 (ISO 12647-2:2004)"`; the
+  /// identifier when that isn't given.
+  pub condition: String,
+  /// `/OutputConditionIdentifier`, e.g. `"FOGRA39"` or a registry name.
+  pub identifier: String,
+  /// The `/DestOutputProfile` ICC profile, decompressed.
+  pub icc_profile: Option<Buffer>,
+}
+
+/// One `stEvt` entry of an XMP `xmpMM:History`.
+#[napi(object)]
+pub struct EditEvent {
+  /// `stEvt:action`, e.g. `"created"`, `"saved"` or `"converted"`.
+  pub action: String,
+  /// `stEvt:softwareAgent`, e.g. `"Adobe InDesign 18.0 (Macintosh)"`.
+  pub software_agent: Option<String>,
+  /// `stEvt:when`, as written (usually ISO 8601).
+  pub when: Option<String>,
 }
 
 #[napi(string_enum)]
@@ -142,12 +529,61 @@ pub struct PageBox {
   pub width: f64,
   pub height: f64,
   pub box_type: BoxType,
+  /// `true` when the box is inherited from an ancestor `/Pages` node rather
+  /// than defined on the page dictionary itself.
+  pub inherited: bool,
+  /// `/UserUnit`: size of one unit in 1/72 inch (default 1.0). Multiply
+  /// `width`/`height` by this to get real-world points on large-format pages.
+  pub user_unit: f64,
+  /// Blending color space of the page's `/Group` transparency group (e.g.
+  /// `"DeviceCMYK"`, `"ICCBased3"`), the working space for flattening. `None`
+  /// when the page has no transparency group or it sets no `/CS`.
+  pub transparency_group: Option<String>,
   /// Present only on non-dominant boxes — lists the specific pages with these
   /// dimensions. `None` on the first (most frequent) entry means "all pages
   /// not listed in any other entry's `pages` array."
   pub pages: Option<Vec<u32>>,
 }
 
+/// Real-world scale of a region of a page (a `/VP` viewport with a
+/// rectilinear `/Measure`), as used by engineering drawings and maps.
+#[napi(object)]
+pub struct Measurement {
+  pub page: u32,
+  /// The viewport's `/BBox`, `[x1, y1, x2, y2]` in default user space; empty
+  /// when it has none.
+  pub rect: Vec<f64>,
+  /// The scale ratio as written by the producer (`/R`), e.g.
+  /// `"1 in = 10 ft"`.
+  pub scale: String,
+  /// Unit label of x-axis distances (`/U` of the first `/X` number format),
+  /// e.g. `"ft"`.
+  pub unit: String,
+  /// Multiply a distance in points by this to get `unit`s (`/C`).
+  pub conversion_factor: Option<f64>,
+}
+
+/// Shape of the `/Pages` tree, from `pageTreeStats`.
+#[napi(object)]
+pub struct PageTreeStats {
+  /// `/Pages` levels on the longest path from the root to a page; 1 for a
+  /// flat tree whose root lists every page.
+  pub depth: u32,
+  /// Largest `/Kids` array of any `/Pages` node.
+  pub max_kids: u32,
+  /// Number of `/Pages` (intermediate) nodes, the root included.
+  pub node_count: u32,
+}
+
+/// Extremes of the displayed page sizes, in points.
+#[napi(object)]
+pub struct PageSizeRange {
+  pub min_width: f64,
+  pub min_height: f64,
+  pub max_width: f64,
+  pub max_height: f64,
+}
+
 #[napi(object)]
 pub struct PageImage {
   pub page: u32,
@@ -160,16 +596,331 @@ pub struct PageImage {
   pub filter: String,
   pub xobject_name: String,
   pub object_id: String,
+  /// Alternate description (`/Alt`) of the image's figure in a tagged PDF's
+  /// structure tree, when the author provided one.
+  pub alt_text: Option<String>,
+  /// Where the PNG was written when it spilled to `ImageOptions.spillDir`;
+  /// `data` is empty then.
+  pub file_path: Option<String>,
+  /// Mean `[r, g, b]` of the decoded pixels (alpha ignored), with
+  /// `ImageOptions.computeStats`.
+  pub mean_color: Option<Vec<u8>>,
+  /// Whether every decoded pixel's channels are nearly equal, with
+  /// `ImageOptions.computeStats`.
+  pub is_grayscale: Option<bool>,
+  /// Size of the image stream as stored in the file (its `/Length`).
+  pub compressed_bytes: u32,
+  /// Decoded size over `compressedBytes`. The decoded size is that of the
+  /// samples the stream's filters produce, or of the decoded pixels at 8
+  /// bits per component for JPEG and JPEG 2000 images. Low ratios point at
+  /// images worth recompressing.
+  pub compression_ratio: f64,
+}
+
+/// Format extracted images are re-encoded in.
+#[napi(string_enum)]
+pub enum ImageOutputFormat {
+  Png,
+  /// Lossy and without transparency, but much smaller for photos.
+  Jpeg,
+}
+
+/// Options for `extractImagesWithOptions` / `PdfDown.imagesWithOptions`.
+#[napi(object)]
+pub struct ImageOptions {
+  /// Collect an entry in `failures` for every image XObject that couldn't be
+  /// decoded instead of silently skipping it. Defaults to `false`.
+  pub report_failures: Option<bool>,
+  /// Downscale images whose longest side exceeds this many pixels, keeping
+  /// the aspect ratio. `width`/`height` on each `PageImage` report the output
+  /// size.
+  pub max_dimension: Option<u32>,
+  /// Directory to write PNGs to once `spill_threshold_bytes` of image data is
+  /// held in memory. Spilled images have an empty `data` and their path in
+  /// `file_path`. Each call writes into a new subdirectory of its own, so
+  /// repeated or concurrent extractions into the same `spill_dir` never
+  /// overwrite each other's files; files are named
+  /// `page-<page>-image-<imageIndex>.png` within it.
+  pub spill_dir: Option<String>,
+  /// In-memory budget for PNG data, in bytes, before images spill to
+  /// `spill_dir`. Defaults to 0, which writes every image to disk.
+  pub spill_threshold_bytes: Option<i64>,
+  /// Encode images as grayscale, e.g. to shrink input for an OCR service.
+  /// Defaults to `false`.
+  pub grayscale: Option<bool>,
+  /// Encode images as black and white: grayscale values at or above this
+  /// threshold (0–255) become white, the rest black. Implies `grayscale`.
+  pub binarize: Option<u8>,
+  /// Report `meanColor` and `isGrayscale` on each image, computed from the
+  /// decoded pixels before any conversion. Defaults to `false`.
+  pub compute_stats: Option<bool>,
+  /// Rotate/flip JPEG (DCTDecode) images so they come out upright when the
+  /// JPEG carries an EXIF orientation tag; `width`/`height` describe the
+  /// rotated image. Defaults to `true`.
+  pub apply_exif_orientation: Option<bool>,
+  /// Format of `data` and of spilled files. Defaults to `Png`.
+  pub format: Option<ImageOutputFormat>,
+  /// JPEG quality, 1–100, when `format` is `Jpeg`; values outside the range
+  /// are clamped. Defaults to 85.
+  pub jpeg_quality: Option<u8>,
+}
+
+/// An image XObject that was found but couldn't be decoded or re-encoded.
+#[napi(object)]
+pub struct ImageDecodeFailure {
+  pub page: u32,
+  pub xobject_name: String,
+  pub filter: String,
+  pub reason: String,
+  /// The image was still extracted after repairing it (e.g. a `/Height` one
+  /// row larger than its pixel data); `reason` says what was wrong.
+  pub recovered: bool,
+}
+
+#[napi(object)]
+pub struct ImageExtractionResult {
+  pub images: Vec<PageImage>,
+  /// Empty unless `reportFailures` is set.
+  pub failures: Vec<ImageDecodeFailure>,
+}
+
+/// Stored size of the images painted on one page, from `imageBytesPerPage`.
+#[napi(object)]
+pub struct PageImageBytes {
+  pub page: u32,
+  /// Sum of the stored (still filtered) stream lengths of the page's image
+  /// XObjects, in bytes.
+  pub total_stream_bytes: u32,
+  /// Distinct image XObjects painted on the page.
+  pub image_count: u32,
+}
+
+/// How heavy one page's content is, from `pageComplexity`.
+#[napi(object)]
+pub struct PageComplexity {
+  pub page: u32,
+  /// Decoded length of the page's content streams, in bytes.
+  pub content_bytes: u32,
+  /// Operators in the page's content streams (not counting form XObjects).
+  pub operator_count: u32,
+  /// Entries in the page's `/XObject` resources (images and forms).
+  pub xobject_count: u32,
+}
+
+/// One stream object's footprint, from `objectSizeReport`.
+#[napi(object)]
+pub struct ObjectSize {
+  pub object_id: String,
+  /// `image`, `font`, `content`, `metadata` or `other`.
+  #[napi(js_name = "type")]
+  pub object_type: String,
+  /// Stored length of the (possibly filtered) stream data in bytes.
+  pub compressed_size: u32,
+  /// Decoded length in bytes — `None` when the filter can't be decoded.
+  pub uncompressed_size: Option<u32>,
+}
+
+/// The object references in the file trailer, from `trailerRefs`.
+#[napi(object)]
+pub struct TrailerRefs {
+  /// The document catalog, e.g. `"1 0 obj"`.
+  pub root: Option<String>,
+  /// The document information dictionary.
+  pub info: Option<String>,
+  /// The encryption dictionary of an encrypted file.
+  pub encrypt: Option<String>,
+}
+
+/// Quick size statistics of a document, from `fileStats`.
+#[napi(object)]
+pub struct FileStats {
+  /// Size of the PDF file in bytes.
+  pub file_bytes: u32,
+  /// Indirect objects, including those packed in object streams.
+  pub object_count: u32,
+  pub stream_count: u32,
+  /// Image XObjects, whether or not any page paints them.
+  pub image_count: u32,
+  /// Font dictionaries; a composite (Type0) font counts once.
+  pub font_count: u32,
+}
+
+/// Document-level scan verdict, from `classifyDocument`.
+#[napi(object)]
+pub struct DocumentClassification {
+  /// `true` when more than half of the pages look scanned, so the document
+  /// is better served by OCR than by its native text.
+  pub likely_scanned: bool,
+  /// Fraction (0–1) of pages with negligible native text but a full-page
+  /// image.
+  pub scanned_page_ratio: f64,
+}
+
+/// One terminal field of an AcroForm interactive form.
+#[napi(object)]
+pub struct FormField {
+  /// The field's own object, e.g. `"12 0 obj"`, as listed in `tab_order`.
+  /// `None` for fields stored directly inside their parent.
+  pub object_id: Option<String>,
+  /// Fully qualified name: the partial names (`/T`) of the field and its
+  /// ancestors joined with `.`.
+  pub name: String,
+  /// `text`, `checkbox`, `radio`, `button` (push button), `choice`,
+  /// `signature` or `unknown`.
+  pub field_type: String,
+  /// The field's `/V`, inherited from ancestors when absent. Button states
+  /// are names without the slash (e.g. `"Yes"`, `"Off"`); multi-select
+  /// choices are joined with `, `.
+  pub value: Option<String>,
+  /// Checkboxes and radio groups: whether a state other than `/Off` is
+  /// selected, from `/V` or, without one, the widgets' `/AS`. `None` for
+  /// other field types.
+  pub checked: Option<bool>,
+  /// Page of the field's first widget annotation.
+  pub page: Option<u32>,
+}
+
+#[napi(object)]
+pub struct FormFieldsResult {
+  pub fields: Vec<FormField>,
+  /// `object_id`s of the fields in the order a viewer tabs through them:
+  /// page by page, following each page's `/Tabs` (`S` structure order, `R`
+  /// rows, `C` columns) or else its `/Annots` order.
+  pub tab_order: Vec<String>,
+  /// The form's default appearance string (`/AcroForm /DA`), e.g.
+  /// `"/Helv 0 Tf 0 g"`, for fields without their own `/DA`.
+  pub default_appearance: Option<String>,
+  /// Resource names of the form's default fonts (`/AcroForm /DR /Font`),
+  /// the names appearance strings select with `Tf`.
+  pub fonts: Vec<String>,
+}
+
+/// Annotation totals for one page, from `annotationSummary`.
+#[napi(object)]
+pub struct AnnotationSummary {
+  pub page: u32,
+  pub annotation_count: u32,
+  /// Union of the annotations' `/Rect`s, `[x1, y1, x2, y2]` in default user
+  /// space; `None` when no annotation on the page has a usable `/Rect`.
+  pub annotation_bounds: Option<Vec<f64>>,
+}
+
+/// The page text under one `Highlight` annotation, from
+/// `extractHighlightedText`.
+#[napi(object)]
+pub struct HighlightedText {
+  pub page: u32,
+  /// Position of the highlight in the page's `/Annots`, 0-based.
+  pub annotation_index: u32,
+  /// The highlighted text, lines joined with spaces; empty when the
+  /// highlight covers no text.
+  pub text: String,
 }
 
 #[napi(object)]
 pub struct PageAnnotation {
   pub page: u32,
+  /// The annotation's own object, e.g. `"12 0 obj"`; what `popup_for` and
+  /// `in_reply_to` of other annotations refer to.
+  pub object_id: String,
   pub subtype: String,
   pub rect: Vec<f64>,
   pub uri: Option<String>,
   pub dest: Option<String>,
   pub content: Option<String>,
+  /// `/T` of markup annotations (comments, highlights): the author's name.
+  pub author: Option<String>,
+  /// The link's action type (`/S`), e.g. `"URI"`, `"GoTo"`, `"GoToR"`,
+  /// `"Launch"` or `"Named"`. A bare `/Dest` reports `"GoTo"`; empty when the
+  /// annotation has no action.
+  pub action_type: String,
+  /// The target file of a `GoToR` or `Launch` action.
+  pub remote_file: Option<String>,
+  /// How the destination is displayed: `"XYZ"`, `"Fit"`, `"FitH"`,
+  /// `"FitV"`, `"FitR"`, `"FitB"`, `"FitBH"` or `"FitBV"`. Named
+  /// destinations are resolved through the catalog.
+  pub dest_fit: Option<String>,
+  /// The numbers following the fit type, e.g. `[left, top, zoom]` for `XYZ`
+  /// or `[left, bottom, right, top]` for `FitR`. `null` entries leave that
+  /// value unchanged.
+  pub dest_params: Option<Vec<Option<f64>>>,
+  /// `/QuadPoints` of text markup (`Highlight`, `Underline`, ...), `Link` and
+  /// `Redact` annotations: eight numbers `x1 y1 x2 y2 x3 y3 x4 y4` per marked
+  /// region, which can be narrower than `rect`.
+  pub quad_points: Option<Vec<f64>>,
+  /// Text a `Redact` annotation shows over the region once applied.
+  pub overlay_text: Option<String>,
+  /// For a `Popup`, the `object_id` of the markup annotation whose comment it
+  /// displays (`/Parent`).
+  pub popup_for: Option<String>,
+  /// The `object_id` of the annotation this one replies to (`/IRT`).
+  pub in_reply_to: Option<String>,
+  /// Whether a `Popup` or `Text` annotation is initially shown open
+  /// (`/Open`).
+  pub open: Option<bool>,
+  /// What a `Screen` or `RichMedia` annotation plays: the MIME type of its
+  /// media clip or embedded file (e.g. `"video/mp4"`), else the RichMedia
+  /// configuration type (`"Video"`, `"Sound"`, `"Flash"`, `"3D"`).
+  pub media_type: Option<String>,
+  /// File name of the media's file specification.
+  pub media_file: Option<String>,
+  /// The embedded file stream holding the media, e.g. `"31 0 obj"`; `None`
+  /// when the media is an external file.
+  pub media_object_id: Option<String>,
+  /// Text drawn by the annotation's normal appearance stream (`/AP /N`),
+  /// e.g. the visible text of a `FreeText` box or `Stamp`. Only read when
+  /// `AnnotationOptions.renderedText` is set.
+  pub rendered_text: Option<String>,
+}
+
+/// Options for `extractAnnotationsPerPage` / `PdfDown.annotationsPerPage`.
+#[napi(object)]
+pub struct AnnotationOptions {
+  /// Fill `renderedText` with the text each annotation's appearance stream
+  /// draws. Defaults to `false`.
+  pub rendered_text: Option<bool>,
+}
+
+/// An action the document runs by itself, from `documentActions`.
+#[napi(object)]
+pub struct DocumentAction {
+  /// When it runs: `Open` (`/OpenAction`), or a catalog `/AA` event:
+  /// `WillClose`, `WillSave`, `DidSave`, `WillPrint` or `DidPrint`.
+  pub trigger: String,
+  /// The action's `/S` type, e.g. `"JavaScript"`, `"URI"`, `"GoTo"`,
+  /// `"Launch"`, `"Named"` or `"SubmitForm"`.
+  pub action_type: String,
+  /// The script of a `JavaScript` action, the target of `URI`, `GoTo`
+  /// (destination name or `page N`), `GoToR`, `Launch` and `SubmitForm`
+  /// actions, or the name of a `Named` action (e.g. `"Print"`).
+  pub detail: Option<String>,
+}
+
+/// Active content found in a document, from `cleanReport`.
+#[napi(object)]
+pub struct CleanReport {
+  /// A JavaScript action anywhere: document-level, on annotations, form
+  /// fields, outlines or in the `/JavaScript` name tree.
+  pub has_javascript: bool,
+  /// A `Launch` action, which asks the viewer to open a file or program.
+  pub has_launch_action: bool,
+  /// An embedded file, attached to the document or to an annotation.
+  pub has_embedded_files: bool,
+  /// The catalog has an `/OpenAction`, even a plain destination.
+  pub has_open_action: bool,
+  /// None of JavaScript, `Launch` actions or embedded files was found.
+  pub is_clean: bool,
+}
+
+/// A URI referenced by the document, from `extractAllUris`.
+#[napi(object)]
+pub struct UriRef {
+  pub uri: String,
+  /// Page the URI appears on; `None` for actions outside pages (outlines,
+  /// open actions, name trees).
+  pub page: Option<u32>,
+  /// Where it was found: `annotation`, `action` or `text`.
+  pub source: String,
 }
 
 #[napi(object)]
@@ -190,6 +941,24 @@ pub struct PdfDocument {
   pub structured_text: Vec<StructuredPageText>,
   pub images: Vec<PageImage>,
   pub annotations: Vec<PageAnnotation>,
+  /// Set when `reindexPages` was requested and the page tree needed it: the
+  /// page each page object was extracted as.
+  pub reindexed_pages: Option<Vec<ReindexedPage>>,
+  /// All page text in one string, joined as `fullTextWithMarkers` joins it.
+  /// Set when `includeFullText` was requested.
+  pub full_text: Option<String>,
+}
+
+/// A page renumbered by `DocumentOptions.reindexPages`.
+#[napi(object)]
+pub struct ReindexedPage {
+  /// The page number used throughout the extracted document.
+  pub page: u32,
+  /// The page object, e.g. `"12 0 obj"`.
+  pub object_id: String,
+  /// Its number before reindexing (its first, if it was listed more than
+  /// once); `None` for a page that was skipped.
+  pub previous_page: Option<u32>,
 }
 
 #[cfg(feature = "ocr")]
@@ -213,6 +982,9 @@ pub struct PdfDocumentOcr {
   pub creation_date: Option<String>,
   pub modification_date: Option<String>,
   pub page_boxes: Vec<PageBox>,
+  /// `false` when Tesseract couldn't start (missing binary, tessdata or
+  /// language data); `text` then holds native text for every page.
+  pub ocr_available: bool,
   pub total_images: u32,
   pub total_annotations: u32,
   pub image_pages: Vec<u32>,
@@ -236,6 +1008,12 @@ pub struct RawPageImage {
   pub filter: String,
   pub xobject_name: String,
   pub object_id: String,
+  pub alt_text: Option<String>,
+  pub file_path: Option<String>,
+  pub mean_color: Option<Vec<u8>>,
+  pub is_grayscale: Option<bool>,
+  pub compressed_bytes: u32,
+  pub compression_ratio: f64,
 }
 
 impl From<RawPageImage> for PageImage {
@@ -251,6 +1029,26 @@ impl From<RawPageImage> for PageImage {
       filter: r.filter,
       xobject_name: r.xobject_name,
       object_id: r.object_id,
+      alt_text: r.alt_text,
+      file_path: r.file_path,
+      mean_color: r.mean_color,
+      is_grayscale: r.is_grayscale,
+      compressed_bytes: r.compressed_bytes,
+      compression_ratio: r.compression_ratio,
+    }
+  }
+}
+
+pub struct RawImageExtraction {
+  pub images: Vec<RawPageImage>,
+  pub failures: Vec<ImageDecodeFailure>,
+}
+
+impl From<RawImageExtraction> for ImageExtractionResult {
+  fn from(r: RawImageExtraction) -> Self {
+    ImageExtractionResult {
+      images: r.images.into_iter().map(PageImage::from).collect(),
+      failures: r.failures,
     }
   }
 }
@@ -263,6 +1061,8 @@ pub struct RawPdfDocument {
   pub annotations: Vec<PageAnnotation>,
   pub image_pages: Vec<u32>,
   pub annotation_pages: Vec<u32>,
+  pub reindexed_pages: Option<Vec<ReindexedPage>>,
+  pub full_text: Option<String>,
 }
 
 impl From<RawPdfDocument> for PdfDocument {
@@ -286,6 +1086,28 @@ impl From<RawPdfDocument> for PdfDocument {
       structured_text: r.structured_text,
       images: r.images.into_iter().map(PageImage::from).collect(),
       annotations: r.annotations,
+      reindexed_pages: r.reindexed_pages,
+      full_text: r.full_text,
+    }
+  }
+}
+
+pub struct RawPageChunk {
+  pub page: u32,
+  pub text: String,
+  pub images: Option<Vec<RawPageImage>>,
+  pub annotations: Option<Vec<PageAnnotation>>,
+}
+
+impl From<RawPageChunk> for PageChunk {
+  fn from(r: RawPageChunk) -> Self {
+    PageChunk {
+      page: r.page,
+      text: r.text,
+      images: r
+        .images
+        .map(|images| images.into_iter().map(PageImage::from).collect()),
+      annotations: r.annotations,
     }
   }
 }
@@ -293,6 +1115,7 @@ impl From<RawPdfDocument> for PdfDocument {
 #[cfg(feature = "ocr")]
 pub struct RawPdfDocumentOcr {
   pub meta: PdfMeta,
+  pub ocr_available: bool,
   pub text: Vec<OcrPageText>,
   pub structured_text: Vec<OcrStructuredPageText>,
   pub images: Vec<RawPageImage>,
@@ -315,6 +1138,7 @@ impl From<RawPdfDocumentOcr> for PdfDocumentOcr {
       creation_date: r.meta.creation_date,
       modification_date: r.meta.modification_date,
       page_boxes: r.meta.page_boxes,
+      ocr_available: r.ocr_available,
       total_images,
       total_annotations,
       image_pages: r.image_pages,